@@ -0,0 +1,177 @@
+//! Message insert latency while other threads page through channel history.
+//!
+//! Runs the same workload twice: with history reads on the writer connection (how every
+//! query ran before the read pool) and with reads from the read-only pool. With the pool,
+//! inserts no longer queue behind reads.
+//!
+//! Run with `cargo bench --bench db_pool --features bench`.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use united_server::bench::{insert_chat_message, query_history};
+use united_server::chat::messages::{CreateMessageRequest, HistoryQuery};
+use united_server::db::DbPool;
+use united_server::roles::permissions::Permissions;
+
+const SEEDED_MESSAGES: usize = 5_000;
+const INSERTS: usize = 500;
+const READER_THREADS: usize = 4;
+const HISTORY_PAGE: u32 = 100;
+
+#[derive(Clone, Copy)]
+enum ReadSide {
+    Writer,
+    ReadPool,
+}
+
+impl ReadSide {
+    fn label(self) -> &'static str {
+        match self {
+            ReadSide::Writer => "reads on writer",
+            ReadSide::ReadPool => "reads on pool",
+        }
+    }
+}
+
+fn message(content: String) -> CreateMessageRequest {
+    CreateMessageRequest {
+        content,
+        reply_to_id: None,
+        block_refs_json: None,
+        title: None,
+    }
+}
+
+/// Fresh database with the starter channels, one user, and a seeded history.
+fn setup() -> (DbPool, String, tempfile::TempDir) {
+    let tmp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let db =
+        united_server::db::init_db(tmp_dir.path().to_str().unwrap()).expect("Failed to init DB");
+
+    let channel_id = {
+        let mut conn = db.lock().unwrap();
+        united_server::channels::seed::seed_starter_template(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO roles (id, name, permissions, position, is_default, created_at, updated_at)
+             VALUES ('everyone', '@everyone', ?1, 0, 1, '', '')",
+            [Permissions::DEFAULT_EVERYONE.bits()],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO users (id, public_key, fingerprint, display_name, created_at, updated_at)
+             VALUES ('bench-user', ?1, 'FP-bench', 'bench', '', '')",
+            [vec![7u8; 32]],
+        )
+        .unwrap();
+        let channel_id: String = conn
+            .query_row(
+                "SELECT id FROM channels WHERE channel_type = 'text' LIMIT 1",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+
+        let tx = conn.transaction().unwrap();
+        for i in 0..SEEDED_MESSAGES {
+            insert_chat_message(
+                &tx,
+                &channel_id,
+                None,
+                "bench-user",
+                Permissions::DEFAULT_EVERYONE,
+                message(format!("seeded message {}", i)),
+            )
+            .unwrap();
+        }
+        tx.commit().unwrap();
+        channel_id
+    };
+
+    (db, channel_id, tmp_dir)
+}
+
+fn run(side: ReadSide) {
+    let (db, channel_id, _tmp_dir) = setup();
+    let stop = Arc::new(AtomicBool::new(false));
+    let reads = Arc::new(AtomicU64::new(0));
+
+    let readers: Vec<_> = (0..READER_THREADS)
+        .map(|_| {
+            let (db, channel_id, stop, reads) =
+                (db.clone(), channel_id.clone(), stop.clone(), reads.clone());
+            thread::spawn(move || {
+                let query = HistoryQuery {
+                    before: None,
+                    limit: Some(HISTORY_PAGE),
+                };
+                while !stop.load(Ordering::Relaxed) {
+                    match side {
+                        ReadSide::Writer => {
+                            let conn = db.lock().unwrap();
+                            query_history(&conn, &channel_id, None, &query).unwrap();
+                        }
+                        ReadSide::ReadPool => {
+                            let conn = db.read().unwrap();
+                            query_history(&conn, &channel_id, None, &query).unwrap();
+                        }
+                    }
+                    reads.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+        })
+        .collect();
+
+    // Let the readers get going before timing inserts
+    thread::sleep(Duration::from_millis(100));
+
+    let started = Instant::now();
+    let mut latencies = Vec::with_capacity(INSERTS);
+    for i in 0..INSERTS {
+        let begin = Instant::now();
+        // Same as the REST handler: the sequence number and the message commit together
+        let mut conn = db.lock().unwrap();
+        let tx = conn.transaction().unwrap();
+        insert_chat_message(
+            &tx,
+            &channel_id,
+            None,
+            "bench-user",
+            Permissions::DEFAULT_EVERYONE,
+            message(format!("bench message {}", i)),
+        )
+        .unwrap();
+        tx.commit().unwrap();
+        drop(conn);
+        latencies.push(begin.elapsed());
+    }
+    let elapsed = started.elapsed();
+
+    stop.store(true, Ordering::Relaxed);
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    latencies.sort();
+    let percentile = |p: f64| latencies[((latencies.len() - 1) as f64 * p) as usize];
+    println!(
+        "{:<16} inserts: p50 {:>9.3?}  p99 {:>9.3?}  max {:>9.3?}  ({:.0}/s)   history reads: {:.0}/s",
+        side.label(),
+        percentile(0.50),
+        percentile(0.99),
+        latencies[latencies.len() - 1],
+        INSERTS as f64 / elapsed.as_secs_f64(),
+        reads.load(Ordering::Relaxed) as f64 / elapsed.as_secs_f64(),
+    );
+}
+
+fn main() {
+    println!(
+        "{} inserts into a {}-message channel while {} threads read {}-message history pages",
+        INSERTS, SEEDED_MESSAGES, READER_THREADS, HISTORY_PAGE
+    );
+    run(ReadSide::Writer);
+    run(ReadSide::ReadPool);
+}
@@ -1,6 +1,8 @@
 use axum::{extract::State, http::StatusCode, Json};
 use serde::{Deserialize, Serialize};
 
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::roles::permissions::{require_permission, Permissions};
 use crate::state::AppState;
 
 /// Public server info response (visible to anyone)
@@ -27,19 +29,8 @@ pub async fn get_server_info(
     let db = state.db.clone();
 
     let info = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-        let name = get_setting(&conn, "name").unwrap_or_else(|| get_default_server_name());
-        let description = get_setting(&conn, "description").unwrap_or_default();
-        let registration_mode =
-            get_setting(&conn, "registration_mode").unwrap_or_else(|| "open".to_string());
-
-        Ok::<ServerInfoResponse, StatusCode>(ServerInfoResponse {
-            name,
-            description,
-            registration_mode,
-            version: env!("CARGO_PKG_VERSION").to_string(),
-        })
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        Ok::<ServerInfoResponse, StatusCode>(load_server_info(&conn))
     })
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
@@ -47,22 +38,25 @@ pub async fn get_server_info(
     Ok(Json(info))
 }
 
-/// PUT /api/server/settings - Admin-only endpoint
+/// PUT /api/server/settings - Requires MANAGE_SERVER (or the legacy admin flag)
 pub async fn update_server_settings(
     State(state): State<AppState>,
     claims: crate::auth::middleware::Claims,
     Json(req): Json<UpdateSettingsRequest>,
 ) -> Result<Json<ServerInfoResponse>, StatusCode> {
-    // Check admin or owner role
-    if !claims.is_admin && !claims.is_owner {
-        return Err(StatusCode::FORBIDDEN);
+    if !claims.is_admin {
+        require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::MANAGE_SERVER)
+            .await?;
     }
 
     let db = state.db.clone();
+    let actor_id = claims.sub.clone();
 
-    let info = tokio::task::spawn_blocking(move || {
+    let (info, entry) = tokio::task::spawn_blocking(move || {
         let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+        let before = load_server_info(&conn);
+
         if let Some(name) = &req.name {
             set_setting(&conn, "name", name)
                 .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -82,24 +76,37 @@ pub async fn update_server_settings(
         }
 
         // Return updated info
-        let name = get_setting(&conn, "name").unwrap_or_else(|| get_default_server_name());
-        let description = get_setting(&conn, "description").unwrap_or_default();
-        let registration_mode =
-            get_setting(&conn, "registration_mode").unwrap_or_else(|| "open".to_string());
-
-        Ok::<ServerInfoResponse, StatusCode>(ServerInfoResponse {
-            name,
-            description,
-            registration_mode,
-            version: env!("CARGO_PKG_VERSION").to_string(),
-        })
+        let info = load_server_info(&conn);
+
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::ServerUpdate, "")
+                .before(&before)
+                .after(&info),
+        )
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        Ok::<_, StatusCode>((info, entry))
     })
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
 
+    audit::notify_admins(&state, entry);
+
     Ok(Json(info))
 }
 
+/// Read the public server info from server_settings (with defaults)
+pub(crate) fn load_server_info(conn: &rusqlite::Connection) -> ServerInfoResponse {
+    ServerInfoResponse {
+        name: get_setting(conn, "name").unwrap_or_else(get_default_server_name),
+        description: get_setting(conn, "description").unwrap_or_default(),
+        registration_mode: get_setting(conn, "registration_mode")
+            .unwrap_or_else(|| "open".to_string()),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
 /// Get a setting value from server_settings table
 fn get_setting(conn: &rusqlite::Connection, key: &str) -> Option<String> {
     conn.query_row(
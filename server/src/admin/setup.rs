@@ -65,7 +65,7 @@ pub fn maybe_generate_setup_token(db: &DbPool) -> Result<Option<String>, Box<dyn
 /// Verify a setup token against the stored hash.
 /// Returns true if the token matches and hasn't been consumed yet.
 pub fn verify_setup_token(db: &DbPool, token: &str) -> Result<bool, Box<dyn std::error::Error>> {
-    let conn = db.lock().map_err(|e| format!("DB lock error: {}", e))?;
+    let conn = db.read().map_err(|e| format!("DB lock error: {}", e))?;
 
     let stored_hash: Option<String> = conn
         .query_row(
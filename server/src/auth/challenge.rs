@@ -240,7 +240,7 @@ pub async fn refresh_tokens(
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
 
     Ok(Json(AuthTokensResponse {
-        access_token: access_token,
+        access_token,
         refresh_token: new_refresh,
     }))
 }
@@ -27,7 +27,7 @@ pub fn load_or_generate_jwt_secret(data_dir: &str) -> Result<Vec<u8>, Box<dyn st
 
     // Generate new 256-bit random key
     let key: [u8; 32] = rand::rng().random();
-    std::fs::write(&key_path, &key)?;
+    std::fs::write(&key_path, key)?;
     tracing::info!("JWT signing key generated at {}", key_path.display());
     Ok(key.to_vec())
 }
@@ -51,7 +51,7 @@ pub fn load_or_generate_encryption_key(data_dir: &str) -> Result<Vec<u8>, Box<dy
     }
 
     let key: [u8; 32] = rand::rng().random();
-    std::fs::write(&key_path, &key)?;
+    std::fs::write(&key_path, key)?;
     tracing::info!("Encryption key generated at {}", key_path.display());
     Ok(key.to_vec())
 }
@@ -29,36 +29,34 @@ where
     type Rejection = StatusCode;
 
     async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        // Extract Bearer token from Authorization header
-        let auth_header = parts
-            .headers
-            .get("Authorization")
-            .and_then(|v| v.to_str().ok())
-            .ok_or(StatusCode::UNAUTHORIZED)?;
-
-        let token = auth_header
-            .strip_prefix("Bearer ")
-            .ok_or(StatusCode::UNAUTHORIZED)?;
-
         // Get JWT secret from request extensions (set by middleware layer)
         let jwt_secret = parts
             .extensions
             .get::<JwtSecret>()
             .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
 
-        // Validate and decode JWT
-        let validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS256);
-        let token_data = jsonwebtoken::decode::<Claims>(
-            token,
-            &jsonwebtoken::DecodingKey::from_secret(&jwt_secret.0),
-            &validation,
-        )
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
-
-        Ok(token_data.claims)
+        claims_from_headers(&parts.headers, &jwt_secret.0).ok_or(StatusCode::UNAUTHORIZED)
     }
 }
 
+/// Validate the Authorization: Bearer token in `headers` and decode its claims.
+/// Returns None when the header is missing or the token is invalid or expired.
+pub fn claims_from_headers(headers: &axum::http::HeaderMap, jwt_secret: &[u8]) -> Option<Claims> {
+    let token = headers
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())?
+        .strip_prefix("Bearer ")?;
+
+    let validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS256);
+    jsonwebtoken::decode::<Claims>(
+        token,
+        &jsonwebtoken::DecodingKey::from_secret(jwt_secret),
+        &validation,
+    )
+    .ok()
+    .map(|data| data.claims)
+}
+
 /// JWT secret stored in request extensions for the Claims extractor
 #[derive(Clone)]
 pub struct JwtSecret(pub Vec<u8>);
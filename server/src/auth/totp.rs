@@ -164,7 +164,7 @@ pub async fn totp_confirm(
     let uid = user_id.clone();
     let encrypted_secret = tokio::task::spawn_blocking(move || {
         let conn = db2
-            .lock()
+            .read()
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB lock: {}", e)))?;
         let result: Result<Option<Vec<u8>>, _> = conn.query_row(
             "SELECT totp_secret_encrypted FROM users WHERE id = ?1",
@@ -234,7 +234,7 @@ pub async fn totp_verify(
     let fp = fingerprint.clone();
     let (encrypted_secret, totp_enrolled) = tokio::task::spawn_blocking(move || {
         let conn = db2
-            .lock()
+            .read()
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB lock: {}", e)))?;
         let result: Result<(Option<Vec<u8>>, bool), _> = conn.query_row(
             "SELECT totp_secret_encrypted, totp_enrolled FROM users WHERE fingerprint = ?1",
@@ -280,7 +280,7 @@ pub fn check_totp_enrolled(
     db: &crate::db::DbPool,
     fingerprint: &str,
 ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-    let conn = db.lock().map_err(|e| format!("DB lock: {}", e))?;
+    let conn = db.read().map_err(|e| format!("DB lock: {}", e))?;
     let enrolled: bool = conn
         .query_row(
             "SELECT totp_enrolled FROM users WHERE fingerprint = ?1",
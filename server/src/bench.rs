@@ -0,0 +1,31 @@
+//! Crate internals the benchmarks drive directly, compiled only with the `bench` feature.
+
+use axum::http::StatusCode;
+
+use crate::chat::messages::{
+    self, CreateMessageRequest, HistoryQuery, HistoryResponse, MessageResponse,
+};
+use crate::proto::chat as proto_chat;
+use crate::roles::permissions::Permissions;
+
+/// See `chat::messages::insert_chat_message`.
+pub fn insert_chat_message(
+    conn: &rusqlite::Connection,
+    channel_id: &str,
+    thread_id: Option<&str>,
+    user_id: &str,
+    perms: Permissions,
+    body: CreateMessageRequest,
+) -> Result<(MessageResponse, proto_chat::ChatMessage), StatusCode> {
+    messages::insert_chat_message(conn, channel_id, thread_id, user_id, perms, body)
+}
+
+/// See `chat::messages::query_history`.
+pub fn query_history(
+    conn: &rusqlite::Connection,
+    channel_id: &str,
+    thread_id: Option<&str>,
+    query: &HistoryQuery,
+) -> Result<HistoryResponse, StatusCode> {
+    messages::query_history(conn, channel_id, thread_id, query)
+}
@@ -13,6 +13,7 @@ use serde::Serialize;
 
 use crate::auth::middleware::Claims;
 use crate::blocks::store;
+use crate::moderation::trust::{self, Capability};
 use crate::state::AppState;
 
 /// Default retention days for blocks (overridden by config)
@@ -31,13 +32,17 @@ pub struct BlockUploadResponse {
 /// Optional header: `X-Channel-Id` (channel association for retention tracking).
 ///
 /// The server verifies the SHA-256 hash matches the body, encrypts with an
-/// HKDF-derived key, and stores the encrypted block on disk.
+/// HKDF-derived key, and stores the encrypted block on disk. Gated during new-member
+/// probation.
 pub async fn put_block_route(
     State(state): State<AppState>,
-    _claims: Claims,
+    claims: Claims,
     headers: HeaderMap,
     body: Bytes,
 ) -> Result<(StatusCode, Json<BlockUploadResponse>), (StatusCode, String)> {
+    trust::require_capabilities(&state, &claims.sub, claims.is_owner, &[Capability::Attachments])
+        .await?;
+
     // Extract required X-Block-Hash header
     let hash_hex = headers
         .get("x-block-hash")
@@ -119,7 +119,7 @@ pub fn get_block(db: &DbPool, data_dir: &str, hash_hex: &str) -> Result<Option<V
 
 /// Check whether a block exists in the metadata table.
 pub fn has_block(db: &DbPool, hash_hex: &str) -> bool {
-    let conn = match db.lock() {
+    let conn = match db.read() {
         Ok(c) => c,
         Err(_) => return false,
     };
@@ -150,7 +150,19 @@ pub fn delete_block(db: &DbPool, data_dir: &str, hash_hex: &str) -> Result<(), S
     Ok(())
 }
 
-/// Delete all blocks whose `expires_at` is in the past.
+/// Expired blocks, excluding those referenced by a pinned (non-deleted) message's
+/// `block_refs_json` so pinned attachments outlive the retention window.
+const EXPIRED_UNPINNED_BLOCKS: &str = "expires_at < datetime('now')
+    AND hash NOT IN (
+        SELECT json_extract(r.value, '$.hash')
+        FROM pins p
+        INNER JOIN messages m ON m.id = p.message_id,
+        json_each(CASE WHEN json_valid(m.block_refs_json) THEN m.block_refs_json ELSE '[]' END) r
+        WHERE m.deleted = 0 AND json_extract(r.value, '$.hash') IS NOT NULL
+    )";
+
+/// Delete all blocks whose `expires_at` is in the past, except blocks attached to
+/// pinned messages.
 ///
 /// Returns the number of blocks purged.
 pub fn delete_expired_blocks(db: &DbPool, data_dir: &str) -> Result<usize, String> {
@@ -158,7 +170,7 @@ pub fn delete_expired_blocks(db: &DbPool, data_dir: &str) -> Result<usize, Strin
 
     // Collect expired block hashes
     let mut stmt = conn
-        .prepare("SELECT hash FROM blocks WHERE expires_at < datetime('now')")
+        .prepare(&format!("SELECT hash FROM blocks WHERE {}", EXPIRED_UNPINNED_BLOCKS))
         .map_err(|e| format!("Failed to prepare expiry query: {}", e))?;
 
     let expired_hashes: Vec<String> = stmt
@@ -181,7 +193,7 @@ pub fn delete_expired_blocks(db: &DbPool, data_dir: &str) -> Result<usize, Strin
 
     // Delete metadata rows
     conn.execute(
-        "DELETE FROM blocks WHERE expires_at < datetime('now')",
+        &format!("DELETE FROM blocks WHERE {}", EXPIRED_UNPINNED_BLOCKS),
         [],
     )
     .map_err(|e| format!("Failed to delete expired block rows: {}", e))?;
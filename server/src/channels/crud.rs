@@ -8,21 +8,19 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::auth::middleware::Claims;
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::p2p::publish::channel_topic;
 use crate::p2p::SwarmCommand;
 use crate::proto::channels as proto_channels;
 use crate::proto::ws::{envelope::Payload, Envelope};
-use crate::roles::permissions::{require_permission, Permissions};
+use crate::roles::permissions::{compute_channel_permissions, require_permission, Permissions};
 use crate::state::AppState;
-use crate::ws::broadcast::broadcast_to_all;
-
-/// Build a gossipsub topic string for a channel.
-/// Format: `{server_peer_id_prefix}/{channel_id}`
-fn gossipsub_topic(server_peer_id: &str, channel_id: &str) -> String {
-    let prefix = &server_peer_id[..std::cmp::min(16, server_peer_id.len())];
-    format!("{}/{}", prefix, channel_id)
-}
+use crate::ws::broadcast::{broadcast_to_all, publish};
+use crate::ws::scope::{self, EventScope};
 
 use super::ordering::next_position;
+use super::slowmode::MAX_SLOWMODE_SECS;
+use super::types::ChannelType;
 
 // --- Response types ---
 
@@ -34,6 +32,7 @@ pub struct ChannelResponse {
     pub category_id: String,
     pub position: i64,
     pub topic: String,
+    pub slowmode_secs: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -54,6 +53,48 @@ pub struct ChannelListResponse {
     pub categories: Vec<CategoryWithChannelsResponse>,
 }
 
+impl ChannelResponse {
+    /// Convert to the protobuf Channel used in events and WS responses.
+    pub fn to_proto(&self) -> proto_channels::Channel {
+        proto_channels::Channel {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            channel_type: self.channel_type.clone(),
+            category_id: self.category_id.clone(),
+            position: self.position,
+            topic: self.topic.clone(),
+            slowmode_secs: self.slowmode_secs,
+        }
+    }
+}
+
+impl CategoryResponse {
+    /// Convert to the protobuf Category used in events and WS responses.
+    pub fn to_proto(&self) -> proto_channels::Category {
+        proto_channels::Category {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            position: self.position,
+        }
+    }
+}
+
+impl ChannelListResponse {
+    /// Convert to the protobuf ChannelListResponse used in WS responses.
+    pub fn to_proto(&self) -> proto_channels::ChannelListResponse {
+        proto_channels::ChannelListResponse {
+            categories: self
+                .categories
+                .iter()
+                .map(|c| proto_channels::CategoryWithChannels {
+                    category: Some(c.category.to_proto()),
+                    channels: c.channels.iter().map(|ch| ch.to_proto()).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
 // --- Request types ---
 
 #[derive(Debug, Deserialize)]
@@ -63,12 +104,15 @@ pub struct CreateChannelRequest {
     pub category_id: String,
 }
 
+/// Fields left out are unchanged.
 #[derive(Debug, Deserialize)]
 pub struct UpdateChannelRequest {
-    pub name: String,
+    pub name: Option<String>,
+    /// Seconds between a member's messages, 0 to turn slow mode off
+    pub slowmode_secs: Option<u32>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ReorderEntry {
     pub id: String,
     pub position: i64,
@@ -84,17 +128,25 @@ pub struct CreateCategoryRequest {
     pub name: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateCategoryRequest {
+    pub name: String,
+}
+
 // --- Handlers ---
 
 /// GET /api/channels — List all categories with their channels, ordered by position.
+/// Channels the caller cannot view (per channel overrides) are omitted.
 pub async fn list_channels(
     State(state): State<AppState>,
-    _claims: Claims,
+    claims: Claims,
 ) -> Result<Json<ChannelListResponse>, StatusCode> {
     let db = state.db.clone();
+    let user_id = claims.sub.clone();
+    let is_owner = claims.is_owner;
 
     let result = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         // Fetch categories ordered by position
         let mut cat_stmt = conn
@@ -115,7 +167,7 @@ pub async fn list_channels(
 
         // Fetch all channels ordered by category then position
         let mut ch_stmt = conn
-            .prepare("SELECT id, name, channel_type, category_id, position, topic FROM channels ORDER BY category_id, position ASC")
+            .prepare("SELECT id, name, channel_type, category_id, position, topic, slowmode_secs FROM channels ORDER BY category_id, position ASC")
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         let channels: Vec<ChannelResponse> = ch_stmt
@@ -127,10 +179,16 @@ pub async fn list_channels(
                     category_id: row.get(3)?,
                     position: row.get(4)?,
                     topic: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                    slowmode_secs: row.get(6)?,
                 })
             })
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
             .filter_map(|r| r.ok())
+            .filter(|ch: &ChannelResponse| {
+                compute_channel_permissions(&conn, &user_id, is_owner, &ch.id)
+                    .map(|p| p.contains(Permissions::VIEW_CHANNEL))
+                    .unwrap_or(false)
+            })
             .collect();
 
         // Group channels by category
@@ -146,6 +204,7 @@ pub async fn list_channels(
                     category_id: ch.category_id.clone(),
                     position: ch.position,
                     topic: ch.topic.clone(),
+                    slowmode_secs: ch.slowmode_secs,
                 })
                 .collect();
 
@@ -186,13 +245,20 @@ pub async fn create_channel(
             "Channel name cannot be empty".to_string(),
         ));
     }
+    if ChannelType::parse(&req.channel_type).is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Channel type must be text, voice, announcement or forum".to_string(),
+        ));
+    }
 
     let db = state.db.clone();
     let name = req.name.clone();
     let channel_type = req.channel_type.clone();
     let category_id = req.category_id.clone();
+    let actor_id = claims.sub.clone();
 
-    let channel = tokio::task::spawn_blocking(move || {
+    let (channel, entry) = tokio::task::spawn_blocking(move || {
         let conn = db
             .lock()
             .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
@@ -232,44 +298,48 @@ pub async fn create_channel(
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Insert channel: {}", e)))?;
 
-        Ok::<_, (StatusCode, String)>(ChannelResponse {
+        let channel = ChannelResponse {
             id: channel_id,
             name,
             channel_type,
             category_id,
             position,
             topic: String::new(),
-        })
+            slowmode_secs: 0,
+        };
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::ChannelCreate, &channel.id).after(&channel),
+        )
+        .map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((channel, entry))
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
-    // Broadcast ChannelCreatedEvent
+    // Announce the channel to everyone who can view it
     let event = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::ChannelCreatedEvent(
             proto_channels::ChannelCreatedEvent {
-                channel: Some(proto_channels::Channel {
-                    id: channel.id.clone(),
-                    name: channel.name.clone(),
-                    channel_type: channel.channel_type.clone(),
-                    category_id: channel.category_id.clone(),
-                    position: channel.position,
-                    topic: channel.topic.clone(),
-                }),
+                channel: Some(channel.to_proto()),
             },
         )),
     };
-    broadcast_to_all(&state.connections, &event);
+    scope::publish_channel_change(&state, &channel.id, &[], &event).await;
 
     // Subscribe the server's gossipsub to the new channel topic
-    let topic = gossipsub_topic(&state.server_peer_id, &channel.id);
+    let topic = channel_topic(&state.server_peer_id, &channel.id);
     let _ = state.swarm_cmd_tx.send(SwarmCommand::SubscribeTopic(topic));
+    audit::notify_admins(&state, entry);
 
     Ok((StatusCode::CREATED, Json(channel)))
 }
 
-/// PUT /api/channels/{id} — Rename a channel (requires MANAGE_CHANNELS).
+/// PUT /api/channels/{id} — Rename a channel and/or set its slow mode interval
+/// (requires MANAGE_CHANNELS).
 pub async fn update_channel(
     State(state): State<AppState>,
     claims: Claims,
@@ -285,20 +355,35 @@ pub async fn update_channel(
     .await
     .map_err(|s| (s, "Insufficient permissions".to_string()))?;
 
+    if req.name.is_none() && req.slowmode_secs.is_none() {
+        return Err((StatusCode::BAD_REQUEST, "Nothing to update".to_string()));
+    }
+    if req.slowmode_secs.is_some_and(|s| s > MAX_SLOWMODE_SECS) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Slow mode cannot exceed {} seconds", MAX_SLOWMODE_SECS),
+        ));
+    }
+
     let db = state.db.clone();
     let cid = channel_id.clone();
     let name = req.name.clone();
+    let slowmode_secs = req.slowmode_secs;
+    let actor_id = claims.sub.clone();
 
-    let channel = tokio::task::spawn_blocking(move || {
+    let (channel, entry) = tokio::task::spawn_blocking(move || {
         let conn = db
             .lock()
             .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
 
-        // Update name
+        let before = load_channel(&conn, &cid)
+            .map_err(|_| (StatusCode::NOT_FOUND, "Channel not found".to_string()))?;
+
         let rows = conn
             .execute(
-                "UPDATE channels SET name = ?1 WHERE id = ?2",
-                rusqlite::params![name, cid],
+                "UPDATE channels SET name = COALESCE(?1, name), slowmode_secs = COALESCE(?2, slowmode_secs)
+                 WHERE id = ?3",
+                rusqlite::params![name, slowmode_secs, cid],
             )
             .map_err(|e| {
                 (
@@ -312,42 +397,34 @@ pub async fn update_channel(
         }
 
         // Read back
-        conn.query_row(
-            "SELECT id, name, channel_type, category_id, position, topic FROM channels WHERE id = ?1",
-            [&cid],
-            |row| {
-                Ok(ChannelResponse {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    channel_type: row.get(2)?,
-                    category_id: row.get(3)?,
-                    position: row.get(4)?,
-                    topic: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
-                })
-            },
+        let channel = load_channel(&conn, &cid)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Read channel: {}", e)))?;
+
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::ChannelUpdate, &cid)
+                .before(&before)
+                .after(&channel),
         )
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Read channel: {}", e)))
+        .map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((channel, entry))
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
-    // Broadcast ChannelUpdatedEvent
+    // Publish ChannelUpdatedEvent to the channel's viewers
     let event = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::ChannelUpdatedEvent(
             proto_channels::ChannelUpdatedEvent {
-                channel: Some(proto_channels::Channel {
-                    id: channel.id.clone(),
-                    name: channel.name.clone(),
-                    channel_type: channel.channel_type.clone(),
-                    category_id: channel.category_id.clone(),
-                    position: channel.position,
-                    topic: channel.topic.clone(),
-                }),
+                channel: Some(channel.to_proto()),
             },
         )),
     };
-    broadcast_to_all(&state.connections, &event);
+    publish(&state.connections, EventScope::Channel(&channel.id), &event);
+    audit::notify_admins(&state, entry);
 
     Ok(Json(channel))
 }
@@ -367,14 +444,21 @@ pub async fn delete_channel(
     .await
     .map_err(|s| (s, "Insufficient permissions".to_string()))?;
 
+    // Viewers of the channel, captured while they can still see it
+    let audience = scope::channel_audience(&state, &channel_id);
+
     let db = state.db.clone();
     let cid = channel_id.clone();
+    let actor_id = claims.sub.clone();
 
-    tokio::task::spawn_blocking(move || {
+    let entry = tokio::task::spawn_blocking(move || {
         let conn = db
             .lock()
             .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
 
+        let before = load_channel(&conn, &cid)
+            .map_err(|_| (StatusCode::NOT_FOUND, "Channel not found".to_string()))?;
+
         let rows = conn
             .execute("DELETE FROM channels WHERE id = ?1", [&cid])
             .map_err(|e| {
@@ -388,25 +472,31 @@ pub async fn delete_channel(
             return Err((StatusCode::NOT_FOUND, "Channel not found".to_string()));
         }
 
-        Ok::<_, (StatusCode, String)>(())
+        audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::ChannelDelete, &cid).before(&before),
+        )
+        .map_err(audit::audit_error)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
-    // Broadcast ChannelDeletedEvent
+    // Publish ChannelDeletedEvent to the users who could view the channel
     let event = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::ChannelDeletedEvent(
             proto_channels::ChannelDeletedEvent {
                 channel_id: channel_id.clone(),
             },
         )),
     };
-    broadcast_to_all(&state.connections, &event);
+    scope::publish_channel_change(&state, &channel_id, &audience, &event).await;
 
     // Unsubscribe the server's gossipsub from the deleted channel topic
-    let topic = gossipsub_topic(&state.server_peer_id, &channel_id);
+    let topic = channel_topic(&state.server_peer_id, &channel_id);
     let _ = state.swarm_cmd_tx.send(SwarmCommand::UnsubscribeTopic(topic));
+    audit::notify_admins(&state, entry);
 
     Ok(StatusCode::OK)
 }
@@ -427,8 +517,9 @@ pub async fn reorder_channels(
     .map_err(|s| (s, "Insufficient permissions".to_string()))?;
 
     let db = state.db.clone();
+    let actor_id = claims.sub.clone();
 
-    tokio::task::spawn_blocking(move || {
+    let entry = tokio::task::spawn_blocking(move || {
         let conn = db
             .lock()
             .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
@@ -446,11 +537,17 @@ pub async fn reorder_channels(
             })?;
         }
 
-        Ok::<_, (StatusCode, String)>(())
+        audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::ChannelReorder, "").after(&req.entries),
+        )
+        .map_err(audit::audit_error)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
+    audit::notify_admins(&state, entry);
+
     Ok(StatusCode::OK)
 }
 
@@ -478,8 +575,9 @@ pub async fn create_category(
 
     let db = state.db.clone();
     let name = req.name.clone();
+    let actor_id = claims.sub.clone();
 
-    let category = tokio::task::spawn_blocking(move || {
+    let (category, entry) = tokio::task::spawn_blocking(move || {
         let conn = db
             .lock()
             .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
@@ -507,18 +605,26 @@ pub async fn create_category(
             )
         })?;
 
-        Ok::<_, (StatusCode, String)>(CategoryResponse {
+        let category = CategoryResponse {
             id: cat_id,
             name,
             position,
-        })
+        };
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::CategoryCreate, &category.id).after(&category),
+        )
+        .map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((category, entry))
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
-    // Broadcast CategoryCreatedEvent
+    // Broadcast CategoryCreatedEvent; every member sees the category list
     let event = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::CategoryCreatedEvent(
             proto_channels::CategoryCreatedEvent {
                 category: Some(proto_channels::Category {
@@ -530,10 +636,149 @@ pub async fn create_category(
         )),
     };
     broadcast_to_all(&state.connections, &event);
+    audit::notify_admins(&state, entry);
 
     Ok((StatusCode::CREATED, Json(category)))
 }
 
+/// PUT /api/categories/{id} — Rename a category (requires MANAGE_CHANNELS).
+pub async fn update_category(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(category_id): Path<String>,
+    Json(req): Json<UpdateCategoryRequest>,
+) -> Result<Json<CategoryResponse>, (StatusCode, String)> {
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        Permissions::MANAGE_CHANNELS,
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    if req.name.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Category name cannot be empty".to_string(),
+        ));
+    }
+
+    let db = state.db.clone();
+    let cid = category_id.clone();
+    let name = req.name.clone();
+    let actor_id = claims.sub.clone();
+
+    let (category, entry) = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        let before = load_category(&conn, &cid)
+            .map_err(|_| (StatusCode::NOT_FOUND, "Category not found".to_string()))?;
+
+        let rows = conn
+            .execute(
+                "UPDATE categories SET name = ?1 WHERE id = ?2",
+                rusqlite::params![name, cid],
+            )
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Update category: {}", e),
+                )
+            })?;
+
+        if rows == 0 {
+            return Err((StatusCode::NOT_FOUND, "Category not found".to_string()));
+        }
+
+        let category = load_category(&conn, &cid)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Read category: {}", e)))?;
+
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::CategoryUpdate, &cid)
+                .before(&before)
+                .after(&category),
+        )
+        .map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((category, entry))
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    // Broadcast CategoryUpdatedEvent; every member sees the category list
+    let event = Envelope {
+        request_id: String::new(),
+        seq: 0,
+        payload: Some(Payload::CategoryUpdatedEvent(
+            proto_channels::CategoryUpdatedEvent {
+                category: Some(proto_channels::Category {
+                    id: category.id.clone(),
+                    name: category.name.clone(),
+                    position: category.position,
+                }),
+            },
+        )),
+    };
+    broadcast_to_all(&state.connections, &event);
+    audit::notify_admins(&state, entry);
+
+    Ok(Json(category))
+}
+
+/// PUT /api/categories/reorder — Update category positions (requires MANAGE_CHANNELS).
+pub async fn reorder_categories(
+    State(state): State<AppState>,
+    claims: Claims,
+    Json(req): Json<ReorderRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        Permissions::MANAGE_CHANNELS,
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let db = state.db.clone();
+    let actor_id = claims.sub.clone();
+
+    let entry = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        for entry in &req.entries {
+            conn.execute(
+                "UPDATE categories SET position = ?1 WHERE id = ?2",
+                rusqlite::params![entry.position, entry.id],
+            )
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Reorder category: {}", e),
+                )
+            })?;
+        }
+
+        audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::CategoryReorder, "").after(&req.entries),
+        )
+        .map_err(audit::audit_error)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    audit::notify_admins(&state, entry);
+
+    Ok(StatusCode::OK)
+}
+
 /// DELETE /api/categories/{id} — Delete a category (requires MANAGE_CHANNELS).
 /// Fails with 400 if the category still has channels.
 pub async fn delete_category(
@@ -552,12 +797,16 @@ pub async fn delete_category(
 
     let db = state.db.clone();
     let cid = category_id.clone();
+    let actor_id = claims.sub.clone();
 
-    tokio::task::spawn_blocking(move || {
+    let entry = tokio::task::spawn_blocking(move || {
         let conn = db
             .lock()
             .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
 
+        let before = load_category(&conn, &cid)
+            .map_err(|_| (StatusCode::NOT_FOUND, "Category not found".to_string()))?;
+
         // Check if category has channels
         let channel_count: i64 = conn
             .query_row(
@@ -587,14 +836,19 @@ pub async fn delete_category(
             return Err((StatusCode::NOT_FOUND, "Category not found".to_string()));
         }
 
-        Ok::<_, (StatusCode, String)>(())
+        audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::CategoryDelete, &cid).before(&before),
+        )
+        .map_err(audit::audit_error)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
-    // Broadcast CategoryDeletedEvent
+    // Broadcast CategoryDeletedEvent; every member sees the category list
     let event = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::CategoryDeletedEvent(
             proto_channels::CategoryDeletedEvent {
                 category_id: category_id.clone(),
@@ -602,6 +856,41 @@ pub async fn delete_category(
         )),
     };
     broadcast_to_all(&state.connections, &event);
+    audit::notify_admins(&state, entry);
 
     Ok(StatusCode::OK)
 }
+
+// --- Helpers ---
+
+fn load_channel(conn: &rusqlite::Connection, channel_id: &str) -> rusqlite::Result<ChannelResponse> {
+    conn.query_row(
+        "SELECT id, name, channel_type, category_id, position, topic, slowmode_secs FROM channels WHERE id = ?1",
+        [channel_id],
+        |row| {
+            Ok(ChannelResponse {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                channel_type: row.get(2)?,
+                category_id: row.get(3)?,
+                position: row.get(4)?,
+                topic: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                slowmode_secs: row.get(6)?,
+            })
+        },
+    )
+}
+
+fn load_category(conn: &rusqlite::Connection, category_id: &str) -> rusqlite::Result<CategoryResponse> {
+    conn.query_row(
+        "SELECT id, name, position FROM categories WHERE id = ?1",
+        [category_id],
+        |row| {
+            Ok(CategoryResponse {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                position: row.get(2)?,
+            })
+        },
+    )
+}
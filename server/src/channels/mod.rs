@@ -1,3 +1,6 @@
 pub mod crud;
 pub mod ordering;
+pub mod overrides;
 pub mod seed;
+pub mod slowmode;
+pub mod types;
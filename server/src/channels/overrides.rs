@@ -0,0 +1,387 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::middleware::Claims;
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::proto::channels as proto_channels;
+use crate::proto::ws::{envelope::Payload, Envelope};
+use crate::roles::hierarchy::{require_outranks, require_role_below};
+use crate::roles::permissions::{compute_channel_permissions, require_permission, Permissions};
+use crate::state::AppState;
+use crate::ws::scope;
+
+// --- Request/Response types ---
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OverrideResponse {
+    pub channel_id: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub allow: u32,
+    pub deny: u32,
+}
+
+impl OverrideResponse {
+    /// Convert to the protobuf PermissionOverride used in events and WS responses.
+    pub fn to_proto(&self) -> proto_channels::PermissionOverride {
+        proto_channels::PermissionOverride {
+            channel_id: self.channel_id.clone(),
+            target_type: self.target_type.clone(),
+            target_id: self.target_id.clone(),
+            allow: self.allow,
+            deny: self.deny,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetOverrideRequest {
+    #[serde(default)]
+    pub allow: u32,
+    #[serde(default)]
+    pub deny: u32,
+}
+
+// --- Handlers ---
+
+/// GET /api/channels/{id}/overrides — List permission overrides for a channel (requires MANAGE_CHANNELS).
+pub async fn list_overrides(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(channel_id): Path<String>,
+) -> Result<Json<Vec<OverrideResponse>>, (StatusCode, String)> {
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        Permissions::MANAGE_CHANNELS,
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let db = state.db.clone();
+
+    let overrides = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .read()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        require_channel_exists(&conn, &channel_id)?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT channel_id, target_type, target_id, allow, deny
+                 FROM channel_permission_overrides
+                 WHERE channel_id = ?1
+                 ORDER BY target_type, target_id",
+            )
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query: {}", e)))?;
+
+        let overrides: Vec<OverrideResponse> = stmt
+            .query_map([&channel_id], |row| {
+                Ok(OverrideResponse {
+                    channel_id: row.get(0)?,
+                    target_type: row.get(1)?,
+                    target_id: row.get(2)?,
+                    allow: row.get(3)?,
+                    deny: row.get(4)?,
+                })
+            })
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query: {}", e)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok::<_, (StatusCode, String)>(overrides)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    Ok(Json(overrides))
+}
+
+/// PUT /api/channels/{id}/overrides/{target_type}/{target_id} — Create or replace a
+/// role or user override (requires MANAGE_CHANNELS, every allowed or denied bit in the
+/// channel, and a target ranked below the caller).
+pub async fn set_override(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path((channel_id, target_type, target_id)): Path<(String, String, String)>,
+    Json(req): Json<SetOverrideRequest>,
+) -> Result<Json<OverrideResponse>, (StatusCode, String)> {
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        Permissions::MANAGE_CHANNELS,
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    validate_target_type(&target_type)?;
+    validate_override_bits(req.allow, req.deny)?;
+
+    // Viewers of the channel, captured before the override can revoke their access
+    let audience = scope::channel_audience(&state, &channel_id);
+
+    let db = state.db.clone();
+    let actor_id = claims.sub.clone();
+    let actor_is_owner = claims.is_owner;
+    let response = OverrideResponse {
+        channel_id,
+        target_type,
+        target_id,
+        allow: req.allow,
+        deny: req.deny,
+    };
+
+    let (response, entry) = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        require_channel_exists(&conn, &response.channel_id)?;
+        require_override_authority(&conn, &actor_id, actor_is_owner, &response)?;
+
+        let before = load_override(
+            &conn,
+            &response.channel_id,
+            &response.target_type,
+            &response.target_id,
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query: {}", e)))?;
+
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO channel_permission_overrides (channel_id, target_type, target_id, allow, deny, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (channel_id, target_type, target_id)
+             DO UPDATE SET allow = excluded.allow, deny = excluded.deny, updated_at = excluded.updated_at",
+            rusqlite::params![
+                response.channel_id,
+                response.target_type,
+                response.target_id,
+                response.allow,
+                response.deny,
+                now
+            ],
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Upsert override: {}", e)))?;
+
+        let mut record =
+            AuditRecord::new(&actor_id, AuditAction::ChannelOverrideUpdate, &response.channel_id)
+                .after(&response);
+        if let Some(before) = before {
+            record = record.before(before);
+        }
+        let entry = audit::record(&conn, record).map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((response, entry))
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    // Publish ChannelOverrideUpdatedEvent to the channel's viewers from before and after the change
+    let event = Envelope {
+        request_id: String::new(),
+        seq: 0,
+        payload: Some(Payload::ChannelOverrideUpdatedEvent(
+            proto_channels::ChannelOverrideUpdatedEvent {
+                permission_override: Some(response.to_proto()),
+            },
+        )),
+    };
+    scope::publish_channel_change(&state, &response.channel_id, &audience, &event).await;
+    audit::notify_admins(&state, entry);
+
+    Ok(Json(response))
+}
+
+/// DELETE /api/channels/{id}/overrides/{target_type}/{target_id} — Remove an override
+/// (requires the same authority over it as setting it).
+pub async fn delete_override(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path((channel_id, target_type, target_id)): Path<(String, String, String)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        Permissions::MANAGE_CHANNELS,
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    validate_target_type(&target_type)?;
+
+    // Viewers of the channel, captured before removing the override can revoke their access
+    let audience = scope::channel_audience(&state, &channel_id);
+
+    let db = state.db.clone();
+    let cid = channel_id.clone();
+    let ttype = target_type.clone();
+    let tid = target_id.clone();
+    let actor_id = claims.sub.clone();
+    let actor_is_owner = claims.is_owner;
+
+    let entry = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        let before = load_override(&conn, &cid, &ttype, &tid)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query: {}", e)))?
+            .ok_or((StatusCode::NOT_FOUND, "Override not found".to_string()))?;
+        require_override_authority(&conn, &actor_id, actor_is_owner, &before)?;
+
+        let rows = conn
+            .execute(
+                "DELETE FROM channel_permission_overrides
+                 WHERE channel_id = ?1 AND target_type = ?2 AND target_id = ?3",
+                rusqlite::params![cid, ttype, tid],
+            )
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Delete override: {}", e)))?;
+
+        if rows == 0 {
+            return Err((StatusCode::NOT_FOUND, "Override not found".to_string()));
+        }
+
+        audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::ChannelOverrideDelete, &cid).before(&before),
+        )
+        .map_err(audit::audit_error)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    // Publish ChannelOverrideDeletedEvent to the channel's viewers from before and after the change
+    let event = Envelope {
+        request_id: String::new(),
+        seq: 0,
+        payload: Some(Payload::ChannelOverrideDeletedEvent(
+            proto_channels::ChannelOverrideDeletedEvent {
+                channel_id: channel_id.clone(),
+                target_type,
+                target_id,
+            },
+        )),
+    };
+    scope::publish_channel_change(&state, &channel_id, &audience, &event).await;
+    audit::notify_admins(&state, entry);
+
+    Ok(StatusCode::OK)
+}
+
+// --- Helpers ---
+
+fn validate_target_type(target_type: &str) -> Result<(), (StatusCode, String)> {
+    match target_type {
+        "role" | "user" => Ok(()),
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            "target_type must be 'role' or 'user'".to_string(),
+        )),
+    }
+}
+
+/// Overrides may only carry known, channel-scoped bits (see `Permissions::CHANNEL_SCOPED`),
+/// and a bit cannot be both allowed and denied.
+fn validate_override_bits(allow: u32, deny: u32) -> Result<(), (StatusCode, String)> {
+    for bits in [allow, deny] {
+        match Permissions::from_bits(bits) {
+            Some(p) if Permissions::CHANNEL_SCOPED.contains(p) => {}
+            _ => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "Invalid permission bits for a channel override".to_string(),
+                ))
+            }
+        }
+    }
+    if allow & deny != 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "A permission cannot be both allowed and denied".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Require the actor to hold every bit the override allows or denies in its channel, and
+/// to rank above its target role or user (see `roles::hierarchy`). Otherwise a channel
+/// manager could grant themselves or a peer permissions they lack, or strip a superior's.
+fn require_override_authority(
+    conn: &rusqlite::Connection,
+    actor_id: &str,
+    actor_is_owner: bool,
+    over: &OverrideResponse,
+) -> Result<(), (StatusCode, String)> {
+    if over.target_type == "role" {
+        let position: i64 = conn
+            .query_row("SELECT position FROM roles WHERE id = ?1", [&over.target_id], |row| row.get(0))
+            .map_err(|_| (StatusCode::NOT_FOUND, "Role not found".to_string()))?;
+        require_role_below(conn, actor_id, actor_is_owner, position)?;
+    } else if over.target_id != actor_id {
+        require_outranks(conn, actor_id, actor_is_owner, &over.target_id)?;
+    }
+
+    let held = compute_channel_permissions(conn, actor_id, actor_is_owner, &over.channel_id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Permissions: {}", e)))?;
+    if held.contains(Permissions::from_bits_truncate(over.allow | over.deny)) {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::FORBIDDEN,
+            "Cannot grant or deny permissions you do not have".to_string(),
+        ))
+    }
+}
+
+fn load_override(
+    conn: &rusqlite::Connection,
+    channel_id: &str,
+    target_type: &str,
+    target_id: &str,
+) -> rusqlite::Result<Option<OverrideResponse>> {
+    conn.query_row(
+        "SELECT channel_id, target_type, target_id, allow, deny
+         FROM channel_permission_overrides
+         WHERE channel_id = ?1 AND target_type = ?2 AND target_id = ?3",
+        rusqlite::params![channel_id, target_type, target_id],
+        |row| {
+            Ok(OverrideResponse {
+                channel_id: row.get(0)?,
+                target_type: row.get(1)?,
+                target_id: row.get(2)?,
+                allow: row.get(3)?,
+                deny: row.get(4)?,
+            })
+        },
+    )
+    .optional()
+}
+
+fn require_channel_exists(
+    conn: &rusqlite::Connection,
+    channel_id: &str,
+) -> Result<(), (StatusCode, String)> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT COUNT(*) FROM channels WHERE id = ?1",
+            [channel_id],
+            |row| row.get::<_, i64>(0).map(|c| c > 0),
+        )
+        .unwrap_or(false);
+    if exists {
+        Ok(())
+    } else {
+        Err((StatusCode::NOT_FOUND, "Channel not found".to_string()))
+    }
+}
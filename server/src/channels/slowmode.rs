@@ -0,0 +1,92 @@
+//! Per-channel slow mode: a minimum interval between a member's messages in a channel.
+//!
+//! Applies to channel messages posted over REST, WS and gossip. Thread posts inherit the
+//! parent channel's interval, counted per thread. Members with MANAGE_MESSAGES in the
+//! channel are exempt. Refusals over REST are 429 with a stable
+//! `slowmode:` prefix and the remaining cooldown in the message.
+
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use rusqlite::OptionalExtension;
+
+use crate::proto::p2p_proto::MessageType;
+use crate::roles::permissions::Permissions;
+
+/// Longest configurable slow mode interval (6 hours).
+pub const MAX_SLOWMODE_SECS: u32 = 6 * 60 * 60;
+
+/// Seconds until `sender_pubkey` (lowercase hex) may post in the channel, or in
+/// `thread_id` when set, again. None if slow mode is off, the sender is exempt, or their
+/// cooldown has passed.
+pub fn cooldown_remaining(
+    conn: &rusqlite::Connection,
+    channel_id: &str,
+    thread_id: Option<&str>,
+    sender_pubkey: &str,
+    perms: Permissions,
+) -> rusqlite::Result<Option<u64>> {
+    if perms.contains(Permissions::MANAGE_MESSAGES) {
+        return Ok(None);
+    }
+    let slowmode_secs: u32 = conn
+        .query_row(
+            "SELECT slowmode_secs FROM channels WHERE id = ?1",
+            [channel_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .unwrap_or(0);
+    if slowmode_secs == 0 {
+        return Ok(None);
+    }
+
+    // Deleted messages still count, so deleting a message does not reset the cooldown
+    let last: Option<String> = conn.query_row(
+        "SELECT MAX(created_at) FROM messages
+         WHERE channel_id = ?1 AND sender_pubkey = ?2 AND thread_id IS ?3 AND message_type = ?4",
+        rusqlite::params![channel_id, sender_pubkey, thread_id, MessageType::Chat as i32],
+        |row| row.get(0),
+    )?;
+    let Some(last) = last.and_then(|t| DateTime::parse_from_rfc3339(&t).ok()) else {
+        return Ok(None);
+    };
+
+    let elapsed_ms = (Utc::now() - last.with_timezone(&Utc)).num_milliseconds();
+    let remaining_ms = slowmode_secs as i64 * 1000 - elapsed_ms;
+    Ok((remaining_ms > 0).then(|| (remaining_ms as u64).div_ceil(1000)))
+}
+
+/// Refuse a new channel or thread message from `user_id` while their slow mode
+/// cooldown runs.
+pub fn check_slowmode(
+    conn: &rusqlite::Connection,
+    channel_id: &str,
+    thread_id: Option<&str>,
+    user_id: &str,
+    perms: Permissions,
+) -> Result<(), (StatusCode, String)> {
+    let db_err = |e: rusqlite::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Slow mode check: {}", e),
+        )
+    };
+    let sender_pubkey: String = conn
+        .query_row(
+            "SELECT lower(hex(public_key)) FROM users WHERE id = ?1",
+            [user_id],
+            |row| row.get(0),
+        )
+        .map_err(db_err)?;
+
+    match cooldown_remaining(conn, channel_id, thread_id, &sender_pubkey, perms).map_err(db_err)? {
+        Some(remaining) => Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            format!(
+                "slowmode: Slow mode is on in this channel; try again in {} seconds",
+                remaining
+            ),
+        )),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,66 @@
+//! Channel types and the posting rules that go with them.
+//!
+//! - `text`: ordinary chat.
+//! - `voice`: voice channel.
+//! - `announcement`: everyone can read, react and discuss in threads, but only members
+//!   with MANAGE_MESSAGES in the channel can post top-level messages.
+//! - `forum`: every top-level post starts a titled thread; replies go in the thread.
+
+use rusqlite::OptionalExtension;
+
+use crate::roles::permissions::Permissions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelType {
+    Text,
+    Voice,
+    Announcement,
+    Forum,
+}
+
+impl ChannelType {
+    pub const ALL: [ChannelType; 4] = [
+        ChannelType::Text,
+        ChannelType::Voice,
+        ChannelType::Announcement,
+        ChannelType::Forum,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Voice => "voice",
+            Self::Announcement => "announcement",
+            Self::Forum => "forum",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == s)
+    }
+
+    /// The type of a channel, or None if it does not exist.
+    pub fn of(conn: &rusqlite::Connection, channel_id: &str) -> rusqlite::Result<Option<Self>> {
+        let channel_type: Option<String> = conn
+            .query_row(
+                "SELECT channel_type FROM channels WHERE id = ?1",
+                [channel_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(channel_type.as_deref().and_then(Self::parse))
+    }
+
+    /// Whether the channel carries chat messages.
+    pub fn has_messages(self) -> bool {
+        self != Self::Voice
+    }
+
+    /// Whether a member with `perms` may post top-level messages (outside threads).
+    pub fn allows_top_level_post(self, perms: Permissions) -> bool {
+        match self {
+            Self::Announcement => perms.contains(Permissions::MANAGE_MESSAGES),
+            _ => true,
+        }
+    }
+}
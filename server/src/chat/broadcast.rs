@@ -1,85 +1,103 @@
 //! WebSocket broadcast helpers for chat events.
-//! Wraps chat proto messages in Envelope and broadcasts to all connected WS clients.
+//! Wraps chat proto messages in Envelope and publishes them to the WS clients that can
+//! see the channel (presence goes to everyone).
 
 use crate::proto::chat as proto_chat;
 use crate::proto::presence as proto_presence;
 use crate::proto::ws::{envelope::Payload, Envelope};
-use crate::ws::broadcast::broadcast_to_all;
+use crate::ws::broadcast::{broadcast_to_all, publish};
+use crate::ws::scope::EventScope;
 use crate::ws::ConnectionRegistry;
 
-/// Broadcast a NewMessageEvent to all connected WS clients.
+/// Broadcast a NewMessageEvent to WS clients that can see the channel.
 pub fn broadcast_new_message(
     registry: &ConnectionRegistry,
     chat_message: proto_chat::ChatMessage,
 ) {
+    let channel_id = chat_message.channel_id.clone();
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::NewMessageEvent(proto_chat::NewMessageEvent {
             message: Some(chat_message),
         })),
     };
-    broadcast_to_all(registry, &envelope);
+    publish(registry, EventScope::Channel(&channel_id), &envelope);
 }
 
-/// Broadcast a MessageEditedEvent to all connected WS clients.
+/// Broadcast a MessageEditedEvent to WS clients that can see the channel.
 pub fn broadcast_message_edited(
     registry: &ConnectionRegistry,
     event: proto_chat::MessageEditedEvent,
 ) {
+    let channel_id = event.channel_id.clone();
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::MessageEditedEvent(event)),
     };
-    broadcast_to_all(registry, &envelope);
+    publish(registry, EventScope::Channel(&channel_id), &envelope);
 }
 
-/// Broadcast a MessageDeletedEvent to all connected WS clients.
+/// Broadcast a MessageDeletedEvent to WS clients that can see the channel.
 pub fn broadcast_message_deleted(
     registry: &ConnectionRegistry,
     event: proto_chat::MessageDeletedEvent,
 ) {
+    let channel_id = event.channel_id.clone();
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::MessageDeletedEvent(event)),
     };
-    broadcast_to_all(registry, &envelope);
+    publish(registry, EventScope::Channel(&channel_id), &envelope);
 }
 
-/// Broadcast a ReactionAddedEvent to all connected WS clients.
+/// Broadcast a ReactionAddedEvent to WS clients that can see the message's channel.
 pub fn broadcast_reaction_added(
     registry: &ConnectionRegistry,
+    channel_id: &str,
     event: proto_chat::ReactionAddedEvent,
 ) {
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::ReactionAddedEvent(event)),
     };
-    broadcast_to_all(registry, &envelope);
+    publish(registry, EventScope::Channel(channel_id), &envelope);
 }
 
-/// Broadcast a ReactionRemovedEvent to all connected WS clients.
+/// Broadcast a ReactionRemovedEvent to WS clients that can see the message's channel.
 pub fn broadcast_reaction_removed(
     registry: &ConnectionRegistry,
+    channel_id: &str,
     event: proto_chat::ReactionRemovedEvent,
 ) {
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::ReactionRemovedEvent(event)),
     };
-    broadcast_to_all(registry, &envelope);
+    publish(registry, EventScope::Channel(channel_id), &envelope);
 }
 
-/// Broadcast a TypingEvent to all connected WS clients.
+/// Broadcast a TypingEvent to WS clients that can see the channel.
 #[allow(dead_code)]
 pub fn broadcast_typing(
     registry: &ConnectionRegistry,
     event: proto_presence::TypingEvent,
 ) {
+    let channel_id = event
+        .indicator
+        .as_ref()
+        .map(|i| i.channel_id.clone())
+        .unwrap_or_default();
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::TypingEvent(event)),
     };
-    broadcast_to_all(registry, &envelope);
+    publish(registry, EventScope::Channel(&channel_id), &envelope);
 }
 
 /// Broadcast a PresenceUpdateEvent to all connected WS clients.
@@ -93,6 +111,7 @@ pub fn broadcast_presence_update(
 ) {
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::PresenceUpdateEvent(proto_presence::PresenceUpdateEvent {
             update: Some(proto_presence::PresenceUpdate {
                 user_pubkey: user_pubkey.to_string(),
@@ -105,7 +124,7 @@ pub fn broadcast_presence_update(
     broadcast_to_all(registry, &envelope);
 }
 
-/// Broadcast a TypingEvent (typing indicator) to all connected WS clients.
+/// Broadcast a TypingEvent (typing indicator) to WS clients that can see the channel.
 /// Called from the presence REST endpoint.
 pub fn broadcast_typing_indicator(
     registry: &ConnectionRegistry,
@@ -116,6 +135,7 @@ pub fn broadcast_typing_indicator(
 ) {
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::TypingEvent(proto_presence::TypingEvent {
             indicator: Some(proto_presence::TypingIndicator {
                 user_pubkey: user_pubkey.to_string(),
@@ -125,5 +145,59 @@ pub fn broadcast_typing_indicator(
             }),
         })),
     };
-    broadcast_to_all(registry, &envelope);
+    publish(registry, EventScope::Channel(channel_id), &envelope);
+}
+
+/// Broadcast a ThreadCreatedEvent to WS clients that can see the thread's channel.
+pub fn broadcast_thread_created(registry: &ConnectionRegistry, thread: proto_chat::Thread) {
+    let channel_id = thread.channel_id.clone();
+    let envelope = Envelope {
+        request_id: String::new(),
+        seq: 0,
+        payload: Some(Payload::ThreadCreatedEvent(proto_chat::ThreadCreatedEvent {
+            thread: Some(thread),
+        })),
+    };
+    publish(registry, EventScope::Channel(&channel_id), &envelope);
+}
+
+/// Broadcast a ThreadUpdatedEvent to WS clients that can see the thread's channel.
+pub fn broadcast_thread_updated(registry: &ConnectionRegistry, thread: proto_chat::Thread) {
+    let channel_id = thread.channel_id.clone();
+    let envelope = Envelope {
+        request_id: String::new(),
+        seq: 0,
+        payload: Some(Payload::ThreadUpdatedEvent(proto_chat::ThreadUpdatedEvent {
+            thread: Some(thread),
+        })),
+    };
+    publish(registry, EventScope::Channel(&channel_id), &envelope);
+}
+
+/// Broadcast a MessagePinnedEvent to WS clients that can see the channel.
+pub fn broadcast_message_pinned(
+    registry: &ConnectionRegistry,
+    event: proto_chat::MessagePinnedEvent,
+) {
+    let channel_id = event.channel_id.clone();
+    let envelope = Envelope {
+        request_id: String::new(),
+        seq: 0,
+        payload: Some(Payload::MessagePinnedEvent(event)),
+    };
+    publish(registry, EventScope::Channel(&channel_id), &envelope);
+}
+
+/// Broadcast a MessageUnpinnedEvent to WS clients that can see the channel.
+pub fn broadcast_message_unpinned(
+    registry: &ConnectionRegistry,
+    event: proto_chat::MessageUnpinnedEvent,
+) {
+    let channel_id = event.channel_id.clone();
+    let envelope = Envelope {
+        request_id: String::new(),
+        seq: 0,
+        payload: Some(Payload::MessageUnpinnedEvent(event)),
+    };
+    publish(registry, EventScope::Channel(&channel_id), &envelope);
 }
@@ -7,12 +7,25 @@ use axum::{
 };
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use rusqlite::OptionalExtension;
 use std::time::{SystemTime, UNIX_EPOCH};
 use base64::Engine as _;
 use crate::auth::middleware::Claims;
+use crate::channels::slowmode;
+use crate::channels::types::ChannelType;
 use crate::chat::broadcast;
+use crate::chat::revisions;
+use crate::chat::threads::{self, ThreadResponse};
+use crate::db::sequences::next_channel_sequence;
+use crate::moderation::abuse::ActivityEvent;
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::moderation::sanctions::{active_mute, SanctionKind};
+use crate::moderation::trust;
+use crate::p2p::publish;
 use crate::proto::blocks as proto_blocks;
 use crate::proto::chat as proto_chat;
+use crate::proto::p2p_proto::MessageType;
+use crate::roles::permissions::{check_channel_permission, require_channel_permission, Permissions};
 use crate::state::AppState;
 
 /// Maximum message content length (chars).
@@ -29,6 +42,9 @@ pub struct CreateMessageRequest {
     pub content: String,
     pub reply_to_id: Option<String>,
     pub block_refs_json: Option<String>,
+    /// Thread title, required for top-level posts in forum channels
+    #[serde(default)]
+    pub title: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -44,6 +60,32 @@ pub struct MessageResponse {
     pub edited: bool,
     pub reactions: Vec<ReactionGroup>,
     pub block_refs_json: Option<String>,
+    pub thread_id: Option<String>,
+    /// The thread this message started (forum posts)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_thread: Option<ThreadResponse>,
+}
+
+impl MessageResponse {
+    /// Convert to the protobuf ChatMessage used in events and WS history responses.
+    pub fn to_proto(&self) -> proto_chat::ChatMessage {
+        proto_chat::ChatMessage {
+            id: self.id.clone(),
+            channel_id: self.channel_id.clone(),
+            sender_pubkey: self.sender_pubkey.clone(),
+            sender_display_name: self.sender_display_name.clone(),
+            content: self.content.clone(),
+            timestamp: self.timestamp,
+            server_sequence: self.server_sequence,
+            signature: vec![],
+            reply_to_id: self.reply_to_id.clone(),
+            edited: self.edited,
+            mention_user_ids: parse_user_mentions(&self.content),
+            mention_role_ids: parse_role_mentions(&self.content),
+            block_refs: parse_block_refs_json(&self.block_refs_json),
+            thread_id: self.thread_id.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -84,134 +126,115 @@ pub struct LastReadResponse {
 
 /// POST /api/channels/{channel_id}/messages
 /// Create a new message via REST. JWT auth required.
+/// Announcement channels only accept posts from MANAGE_MESSAGES holders; forum posts
+/// need a `title` and start a thread of that name.
 pub async fn create_message(
     State(state): State<AppState>,
     claims: Claims,
     Path(channel_id): Path<String>,
-    Json(body): Json<CreateMessageRequest>,
-) -> Result<(StatusCode, Json<MessageResponse>), StatusCode> {
-    // Validate content
-    let content = body.content.trim().to_string();
-    if content.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-    if content.len() > MAX_CONTENT_LENGTH {
-        return Err(StatusCode::PAYLOAD_TOO_LARGE);
-    }
+    Json(mut body): Json<CreateMessageRequest>,
+) -> Result<(StatusCode, Json<MessageResponse>), (StatusCode, String)> {
+    body.content = validate_content(&body.content).map_err(|s| (s, String::new()))?;
+    trust::require_capabilities(
+        &state,
+        &claims.sub,
+        claims.is_owner,
+        &message_capabilities(&body),
+    )
+    .await?;
 
     let db = state.db.clone();
     let user_id = claims.sub.clone();
-    let reply_to_id = body.reply_to_id.clone();
-    let block_refs_json = body.block_refs_json.clone();
+    let is_owner = claims.is_owner;
     let cid = channel_id.clone();
 
     let result = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-        // Verify channel exists
-        let channel_exists: bool = conn
-            .query_row(
-                "SELECT COUNT(*) FROM channels WHERE id = ?1",
-                rusqlite::params![cid],
-                |row| row.get::<_, i64>(0).map(|c| c > 0),
-            )
-            .unwrap_or(false);
-        if !channel_exists {
-            return Err(StatusCode::NOT_FOUND);
-        }
-
-        // Look up sender's display_name and public_key
-        let (display_name, pubkey_hex): (String, String) = conn
-            .query_row(
-                "SELECT display_name, hex(public_key) FROM users WHERE id = ?1",
-                rusqlite::params![user_id],
-                |row| Ok((row.get(0)?, row.get(1)?)),
-            )
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-        let sender_pubkey = pubkey_hex.to_lowercase();
-
-        // Assign next server_sequence for this channel
-        let next_seq: i64 = conn
-            .query_row(
-                "SELECT COALESCE(MAX(server_sequence), 0) + 1 FROM messages WHERE channel_id = ?1",
-                rusqlite::params![cid],
-                |row| row.get(0),
-            )
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-        let now_millis = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
-
-        let now_rfc = Utc::now().to_rfc3339();
-
-        // Parse mention_user_ids and mention_role_ids from content
-        // Simple pattern: @user:<id> and @role:<id>
-        let mention_user_ids = parse_user_mentions(&content);
-        let mention_role_ids = parse_role_mentions(&content);
-
-        // Insert message — message_type = 1 (CHAT), signature empty for REST path
-        conn.execute(
-            "INSERT INTO messages (channel_id, sender_pubkey, message_type, payload, timestamp, sequence_hint, server_sequence, signature, created_at, content_text, edited, deleted, reply_to_id, block_refs_json)
-             VALUES (?1, ?2, 1, NULL, ?3, 0, ?4, X'', ?5, ?6, 0, 0, ?7, ?8)",
-            rusqlite::params![
-                cid,
-                sender_pubkey,
-                now_millis as i64,
-                next_seq,
-                now_rfc,
-                content,
-                reply_to_id,
-                block_refs_json,
-            ],
+        let mut conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        // Verify channel exists and the sender may post in it (channel overrides applied)
+        let perms = check_channel_permission(
+            &conn,
+            &user_id,
+            is_owner,
+            &cid,
+            Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES,
         )
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-        // Use the actual DB row ID as message ID (consistent with history endpoint)
-        let row_id = conn.last_insert_rowid();
-
-        // Build the ChatMessage proto for broadcast
-        let chat_message = proto_chat::ChatMessage {
-            id: row_id.to_string(),
-            channel_id: cid.clone(),
-            sender_pubkey: sender_pubkey.clone(),
-            sender_display_name: display_name.clone(),
-            content: content.clone(),
-            timestamp: now_millis,
-            server_sequence: next_seq as u64,
-            signature: vec![],
-            reply_to_id: reply_to_id.clone(),
-            edited: false,
-            mention_user_ids,
-            mention_role_ids,
-            block_refs: parse_block_refs_json(&block_refs_json),
-        };
-
-        let response = MessageResponse {
-            id: row_id.to_string(),
-            channel_id: cid,
-            sender_pubkey,
-            sender_display_name: display_name,
-            content,
-            timestamp: now_millis,
-            server_sequence: next_seq as u64,
-            reply_to_id,
-            edited: false,
-            reactions: vec![],
-            block_refs_json,
+        .map_err(|s| (s, String::new()))?;
+
+        let channel_type = ChannelType::of(&conn, &cid)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query channel: {}", e)))?
+            .ok_or((StatusCode::NOT_FOUND, "Channel not found".to_string()))?;
+        if !channel_type.has_messages() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Voice channels do not have messages".to_string(),
+            ));
+        }
+        if !channel_type.allows_top_level_post(perms) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "announcement_channel: Only moderators can post in announcement channels"
+                    .to_string(),
+            ));
+        }
+        let forum_title = match channel_type {
+            ChannelType::Forum => Some(
+                body.title
+                    .as_deref()
+                    .and_then(|t| threads::validate_thread_name(t).ok())
+                    .ok_or((
+                        StatusCode::BAD_REQUEST,
+                        "forum_title_required: Forum posts need a title of 1-100 characters"
+                            .to_string(),
+                    ))?,
+            ),
+            _ => None,
         };
+        slowmode::check_slowmode(&conn, &cid, None, &user_id, perms)?;
+
+        // The sequence number, the message and a forum post's thread commit together
+        let tx = conn
+            .transaction()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Begin: {}", e)))?;
+        let (mut response, chat_message) =
+            insert_chat_message(&tx, &cid, None, &user_id, perms, body)
+                .map_err(|s| (s, String::new()))?;
+        if let Some(title) = forum_title {
+            let root_id: i64 = response.id.parse().unwrap_or_default();
+            let thread = threads::insert_thread(&tx, &cid, root_id, &title, &user_id)
+                .map_err(|s| (s, "Create forum thread".to_string()))?;
+            response.started_thread = Some(thread);
+        }
+        tx.commit()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Commit: {}", e)))?;
 
         Ok((response, chat_message))
     })
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
     let (response, chat_message) = result;
 
-    // Broadcast NewMessageEvent to all WS clients
+    state.abuse.report(ActivityEvent::ChannelMessage {
+        user_id: claims.sub,
+        channel_id: response.channel_id.clone(),
+        content: response.content.clone(),
+    });
+
+    // Publish to gossip peers, then broadcast NewMessageEvent to all WS clients
+    publish::publish_channel_event(
+        &state,
+        &response.channel_id,
+        MessageType::Chat,
+        response.server_sequence,
+        &chat_message,
+    );
     broadcast::broadcast_new_message(&state.connections, chat_message);
+    if let Some(thread) = &response.started_thread {
+        threads::announce_thread(&state, thread);
+    }
 
     Ok((StatusCode::CREATED, Json(response)))
 }
@@ -220,97 +243,24 @@ pub async fn create_message(
 /// Paginated message history. JWT auth required.
 pub async fn get_channel_messages(
     State(state): State<AppState>,
-    _claims: Claims,
+    claims: Claims,
     Path(channel_id): Path<String>,
     Query(query): Query<HistoryQuery>,
 ) -> Result<Json<HistoryResponse>, StatusCode> {
+    require_channel_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        &channel_id,
+        Permissions::VIEW_CHANNEL | Permissions::READ_HISTORY,
+    )
+    .await?;
+
     let db = state.db.clone();
-    let cid = channel_id.clone();
-    let before = query.before.unwrap_or(u64::MAX);
-    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
 
     let result = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-        // Query messages with pagination
-        let mut stmt = conn
-            .prepare(
-                "SELECT m.id, m.channel_id, m.sender_pubkey, m.server_sequence,
-                        m.content_text, m.timestamp, m.edited, m.reply_to_id, m.created_at,
-                        u.display_name, m.block_refs_json
-                 FROM messages m
-                 LEFT JOIN users u ON m.sender_pubkey = lower(hex(u.public_key))
-                 WHERE m.channel_id = ?1 AND m.server_sequence < ?2 AND m.deleted = 0
-                 ORDER BY m.server_sequence DESC
-                 LIMIT ?3",
-            )
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-        let messages: Vec<MessageResponse> = stmt
-            .query_map(
-                rusqlite::params![cid, before as i64, (limit + 1) as i64],
-                |row| {
-                    let msg_id: i64 = row.get(0)?;
-                    let channel_id: String = row.get(1)?;
-                    let sender_pubkey: String = row.get(2)?;
-                    let server_sequence: i64 = row.get(3)?;
-                    let content_text: Option<String> = row.get(4)?;
-                    let timestamp: i64 = row.get(5)?;
-                    let edited: bool = row.get::<_, i64>(6)? != 0;
-                    let reply_to_id: Option<String> = row.get(7)?;
-                    let display_name: Option<String> = row.get(9)?;
-                    let block_refs_json: Option<String> = row.get(10)?;
-
-                    Ok(MessageResponse {
-                        id: msg_id.to_string(),
-                        channel_id,
-                        sender_pubkey,
-                        sender_display_name: display_name.unwrap_or_else(|| "Unknown".to_string()),
-                        content: content_text.unwrap_or_default(),
-                        timestamp: timestamp as u64,
-                        server_sequence: server_sequence as u64,
-                        reply_to_id,
-                        edited,
-                        reactions: vec![], // filled below
-                        block_refs_json,
-                    })
-                },
-            )
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        // Determine has_more
-        let has_more = messages.len() > limit as usize;
-        let mut messages: Vec<MessageResponse> = messages.into_iter().take(limit as usize).collect();
-
-        // Fetch reactions for each message
-        for msg in &mut messages {
-            if let Ok(mut rstmt) = conn.prepare(
-                "SELECT emoji, COUNT(*) as cnt, GROUP_CONCAT(user_pubkey) as pubkeys
-                 FROM reactions
-                 WHERE message_id = ?1
-                 GROUP BY emoji
-                 ORDER BY cnt DESC",
-            ) {
-                if let Ok(rows) = rstmt.query_map(rusqlite::params![msg.id.parse::<i64>().unwrap_or(0)], |row| {
-                    let emoji: String = row.get(0)?;
-                    let count: i64 = row.get(1)?;
-                    let pubkeys_str: String = row.get(2)?;
-                    let user_pubkeys: Vec<String> =
-                        pubkeys_str.split(',').map(|s| s.to_string()).collect();
-                    Ok(ReactionGroup {
-                        emoji,
-                        count,
-                        user_pubkeys,
-                    })
-                }) {
-                    msg.reactions = rows.filter_map(|r| r.ok()).collect();
-                }
-            }
-        }
-
-        Ok::<_, StatusCode>(HistoryResponse { messages, has_more })
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        query_history(&conn, &channel_id, None, &query)
     })
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
@@ -319,20 +269,23 @@ pub async fn get_channel_messages(
 }
 
 /// PUT /api/channels/{channel_id}/messages/{message_id}
-/// Edit own message. JWT auth required. Only the sender can edit.
+/// Edit own message. JWT auth required. Only the sender can edit, not while text-muted,
+/// and new content is held to the same probation limits as a new message. The replaced content is kept in
+/// the message's edit history.
 pub async fn edit_message(
     State(state): State<AppState>,
     claims: Claims,
     Path((channel_id, message_id)): Path<(String, String)>,
     Json(body): Json<EditMessageRequest>,
-) -> Result<StatusCode, StatusCode> {
-    let content = body.content.trim().to_string();
-    if content.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-    if content.len() > MAX_CONTENT_LENGTH {
-        return Err(StatusCode::PAYLOAD_TOO_LARGE);
-    }
+) -> Result<StatusCode, (StatusCode, String)> {
+    let content = validate_content(&body.content).map_err(|s| (s, String::new()))?;
+    trust::require_capabilities(
+        &state,
+        &claims.sub,
+        claims.is_owner,
+        &trust::message_capabilities(&content, false),
+    )
+    .await?;
 
     let db = state.db.clone();
     let user_id = claims.sub.clone();
@@ -349,58 +302,61 @@ pub async fn edit_message(
         let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         // Look up user's pubkey
-        let sender_pubkey: String = conn
+        let (sender_pubkey, fingerprint): (String, String) = conn
             .query_row(
-                "SELECT lower(hex(public_key)) FROM users WHERE id = ?1",
+                "SELECT lower(hex(public_key)), fingerprint FROM users WHERE id = ?1",
                 rusqlite::params![user_id],
-                |row| row.get(0),
+                |row| Ok((row.get(0)?, row.get(1)?)),
             )
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+        // Text-muted users cannot edit either
+        if active_mute(&conn, &fingerprint, SanctionKind::TextMute)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .is_some()
+        {
+            return Err(StatusCode::FORBIDDEN);
+        }
+
         // Verify message exists, belongs to sender, and is in the right channel
         let msg_id: i64 = mid.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
-        let row_pubkey: String = conn
-            .query_row(
-                "SELECT sender_pubkey FROM messages WHERE id = ?1 AND channel_id = ?2 AND deleted = 0",
-                rusqlite::params![msg_id, cid],
-                |row| row.get(0),
-            )
-            .map_err(|_| StatusCode::NOT_FOUND)?;
+        let row_pubkey = message_author(&conn, &cid, msg_id)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .ok_or(StatusCode::NOT_FOUND)?;
 
         if row_pubkey != sender_pubkey {
             return Err(StatusCode::FORBIDDEN);
         }
 
-        // Update the message
-        conn.execute(
-            "UPDATE messages SET content_text = ?1, edited = 1, edit_timestamp = ?2 WHERE id = ?3",
-            rusqlite::params![content, now_rfc, msg_id],
-        )
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        apply_edit(&conn, msg_id, &content, &now_rfc)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-        Ok(content)
+        Ok((content, sender_pubkey))
     })
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))?
+    .map_err(|s| (s, String::new()))?;
 
-    let new_content = result;
+    let (new_content, editor_pubkey) = result;
 
-    // Broadcast edit event
-    broadcast::broadcast_message_edited(
-        &state.connections,
-        proto_chat::MessageEditedEvent {
-            message_id,
-            channel_id,
-            new_content,
-            edit_timestamp: now_millis,
-        },
-    );
+    let event = proto_chat::MessageEditedEvent {
+        message_id,
+        channel_id,
+        new_content,
+        edit_timestamp: now_millis,
+        editor_pubkey,
+    };
+
+    // Publish to gossip peers, then broadcast edit event to WS clients
+    publish::publish_channel_event(&state, &event.channel_id, MessageType::Edit, 0, &event);
+    broadcast::broadcast_message_edited(&state.connections, event);
 
     Ok(StatusCode::OK)
 }
 
 /// DELETE /api/channels/{channel_id}/messages/{message_id}
-/// Soft-delete a message. Sender or admin/owner can delete.
+/// Soft-delete a message. The sender, an admin, or anyone with MANAGE_MESSAGES
+/// in the channel can delete; deleting someone else's message is audited.
 pub async fn delete_message(
     State(state): State<AppState>,
     claims: Claims,
@@ -413,7 +369,7 @@ pub async fn delete_message(
     let mid = message_id.clone();
     let cid = channel_id.clone();
 
-    tokio::task::spawn_blocking(move || {
+    let (deleted_by_pubkey, entry) = tokio::task::spawn_blocking(move || {
         let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         // Look up user's pubkey
@@ -428,38 +384,49 @@ pub async fn delete_message(
         let msg_id: i64 = mid.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
 
         // Get message sender
-        let row_pubkey: String = conn
-            .query_row(
-                "SELECT sender_pubkey FROM messages WHERE id = ?1 AND channel_id = ?2 AND deleted = 0",
-                rusqlite::params![msg_id, cid],
-                |row| row.get(0),
-            )
-            .map_err(|_| StatusCode::NOT_FOUND)?;
+        let row_pubkey = message_author(&conn, &cid, msg_id)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .ok_or(StatusCode::NOT_FOUND)?;
 
-        // Only sender, owner, or admin can delete
-        if row_pubkey != sender_pubkey && !is_owner && !is_admin {
-            return Err(StatusCode::FORBIDDEN);
+        // Only the sender, an admin, or a channel MANAGE_MESSAGES holder can delete
+        let moderated = row_pubkey != sender_pubkey;
+        if moderated && !is_admin {
+            check_channel_permission(&conn, &user_id, is_owner, &cid, Permissions::MANAGE_MESSAGES)?;
         }
 
-        conn.execute(
-            "UPDATE messages SET deleted = 1 WHERE id = ?1",
-            rusqlite::params![msg_id],
-        )
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        apply_delete(&conn, msg_id).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-        Ok(())
+        // Removing someone else's message is a moderation action
+        if !moderated {
+            return Ok((sender_pubkey, None));
+        }
+        audit::record(
+            &conn,
+            AuditRecord::new(&user_id, AuditAction::MessageDelete, &mid)
+                .before(serde_json::json!({
+                    "channel_id": cid,
+                    "sender_pubkey": row_pubkey,
+                }))
+                .evidence(std::slice::from_ref(&mid)),
+        )
+        .map(|entry| (sender_pubkey, Some(entry)))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
     })
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
 
-    // Broadcast delete event
-    broadcast::broadcast_message_deleted(
-        &state.connections,
-        proto_chat::MessageDeletedEvent {
-            message_id,
-            channel_id,
-        },
-    );
+    let event = proto_chat::MessageDeletedEvent {
+        message_id,
+        channel_id,
+        deleted_by_pubkey,
+    };
+
+    // Publish to gossip peers, then broadcast delete event to WS clients
+    publish::publish_channel_event(&state, &event.channel_id, MessageType::Delete, 0, &event);
+    broadcast::broadcast_message_deleted(&state.connections, event);
+    if let Some(entry) = entry {
+        audit::notify_admins(&state, entry);
+    }
 
     Ok(StatusCode::OK)
 }
@@ -507,7 +474,7 @@ pub async fn get_last_read(
     let cid = channel_id;
 
     let result = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
         let seq: i64 = conn
             .query_row(
                 "SELECT last_sequence FROM last_read WHERE user_id = ?1 AND channel_id = ?2",
@@ -525,6 +492,296 @@ pub async fn get_last_read(
     Ok(Json(result))
 }
 
+// --- Shared by channel and thread handlers and gossip ingestion ---
+
+/// Trim message content and check it is non-empty and within MAX_CONTENT_LENGTH.
+pub(crate) fn validate_content(content: &str) -> Result<String, StatusCode> {
+    let content = content.trim().to_string();
+    if content.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if content.len() > MAX_CONTENT_LENGTH {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+    Ok(content)
+}
+
+/// Sender (hex public key) of a message in `channel_id` that has not been deleted.
+pub(crate) fn message_author(
+    conn: &rusqlite::Connection,
+    channel_id: &str,
+    message_id: i64,
+) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT sender_pubkey FROM messages WHERE id = ?1 AND channel_id = ?2 AND deleted = 0",
+        rusqlite::params![message_id, channel_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Replace a message's content, keeping the replaced version in the edit history.
+/// Callers check that the editor is the author.
+pub(crate) fn apply_edit(
+    conn: &rusqlite::Connection,
+    message_id: i64,
+    content: &str,
+    edited_at: &str,
+) -> rusqlite::Result<()> {
+    revisions::record_revision(conn, message_id, edited_at)?;
+    conn.execute(
+        "UPDATE messages SET content_text = ?1, edited = 1, edit_timestamp = ?2 WHERE id = ?3",
+        rusqlite::params![content, edited_at, message_id],
+    )?;
+    Ok(())
+}
+
+/// Soft-delete a message. Callers check that the actor may delete it.
+pub(crate) fn apply_delete(conn: &rusqlite::Connection, message_id: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE messages SET deleted = 1 WHERE id = ?1",
+        rusqlite::params![message_id],
+    )?;
+    Ok(())
+}
+
+/// Probation-gated capabilities a new message would use (see `moderation::trust`).
+pub(crate) fn message_capabilities(body: &CreateMessageRequest) -> Vec<trust::Capability> {
+    let has_attachments = !parse_block_refs_json(&body.block_refs_json).is_empty();
+    trust::message_capabilities(&body.content, has_attachments)
+}
+
+/// Persist a REST-created message in a channel, or in a thread of that channel when
+/// `thread_id` is set. `perms` are the sender's effective permissions in the channel
+/// (VIEW_CHANNEL | SEND_MESSAGES already checked); `body.content` must be validated.
+/// Returns the REST response and the ChatMessage to publish/broadcast. Run it in a
+/// transaction so the sequence number and the message commit together.
+pub(crate) fn insert_chat_message(
+    conn: &rusqlite::Connection,
+    channel_id: &str,
+    thread_id: Option<&str>,
+    user_id: &str,
+    perms: Permissions,
+    body: CreateMessageRequest,
+) -> Result<(MessageResponse, proto_chat::ChatMessage), StatusCode> {
+    let CreateMessageRequest {
+        content,
+        reply_to_id,
+        block_refs_json,
+        title: _,
+    } = body;
+
+    if !parse_block_refs_json(&block_refs_json).is_empty()
+        && !perms.contains(Permissions::ATTACH_FILES)
+    {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // Look up sender's display_name and public_key
+    let (display_name, pubkey_hex, fingerprint): (String, String, String) = conn
+        .query_row(
+            "SELECT display_name, hex(public_key), fingerprint FROM users WHERE id = ?1",
+            rusqlite::params![user_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if active_mute(conn, &fingerprint, SanctionKind::TextMute)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .is_some()
+    {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let sender_pubkey = pubkey_hex.to_lowercase();
+
+    // Assign next server_sequence for this channel (or thread)
+    let next_seq = next_channel_sequence(conn, channel_id, thread_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let now_millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+
+    let now_rfc = Utc::now().to_rfc3339();
+
+    // Parse mention_user_ids and mention_role_ids from content
+    // Simple pattern: @user:<id> and @role:<id>
+    let mention_user_ids = parse_user_mentions(&content);
+    let mut mention_role_ids = parse_role_mentions(&content);
+
+    // Mentioning @everyone (the default role) requires MENTION_EVERYONE
+    if !perms.contains(Permissions::MENTION_EVERYONE) {
+        let default_role_id: Option<String> = conn
+            .query_row("SELECT id FROM roles WHERE is_default = 1", [], |row| row.get(0))
+            .optional()
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if let Some(default_role_id) = default_role_id {
+            mention_role_ids.retain(|id| *id != default_role_id);
+        }
+    }
+
+    // Insert message — message_type = 1 (CHAT), signature empty for REST path
+    conn.execute(
+        "INSERT INTO messages (channel_id, sender_pubkey, message_type, payload, timestamp, sequence_hint, server_sequence, signature, created_at, content_text, edited, deleted, reply_to_id, block_refs_json, thread_id)
+         VALUES (?1, ?2, 1, NULL, ?3, 0, ?4, X'', ?5, ?6, 0, 0, ?7, ?8, ?9)",
+        rusqlite::params![
+            channel_id,
+            sender_pubkey,
+            now_millis as i64,
+            next_seq,
+            now_rfc,
+            content,
+            reply_to_id,
+            block_refs_json,
+            thread_id,
+        ],
+    )
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Use the actual DB row ID as message ID (consistent with history endpoint)
+    let row_id = conn.last_insert_rowid();
+
+    // Build the ChatMessage proto for broadcast
+    let chat_message = proto_chat::ChatMessage {
+        id: row_id.to_string(),
+        channel_id: channel_id.to_string(),
+        sender_pubkey: sender_pubkey.clone(),
+        sender_display_name: display_name.clone(),
+        content: content.clone(),
+        timestamp: now_millis,
+        server_sequence: next_seq as u64,
+        signature: vec![],
+        reply_to_id: reply_to_id.clone(),
+        edited: false,
+        mention_user_ids,
+        mention_role_ids,
+        block_refs: parse_block_refs_json(&block_refs_json),
+        thread_id: thread_id.map(str::to_string),
+    };
+
+    let response = MessageResponse {
+        id: row_id.to_string(),
+        channel_id: channel_id.to_string(),
+        sender_pubkey,
+        sender_display_name: display_name,
+        content,
+        timestamp: now_millis,
+        server_sequence: next_seq as u64,
+        reply_to_id,
+        edited: false,
+        reactions: vec![],
+        block_refs_json,
+        thread_id: thread_id.map(str::to_string),
+        started_thread: None,
+    };
+
+    Ok((response, chat_message))
+}
+
+/// Paginated history of a channel's main timeline (`thread_id` None) or of one thread.
+pub(crate) fn query_history(
+    conn: &rusqlite::Connection,
+    channel_id: &str,
+    thread_id: Option<&str>,
+    query: &HistoryQuery,
+) -> Result<HistoryResponse, StatusCode> {
+    // Clamp so the default (no `before`) doesn't wrap to -1 when bound as i64
+    let before = query.before.unwrap_or(u64::MAX).min(i64::MAX as u64);
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    // Query messages with pagination
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} {}
+             WHERE m.channel_id = ?1 AND m.thread_id IS ?2 AND m.server_sequence < ?3 AND m.deleted = 0
+             ORDER BY m.server_sequence DESC
+             LIMIT ?4",
+            MESSAGE_COLUMNS, MESSAGE_FROM
+        ))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let messages: Vec<MessageResponse> = stmt
+        .query_map(
+            rusqlite::params![channel_id, thread_id, before as i64, (limit + 1) as i64],
+            message_from_row,
+        )
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    // Determine has_more
+    let has_more = messages.len() > limit as usize;
+    let mut messages: Vec<MessageResponse> = messages.into_iter().take(limit as usize).collect();
+
+    load_reactions(conn, &mut messages);
+
+    Ok(HistoryResponse { messages, has_more })
+}
+
+/// Fill in grouped reactions for each message.
+pub(crate) fn load_reactions(conn: &rusqlite::Connection, messages: &mut [MessageResponse]) {
+    for msg in messages.iter_mut() {
+        if let Ok(mut rstmt) = conn.prepare(
+            "SELECT emoji, COUNT(*) as cnt, GROUP_CONCAT(user_pubkey) as pubkeys
+             FROM reactions
+             WHERE message_id = ?1
+             GROUP BY emoji
+             ORDER BY cnt DESC",
+        ) {
+            if let Ok(rows) = rstmt.query_map(rusqlite::params![msg.id.parse::<i64>().unwrap_or(0)], |row| {
+                let emoji: String = row.get(0)?;
+                let count: i64 = row.get(1)?;
+                let pubkeys_str: String = row.get(2)?;
+                let user_pubkeys: Vec<String> =
+                    pubkeys_str.split(',').map(|s| s.to_string()).collect();
+                Ok(ReactionGroup {
+                    emoji,
+                    count,
+                    user_pubkeys,
+                })
+            }) {
+                msg.reactions = rows.filter_map(|r| r.ok()).collect();
+            }
+        }
+    }
+}
+
+/// Columns read by `message_from_row`; pair with `MESSAGE_FROM`.
+pub(crate) const MESSAGE_COLUMNS: &str = "m.id, m.channel_id, m.sender_pubkey, m.server_sequence,
+        m.content_text, m.timestamp, m.edited, m.reply_to_id, m.created_at,
+        u.display_name, m.block_refs_json, m.thread_id";
+
+/// `messages m` joined with the sender's user row for display names.
+pub(crate) const MESSAGE_FROM: &str =
+    "FROM messages m LEFT JOIN users u ON m.sender_pubkey = lower(hex(u.public_key))";
+
+/// Map a row selected with `MESSAGE_COLUMNS` to a MessageResponse (reactions left empty).
+pub(crate) fn message_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<MessageResponse> {
+    let msg_id: i64 = row.get(0)?;
+    let server_sequence: i64 = row.get(3)?;
+    let content_text: Option<String> = row.get(4)?;
+    let timestamp: i64 = row.get(5)?;
+    let display_name: Option<String> = row.get(9)?;
+
+    Ok(MessageResponse {
+        id: msg_id.to_string(),
+        channel_id: row.get(1)?,
+        sender_pubkey: row.get(2)?,
+        sender_display_name: display_name.unwrap_or_else(|| "Unknown".to_string()),
+        content: content_text.unwrap_or_default(),
+        timestamp: timestamp as u64,
+        server_sequence: server_sequence as u64,
+        reply_to_id: row.get(7)?,
+        edited: row.get::<_, i64>(6)? != 0,
+        reactions: vec![],
+        block_refs_json: row.get(10)?,
+        thread_id: row.get(11)?,
+        started_thread: None,
+    })
+}
+
 // --- Helpers ---
 
 /// Parse @user:<id> mentions from content.
@@ -1,4 +1,9 @@
 pub mod broadcast;
 pub mod messages;
+pub mod pins;
 pub mod presence;
 pub mod reactions;
+pub mod retention;
+pub mod revisions;
+pub mod search;
+pub mod threads;
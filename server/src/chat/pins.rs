@@ -0,0 +1,286 @@
+//! REST endpoints for pinned channel messages.
+//!
+//! Pinning requires MANAGE_MESSAGES in the channel, and pins and unpins are written to
+//! the audit log. Blocks referenced by pinned messages are exempt from retention purging
+//! (see `blocks::store::delete_expired_blocks`).
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use rusqlite::OptionalExtension;
+use serde::Serialize;
+
+use crate::auth::middleware::Claims;
+use crate::chat::broadcast;
+use crate::chat::messages::{
+    load_reactions, message_from_row, MessageResponse, MESSAGE_COLUMNS, MESSAGE_FROM,
+};
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::proto::chat as proto_chat;
+use crate::roles::permissions::{check_channel_permission, require_channel_permission, Permissions};
+use crate::state::AppState;
+
+/// Maximum number of pinned messages per channel.
+const MAX_PINS_PER_CHANNEL: i64 = 50;
+
+// --- Response types ---
+
+#[derive(Debug, Serialize)]
+pub struct PinResponse {
+    pub message: MessageResponse,
+    pub pinned_by: String,
+    pub pinned_at: String,
+}
+
+impl PinResponse {
+    /// Convert to the protobuf Pin used in WS events.
+    pub fn to_proto(&self) -> proto_chat::Pin {
+        proto_chat::Pin {
+            message: Some(self.message.to_proto()),
+            pinned_by: self.pinned_by.clone(),
+            pinned_at: self.pinned_at.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PinListResponse {
+    pub pins: Vec<PinResponse>,
+}
+
+// --- Handlers ---
+
+/// GET /api/channels/{channel_id}/pins
+/// List pinned messages, most recently pinned first. JWT auth required.
+pub async fn list_pins(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(channel_id): Path<String>,
+) -> Result<Json<PinListResponse>, StatusCode> {
+    require_channel_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        &channel_id,
+        Permissions::VIEW_CHANNEL | Permissions::READ_HISTORY,
+    )
+    .await?;
+
+    let db = state.db.clone();
+
+    let pins = tokio::task::spawn_blocking(move || {
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {}, p.pinned_by, p.pinned_at {}
+                 INNER JOIN pins p ON p.message_id = m.id
+                 WHERE p.channel_id = ?1 AND m.deleted = 0
+                 ORDER BY p.pinned_at DESC",
+                MESSAGE_COLUMNS, MESSAGE_FROM
+            ))
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let rows: Vec<(MessageResponse, String, String)> = stmt
+            .query_map([&channel_id], |row| {
+                Ok((message_from_row(row)?, row.get(12)?, row.get(13)?))
+            })
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let (mut messages, pinned): (Vec<MessageResponse>, Vec<(String, String)>) = rows
+            .into_iter()
+            .map(|(message, pinned_by, pinned_at)| (message, (pinned_by, pinned_at)))
+            .unzip();
+        load_reactions(&conn, &mut messages);
+
+        let pins: Vec<PinResponse> = messages
+            .into_iter()
+            .zip(pinned)
+            .map(|(message, (pinned_by, pinned_at))| PinResponse {
+                message,
+                pinned_by,
+                pinned_at,
+            })
+            .collect();
+
+        Ok::<_, StatusCode>(pins)
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+
+    Ok(Json(PinListResponse { pins }))
+}
+
+/// PUT /api/channels/{channel_id}/pins/{message_id}
+/// Pin a message (requires MANAGE_MESSAGES in the channel). Pinning an already pinned
+/// message is a no-op. JWT auth required.
+pub async fn pin_message(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path((channel_id, message_id)): Path<(String, String)>,
+) -> Result<Json<PinResponse>, StatusCode> {
+    let db = state.db.clone();
+    let user_id = claims.sub.clone();
+    let is_owner = claims.is_owner;
+    let cid = channel_id.clone();
+
+    let (pin, entry) = tokio::task::spawn_blocking(move || {
+        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        check_channel_permission(
+            &conn,
+            &user_id,
+            is_owner,
+            &cid,
+            Permissions::VIEW_CHANNEL | Permissions::MANAGE_MESSAGES,
+        )?;
+
+        let msg_id: i64 = message_id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+        let mut message = conn
+            .query_row(
+                &format!(
+                    "SELECT {} {} WHERE m.id = ?1 AND m.channel_id = ?2 AND m.deleted = 0",
+                    MESSAGE_COLUMNS, MESSAGE_FROM
+                ),
+                rusqlite::params![msg_id, cid],
+                message_from_row,
+            )
+            .optional()
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .ok_or(StatusCode::NOT_FOUND)?;
+        load_reactions(&conn, std::slice::from_mut(&mut message));
+
+        let existing: Option<(String, String)> = conn
+            .query_row(
+                "SELECT pinned_by, pinned_at FROM pins WHERE message_id = ?1",
+                [msg_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if let Some((pinned_by, pinned_at)) = existing {
+            return Ok((
+                PinResponse {
+                    message,
+                    pinned_by,
+                    pinned_at,
+                },
+                None,
+            ));
+        }
+
+        let pin_count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pins p
+                 INNER JOIN messages m ON m.id = p.message_id
+                 WHERE p.channel_id = ?1 AND m.deleted = 0",
+                [&cid],
+                |row| row.get(0),
+            )
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if pin_count >= MAX_PINS_PER_CHANNEL {
+            return Err(StatusCode::CONFLICT);
+        }
+
+        let pinned_at = Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO pins (message_id, channel_id, pinned_by, pinned_at) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![msg_id, cid, user_id, pinned_at],
+        )
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&user_id, AuditAction::MessagePin, &message_id)
+                .after(serde_json::json!({ "channel_id": cid }))
+                .evidence(std::slice::from_ref(&message_id)),
+        )
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        Ok::<_, StatusCode>((
+            PinResponse {
+                message,
+                pinned_by: user_id,
+                pinned_at,
+            },
+            Some(entry),
+        ))
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+
+    if let Some(entry) = entry {
+        broadcast::broadcast_message_pinned(
+            &state.connections,
+            proto_chat::MessagePinnedEvent {
+                channel_id,
+                pin: Some(pin.to_proto()),
+            },
+        );
+        audit::notify_admins(&state, entry);
+    }
+
+    Ok(Json(pin))
+}
+
+/// DELETE /api/channels/{channel_id}/pins/{message_id}
+/// Unpin a message (requires MANAGE_MESSAGES in the channel). JWT auth required.
+pub async fn unpin_message(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path((channel_id, message_id)): Path<(String, String)>,
+) -> Result<StatusCode, StatusCode> {
+    let db = state.db.clone();
+    let user_id = claims.sub.clone();
+    let is_owner = claims.is_owner;
+    let cid = channel_id.clone();
+    let mid = message_id.clone();
+
+    let entry = tokio::task::spawn_blocking(move || {
+        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        check_channel_permission(
+            &conn,
+            &user_id,
+            is_owner,
+            &cid,
+            Permissions::VIEW_CHANNEL | Permissions::MANAGE_MESSAGES,
+        )?;
+
+        let msg_id: i64 = mid.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+        let rows = conn
+            .execute(
+                "DELETE FROM pins WHERE message_id = ?1 AND channel_id = ?2",
+                rusqlite::params![msg_id, cid],
+            )
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if rows == 0 {
+            return Err(StatusCode::NOT_FOUND);
+        }
+
+        audit::record(
+            &conn,
+            AuditRecord::new(&user_id, AuditAction::MessageUnpin, &mid)
+                .before(serde_json::json!({ "channel_id": cid }))
+                .evidence(std::slice::from_ref(&mid)),
+        )
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+
+    broadcast::broadcast_message_unpinned(
+        &state.connections,
+        proto_chat::MessageUnpinnedEvent {
+            channel_id,
+            message_id,
+        },
+    );
+    audit::notify_admins(&state, entry);
+
+    Ok(StatusCode::OK)
+}
@@ -26,7 +26,7 @@ pub enum PresenceStatus {
 }
 
 impl PresenceStatus {
-    pub fn from_str(s: &str) -> Option<Self> {
+    pub fn parse(s: &str) -> Option<Self> {
         match s {
             "online" => Some(Self::Online),
             "away" => Some(Self::Away),
@@ -145,7 +145,7 @@ pub async fn set_presence(
     claims: Claims,
     Json(body): Json<SetPresenceRequest>,
 ) -> Result<StatusCode, StatusCode> {
-    let status = PresenceStatus::from_str(&body.status)
+    let status = PresenceStatus::parse(&body.status)
         .ok_or(StatusCode::BAD_REQUEST)?;
 
     // Look up the user's pubkey and display_name from DB
@@ -153,7 +153,7 @@ pub async fn set_presence(
     let user_id = claims.sub.clone();
 
     let (pubkey_hex, display_name) = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
         let (dn, pk): (String, String) = conn
             .query_row(
                 "SELECT display_name, lower(hex(public_key)) FROM users WHERE id = ?1",
@@ -187,7 +187,7 @@ pub async fn send_typing(
     let user_id = claims.sub.clone();
 
     let (pubkey_hex, display_name) = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
         let (dn, pk): (String, String) = conn
             .query_row(
                 "SELECT display_name, lower(hex(public_key)) FROM users WHERE id = ?1",
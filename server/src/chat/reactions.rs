@@ -10,7 +10,12 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::auth::middleware::Claims;
 use crate::chat::broadcast;
+use crate::moderation::sanctions::{active_mute, SanctionKind};
+use crate::moderation::trust::{self, Capability};
+use crate::p2p::publish;
 use crate::proto::chat as proto_chat;
+use crate::proto::p2p_proto::MessageType;
+use crate::roles::permissions::{check_channel_permission, Permissions};
 use crate::state::AppState;
 
 // --- Request / Response types ---
@@ -37,72 +42,85 @@ pub async fn add_reaction(
     claims: Claims,
     Path(message_id): Path<String>,
     Json(body): Json<AddReactionRequest>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, (StatusCode, String)> {
     let emoji = body.emoji.trim().to_string();
-    if emoji.is_empty() || emoji.len() > 64 {
-        return Err(StatusCode::BAD_REQUEST);
+    if !valid_emoji(&emoji) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid emoji".to_string()));
     }
+    trust::require_capabilities(&state, &claims.sub, claims.is_owner, &[Capability::Reactions])
+        .await?;
 
     let db = state.db.clone();
     let user_id = claims.sub.clone();
+    let is_owner = claims.is_owner;
     let mid = message_id.clone();
     let emoji_clone = emoji.clone();
 
-    let sender_pubkey = tokio::task::spawn_blocking(move || {
+    let (sender_pubkey, channel_id) = tokio::task::spawn_blocking(move || {
         let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         // Look up user's pubkey
-        let pubkey: String = conn
+        let (pubkey, fingerprint): (String, String) = conn
             .query_row(
-                "SELECT lower(hex(public_key)) FROM users WHERE id = ?1",
+                "SELECT lower(hex(public_key)), fingerprint FROM users WHERE id = ?1",
                 rusqlite::params![user_id],
-                |row| row.get(0),
+                |row| Ok((row.get(0)?, row.get(1)?)),
             )
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+        // Text-muted users cannot react either
+        if active_mute(&conn, &fingerprint, SanctionKind::TextMute)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .is_some()
+        {
+            return Err(StatusCode::FORBIDDEN);
+        }
+
         let msg_id: i64 = mid.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
 
-        // Verify message exists and is not deleted
-        let exists: bool = conn
+        // Verify message exists and is not deleted (its channel picks the gossip topic)
+        let channel_id: String = conn
             .query_row(
-                "SELECT COUNT(*) FROM messages WHERE id = ?1 AND deleted = 0",
+                "SELECT channel_id FROM messages WHERE id = ?1 AND deleted = 0",
                 rusqlite::params![msg_id],
-                |row| row.get::<_, i64>(0).map(|c| c > 0),
+                |row| row.get(0),
             )
-            .unwrap_or(false);
-        if !exists {
-            return Err(StatusCode::NOT_FOUND);
-        }
+            .map_err(|_| StatusCode::NOT_FOUND)?;
+
+        check_channel_permission(
+            &conn,
+            &user_id,
+            is_owner,
+            &channel_id,
+            Permissions::VIEW_CHANNEL | Permissions::ADD_REACTIONS,
+        )?;
 
-        // Insert reaction (UNIQUE constraint prevents duplicates)
-        conn.execute(
-            "INSERT OR IGNORE INTO reactions (message_id, user_pubkey, emoji) VALUES (?1, ?2, ?3)",
-            rusqlite::params![msg_id, pubkey, emoji_clone],
-        )
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        apply_reaction_add(&conn, msg_id, &pubkey, &emoji_clone)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-        Ok(pubkey)
+        Ok::<_, StatusCode>((pubkey, channel_id))
     })
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))?
+    .map_err(|s| (s, String::new()))?;
 
     let now_millis = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_millis() as u64;
 
-    // Broadcast reaction added event
-    broadcast::broadcast_reaction_added(
-        &state.connections,
-        proto_chat::ReactionAddedEvent {
-            reaction: Some(proto_chat::Reaction {
-                message_id,
-                user_pubkey: sender_pubkey,
-                emoji,
-                timestamp: now_millis,
-            }),
-        },
-    );
+    let event = proto_chat::ReactionAddedEvent {
+        reaction: Some(proto_chat::Reaction {
+            message_id,
+            user_pubkey: sender_pubkey,
+            emoji,
+            timestamp: now_millis,
+        }),
+    };
+
+    // Publish to gossip peers, then broadcast to WS clients
+    publish::publish_channel_event(&state, &channel_id, MessageType::ReactionAdd, 0, &event);
+    broadcast::broadcast_reaction_added(&state.connections, &channel_id, event);
 
     Ok(StatusCode::CREATED)
 }
@@ -119,7 +137,7 @@ pub async fn remove_reaction(
     let mid = message_id.clone();
     let emoji_clone = emoji.clone();
 
-    let sender_pubkey = tokio::task::spawn_blocking(move || {
+    let (sender_pubkey, channel_id) = tokio::task::spawn_blocking(move || {
         let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         // Look up user's pubkey
@@ -133,32 +151,34 @@ pub async fn remove_reaction(
 
         let msg_id: i64 = mid.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
 
-        // Delete the reaction
-        let rows = conn
-            .execute(
-                "DELETE FROM reactions WHERE message_id = ?1 AND user_pubkey = ?2 AND emoji = ?3",
-                rusqlite::params![msg_id, pubkey, emoji_clone],
+        let channel_id: String = conn
+            .query_row(
+                "SELECT channel_id FROM messages WHERE id = ?1",
+                rusqlite::params![msg_id],
+                |row| row.get(0),
             )
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            .map_err(|_| StatusCode::NOT_FOUND)?;
 
-        if rows == 0 {
+        let removed = apply_reaction_remove(&conn, msg_id, &pubkey, &emoji_clone)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if !removed {
             return Err(StatusCode::NOT_FOUND);
         }
 
-        Ok(pubkey)
+        Ok((pubkey, channel_id))
     })
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
 
-    // Broadcast reaction removed event
-    broadcast::broadcast_reaction_removed(
-        &state.connections,
-        proto_chat::ReactionRemovedEvent {
-            message_id,
-            user_pubkey: sender_pubkey,
-            emoji,
-        },
-    );
+    let event = proto_chat::ReactionRemovedEvent {
+        message_id,
+        user_pubkey: sender_pubkey,
+        emoji,
+    };
+
+    // Publish to gossip peers, then broadcast to WS clients
+    publish::publish_channel_event(&state, &channel_id, MessageType::ReactionRemove, 0, &event);
+    broadcast::broadcast_reaction_removed(&state.connections, &channel_id, event);
 
     Ok(StatusCode::OK)
 }
@@ -173,7 +193,7 @@ pub async fn get_reactions(
     let mid = message_id;
 
     let result = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         let msg_id: i64 = mid.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
 
@@ -211,3 +231,38 @@ pub async fn get_reactions(
 
     Ok(Json(result))
 }
+
+// --- Shared by the REST handlers and gossip ingestion ---
+
+/// Emoji are free-form but bounded.
+pub(crate) fn valid_emoji(emoji: &str) -> bool {
+    !emoji.is_empty() && emoji.len() <= 64
+}
+
+/// Add a reaction (UNIQUE constraint makes repeats a no-op).
+pub(crate) fn apply_reaction_add(
+    conn: &rusqlite::Connection,
+    message_id: i64,
+    user_pubkey: &str,
+    emoji: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO reactions (message_id, user_pubkey, emoji) VALUES (?1, ?2, ?3)",
+        rusqlite::params![message_id, user_pubkey, emoji],
+    )?;
+    Ok(())
+}
+
+/// Remove a reaction. Returns false if there was none.
+pub(crate) fn apply_reaction_remove(
+    conn: &rusqlite::Connection,
+    message_id: i64,
+    user_pubkey: &str,
+    emoji: &str,
+) -> rusqlite::Result<bool> {
+    let rows = conn.execute(
+        "DELETE FROM reactions WHERE message_id = ?1 AND user_pubkey = ?2 AND emoji = ?3",
+        rusqlite::params![message_id, user_pubkey, emoji],
+    )?;
+    Ok(rows > 0)
+}
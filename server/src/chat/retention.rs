@@ -0,0 +1,506 @@
+//! Message retention: per-channel policies that remove messages older than N days.
+//!
+//! A channel's own policy wins; a channel without one inherits its category's, and a
+//! channel policy of 0 days keeps messages forever even under a category policy.
+//! Expired messages are hard-deleted or tombstoned (row kept as a deleted message with
+//! its content scrubbed) by a background task. Pinned messages and the roots of threads
+//! that are still active are exempt. Removals are not broadcast; clients see them on
+//! their next history fetch.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::middleware::Claims;
+use crate::chat::revisions;
+use crate::db::DbPool;
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::roles::permissions::{require_permission, Permissions};
+use crate::state::AppState;
+
+/// Longest configurable retention period (100 years).
+const MAX_RETENTION_DAYS: u32 = 36_500;
+
+/// What happens to an expired message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionMode {
+    /// Remove the row, its reactions and any thread it roots
+    Delete,
+    /// Keep the row as a deleted message with content, payload, attachments, reactions
+    /// and edit history cleared
+    #[default]
+    Tombstone,
+}
+
+impl RetentionMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Delete => "delete",
+            Self::Tombstone => "tombstone",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "delete" => Self::Delete,
+            _ => Self::Tombstone,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RetentionPolicy {
+    /// Days to keep messages; 0 keeps them forever
+    pub days: u32,
+    pub mode: RetentionMode,
+}
+
+impl RetentionPolicy {
+    fn from_columns(days: Option<u32>, mode: Option<String>) -> Option<Self> {
+        days.map(|days| Self {
+            days,
+            mode: mode
+                .as_deref()
+                .map(RetentionMode::parse)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Messages created before this instant have expired, or None when kept forever.
+    pub fn cutoff(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (self.days > 0).then(|| now - Duration::days(self.days as i64))
+    }
+}
+
+/// A channel's own and effective retention policy.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelRetention {
+    pub channel_id: String,
+    pub channel_name: String,
+    pub category_id: String,
+    /// Set on the channel itself (None inherits from the category)
+    pub channel_policy: Option<RetentionPolicy>,
+    /// Set on the channel's category
+    pub category_policy: Option<RetentionPolicy>,
+    /// The policy the retention task applies (None or 0 days keeps forever)
+    pub effective: Option<RetentionPolicy>,
+}
+
+/// Retention policies of every channel that carries messages.
+pub fn channel_policies(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<ChannelRetention>> {
+    let mut stmt = conn.prepare(
+        "SELECT ch.id, ch.name, ch.category_id, ch.retention_days, ch.retention_mode,
+                cat.retention_days, cat.retention_mode
+         FROM channels ch LEFT JOIN categories cat ON cat.id = ch.category_id
+         WHERE ch.channel_type != 'voice'
+         ORDER BY ch.category_id, ch.position",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let channel_policy = RetentionPolicy::from_columns(row.get(3)?, row.get(4)?);
+        let category_policy = RetentionPolicy::from_columns(row.get(5)?, row.get(6)?);
+        Ok(ChannelRetention {
+            channel_id: row.get(0)?,
+            channel_name: row.get(1)?,
+            category_id: row.get(2)?,
+            channel_policy,
+            category_policy,
+            effective: channel_policy.or(category_policy),
+        })
+    })?;
+    rows.collect()
+}
+
+/// Filter over `messages m` selecting a channel's (?1) messages created before ?2 that
+/// are not pinned and do not root a thread with activity since ?2.
+///
+/// Messages store SQLite `datetime('now')` text while the cutoff is RFC 3339, so both
+/// sides go through `datetime()` rather than comparing the strings.
+const EXPIRED_FILTER: &str = "m.channel_id = ?1 AND datetime(m.created_at) < datetime(?2)
+    AND NOT EXISTS (SELECT 1 FROM pins p WHERE p.message_id = m.id)
+    AND NOT EXISTS (
+        SELECT 1 FROM threads t
+        WHERE t.root_message_id = m.id
+          AND datetime(COALESCE(t.last_message_at, t.created_at)) >= datetime(?2)
+    )";
+
+/// Tombstoning skips messages that are already scrubbed.
+const NOT_TOMBSTONED: &str =
+    "(m.deleted = 0 OR m.content_text IS NOT NULL OR m.payload IS NOT NULL OR m.block_refs_json IS NOT NULL)";
+
+fn expired_where(mode: RetentionMode) -> String {
+    match mode {
+        RetentionMode::Delete => EXPIRED_FILTER.to_string(),
+        RetentionMode::Tombstone => format!("{} AND {}", EXPIRED_FILTER, NOT_TOMBSTONED),
+    }
+}
+
+/// One channel's entry in the retention report.
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionReportEntry {
+    #[serde(flatten)]
+    pub retention: ChannelRetention,
+    /// Messages created before this are expired (None when kept forever)
+    pub cutoff: Option<String>,
+    /// Messages the next run would delete or tombstone
+    pub due: u64,
+    /// Expired messages kept because they are pinned
+    pub pinned_exempt: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionReport {
+    pub generated_at: String,
+    pub channels: Vec<RetentionReportEntry>,
+    pub total_due: u64,
+}
+
+/// Dry run: what the retention task would remove at `now`, without changing anything.
+pub fn retention_report(
+    conn: &rusqlite::Connection,
+    now: DateTime<Utc>,
+) -> rusqlite::Result<RetentionReport> {
+    let mut channels = Vec::new();
+    for retention in channel_policies(conn)? {
+        let cutoff = retention.effective.and_then(|p| p.cutoff(now));
+        let (due, pinned_exempt) = match (retention.effective, cutoff) {
+            (Some(policy), Some(cutoff)) => {
+                let cutoff = cutoff.to_rfc3339();
+                let due: i64 = conn.query_row(
+                    &format!(
+                        "SELECT COUNT(*) FROM messages m WHERE {}",
+                        expired_where(policy.mode)
+                    ),
+                    rusqlite::params![retention.channel_id, cutoff],
+                    |row| row.get(0),
+                )?;
+                let pinned: i64 = conn.query_row(
+                    "SELECT COUNT(*) FROM pins p JOIN messages m ON m.id = p.message_id
+                     WHERE m.channel_id = ?1 AND datetime(m.created_at) < datetime(?2)",
+                    rusqlite::params![retention.channel_id, cutoff],
+                    |row| row.get(0),
+                )?;
+                (due as u64, pinned as u64)
+            }
+            _ => (0, 0),
+        };
+        channels.push(RetentionReportEntry {
+            retention,
+            cutoff: cutoff.map(|c| c.to_rfc3339()),
+            due,
+            pinned_exempt,
+        });
+    }
+
+    let total_due = channels.iter().map(|c| c.due).sum();
+    Ok(RetentionReport {
+        generated_at: now.to_rfc3339(),
+        channels,
+        total_due,
+    })
+}
+
+/// Delete or tombstone every expired message at `now`. Returns how many were removed.
+pub fn apply_retention(conn: &rusqlite::Connection, now: DateTime<Utc>) -> rusqlite::Result<u64> {
+    let mut removed = 0;
+    for retention in channel_policies(conn)? {
+        let Some(policy) = retention.effective else {
+            continue;
+        };
+        let Some(cutoff) = policy.cutoff(now) else {
+            continue;
+        };
+        let params = rusqlite::params![retention.channel_id, cutoff.to_rfc3339()];
+        let filter = expired_where(policy.mode);
+
+        removed += match policy.mode {
+            RetentionMode::Delete => conn.execute(
+                &format!("DELETE FROM messages AS m WHERE {}", filter),
+                params,
+            )?,
+            RetentionMode::Tombstone => {
+                for table in ["reactions", "message_revisions"] {
+                    conn.execute(
+                        &format!(
+                            "DELETE FROM {} WHERE message_id IN (SELECT m.id FROM messages m WHERE {})",
+                            table, filter
+                        ),
+                        params,
+                    )?;
+                }
+                conn.execute(
+                    &format!(
+                        "UPDATE messages AS m SET deleted = 1, content_text = NULL, payload = NULL,
+                             block_refs_json = NULL
+                         WHERE {}",
+                        filter
+                    ),
+                    params,
+                )?
+            }
+        } as u64;
+    }
+    Ok(removed)
+}
+
+/// Spawn a background task that applies message retention every `interval_secs` and
+/// prunes edit history older than `revision_retention_days`.
+pub fn spawn_message_retention(db: DbPool, interval_secs: u64, revision_retention_days: u32) {
+    let interval = std::time::Duration::from_secs(interval_secs);
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+
+            let db_clone = db.clone();
+            match tokio::task::spawn_blocking(move || {
+                let conn = db_clone.lock().map_err(|e| e.to_string())?;
+                let now = Utc::now();
+                let messages = apply_retention(&conn, now).map_err(|e| e.to_string())?;
+                let revisions = revisions::prune_revisions(&conn, now, revision_retention_days)
+                    .map_err(|e| e.to_string())?;
+                Ok::<_, String>((messages, revisions))
+            })
+            .await
+            {
+                Ok(Ok((messages, revisions))) => {
+                    if messages > 0 || revisions > 0 {
+                        tracing::info!(
+                            "Message retention: removed {} expired messages and {} old revisions",
+                            messages,
+                            revisions
+                        );
+                    } else {
+                        tracing::debug!("Message retention: nothing expired");
+                    }
+                }
+                Ok(Err(e)) => {
+                    tracing::error!("Message retention error: {}", e);
+                }
+                Err(e) => {
+                    tracing::error!("Message retention task join error: {}", e);
+                }
+            }
+        }
+    });
+}
+
+// --- Handlers ---
+
+#[derive(Debug, Deserialize)]
+pub struct SetRetentionRequest {
+    /// Days to keep messages, 0 to keep forever, or null to clear the policy
+    pub days: Option<u32>,
+    #[serde(default)]
+    pub mode: RetentionMode,
+}
+
+impl SetRetentionRequest {
+    fn validate(&self) -> Result<(), (StatusCode, String)> {
+        if self.days.is_some_and(|d| d > MAX_RETENTION_DAYS) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Retention cannot exceed {} days", MAX_RETENTION_DAYS),
+            ));
+        }
+        Ok(())
+    }
+
+    fn columns(&self) -> (Option<u32>, Option<&'static str>) {
+        (self.days, self.days.map(|_| self.mode.as_str()))
+    }
+}
+
+/// PUT /api/channels/{id}/retention — Set or clear a channel's retention policy
+/// (requires MANAGE_CHANNELS). Clearing it inherits the category's policy.
+pub async fn set_channel_retention(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(channel_id): Path<String>,
+    Json(req): Json<SetRetentionRequest>,
+) -> Result<Json<ChannelRetention>, (StatusCode, String)> {
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        Permissions::MANAGE_CHANNELS,
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+    req.validate()?;
+
+    let db = state.db.clone();
+    let actor_id = claims.sub.clone();
+
+    let (retention, entry) = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+        let load = |conn: &rusqlite::Connection| {
+            channel_policies(conn)
+                .map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Query channels: {}", e),
+                    )
+                })?
+                .into_iter()
+                .find(|r| r.channel_id == channel_id)
+                .ok_or((StatusCode::NOT_FOUND, "Channel not found".to_string()))
+        };
+
+        let before = load(&conn)?;
+        let (days, mode) = req.columns();
+        conn.execute(
+            "UPDATE channels SET retention_days = ?1, retention_mode = ?2 WHERE id = ?3",
+            rusqlite::params![days, mode, channel_id],
+        )
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Update channel: {}", e),
+            )
+        })?;
+        let after = load(&conn)?;
+
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::ChannelRetention, &channel_id)
+                .before(&before)
+                .after(&after),
+        )
+        .map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((after, entry))
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Task join: {}", e),
+        )
+    })??;
+
+    audit::notify_admins(&state, entry);
+
+    Ok(Json(retention))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryRetentionResponse {
+    pub category_id: String,
+    pub policy: Option<RetentionPolicy>,
+}
+
+/// PUT /api/categories/{id}/retention — Set or clear the retention policy inherited by
+/// a category's channels (requires MANAGE_CHANNELS).
+pub async fn set_category_retention(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(category_id): Path<String>,
+    Json(req): Json<SetRetentionRequest>,
+) -> Result<Json<CategoryRetentionResponse>, (StatusCode, String)> {
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        Permissions::MANAGE_CHANNELS,
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+    req.validate()?;
+
+    let db = state.db.clone();
+    let actor_id = claims.sub.clone();
+
+    let (retention, entry) = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+        let load = |conn: &rusqlite::Connection| {
+            conn.query_row(
+                "SELECT retention_days, retention_mode FROM categories WHERE id = ?1",
+                [&category_id],
+                |row| {
+                    Ok(CategoryRetentionResponse {
+                        category_id: category_id.clone(),
+                        policy: RetentionPolicy::from_columns(row.get(0)?, row.get(1)?),
+                    })
+                },
+            )
+            .map_err(|_| (StatusCode::NOT_FOUND, "Category not found".to_string()))
+        };
+
+        let before = load(&conn)?;
+        let (days, mode) = req.columns();
+        conn.execute(
+            "UPDATE categories SET retention_days = ?1, retention_mode = ?2 WHERE id = ?3",
+            rusqlite::params![days, mode, category_id],
+        )
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Update category: {}", e),
+            )
+        })?;
+        let after = load(&conn)?;
+
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::CategoryRetention, &category_id)
+                .before(&before)
+                .after(&after),
+        )
+        .map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((after, entry))
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Task join: {}", e),
+        )
+    })??;
+
+    audit::notify_admins(&state, entry);
+
+    Ok(Json(retention))
+}
+
+/// GET /api/retention/report — Dry run of the retention task: every channel's policy
+/// and how many messages the next run would remove (requires ADMIN).
+pub async fn get_retention_report(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<Json<RetentionReport>, (StatusCode, String)> {
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+        .await
+        .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let db = state.db.clone();
+    let report = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .read()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+        retention_report(&conn, Utc::now()).map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Retention report: {}", e),
+            )
+        })
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Task join: {}", e),
+        )
+    })??;
+
+    Ok(Json(report))
+}
@@ -0,0 +1,136 @@
+//! Message edit history.
+//!
+//! Each edit records the version it replaces in `message_revisions`, so the author and
+//! moderators can see what a message said before. Revisions are pruned by the message
+//! retention task after `[messages] revision_retention_days`.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::auth::middleware::Claims;
+use crate::roles::permissions::{check_channel_permission, Permissions};
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct RevisionResponse {
+    pub content: String,
+    /// When this version was posted or last edited
+    pub written_at: String,
+    /// When an edit replaced this version
+    pub replaced_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevisionsResponse {
+    pub message_id: String,
+    /// Prior versions, oldest first
+    pub revisions: Vec<RevisionResponse>,
+}
+
+/// Save the current content of a message as a revision before an edit replaces it.
+pub(crate) fn record_revision(
+    conn: &rusqlite::Connection,
+    message_id: i64,
+    replaced_at: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO message_revisions (message_id, content_text, written_at, replaced_at)
+         SELECT id, content_text, COALESCE(edit_timestamp, created_at), ?2 FROM messages WHERE id = ?1",
+        rusqlite::params![message_id, replaced_at],
+    )?;
+    Ok(())
+}
+
+/// Delete revisions replaced more than `days` days before `now` (0 keeps them forever).
+/// Returns how many were deleted.
+pub fn prune_revisions(
+    conn: &rusqlite::Connection,
+    now: DateTime<Utc>,
+    days: u32,
+) -> rusqlite::Result<u64> {
+    if days == 0 {
+        return Ok(0);
+    }
+    let cutoff = (now - Duration::days(days as i64)).to_rfc3339();
+    let deleted = conn.execute(
+        "DELETE FROM message_revisions WHERE replaced_at < ?1",
+        [cutoff],
+    )?;
+    Ok(deleted as u64)
+}
+
+/// GET /api/channels/{channel_id}/messages/{message_id}/revisions
+/// Edit history of a message. Visible to its sender and anyone with MANAGE_MESSAGES in
+/// the channel (ADMIN included), checked against current roles rather than the token.
+pub async fn get_revisions(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path((channel_id, message_id)): Path<(String, String)>,
+) -> Result<Json<RevisionsResponse>, StatusCode> {
+    let db = state.db.clone();
+    let user_id = claims.sub.clone();
+    let is_owner = claims.is_owner;
+
+    let revisions = tokio::task::spawn_blocking(move || {
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let sender_pubkey: String = conn
+            .query_row(
+                "SELECT lower(hex(public_key)) FROM users WHERE id = ?1",
+                rusqlite::params![user_id],
+                |row| row.get(0),
+            )
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let msg_id: i64 = message_id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+        let row_pubkey: String = conn
+            .query_row(
+                "SELECT sender_pubkey FROM messages WHERE id = ?1 AND channel_id = ?2",
+                rusqlite::params![msg_id, channel_id],
+                |row| row.get(0),
+            )
+            .map_err(|_| StatusCode::NOT_FOUND)?;
+
+        if row_pubkey != sender_pubkey {
+            check_channel_permission(
+                &conn,
+                &user_id,
+                is_owner,
+                &channel_id,
+                Permissions::MANAGE_MESSAGES,
+            )?;
+        }
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT content_text, written_at, replaced_at FROM message_revisions
+                 WHERE message_id = ?1 ORDER BY id",
+            )
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let revisions = stmt
+            .query_map([msg_id], |row| {
+                Ok(RevisionResponse {
+                    content: row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+                    written_at: row.get(1)?,
+                    replaced_at: row.get(2)?,
+                })
+            })
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        Ok::<_, StatusCode>(RevisionsResponse {
+            message_id,
+            revisions,
+        })
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+
+    Ok(Json(revisions))
+}
@@ -0,0 +1,220 @@
+//! REST endpoint for full-text message search.
+//!
+//! Message text is indexed in the `messages_fts` FTS5 table (kept in sync by triggers,
+//! see migration 12). Queries combine free-text terms with optional filters, and results
+//! are limited to channels where the caller has VIEW_CHANNEL and READ_HISTORY.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDate;
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::middleware::Claims;
+use crate::chat::messages::{
+    load_reactions, message_from_row, MessageResponse, MESSAGE_COLUMNS, MESSAGE_FROM,
+};
+use crate::roles::permissions::{compute_channel_permissions, Permissions};
+use crate::state::AppState;
+
+/// Default page size for search results.
+const DEFAULT_LIMIT: u32 = 25;
+/// Maximum page size for search results.
+const MAX_LIMIT: u32 = 100;
+/// Maximum query length (chars).
+const MAX_QUERY_LENGTH: usize = 500;
+
+// --- Request / Response types ---
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResponse {
+    pub messages: Vec<MessageResponse>,
+    pub has_more: bool,
+}
+
+/// A parsed search query: free-text terms and filters.
+#[derive(Debug, Default, PartialEq)]
+struct ParsedQuery {
+    terms: Vec<String>,
+    from: Option<String>,
+    channel: Option<String>,
+    has_attachment: bool,
+    before: Option<NaiveDate>,
+    after: Option<NaiveDate>,
+}
+
+// --- Handlers ---
+
+/// GET /api/search?q={query}&limit={n}&offset={n}
+/// Search message text. Supported filters in `q`:
+/// `from:<user id or display name>`, `in:<channel id or name>`, `has:attachment`,
+/// `before:YYYY-MM-DD` and `after:YYYY-MM-DD` (both exclusive).
+/// Results are newest first. JWT auth required.
+pub async fn search_messages(
+    State(state): State<AppState>,
+    claims: Claims,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<SearchResponse>, StatusCode> {
+    if query.q.len() > MAX_QUERY_LENGTH {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let parsed = parse_query(&query.q)?;
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+
+    let db = state.db.clone();
+    let user_id = claims.sub.clone();
+    let is_owner = claims.is_owner;
+
+    let result = tokio::task::spawn_blocking(move || {
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let channels = readable_channels(&conn, &user_id, is_owner)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if channels.is_empty() {
+            return Ok(SearchResponse {
+                messages: vec![],
+                has_more: false,
+            });
+        }
+
+        let mut sql = format!(
+            "SELECT {} {} WHERE m.deleted = 0 AND m.channel_id IN ({})",
+            MESSAGE_COLUMNS,
+            MESSAGE_FROM,
+            vec!["?"; channels.len()].join(", ")
+        );
+        let mut params: Vec<Value> = channels.into_iter().map(Value::Text).collect();
+
+        if !parsed.terms.is_empty() {
+            sql.push_str(" AND m.id IN (SELECT rowid FROM messages_fts WHERE messages_fts MATCH ?)");
+            params.push(Value::Text(fts_match_expression(&parsed.terms)));
+        }
+        if let Some(from) = parsed.from {
+            sql.push_str(
+                " AND m.sender_pubkey IN (SELECT lower(hex(public_key)) FROM users
+                   WHERE id = ? OR display_name = ? COLLATE NOCASE)",
+            );
+            params.push(Value::Text(from.clone()));
+            params.push(Value::Text(from));
+        }
+        if let Some(channel) = parsed.channel {
+            sql.push_str(
+                " AND m.channel_id IN (SELECT id FROM channels WHERE id = ? OR name = ? COLLATE NOCASE)",
+            );
+            params.push(Value::Text(channel.clone()));
+            params.push(Value::Text(channel));
+        }
+        if parsed.has_attachment {
+            sql.push_str(
+                " AND m.block_refs_json IS NOT NULL AND m.block_refs_json NOT IN ('', '[]')",
+            );
+        }
+        // created_at is RFC 3339 or SQLite datetime; both start with YYYY-MM-DD
+        if let Some(before) = parsed.before {
+            sql.push_str(" AND substr(m.created_at, 1, 10) < ?");
+            params.push(Value::Text(before.format("%Y-%m-%d").to_string()));
+        }
+        if let Some(after) = parsed.after {
+            sql.push_str(" AND substr(m.created_at, 1, 10) > ?");
+            params.push(Value::Text(after.format("%Y-%m-%d").to_string()));
+        }
+
+        sql.push_str(" ORDER BY m.id DESC LIMIT ? OFFSET ?");
+        params.push(Value::Integer((limit + 1) as i64));
+        params.push(Value::Integer(offset as i64));
+
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let messages: Vec<MessageResponse> = stmt
+            .query_map(rusqlite::params_from_iter(params), message_from_row)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let has_more = messages.len() > limit as usize;
+        let mut messages: Vec<MessageResponse> =
+            messages.into_iter().take(limit as usize).collect();
+        load_reactions(&conn, &mut messages);
+
+        Ok::<_, StatusCode>(SearchResponse { messages, has_more })
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+
+    Ok(Json(result))
+}
+
+// --- Helpers ---
+
+/// Split a query into terms and `key:value` filters.
+/// Unknown `has:` values and malformed dates are rejected; other words are search terms.
+fn parse_query(q: &str) -> Result<ParsedQuery, StatusCode> {
+    let mut parsed = ParsedQuery::default();
+
+    for word in q.split_whitespace() {
+        match word.split_once(':') {
+            Some(("from", value)) if !value.is_empty() => parsed.from = Some(value.to_string()),
+            Some(("in", value)) if !value.is_empty() => {
+                parsed.channel = Some(value.trim_start_matches('#').to_string())
+            }
+            Some(("has", "attachment")) => parsed.has_attachment = true,
+            Some(("has", _)) => return Err(StatusCode::BAD_REQUEST),
+            Some(("before", value)) => parsed.before = Some(parse_date(value)?),
+            Some(("after", value)) => parsed.after = Some(parse_date(value)?),
+            _ => parsed.terms.push(word.to_string()),
+        }
+    }
+
+    if parsed == ParsedQuery::default() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(parsed)
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, StatusCode> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| StatusCode::BAD_REQUEST)
+}
+
+/// Quote each term so FTS5 operators and punctuation in user input are matched literally.
+/// Terms are ANDed together.
+fn fts_match_expression(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// IDs of channels where the user has VIEW_CHANNEL and READ_HISTORY (overrides applied).
+fn readable_channels(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+    is_owner: bool,
+) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT id FROM channels")?;
+    let channel_ids: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut readable = Vec::new();
+    for channel_id in channel_ids {
+        let perms = compute_channel_permissions(conn, user_id, is_owner, &channel_id)?;
+        if perms.contains(Permissions::VIEW_CHANNEL | Permissions::READ_HISTORY) {
+            readable.push(channel_id);
+        }
+    }
+    Ok(readable)
+}
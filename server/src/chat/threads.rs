@@ -0,0 +1,466 @@
+//! REST endpoints for message threads: sub-conversations rooted at a channel message.
+//!
+//! Thread messages are stored in `messages` with `thread_id` set and numbered by their
+//! own per-thread `server_sequence`. Each open (non-archived) thread has its own
+//! gossipsub topic; archiving a thread unsubscribes it.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::middleware::Claims;
+use crate::channels::slowmode;
+use crate::chat::broadcast;
+use crate::chat::messages::{
+    insert_chat_message, message_capabilities, query_history, validate_content,
+    CreateMessageRequest, HistoryQuery, HistoryResponse, MessageResponse,
+};
+use crate::moderation::abuse::ActivityEvent;
+use crate::moderation::trust;
+use crate::p2p::publish::{self, thread_topic};
+use crate::p2p::SwarmCommand;
+use crate::proto::chat as proto_chat;
+use crate::proto::p2p_proto::MessageType;
+use crate::roles::permissions::{check_channel_permission, Permissions};
+use crate::state::AppState;
+
+/// Maximum thread name length (chars).
+const MAX_THREAD_NAME_LENGTH: usize = 100;
+
+// --- Request / Response types ---
+
+#[derive(Debug, Serialize)]
+pub struct ThreadResponse {
+    pub id: String,
+    pub channel_id: String,
+    pub root_message_id: String,
+    pub name: String,
+    pub created_by: String,
+    pub archived: bool,
+    pub locked: bool,
+    pub message_count: u64,
+    pub last_message_at: Option<String>,
+    pub created_at: String,
+}
+
+impl ThreadResponse {
+    /// Convert to the protobuf Thread used in WS events.
+    pub fn to_proto(&self) -> proto_chat::Thread {
+        proto_chat::Thread {
+            id: self.id.clone(),
+            channel_id: self.channel_id.clone(),
+            root_message_id: self.root_message_id.clone(),
+            name: self.name.clone(),
+            created_by: self.created_by.clone(),
+            archived: self.archived,
+            locked: self.locked,
+            message_count: self.message_count,
+            last_message_at: self.last_message_at.clone().unwrap_or_default(),
+            created_at: self.created_at.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ThreadListResponse {
+    pub threads: Vec<ThreadResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateThreadRequest {
+    pub root_message_id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateThreadRequest {
+    pub name: Option<String>,
+    pub archived: Option<bool>,
+    pub locked: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListThreadsQuery {
+    /// List archived threads instead of open ones.
+    #[serde(default)]
+    pub archived: bool,
+}
+
+// --- Handlers ---
+
+/// POST /api/channels/{channel_id}/threads
+/// Start a thread on a channel message. Requires VIEW_CHANNEL | SEND_MESSAGES.
+/// Each message can root at most one thread.
+pub async fn create_thread(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(channel_id): Path<String>,
+    Json(body): Json<CreateThreadRequest>,
+) -> Result<(StatusCode, Json<ThreadResponse>), StatusCode> {
+    let name = validate_thread_name(&body.name)?;
+    let root_id: i64 = body
+        .root_message_id
+        .parse()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let db = state.db.clone();
+    let user_id = claims.sub.clone();
+    let is_owner = claims.is_owner;
+
+    let thread = tokio::task::spawn_blocking(move || {
+        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        check_channel_permission(
+            &conn,
+            &user_id,
+            is_owner,
+            &channel_id,
+            Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES,
+        )?;
+
+        // Root must be a live top-level message in this channel
+        let root_exists: bool = conn
+            .query_row(
+                "SELECT COUNT(*) FROM messages
+                 WHERE id = ?1 AND channel_id = ?2 AND deleted = 0 AND thread_id IS NULL",
+                rusqlite::params![root_id, channel_id],
+                |row| row.get::<_, i64>(0).map(|c| c > 0),
+            )
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if !root_exists {
+            return Err(StatusCode::NOT_FOUND);
+        }
+
+        let already_threaded: bool = conn
+            .query_row(
+                "SELECT COUNT(*) FROM threads WHERE root_message_id = ?1",
+                [root_id],
+                |row| row.get::<_, i64>(0).map(|c| c > 0),
+            )
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if already_threaded {
+            return Err(StatusCode::CONFLICT);
+        }
+
+        insert_thread(&conn, &channel_id, root_id, &name, &user_id)
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+
+    announce_thread(&state, &thread);
+
+    Ok((StatusCode::CREATED, Json(thread)))
+}
+
+/// GET /api/channels/{channel_id}/threads?archived={bool}
+/// List a channel's open (or archived) threads, most recently active first.
+pub async fn list_threads(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(channel_id): Path<String>,
+    Query(query): Query<ListThreadsQuery>,
+) -> Result<Json<ThreadListResponse>, StatusCode> {
+    let db = state.db.clone();
+    let user_id = claims.sub.clone();
+    let is_owner = claims.is_owner;
+
+    let threads = tokio::task::spawn_blocking(move || {
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        check_channel_permission(&conn, &user_id, is_owner, &channel_id, Permissions::VIEW_CHANNEL)?;
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "{} WHERE channel_id = ?1 AND archived = ?2
+                 ORDER BY COALESCE(last_message_at, created_at) DESC",
+                THREAD_SELECT
+            ))
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let threads: Vec<ThreadResponse> = stmt
+            .query_map(rusqlite::params![channel_id, query.archived], thread_from_row)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok::<_, StatusCode>(threads)
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+
+    Ok(Json(ThreadListResponse { threads }))
+}
+
+/// PUT /api/threads/{thread_id}
+/// Rename, archive/unarchive, or lock/unlock a thread. The thread's creator may rename
+/// and archive an unlocked thread; locking, and any change to a locked thread,
+/// requires MANAGE_MESSAGES in the channel.
+pub async fn update_thread(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(thread_id): Path<String>,
+    Json(body): Json<UpdateThreadRequest>,
+) -> Result<Json<ThreadResponse>, StatusCode> {
+    let name = body.name.as_deref().map(validate_thread_name).transpose()?;
+
+    let db = state.db.clone();
+    let user_id = claims.sub.clone();
+    let is_owner = claims.is_owner;
+
+    let (before, thread) = tokio::task::spawn_blocking(move || {
+        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let before = load_thread(&conn, &thread_id)?;
+        let perms = check_channel_permission(
+            &conn,
+            &user_id,
+            is_owner,
+            &before.channel_id,
+            Permissions::VIEW_CHANNEL,
+        )?;
+
+        let can_manage = perms.contains(Permissions::MANAGE_MESSAGES);
+        let is_creator = before.created_by == user_id;
+        if body.locked.is_some() && !can_manage {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        if !can_manage && (!is_creator || before.locked) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "UPDATE threads SET name = COALESCE(?1, name), archived = COALESCE(?2, archived),
+                    locked = COALESCE(?3, locked), updated_at = ?4
+             WHERE id = ?5",
+            rusqlite::params![name, body.archived, body.locked, now, thread_id],
+        )
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let thread = load_thread(&conn, &thread_id)?;
+        Ok::<_, StatusCode>((before, thread))
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+
+    // Only open threads keep a gossipsub topic
+    if before.archived != thread.archived {
+        let topic = thread_topic(&state.server_peer_id, &thread.id);
+        let cmd = if thread.archived {
+            SwarmCommand::UnsubscribeTopic(topic)
+        } else {
+            SwarmCommand::SubscribeTopic(topic)
+        };
+        let _ = state.swarm_cmd_tx.send(cmd);
+    }
+    broadcast::broadcast_thread_updated(&state.connections, thread.to_proto());
+
+    Ok(Json(thread))
+}
+
+/// GET /api/threads/{thread_id}/messages?before={seq}&limit={n}
+/// Paginated thread history, ordered by the thread's own server_sequence.
+pub async fn get_thread_messages(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(thread_id): Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<HistoryResponse>, StatusCode> {
+    let db = state.db.clone();
+    let user_id = claims.sub.clone();
+    let is_owner = claims.is_owner;
+
+    let result = tokio::task::spawn_blocking(move || {
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let thread = load_thread(&conn, &thread_id)?;
+        check_channel_permission(
+            &conn,
+            &user_id,
+            is_owner,
+            &thread.channel_id,
+            Permissions::VIEW_CHANNEL | Permissions::READ_HISTORY,
+        )?;
+
+        query_history(&conn, &thread.channel_id, Some(&thread.id), &query)
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+
+    Ok(Json(result))
+}
+
+/// POST /api/threads/{thread_id}/messages
+/// Post in a thread. Same permissions and slow mode as the parent channel; archived
+/// threads reject posts (409) and locked threads only accept them from MANAGE_MESSAGES
+/// holders.
+pub async fn create_thread_message(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(thread_id): Path<String>,
+    Json(mut body): Json<CreateMessageRequest>,
+) -> Result<(StatusCode, Json<MessageResponse>), (StatusCode, String)> {
+    body.content = validate_content(&body.content).map_err(|s| (s, String::new()))?;
+    trust::require_capabilities(
+        &state,
+        &claims.sub,
+        claims.is_owner,
+        &message_capabilities(&body),
+    )
+    .await?;
+
+    let db = state.db.clone();
+    let user_id = claims.sub.clone();
+    let is_owner = claims.is_owner;
+
+    let (response, chat_message) = tokio::task::spawn_blocking(move || {
+        let status = |s: StatusCode| (s, String::new());
+        let mut conn = db
+            .lock()
+            .map_err(|_| status(StatusCode::INTERNAL_SERVER_ERROR))?;
+
+        let thread = load_thread(&conn, &thread_id).map_err(status)?;
+        let perms = check_channel_permission(
+            &conn,
+            &user_id,
+            is_owner,
+            &thread.channel_id,
+            Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES,
+        )
+        .map_err(status)?;
+        if thread.archived {
+            return Err(status(StatusCode::CONFLICT));
+        }
+        if thread.locked && !perms.contains(Permissions::MANAGE_MESSAGES) {
+            return Err(status(StatusCode::FORBIDDEN));
+        }
+        // Threads inherit the parent channel's slow mode, counted per thread
+        slowmode::check_slowmode(&conn, &thread.channel_id, Some(&thread.id), &user_id, perms)?;
+
+        let tx = conn
+            .transaction()
+            .map_err(|_| status(StatusCode::INTERNAL_SERVER_ERROR))?;
+        let result = insert_chat_message(
+            &tx,
+            &thread.channel_id,
+            Some(&thread.id),
+            &user_id,
+            perms,
+            body,
+        )
+        .map_err(status)?;
+        record_thread_message(&tx, &thread.id, &Utc::now().to_rfc3339())
+            .map_err(|_| status(StatusCode::INTERNAL_SERVER_ERROR))?;
+        tx.commit()
+            .map_err(|_| status(StatusCode::INTERNAL_SERVER_ERROR))?;
+
+        Ok::<_, (StatusCode, String)>(result)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    state.abuse.report(ActivityEvent::ChannelMessage {
+        user_id: claims.sub,
+        channel_id: response.channel_id.clone(),
+        content: response.content.clone(),
+    });
+
+    // Publish on the thread topic, then broadcast NewMessageEvent (thread_id set)
+    let thread_id = response.thread_id.clone().unwrap_or_default();
+    publish::publish_thread_event(
+        &state,
+        &thread_id,
+        MessageType::Chat,
+        response.server_sequence,
+        &chat_message,
+    );
+    broadcast::broadcast_new_message(&state.connections, chat_message);
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+// --- Helpers ---
+
+const THREAD_SELECT: &str = "SELECT id, channel_id, root_message_id, name, created_by, archived,
+        locked, message_count, last_message_at, created_at
+ FROM threads";
+
+fn thread_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ThreadResponse> {
+    Ok(ThreadResponse {
+        id: row.get(0)?,
+        channel_id: row.get(1)?,
+        root_message_id: row.get::<_, i64>(2)?.to_string(),
+        name: row.get(3)?,
+        created_by: row.get(4)?,
+        archived: row.get(5)?,
+        locked: row.get(6)?,
+        message_count: row.get::<_, i64>(7)? as u64,
+        last_message_at: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+fn load_thread(conn: &rusqlite::Connection, thread_id: &str) -> Result<ThreadResponse, StatusCode> {
+    conn.query_row(
+        &format!("{} WHERE id = ?1", THREAD_SELECT),
+        [thread_id],
+        thread_from_row,
+    )
+    .optional()
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)
+}
+
+pub(crate) fn validate_thread_name(name: &str) -> Result<String, StatusCode> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_THREAD_NAME_LENGTH {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(name.to_string())
+}
+
+/// Create a thread rooted at `root_id` (an existing top-level message in the channel).
+pub(crate) fn insert_thread(
+    conn: &rusqlite::Connection,
+    channel_id: &str,
+    root_id: i64,
+    name: &str,
+    user_id: &str,
+) -> Result<ThreadResponse, StatusCode> {
+    let thread_id = Uuid::now_v7().to_string();
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO threads (id, channel_id, root_message_id, name, created_by, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+        rusqlite::params![thread_id, channel_id, root_id, name, user_id, now],
+    )
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    load_thread(conn, &thread_id)
+}
+
+/// Join a new thread's gossipsub topic, then broadcast ThreadCreatedEvent.
+pub(crate) fn announce_thread(state: &AppState, thread: &ThreadResponse) {
+    let topic = thread_topic(&state.server_peer_id, &thread.id);
+    let _ = state.swarm_cmd_tx.send(SwarmCommand::SubscribeTopic(topic));
+    broadcast::broadcast_thread_created(&state.connections, thread.to_proto());
+}
+
+/// Bump a thread's message count and activity time after a message is persisted
+/// (REST or gossip path).
+pub(crate) fn record_thread_message(
+    conn: &rusqlite::Connection,
+    thread_id: &str,
+    now: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE threads SET message_count = message_count + 1, last_message_at = ?1 WHERE id = ?2",
+        rusqlite::params![now, thread_id],
+    )?;
+    Ok(())
+}
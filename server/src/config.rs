@@ -53,6 +53,21 @@ pub struct Config {
     #[arg(skip)]
     #[serde(default)]
     pub turn: Option<TurnConfig>,
+
+    /// New-member probation gates (loaded from [trust] section in TOML)
+    #[arg(skip)]
+    #[serde(default)]
+    pub trust: Option<TrustConfig>,
+
+    /// Per-user request rate limits (loaded from [rate_limits] section in TOML)
+    #[arg(skip)]
+    #[serde(default = "default_rate_limits")]
+    pub rate_limits: Option<RateLimitConfig>,
+
+    /// Message retention settings (loaded from [messages] section in TOML)
+    #[arg(skip)]
+    #[serde(default)]
+    pub messages: Option<MessagesConfig>,
 }
 
 /// Configuration for the content-addressed block store.
@@ -142,6 +157,180 @@ fn default_credential_ttl() -> u64 {
     86400
 }
 
+/// Configuration for new-member probation. While on probation a member cannot use the
+/// gated capabilities; moderators can fast-track a member out of probation early.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustConfig {
+    /// Hours after registration that a member stays on probation (default: 24)
+    #[serde(default = "default_probation_hours")]
+    pub probation_hours: u32,
+
+    /// Gate posting links in messages (default: true)
+    #[serde(default = "default_true")]
+    pub links: bool,
+
+    /// Gate uploading blocks and attaching them to messages (default: true)
+    #[serde(default = "default_true")]
+    pub attachments: bool,
+
+    /// Gate starting new DM conversations (default: true)
+    #[serde(default = "default_true")]
+    pub direct_messages: bool,
+
+    /// Gate @role mentions, including @everyone (default: true)
+    #[serde(default = "default_true")]
+    pub mentions: bool,
+
+    /// Gate adding reactions (default: false)
+    #[serde(default)]
+    pub reactions: bool,
+}
+
+impl Default for TrustConfig {
+    fn default() -> Self {
+        Self {
+            probation_hours: 24,
+            links: true,
+            attachments: true,
+            direct_messages: true,
+            mentions: true,
+            reactions: false,
+        }
+    }
+}
+
+fn default_probation_hours() -> u32 {
+    24
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Token-bucket limits for REST routes and WebSocket requests, keyed by the
+/// authenticated user id (or the peer IP for unauthenticated requests).
+/// Auth and public identity routes keep their own per-IP limits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Master switch (default: true)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Posting and editing channel and thread messages
+    #[serde(default = "default_messages_limit")]
+    pub messages: RateLimit,
+
+    /// Adding and removing reactions
+    #[serde(default = "default_reactions_limit")]
+    pub reactions: RateLimit,
+
+    /// Typing indicators
+    #[serde(default = "default_typing_limit")]
+    pub typing: RateLimit,
+
+    /// Sending DMs and starting DM conversations
+    #[serde(default = "default_direct_messages_limit")]
+    pub direct_messages: RateLimit,
+
+    /// Block uploads
+    #[serde(default = "default_uploads_limit")]
+    pub uploads: RateLimit,
+
+    /// Every other REST route
+    #[serde(default = "default_api_limit")]
+    pub api: RateLimit,
+
+    /// WebSocket request payloads
+    #[serde(default = "default_ws_limit")]
+    pub ws: RateLimit,
+}
+
+/// A single token bucket: refills at `per_minute` and holds at most `burst` tokens.
+/// `per_minute = 0` leaves the bucket unlimited.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+impl RateLimit {
+    const fn new(per_minute: u32, burst: u32) -> Self {
+        Self { per_minute, burst }
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            messages: default_messages_limit(),
+            reactions: default_reactions_limit(),
+            typing: default_typing_limit(),
+            direct_messages: default_direct_messages_limit(),
+            uploads: default_uploads_limit(),
+            api: default_api_limit(),
+            ws: default_ws_limit(),
+        }
+    }
+}
+
+fn default_messages_limit() -> RateLimit {
+    RateLimit::new(30, 10)
+}
+
+fn default_reactions_limit() -> RateLimit {
+    RateLimit::new(60, 20)
+}
+
+fn default_typing_limit() -> RateLimit {
+    RateLimit::new(30, 10)
+}
+
+fn default_direct_messages_limit() -> RateLimit {
+    RateLimit::new(30, 10)
+}
+
+fn default_uploads_limit() -> RateLimit {
+    RateLimit::new(60, 20)
+}
+
+fn default_api_limit() -> RateLimit {
+    RateLimit::new(600, 120)
+}
+
+fn default_ws_limit() -> RateLimit {
+    RateLimit::new(600, 120)
+}
+
+/// Configuration for the message retention task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagesConfig {
+    /// Days to keep the edit history of messages; 0 keeps it forever (default: 90)
+    #[serde(default = "default_revision_retention_days")]
+    pub revision_retention_days: u32,
+
+    /// Interval in seconds between message retention runs (default: 3600 = 1 hour)
+    #[serde(default = "default_cleanup_interval")]
+    pub retention_interval_secs: u64,
+}
+
+impl Default for MessagesConfig {
+    fn default() -> Self {
+        Self {
+            revision_retention_days: 90,
+            retention_interval_secs: 3600,
+        }
+    }
+}
+
+fn default_revision_retention_days() -> u32 {
+    90
+}
+
+fn default_rate_limits() -> Option<RateLimitConfig> {
+    Some(RateLimitConfig::default())
+}
+
 fn default_p2p_config() -> Option<P2pConfig> {
     Some(P2pConfig::default())
 }
@@ -159,6 +348,9 @@ impl Default for Config {
             p2p: Some(P2pConfig::default()),
             blocks: None,
             turn: None,
+            trust: None,
+            rate_limits: Some(RateLimitConfig::default()),
+            messages: None,
         }
     }
 }
@@ -166,7 +358,7 @@ impl Default for Config {
 impl Config {
     /// Load config with layered precedence:
     /// built-in defaults < TOML file < env vars (UNITED_*) < CLI args
-    pub fn load() -> Result<Self, figment::Error> {
+    pub fn load() -> Result<Self, Box<figment::Error>> {
         let cli = Config::parse();
         let config_path = cli.config.clone();
 
@@ -176,6 +368,7 @@ impl Config {
             .merge(Env::prefixed("UNITED_"))
             .merge(Serialized::defaults(cli))
             .extract()
+            .map_err(Box::new)
     }
 }
 
@@ -221,6 +414,10 @@ pub fn generate_config_template() -> String {
 # relay_max_circuit_duration_secs = 1800 # 30 minutes per circuit
 # relay_max_circuit_bytes = 10485760     # 10 MB per circuit
 
+# Peer servers whose gossip is attributed to the users named in each event
+# (hex Ed25519 keys, as logged at their startup). This server is always trusted.
+# trusted_server_keys = []
+
 # ---- Block Storage (Content Distribution) ----
 # [blocks]
 
@@ -243,6 +440,39 @@ pub fn generate_config_template() -> String {
 # port = 3478
 # shared_secret = "CHANGE_ME_GENERATE_A_RANDOM_SECRET"
 # credential_ttl_secs = 86400  # 24 hours
+
+# ---- New-Member Probation ----
+# New accounts cannot use the gated capabilities until probation ends.
+# Moderators can fast-track a member early. Omit this section to disable probation.
+# [trust]
+# probation_hours = 24
+# links = true            # Links in messages
+# attachments = true      # Block uploads and message attachments
+# direct_messages = true  # Starting new DM conversations
+# mentions = true         # @role and @everyone mentions
+# reactions = false       # Adding reactions
+
+# ---- Rate Limits ----
+# Token buckets per authenticated user (per IP when unauthenticated). Each bucket
+# refills at per_minute and allows bursts of up to burst requests; per_minute = 0
+# removes that limit. Limited requests get 429 with a Retry-After header, or an
+# ErrorResponse with code 429 and retry_after_ms over WebSocket.
+# [rate_limits]
+# enabled = true
+# messages = { per_minute = 30, burst = 10 }         # Posting and editing messages
+# reactions = { per_minute = 60, burst = 20 }
+# typing = { per_minute = 30, burst = 10 }
+# direct_messages = { per_minute = 30, burst = 10 }  # DMs and new DM conversations
+# uploads = { per_minute = 60, burst = 20 }          # Block uploads
+# api = { per_minute = 600, burst = 120 }            # All other REST routes
+# ws = { per_minute = 600, burst = 120 }             # WebSocket requests
+
+# ---- Messages ----
+# Per-channel retention policies are set by moderators through the API; these
+# settings control the background task that applies them.
+# [messages]
+# revision_retention_days = 90   # Keep edit history this long (0 = forever)
+# retention_interval_secs = 3600
 "#
     .to_string()
 }
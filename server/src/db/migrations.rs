@@ -270,6 +270,339 @@ ALTER TABLE messages ADD COLUMN block_refs_json TEXT;
             "-- Migration 8: Voice Channels (Phase 8)
 
 ALTER TABLE channels ADD COLUMN max_participants INTEGER;
+",
+        ),
+        M::up(
+            "-- Migration 9: Per-channel permission overrides
+
+-- Allow/deny bits applied on top of server-wide role permissions for one channel.
+-- target_type is 'role' or 'user'; target_id is the role or user ID.
+CREATE TABLE channel_permission_overrides (
+    channel_id TEXT NOT NULL,
+    target_type TEXT NOT NULL CHECK (target_type IN ('role', 'user')),
+    target_id TEXT NOT NULL,
+    allow INTEGER NOT NULL DEFAULT 0,
+    deny INTEGER NOT NULL DEFAULT 0,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (channel_id, target_type, target_id),
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_channel_overrides_target ON channel_permission_overrides(target_type, target_id);
+",
+        ),
+        M::up(
+            "-- Migration 10: Expanded permission bits
+-- Map existing role bitmasks forward so nobody loses an ability that used to be ungated:
+-- viewing/reading channels, attaching files and reacting were open to all members, so
+-- @everyone gets VIEW_CHANNEL | READ_HISTORY | ATTACH_FILES | ADD_REACTIONS
+-- (0x20 | 0x40 | 0x1000 | 0x2000), matching Permissions::DEFAULT_EVERYONE on new servers.
+-- MENTION_EVERYONE (0x4000) stays opt-in, as it is for new servers.
+UPDATE roles SET permissions = permissions | 12384 WHERE is_default = 1;
+
+-- Moderator roles with KICK_MEMBERS (0x04) gain voice moderation:
+-- MUTE_MEMBERS | MOVE_MEMBERS (0x400 | 0x800).
+UPDATE roles SET permissions = permissions | 3072 WHERE (permissions & 4) != 0;
+
+-- ADMIN (0x10) implies everything, so role, invite and server management need no mapping.
+",
+        ),
+        M::up(
+            "-- Migration 11: Message threads
+
+-- A thread is a sub-conversation rooted at one channel message.
+-- Thread messages live in `messages` with thread_id set and their own
+-- server_sequence numbering (channel history only shows thread_id IS NULL).
+CREATE TABLE threads (
+    id TEXT PRIMARY KEY,
+    channel_id TEXT NOT NULL,
+    root_message_id INTEGER NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    created_by TEXT NOT NULL,
+    archived INTEGER NOT NULL DEFAULT 0,
+    locked INTEGER NOT NULL DEFAULT 0,
+    message_count INTEGER NOT NULL DEFAULT 0,
+    last_message_at TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE,
+    FOREIGN KEY (root_message_id) REFERENCES messages(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_threads_channel ON threads(channel_id, archived);
+
+ALTER TABLE messages ADD COLUMN thread_id TEXT;
+CREATE INDEX idx_messages_thread_seq ON messages(thread_id, server_sequence);
+",
+        ),
+        M::up(
+            "-- Migration 12: Full-text message search
+
+-- External-content FTS5 index over messages.content_text, keyed by messages.id.
+-- Only live messages with text are indexed; triggers keep it in sync on
+-- insert, edit, soft delete and hard delete.
+CREATE VIRTUAL TABLE messages_fts USING fts5(
+    content_text,
+    content='messages',
+    content_rowid='id',
+    tokenize='unicode61'
+);
+
+INSERT INTO messages_fts(rowid, content_text)
+    SELECT id, content_text FROM messages
+    WHERE deleted = 0 AND content_text IS NOT NULL;
+
+CREATE TRIGGER messages_fts_insert AFTER INSERT ON messages
+WHEN new.deleted = 0 AND new.content_text IS NOT NULL
+BEGIN
+    INSERT INTO messages_fts(rowid, content_text) VALUES (new.id, new.content_text);
+END;
+
+CREATE TRIGGER messages_fts_update AFTER UPDATE OF content_text, deleted ON messages
+BEGIN
+    INSERT INTO messages_fts(messages_fts, rowid, content_text)
+        SELECT 'delete', old.id, old.content_text
+        WHERE old.deleted = 0 AND old.content_text IS NOT NULL;
+    INSERT INTO messages_fts(rowid, content_text)
+        SELECT new.id, new.content_text
+        WHERE new.deleted = 0 AND new.content_text IS NOT NULL;
+END;
+
+CREATE TRIGGER messages_fts_delete AFTER DELETE ON messages
+WHEN old.deleted = 0 AND old.content_text IS NOT NULL
+BEGIN
+    INSERT INTO messages_fts(messages_fts, rowid, content_text)
+        VALUES ('delete', old.id, old.content_text);
+END;
+",
+        ),
+        M::up(
+            "-- Migration 13: Pinned messages
+
+CREATE TABLE pins (
+    message_id INTEGER PRIMARY KEY,
+    channel_id TEXT NOT NULL,
+    pinned_by TEXT NOT NULL,
+    pinned_at TEXT NOT NULL,
+    FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE,
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_pins_channel ON pins(channel_id, pinned_at);
+",
+        ),
+        M::up(
+            "-- Migration 14: Moderation audit log
+
+-- One row per privileged action. target_id may be empty for server-wide actions
+-- (e.g. reorders); before/after hold JSON snapshots of the changed object.
+CREATE TABLE audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    actor_id TEXT NOT NULL,
+    action TEXT NOT NULL,
+    target_type TEXT NOT NULL,
+    target_id TEXT NOT NULL DEFAULT '',
+    reason TEXT NOT NULL DEFAULT '',
+    before_json TEXT,
+    after_json TEXT,
+    evidence_message_ids TEXT NOT NULL DEFAULT '[]',
+    created_at TEXT NOT NULL
+);
+CREATE INDEX idx_audit_log_action ON audit_log(action, id);
+CREATE INDEX idx_audit_log_actor ON audit_log(actor_id, id);
+CREATE INDEX idx_audit_log_target ON audit_log(target_id, id);
+",
+        ),
+        M::up(
+            "-- Migration 15: Graduated sanctions
+
+-- Sanction history keyed by fingerprint (survives key rotation, like bans).
+-- Mutes always carry expires_at; kicks and warnings never expire.
+CREATE TABLE sanctions (
+    id TEXT PRIMARY KEY,
+    fingerprint TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK(kind IN ('warning', 'text_mute', 'voice_mute', 'kick', 'ban')),
+    reason TEXT NOT NULL DEFAULT '',
+    issued_by TEXT NOT NULL,
+    expires_at TEXT,
+    revoked_at TEXT,
+    revoked_by TEXT,
+    created_at TEXT NOT NULL
+);
+CREATE INDEX idx_sanctions_fingerprint ON sanctions(fingerprint, created_at);
+",
+        ),
+        M::up(
+            "-- Migration 16: Shared ban lists
+
+-- Other servers whose signed ban lists an admin has imported. Sources are pinned
+-- by public key; re-importing a list from the same key refreshes it.
+CREATE TABLE ban_list_sources (
+    id TEXT PRIMARY KEY,
+    public_key TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    url TEXT,
+    generated_at TEXT NOT NULL,
+    imported_by TEXT NOT NULL,
+    imported_at TEXT NOT NULL
+);
+
+-- Latest entries of each source with the local decision. Nothing is banned
+-- locally until an admin picks 'import'.
+CREATE TABLE ban_list_entries (
+    source_id TEXT NOT NULL,
+    fingerprint TEXT NOT NULL,
+    reason TEXT NOT NULL DEFAULT '',
+    banned_at TEXT NOT NULL,
+    expires_at TEXT,
+    banned_by TEXT NOT NULL DEFAULT '',
+    decision TEXT NOT NULL DEFAULT 'pending' CHECK(decision IN ('pending', 'import', 'ignore', 'watch')),
+    decided_by TEXT,
+    decided_at TEXT,
+    PRIMARY KEY (source_id, fingerprint),
+    FOREIGN KEY (source_id) REFERENCES ban_list_sources(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_ban_list_entries_fingerprint ON ban_list_entries(fingerprint);
+",
+        ),
+        M::up(
+            "-- Migration 17: Behavioral abuse flags
+
+-- Accounts flagged by the background abuse analyzer for admin review. A repeat
+-- detection of the same kind bumps occurrences on the open flag. Flags never
+-- trigger sanctions on their own.
+CREATE TABLE user_flags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    occurrences INTEGER NOT NULL DEFAULT 1,
+    status TEXT NOT NULL DEFAULT 'open' CHECK(status IN ('open', 'dismissed', 'reviewed')),
+    created_at TEXT NOT NULL,
+    last_seen_at TEXT NOT NULL,
+    reviewed_by TEXT,
+    reviewed_at TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_user_flags_status ON user_flags(status, id);
+CREATE INDEX idx_user_flags_user ON user_flags(user_id, kind, status);
+",
+        ),
+        M::up(
+            "-- Migration 18: New-member probation fast-track
+
+-- Set when a moderator ends a member's probation early ([trust] in config).
+ALTER TABLE users ADD COLUMN trusted_at TEXT;
+ALTER TABLE users ADD COLUMN trusted_by TEXT;
+",
+        ),
+        M::up(
+            "-- Migration 19: Invitation chains
+
+-- The invite code a member registered with. Codes may be deleted later, so this
+-- is not a foreign key; invite_uses keeps the inviter.
+ALTER TABLE users ADD COLUMN invited_by_invite TEXT;
+-- Set when a moderator revokes a member's invite privileges. Their outstanding
+-- invites stop working and they cannot create new ones.
+ALTER TABLE users ADD COLUMN invites_revoked_at TEXT;
+ALTER TABLE users ADD COLUMN invites_revoked_by TEXT;
+
+-- Every redemption of an invite code, including by existing members joining again.
+CREATE TABLE invite_uses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    code TEXT NOT NULL,
+    inviter_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    used_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_invite_uses_code ON invite_uses(code, id);
+CREATE INDEX idx_invite_uses_inviter ON invite_uses(inviter_id);
+CREATE INDEX idx_invite_uses_user ON invite_uses(user_id);
+",
+        ),
+        M::up(
+            "-- Migration 20: Per-channel slow mode
+
+-- Minimum seconds between a member's messages in the channel (0 = off).
+ALTER TABLE channels ADD COLUMN slowmode_secs INTEGER NOT NULL DEFAULT 0;
+-- Finds a sender's latest message in a channel for the cooldown check.
+CREATE INDEX idx_messages_channel_sender ON messages(channel_id, sender_pubkey, created_at);
+",
+        ),
+        M::up(
+            "-- Migration 21: Message retention policies
+
+-- Messages older than retention_days are removed by the retention task.
+-- retention_mode is 'delete' (hard delete) or 'tombstone' (content scrubbed, row kept).
+-- A channel with NULL retention_days inherits its category's policy; 0 keeps forever.
+ALTER TABLE channels ADD COLUMN retention_days INTEGER;
+ALTER TABLE channels ADD COLUMN retention_mode TEXT;
+ALTER TABLE categories ADD COLUMN retention_days INTEGER;
+ALTER TABLE categories ADD COLUMN retention_mode TEXT;
+",
+        ),
+        M::up(
+            "-- Migration 22: Message edit history
+
+-- Each row is a version of a message that an edit replaced.
+-- written_at is when that version was posted or last edited; replaced_at is when
+-- the edit replaced it. Pruned after [messages] revision_retention_days.
+CREATE TABLE message_revisions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    message_id INTEGER NOT NULL,
+    content_text TEXT,
+    written_at TEXT NOT NULL,
+    replaced_at TEXT NOT NULL,
+    FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_message_revisions_message ON message_revisions(message_id, id);
+CREATE INDEX idx_message_revisions_replaced ON message_revisions(replaced_at);
+",
+        ),
+        M::up(
+            "-- Migration 23: Per-timeline sequence counters
+
+-- server_sequence is taken from these counters instead of MAX(server_sequence) + 1, so
+-- assigning one is O(1) and numbers are never reused after messages are hard-deleted.
+-- A channel's main timeline has thread_id ''; each thread has its own counter.
+CREATE TABLE channel_sequences (
+    channel_id TEXT NOT NULL,
+    thread_id TEXT NOT NULL DEFAULT '',
+    last_sequence INTEGER NOT NULL,
+    PRIMARY KEY (channel_id, thread_id),
+    FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
+INSERT INTO channel_sequences (channel_id, thread_id, last_sequence)
+    SELECT channel_id, COALESCE(thread_id, ''), MAX(server_sequence) FROM messages
+    WHERE channel_id IN (SELECT id FROM channels)
+    GROUP BY channel_id, COALESCE(thread_id, '');
+
+CREATE TABLE dm_sequences (
+    conversation_id TEXT PRIMARY KEY,
+    last_sequence INTEGER NOT NULL,
+    FOREIGN KEY (conversation_id) REFERENCES dm_conversations(id) ON DELETE CASCADE
+);
+INSERT INTO dm_sequences (conversation_id, last_sequence)
+    SELECT conversation_id, MAX(server_sequence) FROM dm_messages
+    WHERE conversation_id IN (SELECT id FROM dm_conversations)
+    GROUP BY conversation_id;
+
+-- A sequence number is used once per timeline.
+CREATE UNIQUE INDEX idx_messages_timeline_seq
+    ON messages(channel_id, COALESCE(thread_id, ''), server_sequence);
+DROP INDEX idx_dm_messages_conv_seq;
+CREATE UNIQUE INDEX idx_dm_messages_conv_seq ON dm_messages(conversation_id, server_sequence);
+",
+        ),
+        M::up(
+            "-- Migration 24: Admin signatures on bans
+
+-- banned_key is the banned member's public key (hex) when the ban was issued.
+-- admin_signature is the issuing admin's Ed25519 signature (hex) over the server key,
+-- banned_key, reason and created_at; NULL for bans that were not signed.
+ALTER TABLE bans ADD COLUMN banned_key TEXT;
+ALTER TABLE bans ADD COLUMN admin_signature TEXT;
+ALTER TABLE ban_list_entries ADD COLUMN banned_key TEXT;
+ALTER TABLE ban_list_entries ADD COLUMN admin_signature TEXT;
 ",
         ),
     ])
@@ -1,17 +1,97 @@
 pub mod migrations;
 pub mod models;
+pub mod sequences;
 
-use rusqlite::Connection;
+use rusqlite::{Connection, OpenFlags};
+use std::ops::Deref;
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, LockResult, Mutex, MutexGuard};
+use std::time::Duration;
 
-/// Type alias for the shared database connection.
-/// rusqlite is synchronous — we wrap in Arc<Mutex> for thread safety
-/// with tokio::task::spawn_blocking for DB operations.
-pub type DbPool = Arc<Mutex<Connection>>;
+/// Read-only connections opened next to the writer.
+pub const READ_CONNECTIONS: usize = 4;
+
+/// How long a connection waits on a locked database before failing with SQLITE_BUSY.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Type alias for the shared database pool.
+/// rusqlite is synchronous — DB operations run in tokio::task::spawn_blocking.
+pub type DbPool = Arc<Pool>;
+
+/// One writer connection plus a pool of read-only connections.
+///
+/// With WAL enabled, readers see the last committed state and never wait for the
+/// writer, so history reads, permission checks and lookups go through `read()` while
+/// everything that writes goes through `lock()`. Work that mixes checks and writes
+/// stays on the writer so it runs against a single consistent connection.
+pub struct Pool {
+    writer: Mutex<Connection>,
+    readers: Mutex<Vec<Connection>>,
+    reader_returned: Condvar,
+}
+
+/// The read pool's mutex was poisoned by a panicking thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolPoisoned;
+
+impl std::fmt::Display for PoolPoisoned {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("read pool poisoned")
+    }
+}
+
+impl std::error::Error for PoolPoisoned {}
+
+impl Pool {
+    /// Lock the writer connection. Use for anything that modifies the database.
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, Connection>> {
+        self.writer.lock()
+    }
+
+    /// Take a read-only connection, waiting for one to be returned if all are in use.
+    pub fn read(&self) -> Result<ReadConnection<'_>, PoolPoisoned> {
+        let mut readers = self.readers.lock().map_err(|_| PoolPoisoned)?;
+        loop {
+            if let Some(conn) = readers.pop() {
+                return Ok(ReadConnection {
+                    pool: self,
+                    conn: Some(conn),
+                });
+            }
+            readers = self
+                .reader_returned
+                .wait(readers)
+                .map_err(|_| PoolPoisoned)?;
+        }
+    }
+}
+
+/// A read-only connection borrowed from the pool; returned when dropped.
+pub struct ReadConnection<'a> {
+    pool: &'a Pool,
+    conn: Option<Connection>,
+}
+
+impl Deref for ReadConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection present until drop")
+    }
+}
+
+impl Drop for ReadConnection<'_> {
+    fn drop(&mut self) {
+        if let (Some(conn), Ok(mut readers)) = (self.conn.take(), self.pool.readers.lock()) {
+            readers.push(conn);
+            self.pool.reader_returned.notify_one();
+        }
+    }
+}
 
 /// Initialize the SQLite database: create data directory if needed,
-/// open (or create) the database file, enable WAL mode, and run migrations.
+/// open (or create) the database file, enable WAL mode, run migrations,
+/// and open the read-only connections.
 pub fn init_db(data_dir: &str) -> Result<DbPool, Box<dyn std::error::Error>> {
     // Ensure data directory exists
     std::fs::create_dir_all(data_dir)?;
@@ -19,16 +99,39 @@ pub fn init_db(data_dir: &str) -> Result<DbPool, Box<dyn std::error::Error>> {
     let db_path = Path::new(data_dir).join("united.db");
     let mut conn = Connection::open(&db_path)?;
 
-    // Enable WAL mode for better concurrent read performance
+    // Enable WAL mode so readers don't block the writer (or each other)
     conn.pragma_update(None, "journal_mode", "WAL")?;
     // Enable foreign key enforcement
     conn.pragma_update(None, "foreign_keys", "ON")?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
 
     // Run migrations
     let migrations = migrations::migrations();
     migrations.to_latest(&mut conn)?;
 
-    tracing::info!("Database initialized at {}", db_path.display());
+    // Readers are opened after migrations so they see the final schema
+    let readers = (0..READ_CONNECTIONS)
+        .map(|_| {
+            let reader = Connection::open_with_flags(
+                &db_path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY
+                    | OpenFlags::SQLITE_OPEN_URI
+                    | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )?;
+            reader.busy_timeout(BUSY_TIMEOUT)?;
+            Ok(reader)
+        })
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    tracing::info!(
+        "Database initialized at {} ({} read connections)",
+        db_path.display(),
+        READ_CONNECTIONS
+    );
 
-    Ok(Arc::new(Mutex::new(conn)))
+    Ok(Arc::new(Pool {
+        writer: Mutex::new(conn),
+        readers: Mutex::new(readers),
+        reader_returned: Condvar::new(),
+    }))
 }
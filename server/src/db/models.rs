@@ -1,5 +1,5 @@
-/// Database row types for all tables.
-/// These correspond 1:1 to the SQLite schema defined in migrations.rs.
+//! Database row types for all tables.
+//! These correspond 1:1 to the SQLite schema defined in migrations.rs.
 
 /// User record in the users table
 #[derive(Debug, Clone)]
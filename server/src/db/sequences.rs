@@ -0,0 +1,36 @@
+//! Per-timeline `server_sequence` counters.
+//!
+//! Every channel timeline (the channel itself, and each thread in it) and every DM
+//! conversation numbers its messages 1, 2, 3, ... Numbers come from the
+//! `channel_sequences` and `dm_sequences` counters. Call these in the same transaction
+//! as the insert that uses the number, so a rolled-back insert doesn't consume it and
+//! the counter can't advance without its message.
+
+use rusqlite::Connection;
+
+/// Claim the next sequence number of a channel's main timeline (`thread_id` None) or of
+/// one of its threads.
+pub fn next_channel_sequence(
+    conn: &Connection,
+    channel_id: &str,
+    thread_id: Option<&str>,
+) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "INSERT INTO channel_sequences (channel_id, thread_id, last_sequence) VALUES (?1, ?2, 1)
+         ON CONFLICT (channel_id, thread_id) DO UPDATE SET last_sequence = last_sequence + 1
+         RETURNING last_sequence",
+        rusqlite::params![channel_id, thread_id.unwrap_or("")],
+        |row| row.get(0),
+    )
+}
+
+/// Claim the next sequence number of a DM conversation.
+pub fn next_dm_sequence(conn: &Connection, conversation_id: &str) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "INSERT INTO dm_sequences (conversation_id, last_sequence) VALUES (?1, 1)
+         ON CONFLICT (conversation_id) DO UPDATE SET last_sequence = last_sequence + 1
+         RETURNING last_sequence",
+        [conversation_id],
+        |row| row.get(0),
+    )
+}
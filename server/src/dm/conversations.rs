@@ -12,6 +12,8 @@ use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::auth::middleware::Claims;
+use crate::moderation::abuse::ActivityEvent;
+use crate::moderation::trust::{check_capabilities, Capability};
 use crate::proto::dm as proto_dm;
 use crate::proto::ws::{envelope::Payload, Envelope};
 use crate::state::AppState;
@@ -34,6 +36,33 @@ pub struct ConversationResponse {
     pub last_message_at: Option<String>,
 }
 
+impl ConversationResponse {
+    /// Convert to the protobuf DmConversation used in WS responses.
+    pub fn to_proto(&self) -> proto_dm::DmConversation {
+        proto_dm::DmConversation {
+            id: self.id.clone(),
+            participant_a_pubkey: self.participant_a_pubkey.clone(),
+            participant_b_pubkey: self.participant_b_pubkey.clone(),
+            participant_a_display_name: self.participant_a_display_name.clone(),
+            participant_b_display_name: self.participant_b_display_name.clone(),
+            created_at: sqlite_datetime_millis(&self.created_at),
+            last_message_at: self
+                .last_message_at
+                .as_deref()
+                .map(sqlite_datetime_millis)
+                .unwrap_or(0),
+            last_message_preview: String::new(),
+        }
+    }
+}
+
+/// Parse a SQLite `datetime('now')` timestamp ("YYYY-MM-DD HH:MM:SS", UTC) into Unix millis.
+fn sqlite_datetime_millis(value: &str) -> u64 {
+    chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc().timestamp_millis() as u64)
+        .unwrap_or(0)
+}
+
 /// POST /api/dm/conversations — Create or get a DM conversation.
 /// JWT auth required. Body: { "recipient_pubkey": "<hex ed25519>" }.
 /// Returns existing conversation if one already exists between the two users.
@@ -41,13 +70,17 @@ pub async fn create_conversation(
     State(state): State<AppState>,
     claims: Claims,
     Json(body): Json<CreateConversationRequest>,
-) -> Result<(StatusCode, Json<ConversationResponse>), StatusCode> {
+) -> Result<(StatusCode, Json<ConversationResponse>), (StatusCode, String)> {
     let db = state.db.clone();
     let user_id = claims.sub.clone();
+    let is_owner = claims.is_owner;
+    let trust_config = state.trust_config.clone();
     let recipient_pubkey = body.recipient_pubkey.to_lowercase();
 
     let result = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
 
         // Look up sender's pubkey and display_name
         let (sender_pubkey, sender_display_name): (String, String) = conn
@@ -56,11 +89,11 @@ pub async fn create_conversation(
                 rusqlite::params![user_id],
                 |row| Ok((row.get(0)?, row.get(1)?)),
             )
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query sender: {}", e)))?;
 
         // Cannot DM yourself
         if sender_pubkey == recipient_pubkey {
-            return Err(StatusCode::BAD_REQUEST);
+            return Err((StatusCode::BAD_REQUEST, "Cannot DM yourself".to_string()));
         }
 
         // Validate recipient exists in users table
@@ -70,7 +103,7 @@ pub async fn create_conversation(
                 rusqlite::params![recipient_pubkey],
                 |row| row.get(0),
             )
-            .map_err(|_| StatusCode::NOT_FOUND)?;
+            .map_err(|_| (StatusCode::NOT_FOUND, "Recipient not found".to_string()))?;
 
         // Normalize participant order: lexicographically smaller pubkey is participant_a
         let (participant_a, participant_b, display_a, display_b) =
@@ -117,13 +150,22 @@ pub async fn create_conversation(
             ));
         }
 
+        // Starting a conversation is gated during new-member probation
+        check_capabilities(
+            &conn,
+            trust_config.as_ref(),
+            &user_id,
+            is_owner,
+            &[Capability::DirectMessages],
+        )?;
+
         // Create new conversation
         let conv_id = uuid::Uuid::now_v7().to_string();
         conn.execute(
             "INSERT INTO dm_conversations (id, participant_a, participant_b) VALUES (?1, ?2, ?3)",
             rusqlite::params![conv_id, participant_a, participant_b],
         )
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Insert conversation: {}", e)))?;
 
         let created_at: String = conn
             .query_row(
@@ -131,7 +173,7 @@ pub async fn create_conversation(
                 rusqlite::params![conv_id],
                 |row| row.get(0),
             )
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query conversation: {}", e)))?;
 
         Ok((
             true,
@@ -149,11 +191,16 @@ pub async fn create_conversation(
         ))
     })
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
     let (is_new, response, sender_pubkey, recipient_pubkey) = result;
 
     if is_new {
+        state.abuse.report(ActivityEvent::DmConversationCreated {
+            user_id: claims.sub.clone(),
+            recipient_pubkey: recipient_pubkey.clone(),
+        });
+
         // Broadcast DmConversationCreatedEvent to BOTH participants
         let now_millis = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -173,6 +220,7 @@ pub async fn create_conversation(
 
         let envelope = Envelope {
             request_id: String::new(),
+            seq: 0,
             payload: Some(Payload::DmConversationCreatedEvent(
                 proto_dm::DmConversationCreatedEvent {
                     conversation: Some(proto_conv),
@@ -200,7 +248,7 @@ pub async fn list_conversations(
     let user_id = claims.sub.clone();
 
     let result = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         // Look up user's pubkey
         let user_pubkey: String = conn
@@ -94,6 +94,7 @@ pub async fn publish_dm_key(
 
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::DmKeyRotatedEvent(proto_dm::DmKeyRotatedEvent {
             user_pubkey: ed25519_pubkey.clone(),
             new_x25519_pubkey: x25519_bytes_for_broadcast.clone(),
@@ -120,7 +121,7 @@ pub async fn get_dm_key(
     let pubkey = ed25519_pubkey.to_lowercase();
 
     let result = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         let (x25519_bytes, published_at): (Vec<u8>, String) = conn
             .query_row(
@@ -12,6 +12,8 @@ use base64::{engine::general_purpose::STANDARD, Engine};
 use serde::{Deserialize, Serialize};
 
 use crate::auth::middleware::Claims;
+use crate::db::sequences::next_dm_sequence;
+use crate::moderation::abuse::ActivityEvent;
 use crate::proto::dm as proto_dm;
 use crate::proto::ws::{envelope::Payload, Envelope};
 use crate::state::AppState;
@@ -60,6 +62,27 @@ pub struct DmHistoryResponse {
     pub has_more: bool,
 }
 
+impl DmMessageResponse {
+    /// Convert to the protobuf EncryptedDmMessage used in WS history responses.
+    pub fn to_proto(&self) -> proto_dm::EncryptedDmMessage {
+        proto_dm::EncryptedDmMessage {
+            id: self.id.clone(),
+            conversation_id: self.conversation_id.clone(),
+            sender_pubkey: self.sender_pubkey.clone(),
+            encrypted_payload: b64_decode(&self.encrypted_payload).unwrap_or_default(),
+            nonce: b64_decode(&self.nonce).unwrap_or_default(),
+            ephemeral_pubkey: self
+                .ephemeral_pubkey
+                .as_deref()
+                .and_then(|k| b64_decode(k).ok())
+                .unwrap_or_default(),
+            timestamp: self.timestamp,
+            server_sequence: self.server_sequence,
+            sender_display_name: self.sender_display_name.clone(),
+        }
+    }
+}
+
 // --- Base64 helpers ---
 
 fn b64_decode(input: &str) -> Result<Vec<u8>, StatusCode> {
@@ -91,7 +114,7 @@ pub async fn send_dm_message(
     let timestamp = body.timestamp;
 
     let result = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let mut conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         // Look up sender's pubkey and display_name
         let (sender_pubkey, sender_display_name): (String, String) = conn
@@ -125,17 +148,17 @@ pub async fn send_dm_message(
         // Generate message UUID
         let msg_id = uuid::Uuid::now_v7().to_string();
 
+        // The sequence number, the message and the conversation update commit together
+        let tx = conn
+            .transaction()
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
         // Assign server_sequence (atomic increment for the conversation)
-        let next_seq: i64 = conn
-            .query_row(
-                "SELECT COALESCE(MAX(server_sequence), 0) + 1 FROM dm_messages WHERE conversation_id = ?1",
-                rusqlite::params![conv_id],
-                |row| row.get(0),
-            )
+        let next_seq = next_dm_sequence(&tx, &conv_id)
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         // Persist encrypted DM
-        conn.execute(
+        tx.execute(
             "INSERT INTO dm_messages (id, conversation_id, sender_pubkey, encrypted_payload, nonce, ephemeral_pubkey, timestamp, server_sequence, sender_display_name)
              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             rusqlite::params![
@@ -153,11 +176,12 @@ pub async fn send_dm_message(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         // Update conversation's last_message_at
-        conn.execute(
+        tx.execute(
             "UPDATE dm_conversations SET last_message_at = datetime('now') WHERE id = ?1",
             rusqlite::params![conv_id],
         )
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        tx.commit().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         Ok((
             msg_id,
@@ -186,6 +210,11 @@ pub async fn send_dm_message(
         ephemeral_pubkey_bytes,
     ) = result;
 
+    state.abuse.report(ActivityEvent::DirectMessage {
+        user_id: claims.sub.clone(),
+        recipient_pubkey: recipient_pubkey.clone(),
+    });
+
     // Build DmMessageEvent proto for WS delivery
     let proto_msg = proto_dm::EncryptedDmMessage {
         id: msg_id.clone(),
@@ -201,6 +230,7 @@ pub async fn send_dm_message(
 
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::DmMessageEvent(proto_dm::DmMessageEvent {
             message: Some(proto_msg),
         })),
@@ -262,7 +292,7 @@ pub async fn get_dm_messages(
     let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
 
     let result = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         // Look up user's pubkey
         let user_pubkey: String = conn
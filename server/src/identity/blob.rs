@@ -45,7 +45,7 @@ pub async fn get_blob(
     let db = state.db.clone();
 
     let blob = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         let result: Result<(Vec<u8>, String, String), _> = conn.query_row(
             "SELECT encrypted_blob, created_at, updated_at FROM identity_blobs WHERE fingerprint = ?1",
@@ -1,4 +1,10 @@
-use axum::{extract::State, http::StatusCode, Json};
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::StatusCode,
+    Json,
+};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -6,6 +12,8 @@ use uuid::Uuid;
 use crate::admin::setup;
 use crate::auth::jwt;
 use crate::db::models::ROLE_ADMIN;
+use crate::moderation::abuse::ActivityEvent;
+use crate::roles::permissions::Permissions;
 use crate::state::AppState;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -41,6 +49,7 @@ pub struct RegisterApiResponse {
 /// make user the server owner.
 pub async fn register(
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Json(req): Json<RegisterApiRequest>,
 ) -> Result<Json<RegisterApiResponse>, (StatusCode, String)> {
     // Validate display name is not empty
@@ -104,9 +113,10 @@ pub async fn register(
         let conn = db.lock().map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB lock: {}", e)))?;
 
         // Consume invite code if provided (must happen before user creation for atomicity)
-        if let Some(ref code) = invite_code {
-            crate::invite::validate::consume_invite(&conn, code)?;
-        }
+        let inviter_id = match invite_code {
+            Some(ref code) => Some(crate::invite::validate::consume_invite(&conn, code)?),
+            None => None,
+        };
 
         // Check fingerprint uniqueness
         let existing: Option<String> = conn
@@ -167,11 +177,11 @@ pub async fn register(
         let everyone_role_id = if let Some(id) = everyone_id {
             id
         } else {
-            // Create @everyone role with SEND_MESSAGES permission (0x01)
+            // Create @everyone role with the default member permissions
             let eid = Uuid::now_v7().to_string();
             conn.execute(
-                "INSERT INTO roles (id, name, permissions, color, position, is_default, created_at, updated_at) VALUES (?1, 'everyone', 1, '', 0, 1, ?2, ?3)",
-                rusqlite::params![eid, now, now],
+                "INSERT INTO roles (id, name, permissions, color, position, is_default, created_at, updated_at) VALUES (?1, 'everyone', ?2, '', 0, 1, ?3, ?4)",
+                rusqlite::params![eid, Permissions::DEFAULT_EVERYONE.bits(), now, now],
             )
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Insert @everyone: {}", e)))?;
             eid
@@ -184,6 +194,11 @@ pub async fn register(
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Assign @everyone: {}", e)))?;
 
+        // Remember who brought the new member in (invite tree)
+        if let (Some(code), Some(inviter_id)) = (&invite_code, &inviter_id) {
+            crate::invite::validate::record_invite_use(&conn, code, inviter_id, &user_id)?;
+        }
+
         // Seed starter template on first boot (owner registration)
         if is_owner {
             crate::channels::seed::seed_starter_template(&conn)
@@ -220,6 +235,11 @@ pub async fn register(
 
     tracing::info!("User registered: {} ({})", req.display_name, req.fingerprint);
 
+    state.abuse.report(ActivityEvent::Registered {
+        user_id: user_id.clone(),
+        ip: addr.ip(),
+    });
+
     Ok(Json(RegisterApiResponse {
         user_id,
         access_token,
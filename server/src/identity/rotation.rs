@@ -341,7 +341,7 @@ pub async fn get_rotation_chain(
     let db = state.db.clone();
 
     let chain = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         let mut stmt = conn
             .prepare(
@@ -360,12 +360,12 @@ pub async fn get_rotation_chain(
                     record_type: row.get(1)?,
                     prev_key: row
                         .get::<_, Option<Vec<u8>>>(2)?
-                        .map(|b| hex::encode(b)),
+                        .map(hex::encode),
                     new_key: hex::encode(row.get::<_, Vec<u8>>(3)?),
                     reason: row.get(4)?,
                     signature_old: row
                         .get::<_, Option<Vec<u8>>>(5)?
-                        .map(|b| hex::encode(b)),
+                        .map(hex::encode),
                     signature_new: hex::encode(row.get::<_, Vec<u8>>(6)?),
                     cancellation_deadline: row.get(7)?,
                     cancelled: row.get(8)?,
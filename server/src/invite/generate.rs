@@ -8,6 +8,8 @@ use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use crate::auth::middleware::Claims;
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::proto::invite as proto_invite;
 use crate::roles::permissions::{require_permission, Permissions};
 use crate::state::AppState;
 
@@ -34,6 +36,20 @@ pub struct InviteListResponse {
     pub invites: Vec<InviteResponse>,
 }
 
+impl InviteResponse {
+    /// Convert to the protobuf Invite used in WS responses.
+    pub fn to_proto(&self) -> proto_invite::Invite {
+        proto_invite::Invite {
+            code: self.code.clone(),
+            created_by: self.created_by.clone(),
+            max_uses: self.max_uses,
+            use_count: self.use_count,
+            expires_at: self.expires_at.clone(),
+            created_at: self.created_at.clone(),
+        }
+    }
+}
+
 /// Generate an 8-character alphanumeric invite code.
 fn generate_code() -> String {
     const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
@@ -46,13 +62,14 @@ fn generate_code() -> String {
         .collect()
 }
 
-/// POST /api/invites — Create a new invite (requires ADMIN).
+/// POST /api/invites — Create a new invite (requires MANAGE_INVITES and unrevoked
+/// invite privileges).
 pub async fn create_invite(
     State(state): State<AppState>,
     claims: Claims,
     Json(req): Json<CreateInviteRequest>,
 ) -> Result<(StatusCode, Json<InviteResponse>), (StatusCode, String)> {
-    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::MANAGE_INVITES)
         .await
         .map_err(|s| (s, "Insufficient permissions".to_string()))?;
 
@@ -61,11 +78,25 @@ pub async fn create_invite(
     let max_uses = req.max_uses;
     let expires_at = req.expires_at.clone();
 
-    let invite = tokio::task::spawn_blocking(move || {
+    let (invite, entry) = tokio::task::spawn_blocking(move || {
         let conn = db
             .lock()
             .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
 
+        let revoked: Option<String> = conn
+            .query_row(
+                "SELECT invites_revoked_at FROM users WHERE id = ?1",
+                [&created_by],
+                |row| row.get(0),
+            )
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query user: {}", e)))?;
+        if revoked.is_some() {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Your invite privileges have been revoked".to_string(),
+            ));
+        }
+
         let code = generate_code();
         let now = Utc::now().to_rfc3339();
         let exp = if expires_at.is_empty() {
@@ -81,27 +112,37 @@ pub async fn create_invite(
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Insert invite: {}", e)))?;
 
-        Ok::<_, (StatusCode, String)>(InviteResponse {
+        let invite = InviteResponse {
             code,
             created_by,
             max_uses,
             use_count: 0,
             expires_at: exp.unwrap_or("").to_string(),
             created_at: now,
-        })
+        };
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&invite.created_by, AuditAction::InviteCreate, &invite.code)
+                .after(&invite),
+        )
+        .map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((invite, entry))
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
+    audit::notify_admins(&state, entry);
+
     Ok((StatusCode::CREATED, Json(invite)))
 }
 
-/// GET /api/invites — List all invites (requires ADMIN).
+/// GET /api/invites — List all invites (requires MANAGE_INVITES).
 pub async fn list_invites(
     State(state): State<AppState>,
     claims: Claims,
 ) -> Result<Json<InviteListResponse>, (StatusCode, String)> {
-    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::MANAGE_INVITES)
         .await
         .map_err(|s| (s, "Insufficient permissions".to_string()))?;
 
@@ -109,7 +150,7 @@ pub async fn list_invites(
 
     let invites = tokio::task::spawn_blocking(move || {
         let conn = db
-            .lock()
+            .read()
             .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
 
         let mut stmt = conn
@@ -205,33 +246,44 @@ pub async fn get_invite(
     Ok(Json(result))
 }
 
-/// DELETE /api/invites/{code} — Delete an invite (requires ADMIN).
+/// DELETE /api/invites/{code} — Delete an invite (requires MANAGE_INVITES).
 pub async fn delete_invite(
     State(state): State<AppState>,
     claims: Claims,
     Path(code): Path<String>,
 ) -> Result<StatusCode, (StatusCode, String)> {
-    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::MANAGE_INVITES)
         .await
         .map_err(|s| (s, "Insufficient permissions".to_string()))?;
 
     let db = state.db.clone();
+    let actor_id = claims.sub.clone();
 
-    tokio::task::spawn_blocking(move || {
+    let entry = tokio::task::spawn_blocking(move || {
         let conn = db
             .lock()
             .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
-        conn.execute("DELETE FROM invites WHERE code = ?1", [&code])
+        let rows = conn
+            .execute("DELETE FROM invites WHERE code = ?1", [&code])
             .map_err(|e| {
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     format!("Delete invite: {}", e),
                 )
             })?;
-        Ok::<_, (StatusCode, String)>(())
+        if rows == 0 {
+            return Ok(None);
+        }
+        audit::record(&conn, AuditRecord::new(&actor_id, AuditAction::InviteDelete, &code))
+            .map(Some)
+            .map_err(audit::audit_error)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
+    if let Some(entry) = entry {
+        audit::notify_admins(&state, entry);
+    }
+
     Ok(StatusCode::OK)
 }
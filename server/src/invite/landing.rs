@@ -26,7 +26,7 @@ pub async fn invite_landing_page(
     let invite_code = code.clone();
 
     let (server_name, server_description) = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         let now = Utc::now().to_rfc3339();
 
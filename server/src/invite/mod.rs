@@ -1,3 +1,4 @@
 pub mod generate;
 pub mod landing;
+pub mod tree;
 pub mod validate;
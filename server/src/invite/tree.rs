@@ -0,0 +1,504 @@
+//! Invitation chains: who brought whom in, per-invite join lists, revoking a member's
+//! invite privileges, and cascading review over everyone a member brought in.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::middleware::Claims;
+use crate::moderation::abuse::{record_flag, FlagCandidate, FlagKind};
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::moderation::ban::check_ban;
+use crate::roles::hierarchy::require_outranks;
+use crate::roles::permissions::{require_permission, user_permissions, Permissions};
+use crate::state::AppState;
+
+/// How far the inviter chain and cascading review follow invitations.
+const MAX_TREE_DEPTH: i64 = 32;
+
+// --- Request / Response types ---
+
+#[derive(Debug, Deserialize)]
+pub struct InvitePrivilegesRequest {
+    /// True to revoke the member's invite privileges, false to restore them
+    pub revoked: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InvitePrivilegesResponse {
+    pub user_id: String,
+    pub invites_revoked_at: Option<String>,
+    pub invites_revoked_by: Option<String>,
+}
+
+/// One step up the chain: `user_id` invited the member below them using `invite_code`.
+#[derive(Debug, Serialize)]
+pub struct InviterLink {
+    pub user_id: String,
+    pub display_name: String,
+    pub invite_code: String,
+    pub joined_at: String,
+}
+
+/// A member who registered with one of the subject's invites.
+#[derive(Debug, Serialize)]
+pub struct Invitee {
+    pub user_id: String,
+    pub display_name: String,
+    pub invite_code: String,
+    pub joined_at: String,
+    pub banned: bool,
+    /// How many members this invitee has brought in themselves
+    pub invited_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InviteTreeResponse {
+    pub user_id: String,
+    pub display_name: String,
+    pub invited_by_invite: Option<String>,
+    pub invites_revoked_at: Option<String>,
+    /// Inviters from the member's direct inviter up to the first member without one
+    pub chain: Vec<InviterLink>,
+    pub invitees: Vec<Invitee>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InviteUse {
+    pub user_id: String,
+    pub display_name: String,
+    pub used_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InviteUsesResponse {
+    pub code: String,
+    pub created_by: String,
+    pub uses: Vec<InviteUse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InviteReviewResponse {
+    /// Number of members flagged for review
+    pub flagged: usize,
+}
+
+// --- Helpers ---
+
+/// The member who invited `user_id` (via the code they registered with), if any.
+fn inviter_of(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+) -> rusqlite::Result<Option<(String, String, String)>> {
+    conn.query_row(
+        "SELECT iu.inviter_id, iu.code, iu.used_at FROM users u
+         INNER JOIN invite_uses iu ON iu.user_id = u.id AND iu.code = u.invited_by_invite
+         WHERE u.id = ?1 ORDER BY iu.id LIMIT 1",
+        [user_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )
+    .optional()
+}
+
+/// Flag everyone `user_id` brought in, directly or further down their invite tree,
+/// in the abuse review queue. Returns how many members were flagged.
+pub fn flag_invitees(conn: &rusqlite::Connection, user_id: &str) -> rusqlite::Result<usize> {
+    let display_name: String = conn.query_row(
+        "SELECT display_name FROM users WHERE id = ?1",
+        [user_id],
+        |row| row.get(0),
+    )?;
+
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE tree(user_id, depth) AS (
+             SELECT iu.user_id, 1 FROM invite_uses iu
+             INNER JOIN users u ON u.id = iu.user_id AND u.invited_by_invite = iu.code
+             WHERE iu.inviter_id = ?1
+             UNION
+             SELECT iu.user_id, t.depth + 1 FROM tree t
+             INNER JOIN invite_uses iu ON iu.inviter_id = t.user_id
+             INNER JOIN users u ON u.id = iu.user_id AND u.invited_by_invite = iu.code
+             WHERE t.depth < ?2
+         )
+         SELECT user_id, MIN(depth) FROM tree WHERE user_id != ?1 GROUP BY user_id",
+    )?;
+    let members: Vec<(String, i64)> = stmt
+        .query_map(rusqlite::params![user_id, MAX_TREE_DEPTH], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    for (member_id, depth) in &members {
+        let reason = if *depth == 1 {
+            format!(
+                "Invited by {}, whose invite tree is under review",
+                display_name
+            )
+        } else {
+            format!(
+                "In the invite tree of {} ({} invitations away), which is under review",
+                display_name, depth
+            )
+        };
+        record_flag(
+            conn,
+            &FlagCandidate {
+                user_id: member_id.clone(),
+                kind: FlagKind::InviteChain,
+                reason,
+            },
+        )?;
+    }
+
+    Ok(members.len())
+}
+
+fn invite_privileges(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+) -> Result<InvitePrivilegesResponse, (StatusCode, String)> {
+    conn.query_row(
+        "SELECT invites_revoked_at, invites_revoked_by FROM users WHERE id = ?1",
+        [user_id],
+        |row| {
+            Ok(InvitePrivilegesResponse {
+                user_id: user_id.to_string(),
+                invites_revoked_at: row.get(0)?,
+                invites_revoked_by: row.get(1)?,
+            })
+        },
+    )
+    .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))
+}
+
+// --- Handlers ---
+
+/// GET /api/members/{user_id}/invite-tree — The member's inviter chain and the members
+/// they brought in. Requires MANAGE_INVITES, KICK_MEMBERS or BAN_MEMBERS.
+pub async fn get_invite_tree(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(user_id): Path<String>,
+) -> Result<Json<InviteTreeResponse>, (StatusCode, String)> {
+    let perms = user_permissions(&state.db, &claims.sub, claims.is_owner)
+        .await
+        .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+    if !perms.intersects(
+        Permissions::MANAGE_INVITES | Permissions::KICK_MEMBERS | Permissions::BAN_MEMBERS,
+    ) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Insufficient permissions".to_string(),
+        ));
+    }
+
+    let db = state.db.clone();
+
+    let tree = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .read()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+        let query_err =
+            |e: rusqlite::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query invites: {}", e));
+
+        let (display_name, invited_by_invite, invites_revoked_at): (
+            String,
+            Option<String>,
+            Option<String>,
+        ) = conn
+            .query_row(
+                "SELECT display_name, invited_by_invite, invites_revoked_at FROM users WHERE id = ?1",
+                [&user_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+        // Walk up the chain; stop at the first member without an inviter (or a cycle)
+        let mut chain = Vec::new();
+        let mut visited = vec![user_id.clone()];
+        let mut current = user_id.clone();
+        while (chain.len() as i64) < MAX_TREE_DEPTH {
+            let Some((inviter_id, code, joined_at)) =
+                inviter_of(&conn, &current).map_err(query_err)?
+            else {
+                break;
+            };
+            if visited.contains(&inviter_id) {
+                break;
+            }
+            let inviter_name: String = conn
+                .query_row(
+                    "SELECT display_name FROM users WHERE id = ?1",
+                    [&inviter_id],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(query_err)?
+                .unwrap_or_default();
+            chain.push(InviterLink {
+                user_id: inviter_id.clone(),
+                display_name: inviter_name,
+                invite_code: code,
+                joined_at,
+            });
+            visited.push(inviter_id.clone());
+            current = inviter_id;
+        }
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT u.id, u.display_name, u.fingerprint, iu.code, iu.used_at,
+                        (SELECT COUNT(*) FROM invite_uses c
+                         INNER JOIN users cu ON cu.id = c.user_id AND cu.invited_by_invite = c.code
+                         WHERE c.inviter_id = u.id)
+                 FROM invite_uses iu
+                 INNER JOIN users u ON u.id = iu.user_id AND u.invited_by_invite = iu.code
+                 WHERE iu.inviter_id = ?1
+                 ORDER BY iu.id",
+            )
+            .map_err(query_err)?;
+        let rows: Vec<(String, String, String, String, String, i64)> = stmt
+            .query_map([&user_id], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            })
+            .map_err(query_err)?
+            .filter_map(|r| r.ok())
+            .collect();
+        let invitees = rows
+            .into_iter()
+            .map(
+                |(id, display_name, fingerprint, invite_code, joined_at, invited_count)| Invitee {
+                    user_id: id,
+                    display_name,
+                    invite_code,
+                    joined_at,
+                    banned: check_ban(&conn, &fingerprint).is_some(),
+                    invited_count,
+                },
+            )
+            .collect();
+
+        Ok::<_, (StatusCode, String)>(InviteTreeResponse {
+            user_id,
+            display_name,
+            invited_by_invite,
+            invites_revoked_at,
+            chain,
+            invitees,
+        })
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    Ok(Json(tree))
+}
+
+/// GET /api/invites/{code}/uses — Who joined with an invite, oldest first
+/// (requires MANAGE_INVITES).
+pub async fn list_invite_uses(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(code): Path<String>,
+) -> Result<Json<InviteUsesResponse>, (StatusCode, String)> {
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        Permissions::MANAGE_INVITES,
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let db = state.db.clone();
+
+    let response = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .read()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+        let query_err = |e: rusqlite::Error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Query invite uses: {}", e),
+            )
+        };
+
+        // Deleted invites keep their join list; the inviter comes from the uses
+        let created_by: String = conn
+            .query_row(
+                "SELECT created_by FROM invites WHERE code = ?1
+                 UNION ALL SELECT inviter_id FROM invite_uses WHERE code = ?1 LIMIT 1",
+                [&code],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(query_err)?
+            .ok_or((StatusCode::NOT_FOUND, "Invite not found".to_string()))?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT iu.user_id, u.display_name, iu.used_at FROM invite_uses iu
+                 INNER JOIN users u ON u.id = iu.user_id
+                 WHERE iu.code = ?1 ORDER BY iu.id",
+            )
+            .map_err(query_err)?;
+        let uses: Vec<InviteUse> = stmt
+            .query_map([&code], |row| {
+                Ok(InviteUse {
+                    user_id: row.get(0)?,
+                    display_name: row.get(1)?,
+                    used_at: row.get(2)?,
+                })
+            })
+            .map_err(query_err)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok::<_, (StatusCode, String)>(InviteUsesResponse {
+            code,
+            created_by,
+            uses,
+        })
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Task join: {}", e),
+        )
+    })??;
+
+    Ok(Json(response))
+}
+
+/// PUT /api/members/{user_id}/invite-privileges — Revoke or restore a member's ability
+/// to invite (requires MANAGE_INVITES and outranking the member). While revoked, their
+/// existing invites stop working and they cannot create new ones.
+pub async fn set_invite_privileges(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(user_id): Path<String>,
+    Json(req): Json<InvitePrivilegesRequest>,
+) -> Result<Json<InvitePrivilegesResponse>, (StatusCode, String)> {
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        Permissions::MANAGE_INVITES,
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let db = state.db.clone();
+    let actor_id = claims.sub.clone();
+    let actor_is_owner = claims.is_owner;
+
+    let (privileges, entry) = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        let before = invite_privileges(&conn, &user_id)?;
+        require_outranks(&conn, &actor_id, actor_is_owner, &user_id)?;
+        if before.invites_revoked_at.is_some() == req.revoked {
+            return Ok((before, None));
+        }
+
+        let (revoked_at, revoked_by) = if req.revoked {
+            (Some(Utc::now().to_rfc3339()), Some(actor_id.clone()))
+        } else {
+            (None, None)
+        };
+        conn.execute(
+            "UPDATE users SET invites_revoked_at = ?1, invites_revoked_by = ?2 WHERE id = ?3",
+            rusqlite::params![revoked_at, revoked_by, user_id],
+        )
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Update user: {}", e),
+            )
+        })?;
+        let after = invite_privileges(&conn, &user_id)?;
+
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::MemberInvitePrivileges, &user_id)
+                .before(&before)
+                .after(&after),
+        )
+        .map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((after, Some(entry)))
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Task join: {}", e),
+        )
+    })??;
+
+    if let Some(entry) = entry {
+        audit::notify_admins(&state, entry);
+    }
+
+    Ok(Json(privileges))
+}
+
+/// POST /api/members/{user_id}/invite-tree/review — Flag everyone the member brought
+/// in for admin review (requires BAN_MEMBERS). Typically used after banning them; the
+/// ban request's `review_invitees` option does the same.
+pub async fn review_invite_tree(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(user_id): Path<String>,
+) -> Result<Json<InviteReviewResponse>, (StatusCode, String)> {
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        Permissions::BAN_MEMBERS,
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let db = state.db.clone();
+
+    let flagged = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+        match flag_invitees(&conn, &user_id) {
+            Ok(flagged) => Ok(flagged),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                Err((StatusCode::NOT_FOUND, "User not found".to_string()))
+            }
+            Err(e) => Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Flag invitees: {}", e),
+            )),
+        }
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Task join: {}", e),
+        )
+    })??;
+
+    Ok(Json(InviteReviewResponse { flagged }))
+}
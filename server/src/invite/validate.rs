@@ -2,17 +2,19 @@ use axum::http::StatusCode;
 use chrono::Utc;
 
 /// Atomically consume an invite code.
-/// Increments use_count if the invite is valid, not expired, and not exhausted.
-/// Returns Ok(()) on success or Err with status and message on failure.
+/// Increments use_count if the invite is valid, not expired, not exhausted, and its
+/// creator still has invite privileges.
+/// Returns the inviter's user ID on success or Err with status and message on failure.
 pub fn consume_invite(
     conn: &rusqlite::Connection,
     code: &str,
-) -> Result<(), (StatusCode, String)> {
+) -> Result<String, (StatusCode, String)> {
     let now = Utc::now().to_rfc3339();
 
     let rows_affected = conn
         .execute(
-            "UPDATE invites SET use_count = use_count + 1 WHERE code = ?1 AND (expires_at IS NULL OR expires_at > ?2) AND (max_uses IS NULL OR use_count < max_uses)",
+            "UPDATE invites SET use_count = use_count + 1 WHERE code = ?1 AND (expires_at IS NULL OR expires_at > ?2) AND (max_uses IS NULL OR use_count < max_uses)
+             AND created_by NOT IN (SELECT id FROM users WHERE invites_revoked_at IS NOT NULL)",
             rusqlite::params![code, now],
         )
         .map_err(|e| {
@@ -29,5 +31,45 @@ pub fn consume_invite(
         ));
     }
 
+    conn.query_row(
+        "SELECT created_by FROM invites WHERE code = ?1",
+        [code],
+        |row| row.get(0),
+    )
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Query invite: {}", e),
+        )
+    })
+}
+
+/// Record that `user_id` joined with `code`. The first invite a member redeems becomes
+/// their `invited_by_invite`.
+pub fn record_invite_use(
+    conn: &rusqlite::Connection,
+    code: &str,
+    inviter_id: &str,
+    user_id: &str,
+) -> Result<(), (StatusCode, String)> {
+    let now = Utc::now().to_rfc3339();
+    let record_err = |e: rusqlite::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Record invite use: {}", e),
+        )
+    };
+
+    conn.execute(
+        "INSERT INTO invite_uses (code, inviter_id, user_id, used_at) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![code, inviter_id, user_id, now],
+    )
+    .map_err(record_err)?;
+    conn.execute(
+        "UPDATE users SET invited_by_invite = ?1 WHERE id = ?2 AND invited_by_invite IS NULL",
+        rusqlite::params![code, user_id],
+    )
+    .map_err(record_err)?;
+
     Ok(())
 }
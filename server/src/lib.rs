@@ -4,6 +4,8 @@
 
 pub mod admin;
 pub mod auth;
+#[cfg(feature = "bench")]
+pub mod bench;
 pub mod blocks;
 pub mod channels;
 pub mod chat;
@@ -15,6 +17,7 @@ pub mod invite;
 pub mod moderation;
 pub mod p2p;
 pub mod proto;
+pub mod rate_limit;
 pub mod roles;
 pub mod routes;
 pub mod state;
@@ -1,29 +1,13 @@
-mod admin;
-mod auth;
-mod blocks;
-mod channels;
-mod chat;
-mod config;
-mod db;
-mod dm;
-mod identity;
-mod invite;
-mod moderation;
-mod p2p;
-mod proto;
-mod roles;
-mod routes;
-mod state;
-mod voice;
-mod ws;
-
 use dashmap::DashMap;
 use libp2p::{gossipsub, PeerId};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::net::TcpListener;
 
-use config::{generate_config_template, Config};
+use united_server::config::{generate_config_template, Config};
+use united_server::{
+    admin, auth, blocks, chat, db, dm, moderation, p2p, rate_limit, routes, state, voice, ws,
+};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -90,23 +74,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load or generate the server's libp2p Ed25519 identity keypair
     let keypair = p2p::identity::server_identity_keypair(&config.data_dir);
     let server_peer_id = PeerId::from(keypair.public()).to_string();
+    let server_signing_key = Arc::new(p2p::identity::server_signing_key(&keypair));
+    let server_public_key = server_signing_key.verifying_key();
+    tracing::info!("Server gossip signing key: {}", hex::encode(server_public_key.as_bytes()));
+    let trusted_servers = Arc::new(p2p::validation::TrustedServers::new(
+        server_public_key.as_bytes(),
+        &p2p_config.trusted_server_keys,
+    ));
 
-    // Query existing channels to subscribe to at startup
+    // Query existing channels and open threads to subscribe to at startup
     let startup_topics = {
         let conn = db.lock().expect("DB lock for channel query");
         let mut stmt = conn
             .prepare("SELECT id FROM channels")
             .expect("Prepare channel query");
-        let topics: Vec<String> = stmt
+        let mut topics: Vec<String> = stmt
             .query_map([], |row| row.get::<_, String>(0))
             .expect("Query channels")
             .filter_map(|r| r.ok())
-            .map(|channel_id| {
-                // Topic namespace: first 16 hex chars of server PeerId / channel UUID
-                let prefix = &server_peer_id[..std::cmp::min(16, server_peer_id.len())];
-                format!("{}/{}", prefix, channel_id)
-            })
+            .map(|channel_id| p2p::publish::channel_topic(&server_peer_id, &channel_id))
             .collect();
+        let mut stmt = conn
+            .prepare("SELECT id FROM threads WHERE archived = 0")
+            .expect("Prepare thread query");
+        topics.extend(
+            stmt.query_map([], |row| row.get::<_, String>(0))
+                .expect("Query threads")
+                .filter_map(|r| r.ok())
+                .map(|thread_id| p2p::publish::thread_topic(&server_peer_id, &thread_id)),
+        );
         topics
     };
 
@@ -153,7 +149,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     if !startup_topics.is_empty() {
         tracing::info!(
-            "Subscribed to {} existing channel gossipsub topics",
+            "Subscribed to {} existing channel and thread gossipsub topics",
             startup_topics.len()
         );
     }
@@ -169,6 +165,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let connections_for_gossip = ws::new_connection_registry();
     let connections_for_state = connections_for_gossip.clone();
+    let gossip_cmd_tx = swarm_cmd_tx.clone();
+    let gossip_rejections = Arc::new(p2p::validation::GossipRejectionCounters::new());
+    let gossip_rejections_for_state = gossip_rejections.clone();
+    let gossip_trust_config = config.trust.clone();
 
     tokio::spawn(async move {
         let mut evt_rx = swarm_evt_rx;
@@ -176,6 +176,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         while let Some(event) = evt_rx.recv().await {
             match event {
                 p2p::SwarmEvent::GossipMessage {
+                    message_id,
                     source,
                     topic,
                     data,
@@ -186,41 +187,64 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         topic,
                         data.len()
                     );
-                    // Decode, verify, and persist the message
+                    // Decode, verify, authorize, and persist the message,
+                    // then report the outcome back to gossipsub for propagation and scoring.
                     let db_clone = evt_db.clone();
                     let conns = gossip_connections.clone();
+                    let cmd_tx = gossip_cmd_tx.clone();
+                    let rejections = gossip_rejections.clone();
+                    let trusted = trusted_servers.clone();
+                    let trust_config = gossip_trust_config.clone();
                     tokio::task::spawn_blocking(move || {
-                        match p2p::messages::decode_and_verify_gossip_envelope(&data) {
-                            Ok(envelope) => {
-                                match p2p::messages::handle_gossip_message(&db_clone, &envelope) {
-                                    Ok(result) => {
-                                        tracing::debug!(
-                                            "Persisted gossipsub message from {} on {}, seq={}",
-                                            source,
-                                            topic,
-                                            result.server_sequence
-                                        );
-                                        // Broadcast to WS clients if it was a chat message
-                                        if let Some(chat_msg) = result.chat_message {
-                                            chat::broadcast::broadcast_new_message(&conns, chat_msg);
-                                        }
-                                    }
-                                    Err(e) => {
-                                        tracing::warn!(
-                                            "Failed to persist gossipsub message: {}",
-                                            e
-                                        );
-                                    }
+                        let outcome = p2p::messages::decode_and_verify_gossip_envelope(&data)
+                            .and_then(|envelope| {
+                                if envelope.topic != topic {
+                                    return Err(p2p::messages::EnvelopeError::Unauthorized(
+                                        p2p::validation::RejectReason::TopicMismatch,
+                                    ));
                                 }
-                            }
-                            Err(e) => {
-                                tracing::warn!(
-                                    "Failed to verify gossipsub message from {}: {}",
+                                p2p::messages::handle_gossip_message(
+                                    &db_clone,
+                                    &trusted,
+                                    trust_config.as_ref(),
+                                    &envelope,
+                                )
+                            });
+
+                        let acceptance = match outcome {
+                            Ok(outcome) => {
+                                tracing::debug!(
+                                    "Applied gossipsub message from {} on {}",
                                     source,
-                                    e
+                                    topic
                                 );
+                                // Relay the message, edit, delete or reaction to WS clients
+                                outcome.broadcast(&conns);
+                                gossipsub::MessageAcceptance::Accept
                             }
-                        }
+                            Err(e) => match e.reject_reason() {
+                                Some(reason) => {
+                                    rejections.record(reason);
+                                    tracing::warn!(
+                                        "Rejected gossipsub message from {} on {}: {}",
+                                        source,
+                                        topic,
+                                        e
+                                    );
+                                    gossipsub::MessageAcceptance::Reject
+                                }
+                                None => {
+                                    tracing::warn!("Failed to persist gossipsub message: {}", e);
+                                    gossipsub::MessageAcceptance::Ignore
+                                }
+                            },
+                        };
+
+                        let _ = cmd_tx.send(p2p::SwarmCommand::ReportValidation {
+                            message_id,
+                            source,
+                            acceptance,
+                        });
                     });
                 }
                 p2p::SwarmEvent::PeerConnected(peer_id) => {
@@ -235,16 +259,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Build application state
     // Use the shared connection registry so gossip event consumer can broadcast to WS clients.
+    let abuse = moderation::abuse::AbuseMonitor::spawn(db.clone());
     let app_state = state::AppState {
         db,
         challenges: Arc::new(DashMap::new()),
         jwt_secret,
         encryption_key,
         connections: connections_for_state,
+        outbound_metrics: Arc::new(ws::outbound::OutboundMetrics::new()),
         registration_mode: config.registration_mode.clone(),
         swarm_cmd_tx,
         peer_directory,
         server_peer_id,
+        server_signing_key,
+        gossip_rejections: gossip_rejections_for_state,
         libp2p_port: p2p_config.libp2p_port,
         presence: Arc::new(DashMap::new()),
         data_dir: config.data_dir.clone(),
@@ -253,6 +281,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         max_upload_size_mb: config.blocks.as_ref().map(|b| b.max_upload_size_mb),
         voice_state: Arc::new(voice::state::VoiceState::new()),
         turn_config: config.turn.clone(),
+        trust_config: config.trust.clone(),
+        abuse,
+        rate_limiter: Arc::new(rate_limit::RateLimiter::new(
+            config.rate_limits.clone().unwrap_or_default(),
+        )),
     };
 
     // Spawn DM offline queue cleanup task (runs hourly, purges entries older than 30 days)
@@ -270,6 +303,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         block_cleanup_interval,
     );
 
+    // Spawn message retention task (applies channel and category policies and prunes
+    // old edit history)
+    let messages_config = config.messages.clone().unwrap_or_default();
+    chat::retention::spawn_message_retention(
+        app_state.db.clone(),
+        messages_config.retention_interval_secs,
+        messages_config.revision_retention_days,
+    );
+
+    // Spawn WebSocket session cleanup task (drops sessions past the resume window)
+    ws::session::spawn_session_cleanup(app_state.connections.clone());
+
+    // Spawn rate limit cleanup task (drops refilled per-user buckets)
+    rate_limit::spawn_bucket_cleanup(app_state.rate_limiter.clone());
+
     // Build router
     let app = routes::build_router(app_state);
 
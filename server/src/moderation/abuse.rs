@@ -0,0 +1,663 @@
+//! Behavioral abuse flagging.
+//!
+//! Handlers report activity (registrations, channel messages, DMs) to the
+//! `AbuseMonitor`. A background task runs the `AbuseDetector` heuristics over sliding
+//! windows and writes matches to `user_flags`, where admins review them. Flags never
+//! sanction anyone on their own.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::types::Value;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::auth::middleware::Claims;
+use crate::db::DbPool;
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::roles::permissions::{require_permission, Permissions};
+use crate::state::AppState;
+
+/// Registrations from one IP within this window count towards a burst.
+const REGISTRATION_WINDOW_MINUTES: i64 = 10;
+/// Registrations from one IP within the window that raise a flag.
+const REGISTRATIONS_PER_IP: usize = 3;
+/// Identical messages within this window count as duplicates.
+const DUPLICATE_WINDOW_MINUTES: i64 = 5;
+/// Distinct channels the same message must appear in to raise a flag.
+const DUPLICATE_CHANNELS: usize = 3;
+/// Shorter messages ("hi", "lol") are not compared for duplicates.
+const MIN_DUPLICATE_LENGTH: usize = 10;
+/// How many messages after registration are checked for links.
+const FIRST_MESSAGES: u32 = 5;
+/// Minimum first messages seen before judging the link ratio.
+const MIN_LINK_SAMPLE: u32 = 3;
+/// Percentage of first messages containing links that raises a flag.
+const LINK_RATIO_PERCENT: u32 = 60;
+/// Stop watching a new account's first messages after this long.
+const FIRST_MESSAGES_TRACKING_HOURS: i64 = 24;
+/// Consecutive posts whose spacing is compared for machine-like regularity.
+const CADENCE_SAMPLES: usize = 8;
+/// Posts further apart than this on average are not considered automated.
+const MAX_CADENCE_INTERVAL_SECS: f64 = 600.0;
+/// Coefficient of variation of post intervals below which posting looks scripted.
+const MAX_CADENCE_VARIATION: f64 = 0.05;
+/// New DM recipients within this window count towards mass DMing.
+const MASS_DM_WINDOW_MINUTES: i64 = 10;
+/// Distinct DM recipients within the window that raise a flag.
+const MASS_DM_RECIPIENTS: usize = 10;
+/// How often the analyzer drops expired window state.
+const PRUNE_INTERVAL_SECS: u64 = 600;
+/// Default page size for the flag queue.
+const DEFAULT_LIMIT: u32 = 50;
+/// Maximum page size for the flag queue.
+const MAX_LIMIT: u32 = 100;
+
+// --- Activity and detection ---
+
+/// Something a user did that the analyzer should look at.
+#[derive(Debug, Clone)]
+pub enum ActivityEvent {
+    Registered {
+        user_id: String,
+        ip: IpAddr,
+    },
+    ChannelMessage {
+        user_id: String,
+        channel_id: String,
+        content: String,
+    },
+    DirectMessage {
+        user_id: String,
+        recipient_pubkey: String,
+    },
+    DmConversationCreated {
+        user_id: String,
+        recipient_pubkey: String,
+    },
+}
+
+/// A kind of suspicious behavior.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlagKind {
+    RapidRegistration,
+    DuplicateMessages,
+    LinkHeavyFirstMessages,
+    RegularCadence,
+    MassDm,
+    /// Raised by a cascading review of someone's invite tree (`invite::tree`)
+    InviteChain,
+}
+
+impl FlagKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::RapidRegistration => "rapid_registration",
+            Self::DuplicateMessages => "duplicate_messages",
+            Self::LinkHeavyFirstMessages => "link_heavy_first_messages",
+            Self::RegularCadence => "regular_cadence",
+            Self::MassDm => "mass_dm",
+            Self::InviteChain => "invite_chain",
+        }
+    }
+}
+
+/// A detection, before it is written to `user_flags`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlagCandidate {
+    pub user_id: String,
+    pub kind: FlagKind,
+    pub reason: String,
+}
+
+#[derive(Debug)]
+struct FirstMessages {
+    registered_at: DateTime<Utc>,
+    seen: u32,
+    with_links: u32,
+}
+
+/// Sliding-window heuristics over reported activity. Pure in-memory state; the
+/// caller supplies the clock so detections are deterministic.
+#[derive(Debug, Default)]
+pub struct AbuseDetector {
+    registrations: HashMap<IpAddr, VecDeque<(DateTime<Utc>, String)>>,
+    recent_content: HashMap<String, VecDeque<(DateTime<Utc>, String, String)>>,
+    post_times: HashMap<String, VecDeque<DateTime<Utc>>>,
+    first_messages: HashMap<String, FirstMessages>,
+    dm_recipients: HashMap<String, VecDeque<(DateTime<Utc>, String)>>,
+}
+
+impl AbuseDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an event and return any flags it triggers.
+    pub fn observe(&mut self, event: &ActivityEvent, now: DateTime<Utc>) -> Vec<FlagCandidate> {
+        match event {
+            ActivityEvent::Registered { user_id, ip } => {
+                self.observe_registration(user_id, *ip, now)
+            }
+            ActivityEvent::ChannelMessage {
+                user_id,
+                channel_id,
+                content,
+            } => {
+                let mut flags = self.observe_content(user_id, channel_id, content, now);
+                flags.extend(self.observe_first_message(user_id, content));
+                flags.extend(self.observe_post(user_id, now));
+                flags
+            }
+            ActivityEvent::DirectMessage {
+                user_id,
+                recipient_pubkey,
+            } => {
+                let mut flags = self.observe_dm_recipient(user_id, recipient_pubkey, now);
+                flags.extend(self.observe_post(user_id, now));
+                flags
+            }
+            ActivityEvent::DmConversationCreated {
+                user_id,
+                recipient_pubkey,
+            } => self.observe_dm_recipient(user_id, recipient_pubkey, now),
+        }
+    }
+
+    /// Drop window state that can no longer contribute to a detection.
+    pub fn prune(&mut self, now: DateTime<Utc>) {
+        let registration_cutoff = now - Duration::minutes(REGISTRATION_WINDOW_MINUTES);
+        self.registrations.retain(|_, q| {
+            q.retain(|(t, _)| *t > registration_cutoff);
+            !q.is_empty()
+        });
+        let duplicate_cutoff = now - Duration::minutes(DUPLICATE_WINDOW_MINUTES);
+        self.recent_content.retain(|_, q| {
+            q.retain(|(t, _, _)| *t > duplicate_cutoff);
+            !q.is_empty()
+        });
+        let cadence_cutoff =
+            now - Duration::seconds((MAX_CADENCE_INTERVAL_SECS as i64) * CADENCE_SAMPLES as i64);
+        self.post_times
+            .retain(|_, q| q.back().is_some_and(|t| *t > cadence_cutoff));
+        let tracking_cutoff = now - Duration::hours(FIRST_MESSAGES_TRACKING_HOURS);
+        self.first_messages
+            .retain(|_, f| f.registered_at > tracking_cutoff);
+        let dm_cutoff = now - Duration::minutes(MASS_DM_WINDOW_MINUTES);
+        self.dm_recipients.retain(|_, q| {
+            q.retain(|(t, _)| *t > dm_cutoff);
+            !q.is_empty()
+        });
+    }
+
+    /// Bursts of accounts from one address. Loopback is ignored: behind a local
+    /// reverse proxy every registration shares it.
+    fn observe_registration(
+        &mut self,
+        user_id: &str,
+        ip: IpAddr,
+        now: DateTime<Utc>,
+    ) -> Vec<FlagCandidate> {
+        self.first_messages.insert(
+            user_id.to_string(),
+            FirstMessages {
+                registered_at: now,
+                seen: 0,
+                with_links: 0,
+            },
+        );
+        if ip.is_loopback() {
+            return vec![];
+        }
+
+        let cutoff = now - Duration::minutes(REGISTRATION_WINDOW_MINUTES);
+        let recent = self.registrations.entry(ip).or_default();
+        recent.retain(|(t, _)| *t > cutoff);
+        recent.push_back((now, user_id.to_string()));
+        if recent.len() < REGISTRATIONS_PER_IP {
+            return vec![];
+        }
+
+        let reason = format!(
+            "{} accounts registered from {} within {} minutes",
+            recent.len(),
+            ip,
+            REGISTRATION_WINDOW_MINUTES
+        );
+        recent
+            .iter()
+            .map(|(_, user_id)| FlagCandidate {
+                user_id: user_id.clone(),
+                kind: FlagKind::RapidRegistration,
+                reason: reason.clone(),
+            })
+            .collect()
+    }
+
+    /// The same text posted across several channels in a short time.
+    fn observe_content(
+        &mut self,
+        user_id: &str,
+        channel_id: &str,
+        content: &str,
+        now: DateTime<Utc>,
+    ) -> Vec<FlagCandidate> {
+        let normalized = content
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        if normalized.chars().count() < MIN_DUPLICATE_LENGTH {
+            return vec![];
+        }
+
+        let cutoff = now - Duration::minutes(DUPLICATE_WINDOW_MINUTES);
+        let recent = self.recent_content.entry(user_id.to_string()).or_default();
+        recent.retain(|(t, _, _)| *t > cutoff);
+        recent.push_back((now, normalized.clone(), channel_id.to_string()));
+
+        let channels: HashSet<&str> = recent
+            .iter()
+            .filter(|(_, text, _)| *text == normalized)
+            .map(|(_, _, channel)| channel.as_str())
+            .collect();
+        if channels.len() < DUPLICATE_CHANNELS {
+            return vec![];
+        }
+
+        let count = channels.len();
+        recent.retain(|(_, text, _)| *text != normalized);
+        vec![FlagCandidate {
+            user_id: user_id.to_string(),
+            kind: FlagKind::DuplicateMessages,
+            reason: format!(
+                "Posted the same message in {} channels within {} minutes",
+                count, DUPLICATE_WINDOW_MINUTES
+            ),
+        }]
+    }
+
+    /// New accounts whose first messages are mostly links.
+    fn observe_first_message(&mut self, user_id: &str, content: &str) -> Option<FlagCandidate> {
+        let first = self.first_messages.get_mut(user_id)?;
+        first.seen += 1;
+        if contains_link(content) {
+            first.with_links += 1;
+        }
+
+        let (seen, with_links) = (first.seen, first.with_links);
+        let link_heavy = seen >= MIN_LINK_SAMPLE && with_links * 100 >= seen * LINK_RATIO_PERCENT;
+        if link_heavy || seen >= FIRST_MESSAGES {
+            self.first_messages.remove(user_id);
+        }
+        link_heavy.then(|| FlagCandidate {
+            user_id: user_id.to_string(),
+            kind: FlagKind::LinkHeavyFirstMessages,
+            reason: format!(
+                "{} of the first {} messages after registering contained links",
+                with_links, seen
+            ),
+        })
+    }
+
+    /// Posts (channel messages and DMs) spaced at near-constant intervals.
+    fn observe_post(&mut self, user_id: &str, now: DateTime<Utc>) -> Option<FlagCandidate> {
+        let times = self.post_times.entry(user_id.to_string()).or_default();
+        times.push_back(now);
+        if times.len() > CADENCE_SAMPLES {
+            times.pop_front();
+        }
+        if times.len() < CADENCE_SAMPLES {
+            return None;
+        }
+
+        let intervals: Vec<f64> = times
+            .iter()
+            .zip(times.iter().skip(1))
+            .map(|(a, b)| (*b - *a).num_milliseconds() as f64 / 1000.0)
+            .collect();
+        let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+        if mean <= 0.0 || mean > MAX_CADENCE_INTERVAL_SECS {
+            return None;
+        }
+        let variance =
+            intervals.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / intervals.len() as f64;
+        if variance.sqrt() / mean >= MAX_CADENCE_VARIATION {
+            return None;
+        }
+
+        times.clear();
+        Some(FlagCandidate {
+            user_id: user_id.to_string(),
+            kind: FlagKind::RegularCadence,
+            reason: format!(
+                "{} posts at a near-constant interval of {:.1}s",
+                CADENCE_SAMPLES, mean
+            ),
+        })
+    }
+
+    /// DMs to many different people in a short time.
+    fn observe_dm_recipient(
+        &mut self,
+        user_id: &str,
+        recipient: &str,
+        now: DateTime<Utc>,
+    ) -> Vec<FlagCandidate> {
+        let cutoff = now - Duration::minutes(MASS_DM_WINDOW_MINUTES);
+        let recent = self.dm_recipients.entry(user_id.to_string()).or_default();
+        recent.retain(|(t, _)| *t > cutoff);
+        recent.push_back((now, recipient.to_string()));
+
+        let recipients: HashSet<&str> = recent.iter().map(|(_, r)| r.as_str()).collect();
+        if recipients.len() < MASS_DM_RECIPIENTS {
+            return vec![];
+        }
+
+        let count = recipients.len();
+        recent.clear();
+        vec![FlagCandidate {
+            user_id: user_id.to_string(),
+            kind: FlagKind::MassDm,
+            reason: format!(
+                "Sent DMs to {} different users within {} minutes",
+                count, MASS_DM_WINDOW_MINUTES
+            ),
+        }]
+    }
+}
+
+/// Whether message content contains a web link.
+pub(crate) fn contains_link(content: &str) -> bool {
+    let lower = content.to_lowercase();
+    lower.contains("http://") || lower.contains("https://") || lower.contains("www.")
+}
+
+// --- Background analyzer ---
+
+/// Handle for reporting activity to the background analyzer.
+#[derive(Debug, Clone)]
+pub struct AbuseMonitor {
+    tx: mpsc::UnboundedSender<ActivityEvent>,
+}
+
+impl AbuseMonitor {
+    /// Start the analyzer task. Must be called from within a Tokio runtime.
+    pub fn spawn(db: DbPool) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(run_analyzer(db, rx));
+        Self { tx }
+    }
+
+    /// Report activity. Never blocks; events are dropped if the analyzer has stopped.
+    pub fn report(&self, event: ActivityEvent) {
+        let _ = self.tx.send(event);
+    }
+}
+
+async fn run_analyzer(db: DbPool, mut rx: mpsc::UnboundedReceiver<ActivityEvent>) {
+    let mut detector = AbuseDetector::new();
+    let mut prune = tokio::time::interval(std::time::Duration::from_secs(PRUNE_INTERVAL_SECS));
+
+    loop {
+        tokio::select! {
+            event = rx.recv() => {
+                let Some(event) = event else { break };
+                let flags = detector.observe(&event, Utc::now());
+                if flags.is_empty() {
+                    continue;
+                }
+
+                let db = db.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let conn = db.lock().map_err(|e| e.to_string())?;
+                    for flag in &flags {
+                        record_flag(&conn, flag).map_err(|e| e.to_string())?;
+                    }
+                    Ok::<_, String>(flags.len())
+                })
+                .await;
+                match result {
+                    Ok(Ok(count)) => tracing::info!("Abuse analyzer raised {} flag(s)", count),
+                    Ok(Err(e)) => tracing::error!("Abuse analyzer: {}", e),
+                    Err(e) => tracing::error!("Abuse analyzer task join error: {}", e),
+                }
+            }
+            _ = prune.tick() => detector.prune(Utc::now()),
+        }
+    }
+}
+
+/// Write a detection. An open flag of the same kind for the user is bumped instead of
+/// duplicated.
+pub fn record_flag(conn: &rusqlite::Connection, flag: &FlagCandidate) -> rusqlite::Result<()> {
+    let now = Utc::now().to_rfc3339();
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM user_flags WHERE user_id = ?1 AND kind = ?2 AND status = 'open'",
+            rusqlite::params![flag.user_id, flag.kind.as_str()],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match existing {
+        Some(id) => conn.execute(
+            "UPDATE user_flags SET occurrences = occurrences + 1, reason = ?1, last_seen_at = ?2 WHERE id = ?3",
+            rusqlite::params![flag.reason, now, id],
+        )?,
+        None => conn.execute(
+            "INSERT INTO user_flags (user_id, kind, reason, created_at, last_seen_at) VALUES (?1, ?2, ?3, ?4, ?4)",
+            rusqlite::params![flag.user_id, flag.kind.as_str(), flag.reason, now],
+        )?,
+    };
+    Ok(())
+}
+
+// --- Request / Response types ---
+
+#[derive(Debug, Deserialize)]
+pub struct FlagQuery {
+    /// "open" (default), "dismissed", "reviewed" or "all"
+    pub status: Option<String>,
+    pub kind: Option<String>,
+    pub user_id: Option<String>,
+    /// Only flags with an ID below this one (pagination cursor).
+    pub before: Option<i64>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewFlagRequest {
+    /// "dismissed" or "reviewed"
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UserFlagResponse {
+    pub id: String,
+    pub user_id: String,
+    pub display_name: String,
+    pub fingerprint: String,
+    pub kind: String,
+    pub reason: String,
+    pub occurrences: i64,
+    pub status: String,
+    pub created_at: String,
+    pub last_seen_at: String,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FlagListResponse {
+    pub flags: Vec<UserFlagResponse>,
+    pub has_more: bool,
+}
+
+const FLAG_SELECT: &str = "SELECT f.id, f.user_id, u.display_name, u.fingerprint, f.kind, f.reason,
+        f.occurrences, f.status, f.created_at, f.last_seen_at, f.reviewed_by, f.reviewed_at
+     FROM user_flags f INNER JOIN users u ON u.id = f.user_id";
+
+fn flag_from_row(row: &rusqlite::Row) -> rusqlite::Result<UserFlagResponse> {
+    Ok(UserFlagResponse {
+        id: row.get::<_, i64>(0)?.to_string(),
+        user_id: row.get(1)?,
+        display_name: row.get(2)?,
+        fingerprint: row.get(3)?,
+        kind: row.get(4)?,
+        reason: row.get(5)?,
+        occurrences: row.get(6)?,
+        status: row.get(7)?,
+        created_at: row.get(8)?,
+        last_seen_at: row.get(9)?,
+        reviewed_by: row.get(10)?,
+        reviewed_at: row.get(11)?,
+    })
+}
+
+// --- Handlers ---
+
+/// GET /api/moderation/flags?status=&kind=&user_id=&before={id}&limit={n}
+/// The abuse review queue, newest first (requires ADMIN).
+pub async fn list_flags(
+    State(state): State<AppState>,
+    claims: Claims,
+    Query(query): Query<FlagQuery>,
+) -> Result<Json<FlagListResponse>, (StatusCode, String)> {
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+        .await
+        .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let status = query.status.unwrap_or_else(|| "open".to_string());
+    let db = state.db.clone();
+
+    let response = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .read()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        let mut sql = format!("{} WHERE 1 = 1", FLAG_SELECT);
+        let mut params: Vec<Value> = Vec::new();
+        let status = (status != "all").then_some(status);
+        for (column, value) in [
+            ("f.status", status),
+            ("f.kind", query.kind),
+            ("f.user_id", query.user_id),
+        ] {
+            if let Some(value) = value {
+                sql.push_str(&format!(" AND {} = ?", column));
+                params.push(Value::Text(value));
+            }
+        }
+        if let Some(before) = query.before {
+            sql.push_str(" AND f.id < ?");
+            params.push(Value::Integer(before));
+        }
+        sql.push_str(" ORDER BY f.id DESC LIMIT ?");
+        params.push(Value::Integer((limit + 1) as i64));
+
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query: {}", e)))?;
+        let flags: Vec<UserFlagResponse> = stmt
+            .query_map(rusqlite::params_from_iter(params), flag_from_row)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query: {}", e)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let has_more = flags.len() > limit as usize;
+        let flags = flags.into_iter().take(limit as usize).collect();
+
+        Ok::<_, (StatusCode, String)>(FlagListResponse { flags, has_more })
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Task join: {}", e),
+        )
+    })??;
+
+    Ok(Json(response))
+}
+
+/// PUT /api/moderation/flags/{flag_id} — Close a flag as dismissed or reviewed
+/// (requires ADMIN). Any action against the user is taken separately.
+pub async fn review_flag(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(flag_id): Path<i64>,
+    Json(req): Json<ReviewFlagRequest>,
+) -> Result<Json<UserFlagResponse>, (StatusCode, String)> {
+    if !matches!(req.status.as_str(), "dismissed" | "reviewed") {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Status must be dismissed or reviewed".to_string(),
+        ));
+    }
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+        .await
+        .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let db = state.db.clone();
+    let actor_id = claims.sub.clone();
+
+    let (flag, entry) =
+        tokio::task::spawn_blocking(move || {
+            let conn = db
+                .lock()
+                .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+            let load = |conn: &rusqlite::Connection| {
+                conn.query_row(
+                    &format!("{} WHERE f.id = ?1", FLAG_SELECT),
+                    [flag_id],
+                    flag_from_row,
+                )
+                .optional()
+                .map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Query flag: {}", e),
+                    )
+                })?
+                .ok_or((StatusCode::NOT_FOUND, "Flag not found".to_string()))
+            };
+
+            let before = load(&conn)?;
+            conn.execute(
+            "UPDATE user_flags SET status = ?1, reviewed_by = ?2, reviewed_at = ?3 WHERE id = ?4",
+            rusqlite::params![req.status, actor_id, Utc::now().to_rfc3339(), flag_id],
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Update flag: {}", e)))?;
+            let flag = load(&conn)?;
+
+            let entry = audit::record(
+                &conn,
+                AuditRecord::new(&actor_id, AuditAction::FlagReview, &flag.id)
+                    .before(&before)
+                    .after(&flag),
+            )
+            .map_err(audit::audit_error)?;
+
+            Ok::<_, (StatusCode, String)>((flag, entry))
+        })
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Task join: {}", e),
+            )
+        })??;
+
+    audit::notify_admins(&state, entry);
+
+    Ok(Json(flag))
+}
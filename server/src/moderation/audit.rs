@@ -0,0 +1,418 @@
+//! Moderation audit log: a persistent record of privileged actions.
+//!
+//! Privileged handlers call `record` inside the same DB closure that performs the
+//! action, then `notify_admins` to push the new entry to connected admins.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::middleware::Claims;
+use crate::proto::moderation as proto_mod;
+use crate::proto::ws::{envelope::Payload, Envelope};
+use crate::roles::permissions::{require_permission, Permissions};
+use crate::state::AppState;
+use crate::ws::broadcast::publish;
+use crate::ws::scope::EventScope;
+
+/// Default page size for the audit log.
+const DEFAULT_LIMIT: u32 = 50;
+/// Maximum page size for the audit log.
+const MAX_LIMIT: u32 = 100;
+
+/// A kind of audited action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    MemberKick,
+    MemberBan,
+    MemberUnban,
+    MemberWarn,
+    MemberMute,
+    MemberFastTrack,
+    MemberInvitePrivileges,
+    SanctionRevoke,
+    BanListImport,
+    BanListRemove,
+    BanListDecision,
+    FlagReview,
+    MemberRoleAdd,
+    MemberRoleRemove,
+    RoleCreate,
+    RoleUpdate,
+    RoleDelete,
+    RoleReorder,
+    ChannelCreate,
+    ChannelUpdate,
+    ChannelDelete,
+    ChannelReorder,
+    CategoryCreate,
+    CategoryUpdate,
+    CategoryDelete,
+    CategoryReorder,
+    ChannelOverrideUpdate,
+    ChannelOverrideDelete,
+    ChannelRetention,
+    CategoryRetention,
+    InviteCreate,
+    InviteDelete,
+    ServerUpdate,
+    MessageDelete,
+    MessagePin,
+    MessageUnpin,
+    VoiceMove,
+    VoiceMute,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MemberKick => "member_kick",
+            Self::MemberBan => "member_ban",
+            Self::MemberUnban => "member_unban",
+            Self::MemberWarn => "member_warn",
+            Self::MemberMute => "member_mute",
+            Self::MemberFastTrack => "member_fast_track",
+            Self::MemberInvitePrivileges => "member_invite_privileges",
+            Self::SanctionRevoke => "sanction_revoke",
+            Self::BanListImport => "ban_list_import",
+            Self::BanListRemove => "ban_list_remove",
+            Self::BanListDecision => "ban_list_decision",
+            Self::FlagReview => "flag_review",
+            Self::MemberRoleAdd => "member_role_add",
+            Self::MemberRoleRemove => "member_role_remove",
+            Self::RoleCreate => "role_create",
+            Self::RoleUpdate => "role_update",
+            Self::RoleDelete => "role_delete",
+            Self::RoleReorder => "role_reorder",
+            Self::ChannelCreate => "channel_create",
+            Self::ChannelUpdate => "channel_update",
+            Self::ChannelDelete => "channel_delete",
+            Self::ChannelReorder => "channel_reorder",
+            Self::CategoryCreate => "category_create",
+            Self::CategoryUpdate => "category_update",
+            Self::CategoryDelete => "category_delete",
+            Self::CategoryReorder => "category_reorder",
+            Self::ChannelOverrideUpdate => "channel_override_update",
+            Self::ChannelOverrideDelete => "channel_override_delete",
+            Self::ChannelRetention => "channel_retention",
+            Self::CategoryRetention => "category_retention",
+            Self::InviteCreate => "invite_create",
+            Self::InviteDelete => "invite_delete",
+            Self::ServerUpdate => "server_update",
+            Self::MessageDelete => "message_delete",
+            Self::MessagePin => "message_pin",
+            Self::MessageUnpin => "message_unpin",
+            Self::VoiceMove => "voice_move",
+            Self::VoiceMute => "voice_mute",
+        }
+    }
+
+    /// What kind of object `target_id` refers to for this action.
+    pub fn target_type(self) -> &'static str {
+        match self {
+            Self::MemberKick
+            | Self::MemberBan
+            | Self::MemberWarn
+            | Self::MemberMute
+            | Self::MemberFastTrack
+            | Self::MemberInvitePrivileges
+            | Self::MemberRoleAdd
+            | Self::MemberRoleRemove
+            | Self::VoiceMove
+            | Self::VoiceMute => "user",
+            // Bans are keyed by fingerprint so they survive key rotation
+            Self::MemberUnban | Self::BanListDecision => "fingerprint",
+            Self::BanListImport | Self::BanListRemove => "ban_list",
+            Self::SanctionRevoke => "sanction",
+            Self::FlagReview => "flag",
+            Self::RoleCreate | Self::RoleUpdate | Self::RoleDelete | Self::RoleReorder => "role",
+            Self::ChannelCreate
+            | Self::ChannelUpdate
+            | Self::ChannelDelete
+            | Self::ChannelReorder
+            | Self::ChannelOverrideUpdate
+            | Self::ChannelOverrideDelete
+            | Self::ChannelRetention => "channel",
+            Self::CategoryCreate
+            | Self::CategoryUpdate
+            | Self::CategoryDelete
+            | Self::CategoryReorder
+            | Self::CategoryRetention => "category",
+            Self::InviteCreate | Self::InviteDelete => "invite",
+            Self::ServerUpdate => "server",
+            Self::MessageDelete | Self::MessagePin | Self::MessageUnpin => "message",
+        }
+    }
+}
+
+/// An audit entry about to be written. Build with `AuditRecord::new` and the
+/// optional setters, then pass to `record`.
+#[derive(Debug)]
+pub struct AuditRecord {
+    actor_id: String,
+    action: AuditAction,
+    target_id: String,
+    reason: String,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+    evidence_message_ids: Vec<String>,
+}
+
+impl AuditRecord {
+    pub fn new(actor_id: &str, action: AuditAction, target_id: &str) -> Self {
+        Self {
+            actor_id: actor_id.to_string(),
+            action,
+            target_id: target_id.to_string(),
+            reason: String::new(),
+            before: None,
+            after: None,
+            evidence_message_ids: Vec::new(),
+        }
+    }
+
+    pub fn reason(mut self, reason: &str) -> Self {
+        self.reason = reason.to_string();
+        self
+    }
+
+    /// Snapshot of the target before the change.
+    pub fn before(mut self, before: impl Serialize) -> Self {
+        self.before = serde_json::to_value(before).ok();
+        self
+    }
+
+    /// Snapshot of the target after the change.
+    pub fn after(mut self, after: impl Serialize) -> Self {
+        self.after = serde_json::to_value(after).ok();
+        self
+    }
+
+    /// IDs of messages cited as evidence (e.g. for a kick or ban).
+    pub fn evidence(mut self, message_ids: &[String]) -> Self {
+        self.evidence_message_ids = message_ids.to_vec();
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditLogEntry {
+    pub id: String,
+    pub actor_id: String,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub reason: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub evidence_message_ids: Vec<String>,
+    pub created_at: String,
+}
+
+impl AuditLogEntry {
+    /// Convert to the protobuf AuditLogEntry used in WS events.
+    pub fn to_proto(&self) -> proto_mod::AuditLogEntry {
+        let json = |v: &Option<serde_json::Value>| {
+            v.as_ref().map(|v| v.to_string()).unwrap_or_default()
+        };
+        proto_mod::AuditLogEntry {
+            id: self.id.clone(),
+            actor_id: self.actor_id.clone(),
+            action: self.action.clone(),
+            target_type: self.target_type.clone(),
+            target_id: self.target_id.clone(),
+            reason: self.reason.clone(),
+            before_json: json(&self.before),
+            after_json: json(&self.after),
+            evidence_message_ids: self.evidence_message_ids.clone(),
+            created_at: self.created_at.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditLogQuery {
+    pub action: Option<String>,
+    pub actor_id: Option<String>,
+    pub target_id: Option<String>,
+    /// Only entries with an ID below this one (pagination cursor).
+    pub before: Option<i64>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogResponse {
+    pub entries: Vec<AuditLogEntry>,
+    pub has_more: bool,
+}
+
+// --- Recording ---
+
+/// Write an audit entry. Call from inside the DB closure that performs the action so
+/// the entry is written under the same lock.
+pub fn record(conn: &rusqlite::Connection, record: AuditRecord) -> rusqlite::Result<AuditLogEntry> {
+    let created_at = Utc::now().to_rfc3339();
+    let evidence_json =
+        serde_json::to_string(&record.evidence_message_ids).unwrap_or_else(|_| "[]".to_string());
+
+    conn.execute(
+        "INSERT INTO audit_log (actor_id, action, target_type, target_id, reason, before_json, after_json, evidence_message_ids, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        rusqlite::params![
+            record.actor_id,
+            record.action.as_str(),
+            record.action.target_type(),
+            record.target_id,
+            record.reason,
+            record.before.as_ref().map(|v| v.to_string()),
+            record.after.as_ref().map(|v| v.to_string()),
+            evidence_json,
+            created_at,
+        ],
+    )?;
+
+    Ok(AuditLogEntry {
+        id: conn.last_insert_rowid().to_string(),
+        actor_id: record.actor_id,
+        action: record.action.as_str().to_string(),
+        target_type: record.action.target_type().to_string(),
+        target_id: record.target_id,
+        reason: record.reason,
+        before: record.before,
+        after: record.after,
+        evidence_message_ids: record.evidence_message_ids,
+        created_at,
+    })
+}
+
+/// Push an AuditLogEntryCreatedEvent to connected admins: holders of a role with ADMIN,
+/// and the owner. Runs in the background; the action has already been committed.
+pub fn notify_admins(state: &AppState, entry: AuditLogEntry) {
+    let db = state.db.clone();
+    let connections = state.connections.clone();
+
+    tokio::spawn(async move {
+        let admin_roles = tokio::task::spawn_blocking(move || {
+            let conn = db.read().map_err(|e| e.to_string())?;
+            conn.prepare("SELECT id FROM roles WHERE permissions & ?1 != 0")
+                .and_then(|mut stmt| {
+                    stmt.query_map([Permissions::ADMIN.bits()], |row| row.get(0))?
+                        .collect::<rusqlite::Result<Vec<String>>>()
+                })
+                .map_err(|e| e.to_string())
+        })
+        .await;
+
+        let admin_roles = match admin_roles {
+            Ok(Ok(admin_roles)) => admin_roles,
+            Ok(Err(e)) => {
+                tracing::error!("Audit log notify: {}", e);
+                return;
+            }
+            Err(e) => {
+                tracing::error!("Audit log notify task join error: {}", e);
+                return;
+            }
+        };
+
+        let event = Envelope {
+            request_id: String::new(),
+            seq: 0,
+            payload: Some(Payload::AuditLogEntryCreatedEvent(
+                proto_mod::AuditLogEntryCreatedEvent {
+                    entry: Some(entry.to_proto()),
+                },
+            )),
+        };
+        publish(&connections, EventScope::Roles(&admin_roles), &event);
+    });
+}
+
+/// Map an audit write failure to a handler error.
+pub fn audit_error(e: rusqlite::Error) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("Audit log: {}", e))
+}
+
+// --- Handlers ---
+
+/// GET /api/audit-log?action=&actor_id=&target_id=&before={id}&limit={n}
+/// Newest entries first (requires ADMIN).
+pub async fn get_audit_log(
+    State(state): State<AppState>,
+    claims: Claims,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Json<AuditLogResponse>, (StatusCode, String)> {
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+        .await
+        .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let db = state.db.clone();
+
+    let response = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .read()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        let mut sql = "SELECT id, actor_id, action, target_type, target_id, reason, before_json, after_json, evidence_message_ids, created_at
+             FROM audit_log WHERE 1 = 1"
+            .to_string();
+        let mut params: Vec<Value> = Vec::new();
+        for (column, value) in [
+            ("action", query.action),
+            ("actor_id", query.actor_id),
+            ("target_id", query.target_id),
+        ] {
+            if let Some(value) = value {
+                sql.push_str(&format!(" AND {} = ?", column));
+                params.push(Value::Text(value));
+            }
+        }
+        if let Some(before) = query.before {
+            sql.push_str(" AND id < ?");
+            params.push(Value::Integer(before));
+        }
+        sql.push_str(" ORDER BY id DESC LIMIT ?");
+        params.push(Value::Integer((limit + 1) as i64));
+
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query: {}", e)))?;
+        let json = |s: Option<String>| -> Option<serde_json::Value> {
+            s.and_then(|s| serde_json::from_str(&s).ok())
+        };
+        let entries: Vec<AuditLogEntry> = stmt
+            .query_map(rusqlite::params_from_iter(params), |row| {
+                Ok(AuditLogEntry {
+                    id: row.get::<_, i64>(0)?.to_string(),
+                    actor_id: row.get(1)?,
+                    action: row.get(2)?,
+                    target_type: row.get(3)?,
+                    target_id: row.get(4)?,
+                    reason: row.get(5)?,
+                    before: json(row.get(6)?),
+                    after: json(row.get(7)?),
+                    evidence_message_ids: serde_json::from_str(&row.get::<_, String>(8)?)
+                        .unwrap_or_default(),
+                    created_at: row.get(9)?,
+                })
+            })
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query: {}", e)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let has_more = entries.len() > limit as usize;
+        let entries = entries.into_iter().take(limit as usize).collect();
+
+        Ok::<_, (StatusCode, String)>(AuditLogResponse { entries, has_more })
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    Ok(Json(response))
+}
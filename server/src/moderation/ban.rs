@@ -3,17 +3,26 @@ use axum::{
     http::StatusCode,
     Json,
 };
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::auth::middleware::Claims;
+use crate::invite::tree::flag_invitees;
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::moderation::ban_list::{ban_signing_bytes, verify_ban_signature};
+use crate::moderation::sanctions::{self, SanctionKind};
 use crate::proto::moderation as proto_mod;
 use crate::proto::ws::{envelope::Payload, Envelope};
+use crate::roles::hierarchy::require_outranks;
 use crate::roles::permissions::{require_permission, Permissions};
 use crate::state::AppState;
 use crate::ws::broadcast::{broadcast_to_all, force_close_user};
 
+/// How far `signed_at` on a signed ban may be from the server's clock.
+const MAX_SIGNATURE_SKEW_SECS: i64 = 300;
+
 #[derive(Debug, Deserialize)]
 pub struct BanRequest {
     pub user_id: String,
@@ -21,6 +30,22 @@ pub struct BanRequest {
     pub reason: String,
     #[serde(default)]
     pub expires_at: String,
+    /// Temporary ban length; alternative to `expires_at`
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
+    #[serde(default)]
+    pub evidence_message_ids: Vec<String>,
+    /// Also flag everyone the banned member brought in for review
+    #[serde(default)]
+    pub review_invitees: bool,
+    /// The admin's Ed25519 signature (hex) over `ban_list::ban_signing_bytes` for this
+    /// server's key, the member's key, `reason` and `signed_at`. Published with the ban
+    /// on the exported ban list.
+    #[serde(default)]
+    pub admin_signature: Option<String>,
+    /// Ban time covered by `admin_signature` (RFC 3339); becomes the ban's created_at
+    #[serde(default)]
+    pub signed_at: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -48,12 +73,52 @@ pub struct BanListResponse {
     pub bans: Vec<BanInfoResponse>,
 }
 
-/// POST /api/moderation/ban — Ban a user (requires BAN_MEMBERS).
+/// POST /api/moderation/ban — Ban a user (requires BAN_MEMBERS and a higher role
+/// than the target). Temporary if `expires_at` or `duration_secs` is given. An
+/// `admin_signature` is checked against the caller's key and stored with the ban.
 pub async fn ban_user(
     State(state): State<AppState>,
     claims: Claims,
     Json(req): Json<BanRequest>,
 ) -> Result<Json<BanResponse>, (StatusCode, String)> {
+    let expires_at = match req.duration_secs {
+        None => req.expires_at.clone(),
+        Some(_) if !req.expires_at.is_empty() => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Give either expires_at or duration_secs, not both".to_string(),
+            ))
+        }
+        Some(0) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Ban duration must be positive".to_string(),
+            ))
+        }
+        Some(secs) => (Utc::now() + Duration::seconds(secs as i64)).to_rfc3339(),
+    };
+    let signature = match (req.admin_signature.clone(), req.signed_at.clone()) {
+        (None, None) => None,
+        (Some(signature), Some(signed_at)) => {
+            let skew = DateTime::parse_from_rfc3339(&signed_at)
+                .map(|t| (Utc::now() - t.with_timezone(&Utc)).num_seconds().abs())
+                .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid signed_at".to_string()))?;
+            if skew > MAX_SIGNATURE_SKEW_SECS {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "signed_at is too far from the server time".to_string(),
+                ));
+            }
+            Some((signature, signed_at))
+        }
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "admin_signature and signed_at go together".to_string(),
+            ))
+        }
+    };
+
     require_permission(
         &state.db,
         &claims.sub,
@@ -66,20 +131,23 @@ pub async fn ban_user(
     let db = state.db.clone();
     let target_id = req.user_id.clone();
     let banned_by = claims.sub.clone();
+    let actor_is_owner = claims.is_owner;
     let reason = req.reason.clone();
-    let expires_at = req.expires_at.clone();
+    let evidence = req.evidence_message_ids.clone();
+    let review_invitees = req.review_invitees;
+    let server_public_key = hex::encode(state.server_signing_key.verifying_key().as_bytes());
 
-    let (ban_id, _fingerprint) = tokio::task::spawn_blocking(move || {
+    let (ban_id, entry) = tokio::task::spawn_blocking(move || {
         let conn = db
             .lock()
             .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
 
         // Look up target user
-        let (is_owner, fingerprint): (bool, String) = conn
+        let (is_owner, fingerprint, banned_key): (bool, String, String) = conn
             .query_row(
-                "SELECT is_owner, fingerprint FROM users WHERE id = ?1",
+                "SELECT is_owner, fingerprint, lower(hex(public_key)) FROM users WHERE id = ?1",
                 [&target_id],
-                |row| Ok((row.get::<_, bool>(0)?, row.get(1)?)),
+                |row| Ok((row.get::<_, bool>(0)?, row.get(1)?, row.get(2)?)),
             )
             .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
 
@@ -90,8 +158,27 @@ pub async fn ban_user(
             ));
         }
 
+        require_outranks(&conn, &banned_by, actor_is_owner, &target_id)?;
+
+        // A signed ban must verify against the issuing admin's own key
+        let (admin_signature, created_at) = match signature {
+            Some((signature, signed_at)) => {
+                let admin_key: String = conn
+                    .query_row(
+                        "SELECT lower(hex(public_key)) FROM users WHERE id = ?1",
+                        [&banned_by],
+                        |row| row.get(0),
+                    )
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Load admin key: {}", e)))?;
+                let signed = ban_signing_bytes(&server_public_key, &banned_key, &reason, &signed_at);
+                verify_ban_signature(&admin_key, &signature, &signed)
+                    .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+                (Some(signature.to_lowercase()), signed_at)
+            }
+            None => (None, Utc::now().to_rfc3339()),
+        };
+
         let ban_id = Uuid::now_v7().to_string();
-        let now = Utc::now().to_rfc3339();
         let exp = if expires_at.is_empty() {
             None
         } else {
@@ -99,12 +186,41 @@ pub async fn ban_user(
         };
 
         conn.execute(
-            "INSERT OR REPLACE INTO bans (id, fingerprint, banned_by, reason, expires_at, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            rusqlite::params![ban_id, fingerprint, banned_by, reason, exp, now],
+            "INSERT OR REPLACE INTO bans (id, fingerprint, banned_by, reason, expires_at, created_at, banned_key, admin_signature)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![ban_id, fingerprint, banned_by, reason, exp, created_at, banned_key, admin_signature],
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Insert ban: {}", e)))?;
+        sanctions::issue(
+            &conn,
+            &fingerprint,
+            &target_id,
+            SanctionKind::Ban,
+            &reason,
+            &banned_by,
+            exp,
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Insert sanction: {}", e)))?;
+        if review_invitees {
+            flag_invitees(&conn, &target_id)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Flag invitees: {}", e)))?;
+        }
+
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&banned_by, AuditAction::MemberBan, &target_id)
+                .reason(&reason)
+                .after(serde_json::json!({
+                    "ban_id": ban_id,
+                    "fingerprint": fingerprint,
+                    "expires_at": exp,
+                    "admin_signed": admin_signature.is_some(),
+                }))
+                .evidence(&evidence),
+        )
+        .map_err(audit::audit_error)?;
 
-        Ok::<_, (StatusCode, String)>((ban_id, fingerprint))
+        Ok::<_, (StatusCode, String)>((ban_id, entry))
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
@@ -120,12 +236,14 @@ pub async fn ban_user(
     // Broadcast UserBannedEvent
     let event = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::UserBannedEvent(proto_mod::UserBannedEvent {
             user_id: req.user_id.clone(),
             reason: req.reason.clone(),
         })),
     };
     broadcast_to_all(&state.connections, &event);
+    audit::notify_admins(&state, entry);
 
     Ok(Json(BanResponse { ban_id }))
 }
@@ -147,11 +265,32 @@ pub async fn unban_user(
 
     let db = state.db.clone();
     let fingerprint = req.fingerprint.clone();
+    let actor_id = claims.sub.clone();
 
-    tokio::task::spawn_blocking(move || {
+    let entry = tokio::task::spawn_blocking(move || {
         let conn = db
             .lock()
             .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        // Keep the lifted ban in the audit trail
+        let before: Option<BanInfoResponse> = conn
+            .query_row(
+                "SELECT id, fingerprint, banned_by, reason, expires_at, created_at FROM bans WHERE fingerprint = ?1",
+                [&fingerprint],
+                |row| {
+                    Ok(BanInfoResponse {
+                        id: row.get(0)?,
+                        fingerprint: row.get(1)?,
+                        banned_by: row.get(2)?,
+                        reason: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                        expires_at: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                        created_at: row.get(5)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query ban: {}", e)))?;
+
         conn.execute("DELETE FROM bans WHERE fingerprint = ?1", [&fingerprint])
             .map_err(|e| {
                 (
@@ -159,7 +298,18 @@ pub async fn unban_user(
                     format!("Delete ban: {}", e),
                 )
             })?;
-        Ok::<_, (StatusCode, String)>(())
+        sanctions::revoke_bans(&conn, &fingerprint, &actor_id).map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Revoke sanction: {}", e),
+            )
+        })?;
+
+        let mut record = AuditRecord::new(&actor_id, AuditAction::MemberUnban, &fingerprint);
+        if let Some(before) = before {
+            record = record.before(before);
+        }
+        audit::record(&conn, record).map_err(audit::audit_error)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
@@ -167,11 +317,13 @@ pub async fn unban_user(
     // Broadcast UserUnbannedEvent
     let event = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::UserUnbannedEvent(proto_mod::UserUnbannedEvent {
             fingerprint: req.fingerprint.clone(),
         })),
     };
     broadcast_to_all(&state.connections, &event);
+    audit::notify_admins(&state, entry);
 
     Ok(StatusCode::OK)
 }
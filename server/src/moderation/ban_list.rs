@@ -0,0 +1,867 @@
+//! Shared ban lists: each server publishes its active bans as a list signed with its
+//! Ed25519 identity key, and admins can import other servers' lists.
+//!
+//! Imported entries are only recorded; nothing is banned locally until a moderator
+//! reviews an entry and chooses `import`. `watch` keeps the entry visible on the
+//! matching user's moderation record, and `ignore` dismisses it. Re-importing a list
+//! from the same key refreshes the source and reports what changed.
+//!
+//! Each entry can also carry the issuing admin's own signature over the ban (see
+//! `ban_signing_bytes`), so an importing server can tell which admin key stands behind
+//! it. Signed entries are verified on import; a list with a bad entry signature is
+//! rejected as a whole.
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::admin::settings::load_server_info;
+use crate::auth::middleware::Claims;
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::moderation::sanctions::{self, SanctionKind};
+use crate::proto::moderation as proto_mod;
+use crate::proto::ws::{envelope::Payload, Envelope};
+use crate::roles::permissions::{require_permission, Permissions};
+use crate::state::AppState;
+use crate::ws::broadcast::{broadcast_to_all, force_close_user};
+
+/// Ban list format version.
+const FORMAT_VERSION: u32 = 1;
+/// Prefixed to the signed bytes so a ban list signature cannot be replayed elsewhere.
+const SIGNING_CONTEXT: &[u8] = b"united-ban-list-v1\n";
+/// Prefixed to the bytes an admin signs when issuing a single ban.
+const BAN_SIGNING_CONTEXT: &[u8] = b"united-ban-v1\n";
+/// Timeout for fetching a ban list by URL.
+const FETCH_TIMEOUT_SECS: u64 = 10;
+
+// --- Ban list format ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BanListEntry {
+    pub fingerprint: String,
+    pub reason: String,
+    pub banned_at: String,
+    pub expires_at: Option<String>,
+    /// Public key (hex) of the admin who issued the ban
+    pub banned_by: String,
+    /// Public key (hex) of the banned member when the ban was issued
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub banned_key: Option<String>,
+    /// `banned_by`'s signature (hex) over `ban_signing_bytes`, if the ban was signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin_signature: Option<String>,
+}
+
+/// The signed part of a ban list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BanList {
+    pub version: u32,
+    pub server_name: String,
+    /// Ed25519 public key (hex) of the publishing server
+    pub server_public_key: String,
+    pub generated_at: String,
+    pub entries: Vec<BanListEntry>,
+}
+
+/// A ban list as exported. `signature` (hex) covers `SIGNING_CONTEXT` followed by the
+/// JSON serialization of the `BanList` fields in declaration order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedBanList {
+    #[serde(flatten)]
+    pub list: BanList,
+    pub signature: String,
+}
+
+/// Bytes an admin signs for one ban: `BAN_SIGNING_CONTEXT` followed by the JSON array
+/// `[server_public_key, banned_key, reason, banned_at]`, keys in hex.
+pub fn ban_signing_bytes(
+    server_public_key: &str,
+    banned_key: &str,
+    reason: &str,
+    banned_at: &str,
+) -> Vec<u8> {
+    let mut bytes = BAN_SIGNING_CONTEXT.to_vec();
+    bytes.extend(
+        serde_json::to_vec(&[
+            server_public_key.to_lowercase(),
+            banned_key.to_lowercase(),
+            reason.to_string(),
+            banned_at.to_string(),
+        ])
+        .unwrap_or_default(),
+    );
+    bytes
+}
+
+/// Check an admin's signature over a ban.
+pub fn verify_ban_signature(
+    admin_key: &str,
+    signature: &str,
+    signed_bytes: &[u8],
+) -> Result<(), String> {
+    let key = decode_public_key(admin_key).ok_or("Invalid admin public key")?;
+    let signature = decode_signature(signature).ok_or("Invalid admin signature")?;
+    key.verify(signed_bytes, &signature)
+        .map_err(|_| "Invalid admin signature".to_string())
+}
+
+fn decode_public_key(key: &str) -> Option<VerifyingKey> {
+    let bytes: [u8; 32] = hex::decode(key).ok()?.try_into().ok()?;
+    VerifyingKey::from_bytes(&bytes).ok()
+}
+
+fn decode_signature(signature: &str) -> Option<Signature> {
+    let bytes: [u8; 64] = hex::decode(signature).ok()?.try_into().ok()?;
+    Some(Signature::from_bytes(&bytes))
+}
+
+/// Fingerprint of a public key: base32 of the first 20 bytes of its SHA-256.
+fn fingerprint_of(key: &VerifyingKey) -> String {
+    let hash = Sha256::digest(key.as_bytes());
+    base32::encode(base32::Alphabet::Rfc4648 { padding: false }, &hash[..20])
+}
+
+impl BanListEntry {
+    /// Check the admin signature of a signed entry against `banned_by`. The signed key
+    /// must also be the one the entry's fingerprint belongs to. Unsigned entries pass.
+    fn verify(&self, server_public_key: &str) -> Result<(), String> {
+        let Some(signature) = &self.admin_signature else {
+            return Ok(());
+        };
+        let banned_key = self
+            .banned_key
+            .as_deref()
+            .ok_or("Signed entry has no banned_key")?;
+        let key = decode_public_key(banned_key).ok_or("Invalid banned_key")?;
+        if fingerprint_of(&key) != self.fingerprint {
+            return Err("banned_key does not match the fingerprint".to_string());
+        }
+        verify_ban_signature(
+            &self.banned_by,
+            signature,
+            &ban_signing_bytes(server_public_key, banned_key, &self.reason, &self.banned_at),
+        )
+    }
+}
+
+impl BanList {
+    fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = SIGNING_CONTEXT.to_vec();
+        bytes.extend(serde_json::to_vec(self).unwrap_or_default());
+        bytes
+    }
+
+    pub fn sign(self, key: &SigningKey) -> SignedBanList {
+        let signature = hex::encode(key.sign(&self.signing_bytes()).to_bytes());
+        SignedBanList {
+            list: self,
+            signature,
+        }
+    }
+}
+
+impl SignedBanList {
+    /// Check the signature against the embedded server public key.
+    pub fn verify(&self) -> Result<(), String> {
+        let key = decode_public_key(&self.list.server_public_key)
+            .ok_or("Invalid server public key")?;
+        let signature = decode_signature(&self.signature).ok_or("Invalid signature")?;
+        key.verify(&self.list.signing_bytes(), &signature)
+            .map_err(|_| "Invalid ban list signature".to_string())
+    }
+
+    /// Check the list signature, then the admin signature of every signed entry.
+    pub fn verify_entries(&self) -> Result<(), String> {
+        self.verify()?;
+        for entry in &self.list.entries {
+            entry
+                .verify(&self.list.server_public_key)
+                .map_err(|e| format!("Entry {}: {}", entry.fingerprint, e))?;
+        }
+        Ok(())
+    }
+}
+
+// --- Request / Response types ---
+
+/// Import a ban list either by URL (fetched by this server) or as uploaded JSON.
+#[derive(Debug, Deserialize)]
+pub struct ImportBanListRequest {
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub list: Option<SignedBanList>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BanListDecisionRequest {
+    /// "import", "ignore" or "watch"
+    pub decision: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BanListSourceResponse {
+    pub id: String,
+    pub public_key: String,
+    pub name: String,
+    pub url: Option<String>,
+    pub generated_at: String,
+    pub imported_by: String,
+    pub imported_at: String,
+    pub pending_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BanListEntryResponse {
+    pub fingerprint: String,
+    pub reason: String,
+    pub banned_at: String,
+    pub expires_at: Option<String>,
+    pub banned_by: String,
+    /// Whether the entry carried a verified admin signature
+    pub admin_signed: bool,
+    /// "pending", "import", "ignore" or "watch"
+    pub decision: String,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<String>,
+    /// Local user with this fingerprint, if any
+    pub local_user_id: Option<String>,
+    pub locally_banned: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BanListSourceListResponse {
+    pub sources: Vec<BanListSourceResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BanListSourceDetailResponse {
+    pub source: BanListSourceResponse,
+    pub entries: Vec<BanListEntryResponse>,
+}
+
+/// What changed since the previous import of the same source.
+#[derive(Debug, Serialize)]
+pub struct ImportBanListResponse {
+    pub source: BanListSourceResponse,
+    pub added: Vec<BanListEntryResponse>,
+    /// Entries whose reason or expiry changed (decisions are kept)
+    pub changed: Vec<BanListEntryResponse>,
+    /// Fingerprints no longer on the list (local bans made from them are kept)
+    pub removed: Vec<String>,
+}
+
+/// An entry a moderator chose to watch, shown on the user's moderation record.
+#[derive(Debug, Serialize)]
+pub struct WatchedBanEntry {
+    pub source_id: String,
+    pub source_name: String,
+    pub reason: String,
+    pub banned_at: String,
+}
+
+// --- Helpers ---
+
+fn db_error(e: rusqlite::Error) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("Ban list: {}", e))
+}
+
+fn load_source(
+    conn: &rusqlite::Connection,
+    source_id: &str,
+) -> rusqlite::Result<Option<BanListSourceResponse>> {
+    conn.query_row(
+        "SELECT s.id, s.public_key, s.name, s.url, s.generated_at, s.imported_by, s.imported_at,
+                (SELECT COUNT(*) FROM ban_list_entries e
+                 WHERE e.source_id = s.id AND e.decision = 'pending')
+         FROM ban_list_sources s WHERE s.id = ?1",
+        [source_id],
+        |row| {
+            Ok(BanListSourceResponse {
+                id: row.get(0)?,
+                public_key: row.get(1)?,
+                name: row.get(2)?,
+                url: row.get(3)?,
+                generated_at: row.get(4)?,
+                imported_by: row.get(5)?,
+                imported_at: row.get(6)?,
+                pending_count: row.get(7)?,
+            })
+        },
+    )
+    .optional()
+}
+
+/// Entries of a source, pending first, then newest bans first.
+fn load_entries(
+    conn: &rusqlite::Connection,
+    source_id: &str,
+) -> rusqlite::Result<Vec<BanListEntryResponse>> {
+    let mut stmt = conn.prepare(
+        "SELECT e.fingerprint, e.reason, e.banned_at, e.expires_at, e.banned_by,
+                e.admin_signature IS NOT NULL, e.decision, e.decided_by, e.decided_at,
+                (SELECT id FROM users WHERE fingerprint = e.fingerprint),
+                EXISTS(SELECT 1 FROM bans WHERE fingerprint = e.fingerprint)
+         FROM ban_list_entries e WHERE e.source_id = ?1
+         ORDER BY e.decision = 'pending' DESC, e.banned_at DESC",
+    )?;
+    let entries = stmt
+        .query_map([source_id], |row| {
+            Ok(BanListEntryResponse {
+                fingerprint: row.get(0)?,
+                reason: row.get(1)?,
+                banned_at: row.get(2)?,
+                expires_at: row.get(3)?,
+                banned_by: row.get(4)?,
+                admin_signed: row.get(5)?,
+                decision: row.get(6)?,
+                decided_by: row.get(7)?,
+                decided_at: row.get(8)?,
+                local_user_id: row.get(9)?,
+                locally_banned: row.get(10)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(entries)
+}
+
+/// Watched entries from imported ban lists for a fingerprint.
+pub fn watched_entries(
+    conn: &rusqlite::Connection,
+    fingerprint: &str,
+) -> rusqlite::Result<Vec<WatchedBanEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.name, e.reason, e.banned_at FROM ban_list_entries e
+         INNER JOIN ban_list_sources s ON s.id = e.source_id
+         WHERE e.fingerprint = ?1 AND e.decision = 'watch'
+         ORDER BY e.banned_at DESC",
+    )?;
+    let entries = stmt
+        .query_map([fingerprint], |row| {
+            Ok(WatchedBanEntry {
+                source_id: row.get(0)?,
+                source_name: row.get(1)?,
+                reason: row.get(2)?,
+                banned_at: row.get(3)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(entries)
+}
+
+async fn fetch_ban_list(url: &str) -> Result<SignedBanList, (StatusCode, String)> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Ban list URL must be http(s)".to_string(),
+        ));
+    }
+    let fetch_error = |e: reqwest::Error| {
+        (StatusCode::BAD_GATEWAY, format!("Fetch ban list: {}", e))
+    };
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS))
+        .build()
+        .map_err(fetch_error)?;
+    client
+        .get(url)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(fetch_error)?
+        .json::<SignedBanList>()
+        .await
+        .map_err(fetch_error)
+}
+
+// --- Handlers ---
+
+/// GET /api/moderation/ban-list — This server's active bans as a signed ban list.
+/// Public, so other servers can subscribe by URL.
+pub async fn export_ban_list(
+    State(state): State<AppState>,
+) -> Result<Json<SignedBanList>, (StatusCode, String)> {
+    let db = state.db.clone();
+    let server_public_key = hex::encode(state.server_signing_key.verifying_key().as_bytes());
+
+    let list = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .read()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        let now = Utc::now().to_rfc3339();
+        let mut stmt = conn
+            .prepare(
+                "SELECT b.fingerprint, COALESCE(b.reason, ''), b.created_at, b.expires_at,
+                        COALESCE(lower(hex(u.public_key)), ''), b.banned_key, b.admin_signature
+                 FROM bans b LEFT JOIN users u ON u.id = b.banned_by
+                 WHERE b.expires_at IS NULL OR b.expires_at > ?1
+                 ORDER BY b.created_at",
+            )
+            .map_err(db_error)?;
+        let entries: Vec<BanListEntry> = stmt
+            .query_map([&now], |row| {
+                Ok(BanListEntry {
+                    fingerprint: row.get(0)?,
+                    reason: row.get(1)?,
+                    banned_at: row.get(2)?,
+                    expires_at: row.get(3)?,
+                    banned_by: row.get(4)?,
+                    banned_key: row.get(5)?,
+                    admin_signature: row.get(6)?,
+                })
+            })
+            .map_err(db_error)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok::<_, (StatusCode, String)>(BanList {
+            version: FORMAT_VERSION,
+            server_name: load_server_info(&conn).name,
+            server_public_key,
+            generated_at: now,
+            entries,
+        })
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    Ok(Json(list.sign(&state.server_signing_key)))
+}
+
+/// POST /api/moderation/ban-lists — Import another server's ban list by `url` or as an
+/// uploaded `list` (requires ADMIN). The list signature and any admin signatures on its
+/// entries are verified and the source pinned by its public key; entries are recorded
+/// as pending for review.
+pub async fn import_ban_list(
+    State(state): State<AppState>,
+    claims: Claims,
+    Json(req): Json<ImportBanListRequest>,
+) -> Result<Json<ImportBanListResponse>, (StatusCode, String)> {
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+        .await
+        .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let signed = match (&req.url, req.list) {
+        (Some(url), None) => fetch_ban_list(url).await?,
+        (None, Some(list)) => list,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Give either url or list".to_string(),
+            ))
+        }
+    };
+    signed
+        .verify_entries()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let list = signed.list;
+    if list.version != FORMAT_VERSION {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Unsupported ban list version {}", list.version),
+        ));
+    }
+    let own_key = hex::encode(state.server_signing_key.verifying_key().as_bytes());
+    if list.server_public_key.eq_ignore_ascii_case(&own_key) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Cannot import this server's own ban list".to_string(),
+        ));
+    }
+
+    let db = state.db.clone();
+    let actor_id = claims.sub.clone();
+    let url = req.url;
+
+    let (response, entry) = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+        let now = Utc::now().to_rfc3339();
+        let public_key = list.server_public_key.to_lowercase();
+
+        let existing: Option<(String, String)> = conn
+            .query_row(
+                "SELECT id, generated_at FROM ban_list_sources WHERE public_key = ?1",
+                [&public_key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(db_error)?;
+        let source_id = match existing {
+            Some((_, generated_at)) if list.generated_at < generated_at => {
+                return Err((
+                    StatusCode::CONFLICT,
+                    "Ban list is older than the last import".to_string(),
+                ));
+            }
+            Some((source_id, _)) => {
+                conn.execute(
+                    "UPDATE ban_list_sources
+                     SET name = ?1, url = COALESCE(?2, url), generated_at = ?3, imported_by = ?4, imported_at = ?5
+                     WHERE id = ?6",
+                    rusqlite::params![list.server_name, url, list.generated_at, actor_id, now, source_id],
+                )
+                .map_err(db_error)?;
+                source_id
+            }
+            None => {
+                let source_id = Uuid::now_v7().to_string();
+                conn.execute(
+                    "INSERT INTO ban_list_sources (id, public_key, name, url, generated_at, imported_by, imported_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    rusqlite::params![source_id, public_key, list.server_name, url, list.generated_at, actor_id, now],
+                )
+                .map_err(db_error)?;
+                source_id
+            }
+        };
+
+        // Diff against the previous import
+        let previous: HashMap<String, (String, Option<String>)> = load_entries(&conn, &source_id)
+            .map_err(db_error)?
+            .into_iter()
+            .map(|e| (e.fingerprint, (e.reason, e.expires_at)))
+            .collect();
+        let mut seen = HashSet::new();
+        let mut added = HashSet::new();
+        let mut changed = HashSet::new();
+        for e in &list.entries {
+            if !seen.insert(e.fingerprint.clone()) {
+                continue;
+            }
+            match previous.get(&e.fingerprint) {
+                None => {
+                    conn.execute(
+                        "INSERT INTO ban_list_entries (source_id, fingerprint, reason, banned_at, expires_at, banned_by, banned_key, admin_signature)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                        rusqlite::params![source_id, e.fingerprint, e.reason, e.banned_at, e.expires_at, e.banned_by, e.banned_key, e.admin_signature],
+                    )
+                    .map_err(db_error)?;
+                    added.insert(e.fingerprint.clone());
+                }
+                Some((reason, expires_at)) if *reason != e.reason || *expires_at != e.expires_at => {
+                    conn.execute(
+                        "UPDATE ban_list_entries SET reason = ?1, banned_at = ?2, expires_at = ?3, banned_by = ?4,
+                                banned_key = ?5, admin_signature = ?6
+                         WHERE source_id = ?7 AND fingerprint = ?8",
+                        rusqlite::params![e.reason, e.banned_at, e.expires_at, e.banned_by, e.banned_key, e.admin_signature, source_id, e.fingerprint],
+                    )
+                    .map_err(db_error)?;
+                    changed.insert(e.fingerprint.clone());
+                }
+                Some(_) => {}
+            }
+        }
+        let mut removed: Vec<String> = previous
+            .into_keys()
+            .filter(|fingerprint| !seen.contains(fingerprint))
+            .collect();
+        removed.sort();
+        for fingerprint in &removed {
+            conn.execute(
+                "DELETE FROM ban_list_entries WHERE source_id = ?1 AND fingerprint = ?2",
+                rusqlite::params![source_id, fingerprint],
+            )
+            .map_err(db_error)?;
+        }
+
+        let (added, changed): (Vec<_>, Vec<_>) = load_entries(&conn, &source_id)
+            .map_err(db_error)?
+            .into_iter()
+            .filter(|e| added.contains(&e.fingerprint) || changed.contains(&e.fingerprint))
+            .partition(|e| added.contains(&e.fingerprint));
+        let source = load_source(&conn, &source_id)
+            .map_err(db_error)?
+            .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Source vanished".to_string()))?;
+
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::BanListImport, &source_id).after(
+                serde_json::json!({
+                    "name": source.name,
+                    "public_key": source.public_key,
+                    "url": source.url,
+                    "added": added.len(),
+                    "changed": changed.len(),
+                    "removed": removed.len(),
+                }),
+            ),
+        )
+        .map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((
+            ImportBanListResponse {
+                source,
+                added,
+                changed,
+                removed,
+            },
+            entry,
+        ))
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    audit::notify_admins(&state, entry);
+
+    Ok(Json(response))
+}
+
+/// GET /api/moderation/ban-lists — Imported ban list sources (requires BAN_MEMBERS).
+pub async fn list_ban_list_sources(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<Json<BanListSourceListResponse>, (StatusCode, String)> {
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        Permissions::BAN_MEMBERS,
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let db = state.db.clone();
+
+    let sources = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .read()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        let mut stmt = conn
+            .prepare("SELECT id FROM ban_list_sources ORDER BY imported_at DESC")
+            .map_err(db_error)?;
+        let ids: Vec<String> = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(db_error)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let mut sources = Vec::new();
+        for id in ids {
+            if let Some(source) = load_source(&conn, &id).map_err(db_error)? {
+                sources.push(source);
+            }
+        }
+        Ok::<_, (StatusCode, String)>(sources)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    Ok(Json(BanListSourceListResponse { sources }))
+}
+
+/// GET /api/moderation/ban-lists/{source_id} — A source and its entries for review,
+/// pending entries first (requires BAN_MEMBERS).
+pub async fn get_ban_list_source(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(source_id): Path<String>,
+) -> Result<Json<BanListSourceDetailResponse>, (StatusCode, String)> {
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        Permissions::BAN_MEMBERS,
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let db = state.db.clone();
+
+    let detail = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .read()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        let source = load_source(&conn, &source_id)
+            .map_err(db_error)?
+            .ok_or((StatusCode::NOT_FOUND, "Ban list not found".to_string()))?;
+        let entries = load_entries(&conn, &source_id).map_err(db_error)?;
+
+        Ok::<_, (StatusCode, String)>(BanListSourceDetailResponse { source, entries })
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    Ok(Json(detail))
+}
+
+/// PUT /api/moderation/ban-lists/{source_id}/entries/{fingerprint} — Decide on an entry
+/// (requires BAN_MEMBERS). `import` bans the fingerprint locally; changing the decision
+/// later does not lift that ban (use unban).
+pub async fn decide_ban_list_entry(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path((source_id, fingerprint)): Path<(String, String)>,
+    Json(req): Json<BanListDecisionRequest>,
+) -> Result<Json<BanListEntryResponse>, (StatusCode, String)> {
+    if !matches!(req.decision.as_str(), "import" | "ignore" | "watch") {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Decision must be import, ignore or watch".to_string(),
+        ));
+    }
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        Permissions::BAN_MEMBERS,
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let db = state.db.clone();
+    let actor_id = claims.sub.clone();
+    let decision = req.decision.clone();
+
+    let (response, banned_user, entry) = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        let source = load_source(&conn, &source_id)
+            .map_err(db_error)?
+            .ok_or((StatusCode::NOT_FOUND, "Ban list not found".to_string()))?;
+        let current = load_entries(&conn, &source_id)
+            .map_err(db_error)?
+            .into_iter()
+            .find(|e| e.fingerprint == fingerprint)
+            .ok_or((StatusCode::NOT_FOUND, "Entry not found".to_string()))?;
+
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "UPDATE ban_list_entries SET decision = ?1, decided_by = ?2, decided_at = ?3
+             WHERE source_id = ?4 AND fingerprint = ?5",
+            rusqlite::params![decision, actor_id, now, source_id, fingerprint],
+        )
+        .map_err(db_error)?;
+
+        // Ban locally unless the fingerprint is already banned here
+        let mut ban_id = None;
+        let mut banned_user = None;
+        if decision == "import" && !current.locally_banned {
+            let reason = format!("{} (imported from {})", current.reason, source.name);
+            let id = Uuid::now_v7().to_string();
+            conn.execute(
+                "INSERT INTO bans (id, fingerprint, banned_by, reason, expires_at, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![id, fingerprint, actor_id, reason, current.expires_at, now],
+            )
+            .map_err(db_error)?;
+            if let Some(user_id) = &current.local_user_id {
+                sanctions::issue(
+                    &conn,
+                    &fingerprint,
+                    user_id,
+                    SanctionKind::Ban,
+                    &reason,
+                    &actor_id,
+                    current.expires_at.as_deref(),
+                )
+                .map_err(db_error)?;
+                banned_user = Some((user_id.clone(), reason));
+            }
+            ban_id = Some(id);
+        }
+
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::BanListDecision, &fingerprint)
+                .reason(&current.reason)
+                .before(serde_json::json!({ "decision": current.decision }))
+                .after(serde_json::json!({
+                    "source_id": source.id,
+                    "source_name": source.name,
+                    "decision": decision,
+                    "ban_id": ban_id,
+                })),
+        )
+        .map_err(audit::audit_error)?;
+
+        let response = load_entries(&conn, &source_id)
+            .map_err(db_error)?
+            .into_iter()
+            .find(|e| e.fingerprint == fingerprint)
+            .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Entry vanished".to_string()))?;
+
+        Ok::<_, (StatusCode, String)>((response, banned_user, entry))
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    if let Some((user_id, reason)) = banned_user {
+        force_close_user(
+            &state.connections,
+            &user_id,
+            4003,
+            &format!("Banned: {}", reason),
+        );
+        let event = Envelope {
+            request_id: String::new(),
+            seq: 0,
+            payload: Some(Payload::UserBannedEvent(proto_mod::UserBannedEvent {
+                user_id,
+                reason,
+            })),
+        };
+        broadcast_to_all(&state.connections, &event);
+    }
+    audit::notify_admins(&state, entry);
+
+    Ok(Json(response))
+}
+
+/// DELETE /api/moderation/ban-lists/{source_id} — Unsubscribe from a ban list
+/// (requires ADMIN). Local bans imported from it are kept.
+pub async fn delete_ban_list_source(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(source_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+        .await
+        .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let db = state.db.clone();
+    let actor_id = claims.sub.clone();
+
+    let entry = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        let source = load_source(&conn, &source_id)
+            .map_err(db_error)?
+            .ok_or((StatusCode::NOT_FOUND, "Ban list not found".to_string()))?;
+        conn.execute("DELETE FROM ban_list_sources WHERE id = ?1", [&source_id])
+            .map_err(db_error)?;
+
+        audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::BanListRemove, &source_id).before(source),
+        )
+        .map_err(audit::audit_error)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    audit::notify_admins(&state, entry);
+
+    Ok(StatusCode::OK)
+}
@@ -2,8 +2,11 @@ use axum::{extract::State, http::StatusCode, Json};
 use serde::Deserialize;
 
 use crate::auth::middleware::Claims;
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::moderation::sanctions::{self, SanctionKind};
 use crate::proto::moderation as proto_mod;
 use crate::proto::ws::{envelope::Payload, Envelope};
+use crate::roles::hierarchy::require_outranks;
 use crate::roles::permissions::{require_permission, Permissions};
 use crate::state::AppState;
 use crate::ws::broadcast::{broadcast_to_all, force_close_user};
@@ -11,9 +14,14 @@ use crate::ws::broadcast::{broadcast_to_all, force_close_user};
 #[derive(Debug, Deserialize)]
 pub struct KickRequest {
     pub user_id: String,
+    #[serde(default)]
+    pub reason: String,
+    #[serde(default)]
+    pub evidence_message_ids: Vec<String>,
 }
 
-/// POST /api/moderation/kick — Kick a user (requires KICK_MEMBERS).
+/// POST /api/moderation/kick — Kick a user (requires KICK_MEMBERS and a higher role
+/// than the target).
 /// Soft removal: force-closes WS with 4004, user can rejoin.
 pub async fn kick_user(
     State(state): State<AppState>,
@@ -29,33 +37,49 @@ pub async fn kick_user(
     .await
     .map_err(|s| (s, "Insufficient permissions".to_string()))?;
 
-    // Cannot kick the owner
+    // Cannot kick the owner or anyone at or above the caller's highest role
     let db = state.db.clone();
     let target_id = req.user_id.clone();
+    let actor_id = claims.sub.clone();
+    let actor_is_owner = claims.is_owner;
+    let reason = req.reason.clone();
+    let record = AuditRecord::new(&claims.sub, AuditAction::MemberKick, &req.user_id)
+        .reason(&req.reason)
+        .evidence(&req.evidence_message_ids);
 
-    let is_target_owner = tokio::task::spawn_blocking(move || {
+    let entry = tokio::task::spawn_blocking(move || {
         let conn = db
             .lock()
             .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
-        let is_owner: bool = conn
+        let (is_owner, fingerprint): (bool, String) = conn
             .query_row(
-                "SELECT is_owner FROM users WHERE id = ?1",
+                "SELECT is_owner, fingerprint FROM users WHERE id = ?1",
                 [&target_id],
-                |row| row.get::<_, bool>(0),
+                |row| Ok((row.get::<_, bool>(0)?, row.get(1)?)),
             )
             .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
-        Ok::<_, (StatusCode, String)>(is_owner)
+        if is_owner {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Cannot kick the server owner".to_string(),
+            ));
+        }
+        require_outranks(&conn, &actor_id, actor_is_owner, &target_id)?;
+        sanctions::issue(
+            &conn,
+            &fingerprint,
+            &target_id,
+            SanctionKind::Kick,
+            &reason,
+            &actor_id,
+            None,
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Insert sanction: {}", e)))?;
+        audit::record(&conn, record).map_err(audit::audit_error)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
-    if is_target_owner {
-        return Err((
-            StatusCode::FORBIDDEN,
-            "Cannot kick the server owner".to_string(),
-        ));
-    }
-
     // Force-close WS connections with 4004
     force_close_user(
         &state.connections,
@@ -67,12 +91,14 @@ pub async fn kick_user(
     // Broadcast UserKickedEvent
     let event = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::UserKickedEvent(proto_mod::UserKickedEvent {
             user_id: req.user_id.clone(),
-            reason: String::new(),
+            reason: req.reason.clone(),
         })),
     };
     broadcast_to_all(&state.connections, &event);
+    audit::notify_admins(&state, entry);
 
     Ok(StatusCode::OK)
 }
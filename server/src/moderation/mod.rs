@@ -1,2 +1,7 @@
+pub mod abuse;
+pub mod audit;
 pub mod ban;
+pub mod ban_list;
 pub mod kick;
+pub mod sanctions;
+pub mod trust;
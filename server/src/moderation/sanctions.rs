@@ -0,0 +1,641 @@
+//! Graduated sanctions: warnings, timed text and voice mutes, and the per-fingerprint
+//! history that kicks and bans also feed into.
+//!
+//! Mutes are enforced where the muted user acts: message and reaction creation
+//! (`chat`), gossip ingestion (`p2p::validation`) and voice state (`voice::signaling`).
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::middleware::Claims;
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::moderation::ban::check_ban;
+use crate::moderation::ban_list::{watched_entries, WatchedBanEntry};
+use crate::moderation::trust::probation_until;
+use crate::proto::moderation as proto_mod;
+use crate::proto::ws::{envelope::Payload, Envelope};
+use crate::roles::hierarchy::require_outranks;
+use crate::roles::permissions::{require_permission, user_permissions, Permissions};
+use crate::state::AppState;
+use crate::voice::signaling::enforce_voice_mute;
+use crate::ws::broadcast::send_to_user;
+
+/// Longest allowed mute (28 days).
+const MAX_MUTE_SECS: u64 = 28 * 24 * 60 * 60;
+/// Sanctions older than this no longer count towards the suggested next step.
+const ESCALATION_WINDOW_DAYS: i64 = 90;
+/// Text mute length suggested after a warning (1 hour).
+const SUGGESTED_MUTE_SECS: u64 = 60 * 60;
+/// Ban length suggested after a kick (7 days).
+const SUGGESTED_TEMP_BAN_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// A kind of sanction on a user's record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanctionKind {
+    Warning,
+    TextMute,
+    VoiceMute,
+    Kick,
+    Ban,
+}
+
+impl SanctionKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Warning => "warning",
+            Self::TextMute => "text_mute",
+            Self::VoiceMute => "voice_mute",
+            Self::Kick => "kick",
+            Self::Ban => "ban",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "warning" => Some(Self::Warning),
+            "text_mute" => Some(Self::TextMute),
+            "voice_mute" => Some(Self::VoiceMute),
+            "kick" => Some(Self::Kick),
+            "ban" => Some(Self::Ban),
+            _ => None,
+        }
+    }
+
+    /// Permission needed to issue or revoke this kind of sanction.
+    fn required_permission(self) -> Permissions {
+        match self {
+            Self::Warning | Self::Kick => Permissions::KICK_MEMBERS,
+            Self::TextMute | Self::VoiceMute => Permissions::MUTE_MEMBERS,
+            Self::Ban => Permissions::BAN_MEMBERS,
+        }
+    }
+}
+
+// --- Request / Response types ---
+
+#[derive(Debug, Deserialize)]
+pub struct WarnRequest {
+    pub user_id: String,
+    #[serde(default)]
+    pub reason: String,
+    #[serde(default)]
+    pub evidence_message_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MuteRequest {
+    pub user_id: String,
+    /// "text" or "voice"
+    pub kind: String,
+    pub duration_secs: u64,
+    #[serde(default)]
+    pub reason: String,
+    #[serde(default)]
+    pub evidence_message_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SanctionResponse {
+    pub id: String,
+    pub fingerprint: String,
+    pub user_id: String,
+    pub kind: String,
+    pub reason: String,
+    pub issued_by: String,
+    pub expires_at: Option<String>,
+    pub revoked_at: Option<String>,
+    pub created_at: String,
+}
+
+impl SanctionResponse {
+    /// Convert to the protobuf Sanction used in WS events.
+    pub fn to_proto(&self) -> proto_mod::Sanction {
+        proto_mod::Sanction {
+            id: self.id.clone(),
+            fingerprint: self.fingerprint.clone(),
+            user_id: self.user_id.clone(),
+            kind: self.kind.clone(),
+            reason: self.reason.clone(),
+            issued_by: self.issued_by.clone(),
+            expires_at: self.expires_at.clone().unwrap_or_default(),
+            revoked_at: self.revoked_at.clone().unwrap_or_default(),
+            created_at: self.created_at.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SanctionListResponse {
+    pub sanctions: Vec<SanctionResponse>,
+}
+
+/// The next rung of the escalation ladder for a user.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct SuggestedStep {
+    /// "warning", "text_mute", "kick", "temporary_ban" or "ban"
+    pub action: String,
+    pub duration_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserRecordResponse {
+    pub user_id: String,
+    pub fingerprint: String,
+    pub display_name: String,
+    pub banned: bool,
+    pub text_muted_until: Option<String>,
+    pub voice_muted_until: Option<String>,
+    /// End of new-member probation, if still on it
+    pub probation_until: Option<String>,
+    /// Entries on imported ban lists that a moderator chose to watch
+    pub watchlist: Vec<WatchedBanEntry>,
+    /// Newest first, including revoked and expired sanctions
+    pub sanctions: Vec<SanctionResponse>,
+    pub suggested_next_step: SuggestedStep,
+}
+
+// --- Helpers ---
+
+const SANCTION_COLUMNS: &str =
+    "id, fingerprint, user_id, kind, reason, issued_by, expires_at, revoked_at, created_at";
+
+fn sanction_from_row(row: &rusqlite::Row) -> rusqlite::Result<SanctionResponse> {
+    Ok(SanctionResponse {
+        id: row.get(0)?,
+        fingerprint: row.get(1)?,
+        user_id: row.get(2)?,
+        kind: row.get(3)?,
+        reason: row.get(4)?,
+        issued_by: row.get(5)?,
+        expires_at: row.get(6)?,
+        revoked_at: row.get(7)?,
+        created_at: row.get(8)?,
+    })
+}
+
+/// Add a sanction to a user's record. Kick and ban handlers call this alongside their
+/// own bookkeeping so the history covers every rung of the ladder.
+pub fn issue(
+    conn: &rusqlite::Connection,
+    fingerprint: &str,
+    user_id: &str,
+    kind: SanctionKind,
+    reason: &str,
+    issued_by: &str,
+    expires_at: Option<&str>,
+) -> rusqlite::Result<SanctionResponse> {
+    let sanction = SanctionResponse {
+        id: Uuid::now_v7().to_string(),
+        fingerprint: fingerprint.to_string(),
+        user_id: user_id.to_string(),
+        kind: kind.as_str().to_string(),
+        reason: reason.to_string(),
+        issued_by: issued_by.to_string(),
+        expires_at: expires_at.map(str::to_string),
+        revoked_at: None,
+        created_at: Utc::now().to_rfc3339(),
+    };
+    conn.execute(
+        "INSERT INTO sanctions (id, fingerprint, user_id, kind, reason, issued_by, expires_at, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        rusqlite::params![
+            sanction.id,
+            sanction.fingerprint,
+            sanction.user_id,
+            sanction.kind,
+            sanction.reason,
+            sanction.issued_by,
+            sanction.expires_at,
+            sanction.created_at,
+        ],
+    )?;
+    Ok(sanction)
+}
+
+/// When the fingerprint's active mute of `kind` ends, or None if not muted.
+pub fn active_mute(
+    conn: &rusqlite::Connection,
+    fingerprint: &str,
+    kind: SanctionKind,
+) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT MAX(expires_at) FROM sanctions
+         WHERE fingerprint = ?1 AND kind = ?2 AND revoked_at IS NULL AND expires_at > ?3",
+        rusqlite::params![fingerprint, kind.as_str(), Utc::now().to_rfc3339()],
+        |row| row.get(0),
+    )
+}
+
+/// Mark a fingerprint's outstanding bans as revoked (after an unban).
+pub fn revoke_bans(
+    conn: &rusqlite::Connection,
+    fingerprint: &str,
+    revoked_by: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE sanctions SET revoked_at = ?1, revoked_by = ?2
+         WHERE fingerprint = ?3 AND kind = 'ban' AND revoked_at IS NULL",
+        rusqlite::params![Utc::now().to_rfc3339(), revoked_by, fingerprint],
+    )?;
+    Ok(())
+}
+
+/// Escalation ladder: warning -> text mute -> kick -> temporary ban -> ban.
+/// Suggests the rung after the most severe sanction issued within the escalation
+/// window. Revoked sanctions do not count.
+fn suggest_next_step(sanctions: &[SanctionResponse], now: DateTime<Utc>) -> SuggestedStep {
+    let window_start = now - Duration::days(ESCALATION_WINDOW_DAYS);
+    let severity = sanctions
+        .iter()
+        .filter(|s| s.revoked_at.is_none())
+        .filter(|s| {
+            DateTime::parse_from_rfc3339(&s.created_at)
+                .map(|t| t >= window_start)
+                .unwrap_or(false)
+        })
+        .map(|s| match (SanctionKind::parse(&s.kind), &s.expires_at) {
+            (Some(SanctionKind::Warning), _) => 1,
+            (Some(SanctionKind::TextMute | SanctionKind::VoiceMute), _) => 2,
+            (Some(SanctionKind::Kick), _) => 3,
+            (Some(SanctionKind::Ban), Some(_)) => 4,
+            (Some(SanctionKind::Ban), None) => 5,
+            (None, _) => 0,
+        })
+        .max()
+        .unwrap_or(0);
+
+    let (action, duration_secs) = match severity {
+        0 => ("warning", None),
+        1 => ("text_mute", Some(SUGGESTED_MUTE_SECS)),
+        2 => ("kick", None),
+        3 => ("temporary_ban", Some(SUGGESTED_TEMP_BAN_SECS)),
+        _ => ("ban", None),
+    };
+    SuggestedStep {
+        action: action.to_string(),
+        duration_secs,
+    }
+}
+
+/// Issue a warning or mute against `user_id`: checks the hierarchy, writes the sanction
+/// and audit entry, and notifies the target and admins.
+async fn issue_for_user(
+    state: &AppState,
+    claims: &Claims,
+    user_id: &str,
+    kind: SanctionKind,
+    reason: &str,
+    expires_at: Option<String>,
+    evidence_message_ids: &[String],
+) -> Result<SanctionResponse, (StatusCode, String)> {
+    let db = state.db.clone();
+    let target_id = user_id.to_string();
+    let actor_id = claims.sub.clone();
+    let actor_is_owner = claims.is_owner;
+    let reason = reason.to_string();
+    let action = if kind == SanctionKind::Warning {
+        AuditAction::MemberWarn
+    } else {
+        AuditAction::MemberMute
+    };
+    let evidence = evidence_message_ids.to_vec();
+
+    let (sanction, entry) = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        let fingerprint: String = conn
+            .query_row(
+                "SELECT fingerprint FROM users WHERE id = ?1",
+                [&target_id],
+                |row| row.get(0),
+            )
+            .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+        // Also refuses the owner as a target
+        require_outranks(&conn, &actor_id, actor_is_owner, &target_id)?;
+
+        let sanction = issue(
+            &conn,
+            &fingerprint,
+            &target_id,
+            kind,
+            &reason,
+            &actor_id,
+            expires_at.as_deref(),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Insert sanction: {}", e)))?;
+
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, action, &target_id)
+                .reason(&reason)
+                .after(&sanction)
+                .evidence(&evidence),
+        )
+        .map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((sanction, entry))
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    if kind == SanctionKind::VoiceMute {
+        enforce_voice_mute(state, user_id);
+    }
+
+    let event = Envelope {
+        request_id: String::new(),
+        seq: 0,
+        payload: Some(Payload::SanctionIssuedEvent(proto_mod::SanctionIssuedEvent {
+            sanction: Some(sanction.to_proto()),
+        })),
+    };
+    send_to_user(&state.connections, user_id, &event);
+    audit::notify_admins(state, entry);
+
+    Ok(sanction)
+}
+
+// --- Handlers ---
+
+/// POST /api/moderation/warn — Warn a user (requires KICK_MEMBERS and a higher role
+/// than the target). The warning is pushed to the target and kept on their record.
+pub async fn warn_user(
+    State(state): State<AppState>,
+    claims: Claims,
+    Json(req): Json<WarnRequest>,
+) -> Result<Json<SanctionResponse>, (StatusCode, String)> {
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        SanctionKind::Warning.required_permission(),
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let sanction = issue_for_user(
+        &state,
+        &claims,
+        &req.user_id,
+        SanctionKind::Warning,
+        &req.reason,
+        None,
+        &req.evidence_message_ids,
+    )
+    .await?;
+
+    Ok(Json(sanction))
+}
+
+/// POST /api/moderation/mute — Mute a user's text or voice for `duration_secs`
+/// (requires MUTE_MEMBERS and a higher role than the target).
+pub async fn mute_user(
+    State(state): State<AppState>,
+    claims: Claims,
+    Json(req): Json<MuteRequest>,
+) -> Result<Json<SanctionResponse>, (StatusCode, String)> {
+    let kind = match req.kind.as_str() {
+        "text" => SanctionKind::TextMute,
+        "voice" => SanctionKind::VoiceMute,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Mute kind must be \"text\" or \"voice\"".to_string(),
+            ))
+        }
+    };
+    if req.duration_secs == 0 || req.duration_secs > MAX_MUTE_SECS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Mute duration must be between 1 and {} seconds", MAX_MUTE_SECS),
+        ));
+    }
+
+    require_permission(
+        &state.db,
+        &claims.sub,
+        claims.is_owner,
+        kind.required_permission(),
+    )
+    .await
+    .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let expires_at = (Utc::now() + Duration::seconds(req.duration_secs as i64)).to_rfc3339();
+    let sanction = issue_for_user(
+        &state,
+        &claims,
+        &req.user_id,
+        kind,
+        &req.reason,
+        Some(expires_at),
+        &req.evidence_message_ids,
+    )
+    .await?;
+
+    Ok(Json(sanction))
+}
+
+/// DELETE /api/moderation/sanctions/{sanction_id} — Revoke a warning or mute (requires
+/// the permission that issues it). Bans are lifted with unban instead.
+pub async fn revoke_sanction(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(sanction_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let perms = user_permissions(&state.db, &claims.sub, claims.is_owner)
+        .await
+        .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let db = state.db.clone();
+    let actor_id = claims.sub.clone();
+
+    let (sanction, entry) = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        let sanction = conn
+            .query_row(
+                &format!("SELECT {} FROM sanctions WHERE id = ?1", SANCTION_COLUMNS),
+                [&sanction_id],
+                sanction_from_row,
+            )
+            .optional()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query sanction: {}", e)))?
+            .ok_or((StatusCode::NOT_FOUND, "Sanction not found".to_string()))?;
+
+        let kind = SanctionKind::parse(&sanction.kind)
+            .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Unknown sanction kind".to_string()))?;
+        if !perms.contains(kind.required_permission()) {
+            return Err((StatusCode::FORBIDDEN, "Insufficient permissions".to_string()));
+        }
+        if !matches!(
+            kind,
+            SanctionKind::Warning | SanctionKind::TextMute | SanctionKind::VoiceMute
+        ) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Only warnings and mutes can be revoked".to_string(),
+            ));
+        }
+        if sanction.revoked_at.is_some() {
+            return Err((StatusCode::CONFLICT, "Sanction already revoked".to_string()));
+        }
+
+        conn.execute(
+            "UPDATE sanctions SET revoked_at = ?1, revoked_by = ?2 WHERE id = ?3",
+            rusqlite::params![Utc::now().to_rfc3339(), actor_id, sanction.id],
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Revoke sanction: {}", e)))?;
+
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::SanctionRevoke, &sanction.id)
+                .before(&sanction),
+        )
+        .map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((sanction, entry))
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    let event = Envelope {
+        request_id: String::new(),
+        seq: 0,
+        payload: Some(Payload::SanctionRevokedEvent(proto_mod::SanctionRevokedEvent {
+            sanction_id: sanction.id.clone(),
+            kind: sanction.kind.clone(),
+        })),
+    };
+    send_to_user(&state.connections, &sanction.user_id, &event);
+    audit::notify_admins(&state, entry);
+
+    Ok(StatusCode::OK)
+}
+
+/// GET /api/moderation/users/{user_id}/record — A user's sanction history (by
+/// fingerprint), active mutes and ban, watched ban list entries, and the suggested
+/// next step (requires KICK_MEMBERS, MUTE_MEMBERS or BAN_MEMBERS).
+pub async fn get_user_record(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(user_id): Path<String>,
+) -> Result<Json<UserRecordResponse>, (StatusCode, String)> {
+    let perms = user_permissions(&state.db, &claims.sub, claims.is_owner)
+        .await
+        .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+    if !perms.intersects(
+        Permissions::KICK_MEMBERS | Permissions::MUTE_MEMBERS | Permissions::BAN_MEMBERS,
+    ) {
+        return Err((StatusCode::FORBIDDEN, "Insufficient permissions".to_string()));
+    }
+
+    let db = state.db.clone();
+    let trust_config = state.trust_config.clone();
+
+    let record = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .read()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+        let query_err = |e: rusqlite::Error| {
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Query sanctions: {}", e))
+        };
+
+        let (fingerprint, display_name): (String, String) = conn
+            .query_row(
+                "SELECT fingerprint, display_name FROM users WHERE id = ?1",
+                [&user_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM sanctions WHERE fingerprint = ?1 ORDER BY created_at DESC",
+                SANCTION_COLUMNS
+            ))
+            .map_err(query_err)?;
+        let sanctions: Vec<SanctionResponse> = stmt
+            .query_map([&fingerprint], sanction_from_row)
+            .map_err(query_err)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let text_muted_until =
+            active_mute(&conn, &fingerprint, SanctionKind::TextMute).map_err(query_err)?;
+        let voice_muted_until =
+            active_mute(&conn, &fingerprint, SanctionKind::VoiceMute).map_err(query_err)?;
+        let probation_until = match &trust_config {
+            Some(config) => probation_until(&conn, config, &user_id)
+                .map_err(query_err)?
+                .map(|t| t.to_rfc3339()),
+            None => None,
+        };
+        let banned = check_ban(&conn, &fingerprint).is_some();
+        let watchlist = watched_entries(&conn, &fingerprint).map_err(query_err)?;
+        let suggested_next_step = suggest_next_step(&sanctions, Utc::now());
+
+        Ok::<_, (StatusCode, String)>(UserRecordResponse {
+            user_id,
+            fingerprint,
+            display_name,
+            banned,
+            text_muted_until,
+            voice_muted_until,
+            probation_until,
+            watchlist,
+            sanctions,
+            suggested_next_step,
+        })
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    Ok(Json(record))
+}
+
+/// GET /api/moderation/sanctions/me — The caller's own warnings and mutes, newest first.
+pub async fn my_sanctions(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<Json<SanctionListResponse>, (StatusCode, String)> {
+    let db = state.db.clone();
+    let user_id = claims.sub.clone();
+
+    let sanctions = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .read()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM sanctions
+                 WHERE fingerprint = (SELECT fingerprint FROM users WHERE id = ?1)
+                   AND kind IN ('warning', 'text_mute', 'voice_mute')
+                 ORDER BY created_at DESC",
+                SANCTION_COLUMNS
+            ))
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query sanctions: {}", e)))?;
+        let sanctions: Vec<SanctionResponse> = stmt
+            .query_map([&user_id], sanction_from_row)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query sanctions: {}", e)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok::<_, (StatusCode, String)>(sanctions)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    Ok(Json(SanctionListResponse { sanctions }))
+}
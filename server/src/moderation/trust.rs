@@ -0,0 +1,342 @@
+//! New-member probation ("structural friction").
+//!
+//! For `[trust].probation_hours` after registering, members cannot use the capabilities
+//! gated in `TrustConfig`. Refusals are 403 with a stable `probation_<capability>` code
+//! at the start of the message. The owner is never on probation, and moderators can
+//! fast-track a member out of it early.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::OptionalExtension;
+use serde::Serialize;
+
+use crate::auth::middleware::Claims;
+use crate::config::TrustConfig;
+use crate::moderation::abuse::contains_link;
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::roles::permissions::{user_permissions, Permissions};
+use crate::state::AppState;
+
+/// A capability that can be withheld during probation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    Links,
+    Attachments,
+    DirectMessages,
+    Mentions,
+    Reactions,
+}
+
+impl Capability {
+    const ALL: [Capability; 5] = [
+        Self::Links,
+        Self::Attachments,
+        Self::DirectMessages,
+        Self::Mentions,
+        Self::Reactions,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Links => "links",
+            Self::Attachments => "attachments",
+            Self::DirectMessages => "direct_messages",
+            Self::Mentions => "mentions",
+            Self::Reactions => "reactions",
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Links => "post links",
+            Self::Attachments => "upload attachments",
+            Self::DirectMessages => "start direct messages",
+            Self::Mentions => "mention roles or @everyone",
+            Self::Reactions => "add reactions",
+        }
+    }
+
+    fn is_gated(self, config: &TrustConfig) -> bool {
+        match self {
+            Self::Links => config.links,
+            Self::Attachments => config.attachments,
+            Self::DirectMessages => config.direct_messages,
+            Self::Mentions => config.mentions,
+            Self::Reactions => config.reactions,
+        }
+    }
+}
+
+/// Capabilities a chat message exercises: links and role mentions in `content`, and
+/// attachments when it references blocks.
+pub fn message_capabilities(content: &str, has_attachments: bool) -> Vec<Capability> {
+    let mut capabilities = Vec::new();
+    if contains_link(content) {
+        capabilities.push(Capability::Links);
+    }
+    if has_attachments {
+        capabilities.push(Capability::Attachments);
+    }
+    if content
+        .split_whitespace()
+        .any(|part| part.starts_with("@role:"))
+    {
+        capabilities.push(Capability::Mentions);
+    }
+    capabilities
+}
+
+/// When the user's probation ends, or None if they are not on probation (owner,
+/// fast-tracked, or registered long enough ago).
+pub fn probation_until(
+    conn: &rusqlite::Connection,
+    config: &TrustConfig,
+    user_id: &str,
+) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    let row: Option<(String, bool, Option<String>)> = conn
+        .query_row(
+            "SELECT created_at, is_owner, trusted_at FROM users WHERE id = ?1",
+            [user_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    let Some((created_at, is_owner, trusted_at)) = row else {
+        return Ok(None);
+    };
+    if is_owner || trusted_at.is_some() {
+        return Ok(None);
+    }
+
+    let Ok(created_at) = DateTime::parse_from_rfc3339(&created_at) else {
+        return Ok(None);
+    };
+    let until = created_at.with_timezone(&Utc) + Duration::hours(config.probation_hours as i64);
+    Ok((until > Utc::now()).then_some(until))
+}
+
+/// Refuse the first gated capability in `capabilities` if the user is on probation.
+/// A no-op when probation is disabled (`config` is None).
+pub fn check_capabilities(
+    conn: &rusqlite::Connection,
+    config: Option<&TrustConfig>,
+    user_id: &str,
+    is_owner: bool,
+    capabilities: &[Capability],
+) -> Result<(), (StatusCode, String)> {
+    let Some(config) = config else {
+        return Ok(());
+    };
+    let Some(capability) = capabilities.iter().find(|c| c.is_gated(config)) else {
+        return Ok(());
+    };
+    if is_owner {
+        return Ok(());
+    }
+
+    let until = probation_until(conn, config, user_id).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Query user: {}", e),
+        )
+    })?;
+    match until {
+        Some(until) => Err((
+            StatusCode::FORBIDDEN,
+            format!(
+                "probation_{}: New members can {} from {}",
+                capability.as_str(),
+                capability.describe(),
+                until.to_rfc3339()
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Async wrapper around `check_capabilities` for handlers that check before their
+/// own DB work.
+pub async fn require_capabilities(
+    state: &AppState,
+    user_id: &str,
+    is_owner: bool,
+    capabilities: &[Capability],
+) -> Result<(), (StatusCode, String)> {
+    let Some(config) = state.trust_config.clone() else {
+        return Ok(());
+    };
+    if is_owner || !capabilities.iter().any(|c| c.is_gated(&config)) {
+        return Ok(());
+    }
+
+    let db = state.db.clone();
+    let user_id = user_id.to_string();
+    let capabilities = capabilities.to_vec();
+    tokio::task::spawn_blocking(move || {
+        let conn = db
+            .read()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+        check_capabilities(&conn, Some(&config), &user_id, is_owner, &capabilities)
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Task join: {}", e),
+        )
+    })?
+}
+
+// --- Response types ---
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrustStatusResponse {
+    pub user_id: String,
+    /// None when the user is not on probation
+    pub probation_until: Option<String>,
+    /// Capabilities withheld until probation ends
+    pub gated: Vec<&'static str>,
+    pub trusted_at: Option<String>,
+    pub trusted_by: Option<String>,
+}
+
+fn trust_status(
+    conn: &rusqlite::Connection,
+    config: Option<&TrustConfig>,
+    user_id: &str,
+) -> Result<TrustStatusResponse, (StatusCode, String)> {
+    let (trusted_at, trusted_by): (Option<String>, Option<String>) = conn
+        .query_row(
+            "SELECT trusted_at, trusted_by FROM users WHERE id = ?1",
+            [user_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    let until = match config {
+        Some(config) => probation_until(conn, config, user_id).map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Query user: {}", e),
+            )
+        })?,
+        None => None,
+    };
+    let gated = match (until, config) {
+        (Some(_), Some(config)) => Capability::ALL
+            .iter()
+            .filter(|c| c.is_gated(config))
+            .map(|c| c.as_str())
+            .collect(),
+        _ => vec![],
+    };
+
+    Ok(TrustStatusResponse {
+        user_id: user_id.to_string(),
+        probation_until: until.map(|t| t.to_rfc3339()),
+        gated,
+        trusted_at,
+        trusted_by,
+    })
+}
+
+// --- Handlers ---
+
+/// GET /api/moderation/probation/me — The caller's probation status and which
+/// capabilities it withholds.
+pub async fn my_probation(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<Json<TrustStatusResponse>, (StatusCode, String)> {
+    let db = state.db.clone();
+    let config = state.trust_config.clone();
+
+    let status = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .read()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+        trust_status(&conn, config.as_ref(), &claims.sub)
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Task join: {}", e),
+        )
+    })??;
+
+    Ok(Json(status))
+}
+
+/// POST /api/moderation/users/{user_id}/fast-track — End a member's probation early.
+/// Requires any moderation permission (KICK_MEMBERS, MUTE_MEMBERS or BAN_MEMBERS).
+pub async fn fast_track(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(user_id): Path<String>,
+) -> Result<Json<TrustStatusResponse>, (StatusCode, String)> {
+    let perms = user_permissions(&state.db, &claims.sub, claims.is_owner)
+        .await
+        .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+    if !perms.intersects(
+        Permissions::KICK_MEMBERS | Permissions::MUTE_MEMBERS | Permissions::BAN_MEMBERS,
+    ) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Insufficient permissions".to_string(),
+        ));
+    }
+
+    let db = state.db.clone();
+    let config = state.trust_config.clone();
+    let actor_id = claims.sub.clone();
+
+    let (status, entry) = tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        let before = trust_status(&conn, config.as_ref(), &user_id)?;
+        if before.trusted_at.is_some() {
+            return Ok((before, None));
+        }
+
+        conn.execute(
+            "UPDATE users SET trusted_at = ?1, trusted_by = ?2 WHERE id = ?3",
+            rusqlite::params![Utc::now().to_rfc3339(), actor_id, user_id],
+        )
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Update user: {}", e),
+            )
+        })?;
+        let after = trust_status(&conn, config.as_ref(), &user_id)?;
+
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::MemberFastTrack, &user_id)
+                .before(&before)
+                .after(&after),
+        )
+        .map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((after, Some(entry)))
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Task join: {}", e),
+        )
+    })??;
+
+    if let Some(entry) = entry {
+        audit::notify_admins(&state, entry);
+    }
+
+    Ok(Json(status))
+}
@@ -87,6 +87,8 @@ pub fn build_behaviour(
         .heartbeat_interval(Duration::from_secs(1))
         .max_transmit_size(config.gossipsub_max_transmit_size)
         .validation_mode(gossipsub::ValidationMode::Strict)
+        // Hold messages until the server authorizes them (see p2p::validation)
+        .validate_messages()
         .flood_publish(true)
         .message_id_fn(|msg| {
             // Dedup by SHA-256 content hash
@@ -110,11 +112,13 @@ pub fn build_behaviour(
 
     // --- Relay configuration (tuned for chat per RESEARCH.md Pitfall 4) ---
     // Start from defaults (which include rate limiters) and override numeric fields
-    let mut relay_config = relay::Config::default();
-    relay_config.max_circuits = config.relay_max_circuits;
-    relay_config.max_circuits_per_peer = config.relay_max_circuits_per_peer;
-    relay_config.max_circuit_duration = Duration::from_secs(config.relay_max_circuit_duration_secs);
-    relay_config.max_circuit_bytes = config.relay_max_circuit_bytes;
+    let relay_config = relay::Config {
+        max_circuits: config.relay_max_circuits,
+        max_circuits_per_peer: config.relay_max_circuits_per_peer,
+        max_circuit_duration: Duration::from_secs(config.relay_max_circuit_duration_secs),
+        max_circuit_bytes: config.relay_max_circuit_bytes,
+        ..Default::default()
+    };
 
     UnitedBehaviour {
         gossipsub: gossipsub_behaviour,
@@ -52,6 +52,13 @@ pub struct P2pConfig {
     /// Default: 10485760 (10 MB — up from 128 KB default for chat)
     #[serde(default = "default_relay_max_circuit_bytes")]
     pub relay_max_circuit_bytes: u64,
+
+    /// Hex-encoded Ed25519 keys of peer servers whose signed envelopes relay their
+    /// users' actions (see `p2p::validation::TrustedServers`). This server's own key is
+    /// always trusted.
+    /// Default: empty
+    #[serde(default)]
+    pub trusted_server_keys: Vec<String>,
 }
 
 impl Default for P2pConfig {
@@ -66,6 +73,7 @@ impl Default for P2pConfig {
             relay_max_circuits_per_peer: default_relay_max_circuits_per_peer(),
             relay_max_circuit_duration_secs: default_relay_max_circuit_duration_secs(),
             relay_max_circuit_bytes: default_relay_max_circuit_bytes(),
+            trusted_server_keys: Vec::new(),
         }
     }
 }
@@ -31,6 +31,12 @@ pub struct PeerDirectory {
     identity_to_peer: DashMap<String, PeerId>,
 }
 
+impl Default for PeerDirectory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PeerDirectory {
     pub fn new() -> Self {
         Self {
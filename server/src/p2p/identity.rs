@@ -1,3 +1,4 @@
+use ed25519_dalek::SigningKey;
 use libp2p::identity;
 use libp2p::PeerId;
 use std::fs;
@@ -56,3 +57,18 @@ pub fn server_identity_keypair(data_dir: &str) -> identity::Keypair {
         keypair
     }
 }
+
+/// Derive an Ed25519 signing key from the server's libp2p identity keypair.
+///
+/// Used to sign server-originated GossipEnvelopes, so peers can verify them
+/// against the public key embedded in the server's PeerId.
+pub fn server_signing_key(keypair: &identity::Keypair) -> SigningKey {
+    let ed25519_kp = keypair
+        .clone()
+        .try_into_ed25519()
+        .expect("Keypair is Ed25519");
+    let full_bytes = ed25519_kp.to_bytes();
+    let mut seed = [0u8; 32];
+    seed.copy_from_slice(&full_bytes[..32]);
+    SigningKey::from_bytes(&seed)
+}
@@ -4,9 +4,17 @@ use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use prost::Message as ProstMessage;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::chat::broadcast;
+use crate::chat::messages::{apply_delete, apply_edit, message_author, validate_content};
+use crate::chat::reactions::{apply_reaction_add, apply_reaction_remove, valid_emoji};
+use crate::config::TrustConfig;
+use crate::db::sequences::next_channel_sequence;
 use crate::db::DbPool;
+use crate::p2p::validation::{authorize_envelope, GossipTarget, RejectReason, TrustedServers};
 use crate::proto::chat as proto_chat;
 use crate::proto::p2p_proto::{GossipEnvelope, MessageType};
+use crate::roles::permissions::Permissions;
+use crate::ws::ConnectionRegistry;
 
 /// Errors that can occur during envelope operations.
 #[derive(Debug)]
@@ -21,6 +29,8 @@ pub enum EnvelopeError {
     DbError(String),
     /// Invalid topic format
     InvalidTopic(String),
+    /// Sender or channel failed ingestion authorization
+    Unauthorized(RejectReason),
 }
 
 impl std::fmt::Display for EnvelopeError {
@@ -31,6 +41,7 @@ impl std::fmt::Display for EnvelopeError {
             Self::InvalidSignature => write!(f, "Invalid Ed25519 signature"),
             Self::DbError(e) => write!(f, "Database error: {}", e),
             Self::InvalidTopic(e) => write!(f, "Invalid topic: {}", e),
+            Self::Unauthorized(reason) => write!(f, "Unauthorized: {}", reason.as_str()),
         }
     }
 }
@@ -142,52 +153,205 @@ pub fn extract_channel_id(topic: &str) -> Result<String, EnvelopeError> {
     }
 }
 
-/// Result from handle_gossip_message: contains the server_sequence and
-/// optionally the decoded ChatMessage proto (if message_type is CHAT).
+/// Extract thread_id from a thread gossipsub topic, or None for a channel topic.
+/// Topic format: `{server_prefix}/thread/{thread_id}`
+pub fn extract_thread_id(topic: &str) -> Option<String> {
+    match topic.rsplit_once('/') {
+        Some((head, thread_id)) if head.ends_with("/thread") && !thread_id.is_empty() => {
+            Some(thread_id.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// A chat message stored from gossip: its server_sequence and the decoded ChatMessage
+/// (None if the payload did not decode).
 pub struct GossipPersistResult {
     pub server_sequence: u64,
     pub channel_id: String,
     pub chat_message: Option<proto_chat::ChatMessage>,
 }
 
-/// Handle a received gossipsub message: verify, extract, and persist to SQLite.
+/// What an accepted envelope did, for relaying to WebSocket clients.
+pub enum GossipOutcome {
+    /// A chat message was stored
+    Message(Box<GossipPersistResult>),
+    /// An edit was applied to the author's message
+    Edited(proto_chat::MessageEditedEvent),
+    /// A message was deleted by its author or a MANAGE_MESSAGES holder
+    Deleted(proto_chat::MessageDeletedEvent),
+    ReactionAdded {
+        channel_id: String,
+        event: proto_chat::ReactionAddedEvent,
+    },
+    ReactionRemoved {
+        channel_id: String,
+        event: proto_chat::ReactionRemovedEvent,
+    },
+    /// Typing indicators propagate on the mesh but are not stored
+    Typing,
+}
+
+impl GossipOutcome {
+    /// Relay the change to WebSocket clients that can see the channel.
+    pub fn broadcast(self, registry: &ConnectionRegistry) {
+        match self {
+            Self::Message(result) => {
+                if let Some(chat_message) = result.chat_message {
+                    broadcast::broadcast_new_message(registry, chat_message);
+                }
+            }
+            Self::Edited(event) => broadcast::broadcast_message_edited(registry, event),
+            Self::Deleted(event) => broadcast::broadcast_message_deleted(registry, event),
+            Self::ReactionAdded { channel_id, event } => {
+                broadcast::broadcast_reaction_added(registry, &channel_id, event)
+            }
+            Self::ReactionRemoved { channel_id, event } => {
+                broadcast::broadcast_reaction_removed(registry, &channel_id, event)
+            }
+            Self::Typing => {}
+        }
+    }
+}
+
+/// Handle a received gossipsub message: authorize it, then apply it by message type.
 ///
-/// Returns the server-assigned sequence number and decoded ChatMessage (if CHAT type).
-/// Uses `SELECT COALESCE(MAX(server_sequence), 0) + 1` for single-writer sequencing.
-pub fn handle_gossip_message(db: &DbPool, envelope: &GossipEnvelope) -> Result<GossipPersistResult, EnvelopeError> {
-    let channel_id = extract_channel_id(&envelope.topic)?;
-    let sender_hex = hex::encode(&envelope.sender_pubkey);
-
-    let conn = db.lock().map_err(|e| EnvelopeError::DbError(e.to_string()))?;
-
-    // Get next sequence number for this channel (single-writer, safe for Phase 3)
-    let next_seq: i64 = conn
-        .query_row(
-            "SELECT COALESCE(MAX(server_sequence), 0) + 1 FROM messages WHERE channel_id = ?1",
-            rusqlite::params![channel_id],
-            |row| row.get(0),
-        )
-        .map_err(|e| EnvelopeError::DbError(format!("Sequence query: {}", e)))?;
+/// Envelopes failing `authorize_envelope` change nothing and return `Unauthorized`.
+/// Envelopes signed by a `trusted` server act as the user their payload names, and
+/// `trust_config` sets the probation limits new members are held to.
+/// Chat messages are stored with a sequence number from the channel (or thread)
+/// counter, claimed in the same transaction as the insert. Edits, deletes and reactions
+/// go through the same apply paths as the REST handlers; their event payloads name the
+/// target message, which must be in the topic's channel. Typing indicators are accepted
+/// without being stored.
+pub fn handle_gossip_message(
+    db: &DbPool,
+    trusted: &TrustedServers,
+    trust_config: Option<&TrustConfig>,
+    envelope: &GossipEnvelope,
+) -> Result<GossipOutcome, EnvelopeError> {
+    let mut conn = db.lock().map_err(|e| EnvelopeError::DbError(e.to_string()))?;
+
+    let target = authorize_envelope(&conn, trusted, trust_config, envelope)?;
+    let sender_hex = target.sender_pubkey.clone();
+    let db_err = |context: &str, e: rusqlite::Error| EnvelopeError::DbError(format!("{}: {}", context, e));
+
+    match target.message_type {
+        MessageType::Chat => persist_chat_message(&mut conn, envelope, target)
+            .map(|result| GossipOutcome::Message(Box::new(result))),
+        MessageType::Edit => {
+            let mut event = decode_payload::<proto_chat::MessageEditedEvent>(envelope)?;
+            let (message_id, author) = target_message(&conn, &target.channel_id, &event.message_id)?;
+            if author != sender_hex {
+                return Err(EnvelopeError::Unauthorized(RejectReason::MissingPermission));
+            }
+            let content = validate_content(&event.new_content)
+                .map_err(|_| EnvelopeError::DecodeError("Invalid edit content".to_string()))?;
+
+            let tx = conn.transaction().map_err(|e| db_err("Begin", e))?;
+            apply_edit(&tx, message_id, &content, &chrono::Utc::now().to_rfc3339())
+                .map_err(|e| db_err("Edit message", e))?;
+            tx.commit().map_err(|e| db_err("Commit", e))?;
+
+            event.channel_id = target.channel_id;
+            event.new_content = content;
+            event.edit_timestamp = now_millis();
+            event.editor_pubkey = sender_hex;
+            Ok(GossipOutcome::Edited(event))
+        }
+        MessageType::Delete => {
+            let mut event = decode_payload::<proto_chat::MessageDeletedEvent>(envelope)?;
+            let (message_id, author) = target_message(&conn, &target.channel_id, &event.message_id)?;
+            if author != sender_hex
+                && !target.sender_permissions.contains(Permissions::MANAGE_MESSAGES)
+            {
+                return Err(EnvelopeError::Unauthorized(RejectReason::MissingPermission));
+            }
+            apply_delete(&conn, message_id).map_err(|e| db_err("Delete message", e))?;
+
+            event.channel_id = target.channel_id;
+            event.deleted_by_pubkey = sender_hex;
+            Ok(GossipOutcome::Deleted(event))
+        }
+        MessageType::ReactionAdd => {
+            let mut event = decode_payload::<proto_chat::ReactionAddedEvent>(envelope)?;
+            let reaction = event
+                .reaction
+                .as_mut()
+                .ok_or_else(|| EnvelopeError::DecodeError("Missing reaction".to_string()))?;
+            if !valid_emoji(&reaction.emoji) {
+                return Err(EnvelopeError::DecodeError("Invalid emoji".to_string()));
+            }
+            let (message_id, _) = target_message(&conn, &target.channel_id, &reaction.message_id)?;
+            apply_reaction_add(&conn, message_id, &sender_hex, &reaction.emoji)
+                .map_err(|e| db_err("Add reaction", e))?;
+
+            reaction.user_pubkey = sender_hex;
+            reaction.timestamp = now_millis();
+            Ok(GossipOutcome::ReactionAdded {
+                channel_id: target.channel_id,
+                event,
+            })
+        }
+        MessageType::ReactionRemove => {
+            let mut event = decode_payload::<proto_chat::ReactionRemovedEvent>(envelope)?;
+            let (message_id, _) = target_message(&conn, &target.channel_id, &event.message_id)?;
+            apply_reaction_remove(&conn, message_id, &sender_hex, &event.emoji)
+                .map_err(|e| db_err("Remove reaction", e))?;
+
+            event.user_pubkey = sender_hex;
+            Ok(GossipOutcome::ReactionRemoved {
+                channel_id: target.channel_id,
+                event,
+            })
+        }
+        MessageType::Typing => Ok(GossipOutcome::Typing),
+        // authorize_envelope only accepts the types above
+        MessageType::Unspecified | MessageType::Presence | MessageType::Test => {
+            Err(EnvelopeError::Unauthorized(RejectReason::UnsupportedType))
+        }
+    }
+}
+
+/// Store a chat envelope as a new message in its channel or thread.
+fn persist_chat_message(
+    conn: &mut rusqlite::Connection,
+    envelope: &GossipEnvelope,
+    target: GossipTarget,
+) -> Result<GossipPersistResult, EnvelopeError> {
+    let GossipTarget {
+        channel_id,
+        thread_id,
+        sender_pubkey: sender_hex,
+        ..
+    } = target;
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| EnvelopeError::DbError(format!("Begin: {}", e)))?;
+
+    // Get next sequence number for this channel or thread
+    let next_seq = next_channel_sequence(&tx, &channel_id, thread_id.as_deref())
+        .map_err(|e| EnvelopeError::DbError(format!("Sequence counter: {}", e)))?;
 
     let now = chrono::Utc::now().to_rfc3339();
 
-    // If message_type is CHAT, decode the ChatMessage payload and extract content_text
+    // Decode the ChatMessage payload and extract content_text
     let mut content_text: Option<String> = None;
     let mut chat_message: Option<proto_chat::ChatMessage> = None;
 
-    if envelope.message_type == MessageType::Chat as i32 {
-        if let Ok(mut msg) = proto_chat::ChatMessage::decode(envelope.payload.as_slice()) {
-            content_text = Some(msg.content.clone());
-            // Fill in server-assigned fields
-            msg.server_sequence = next_seq as u64;
-            msg.sender_pubkey = sender_hex.clone();
-            chat_message = Some(msg);
-        }
+    if let Ok(mut msg) = proto_chat::ChatMessage::decode(envelope.payload.as_slice()) {
+        content_text = Some(msg.content.clone());
+        // Fill in server-assigned fields
+        msg.server_sequence = next_seq as u64;
+        msg.sender_pubkey = sender_hex.clone();
+        msg.thread_id = thread_id.clone();
+        chat_message = Some(msg);
     }
 
-    conn.execute(
-        "INSERT INTO messages (channel_id, sender_pubkey, message_type, payload, timestamp, sequence_hint, server_sequence, signature, created_at, content_text, edited, deleted)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 0, 0)",
+    tx.execute(
+        "INSERT INTO messages (channel_id, sender_pubkey, message_type, payload, timestamp, sequence_hint, server_sequence, signature, created_at, content_text, edited, deleted, thread_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 0, 0, ?11)",
         rusqlite::params![
             channel_id,
             sender_hex,
@@ -199,13 +363,49 @@ pub fn handle_gossip_message(db: &DbPool, envelope: &GossipEnvelope) -> Result<G
             envelope.signature,
             now,
             content_text,
+            thread_id,
         ],
     )
     .map_err(|e| EnvelopeError::DbError(format!("Insert message: {}", e)))?;
 
+    if let Some(ref thread_id) = thread_id {
+        crate::chat::threads::record_thread_message(&tx, thread_id, &now)
+            .map_err(|e| EnvelopeError::DbError(format!("Update thread: {}", e)))?;
+    }
+    tx.commit()
+        .map_err(|e| EnvelopeError::DbError(format!("Commit: {}", e)))?;
+
     Ok(GossipPersistResult {
         server_sequence: next_seq as u64,
         channel_id,
         chat_message,
     })
 }
+
+/// Decode an envelope's payload as the event its message type carries.
+fn decode_payload<M: ProstMessage + Default>(envelope: &GossipEnvelope) -> Result<M, EnvelopeError> {
+    M::decode(envelope.payload.as_slice()).map_err(|e| EnvelopeError::DecodeError(e.to_string()))
+}
+
+/// Resolve the message an edit, delete or reaction refers to: (row id, author pubkey).
+fn target_message(
+    conn: &rusqlite::Connection,
+    channel_id: &str,
+    message_id: &str,
+) -> Result<(i64, String), EnvelopeError> {
+    let unknown = EnvelopeError::Unauthorized(RejectReason::UnknownMessage);
+    let Ok(id) = message_id.parse::<i64>() else {
+        return Err(unknown);
+    };
+    let author = message_author(conn, channel_id, id)
+        .map_err(|e| EnvelopeError::DbError(format!("Find message: {}", e)))?
+        .ok_or(unknown)?;
+    Ok((id, author))
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
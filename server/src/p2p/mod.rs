@@ -3,7 +3,9 @@ pub mod config;
 pub mod directory;
 pub mod identity;
 pub mod messages;
+pub mod publish;
 pub mod swarm;
+pub mod validation;
 
 // Re-export key types for convenient access
 pub use config::P2pConfig;
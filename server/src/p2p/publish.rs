@@ -0,0 +1,78 @@
+//! Server-originated gossipsub publishing.
+//!
+//! Events created through REST (messages, edits, deletes, reactions) are wrapped in a
+//! GossipEnvelope signed with the server's libp2p Ed25519 identity and published on the
+//! channel (or thread) topic, so peers on the mesh see the same events as WebSocket clients.
+//!
+//! Each message type carries the event proto named in `p2p.proto`; receivers apply edits,
+//! deletes and reactions to the message they name rather than storing them (see
+//! `p2p::messages::handle_gossip_message`). Those events do not take a place in the
+//! channel's timeline, so they are published with a `sequence_hint` of 0.
+//!
+//! Every event names the user who made it, so a peer that lists this server's key in
+//! `p2p.trusted_server_keys` authorizes it as that user (see `p2p::validation::TrustedServers`).
+
+use prost::Message as ProstMessage;
+
+use crate::p2p::messages::encode_gossip_envelope;
+use crate::p2p::SwarmCommand;
+use crate::proto::p2p_proto::MessageType;
+use crate::state::AppState;
+
+/// Build a gossipsub topic string for a channel.
+/// Format: `{server_peer_id_prefix}/{channel_id}` (first 16 chars of the PeerId).
+pub fn channel_topic(server_peer_id: &str, channel_id: &str) -> String {
+    let prefix = &server_peer_id[..std::cmp::min(16, server_peer_id.len())];
+    format!("{}/{}", prefix, channel_id)
+}
+
+/// Build a gossipsub topic string for an active thread.
+/// Format: `{server_peer_id_prefix}/thread/{thread_id}`.
+pub fn thread_topic(server_peer_id: &str, thread_id: &str) -> String {
+    channel_topic(server_peer_id, &format!("thread/{}", thread_id))
+}
+
+/// Sign `payload` as the server and publish it on the channel's gossipsub topic.
+///
+/// Publishing is fire-and-forget: the swarm loop logs failures (e.g. no subscribed peers).
+pub fn publish_channel_event<M: ProstMessage>(
+    state: &AppState,
+    channel_id: &str,
+    message_type: MessageType,
+    sequence_hint: u64,
+    payload: &M,
+) {
+    let topic = channel_topic(&state.server_peer_id, channel_id);
+    publish_event(state, topic, message_type, sequence_hint, payload);
+}
+
+/// Sign `payload` as the server and publish it on the thread's gossipsub topic.
+pub fn publish_thread_event<M: ProstMessage>(
+    state: &AppState,
+    thread_id: &str,
+    message_type: MessageType,
+    sequence_hint: u64,
+    payload: &M,
+) {
+    let topic = thread_topic(&state.server_peer_id, thread_id);
+    publish_event(state, topic, message_type, sequence_hint, payload);
+}
+
+fn publish_event<M: ProstMessage>(
+    state: &AppState,
+    topic: String,
+    message_type: MessageType,
+    sequence_hint: u64,
+    payload: &M,
+) {
+    let signing_key = &state.server_signing_key;
+    let data = encode_gossip_envelope(
+        signing_key.verifying_key().as_bytes(),
+        signing_key,
+        &topic,
+        message_type,
+        sequence_hint,
+        &payload.encode_to_vec(),
+    );
+    let _ = state.swarm_cmd_tx.send(SwarmCommand::Publish { topic, data });
+}
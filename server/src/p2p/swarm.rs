@@ -17,6 +17,13 @@ pub enum SwarmCommand {
     UnsubscribeTopic(String),
     /// Publish data to a gossipsub topic.
     Publish { topic: String, data: Vec<u8> },
+    /// Report the validation outcome of a received message back to gossipsub.
+    /// Accepted messages are forwarded; rejected ones penalize the propagating peer.
+    ReportValidation {
+        message_id: gossipsub::MessageId,
+        source: PeerId,
+        acceptance: gossipsub::MessageAcceptance,
+    },
     /// Query peer info for all connected peers.
     GetPeerInfo(oneshot::Sender<Vec<PeerInfoEntry>>),
     /// Query peers subscribed to a specific topic.
@@ -28,8 +35,9 @@ pub enum SwarmCommand {
 
 /// Events emitted from the Swarm event loop to the message handler task.
 pub enum SwarmEvent {
-    /// Received a gossipsub message.
+    /// Received a gossipsub message. Must be answered with `SwarmCommand::ReportValidation`.
     GossipMessage {
+        message_id: gossipsub::MessageId,
         source: PeerId,
         topic: String,
         data: Vec<u8>,
@@ -154,8 +162,8 @@ fn handle_behaviour_event(
     match event {
         UnitedBehaviourEvent::Gossipsub(gossipsub::Event::Message {
             propagation_source,
+            message_id,
             message,
-            ..
         }) => {
             let topic = message.topic.to_string();
             tracing::debug!(
@@ -164,6 +172,7 @@ fn handle_behaviour_event(
                 topic
             );
             let _ = evt_tx.send(SwarmEvent::GossipMessage {
+                message_id,
                 source: propagation_source,
                 topic,
                 data: message.data,
@@ -231,9 +240,22 @@ fn handle_swarm_command(swarm: &mut Swarm<UnitedBehaviour>, cmd: SwarmCommand) {
                 Ok(msg_id) => {
                     tracing::debug!("Published to {}, message_id: {:?}", topic, msg_id)
                 }
+                Err(gossipsub::PublishError::NoPeersSubscribedToTopic) => {
+                    tracing::debug!("No peers subscribed to {}, publish skipped", topic)
+                }
                 Err(e) => tracing::error!("Failed to publish to {}: {:?}", topic, e),
             }
         }
+        SwarmCommand::ReportValidation {
+            message_id,
+            source,
+            acceptance,
+        } => {
+            swarm
+                .behaviour_mut()
+                .gossipsub
+                .report_message_validation_result(&message_id, &source, acceptance);
+        }
         SwarmCommand::GetPeerInfo(reply) => {
             let peers: Vec<PeerInfoEntry> = swarm
                 .connected_peers()
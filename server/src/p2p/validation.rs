@@ -0,0 +1,384 @@
+//! Gossipsub ingestion authorization: decides whether a verified GossipEnvelope may be
+//! persisted, and tracks rejection counts per reason.
+//!
+//! Rejected messages are reported back to gossipsub as `MessageAcceptance::Reject`,
+//! which stops propagation and penalizes the forwarding peer's score.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::http::StatusCode;
+use prost::Message as ProstMessage;
+use rusqlite::OptionalExtension;
+
+use crate::channels::slowmode::cooldown_remaining;
+use crate::channels::types::ChannelType;
+use crate::config::TrustConfig;
+use crate::moderation::ban::check_ban;
+use crate::moderation::sanctions::{active_mute, SanctionKind};
+use crate::moderation::trust::{self, check_capabilities, Capability};
+use crate::p2p::messages::{extract_channel_id, extract_thread_id, EnvelopeError};
+use crate::proto::chat as proto_chat;
+use crate::proto::p2p_proto::{GossipEnvelope, MessageType};
+use crate::proto::presence as proto_presence;
+use crate::roles::permissions::{compute_channel_permissions, Permissions};
+
+/// Why an incoming gossip message was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    /// Envelope could not be decoded or its topic is malformed
+    Malformed,
+    /// Invalid sender public key or Ed25519 signature
+    InvalidSignature,
+    /// Signed envelope topic differs from the gossipsub topic it arrived on
+    TopicMismatch,
+    /// Channel referenced by the topic does not exist
+    UnknownChannel,
+    /// Channel exists but has no messages (voice)
+    NotTextChannel,
+    /// Sender public key does not belong to a registered user
+    UnknownSender,
+    /// Sender is banned
+    Banned,
+    /// Sender has an active text mute
+    Muted,
+    /// Sender lacks the channel permission the message type needs (see
+    /// `required_permissions`), MANAGE_MESSAGES for a top-level post in an announcement
+    /// channel, or is not allowed to edit or delete the target message
+    MissingPermission,
+    /// Thread is archived, or locked and the sender lacks MANAGE_MESSAGES
+    ThreadClosed,
+    /// Sender's slow mode cooldown in the channel has not passed
+    SlowMode,
+    /// Sender is on new-member probation and the message uses a withheld capability
+    /// (see `moderation::trust`)
+    Probation,
+    /// Top-level post in a forum channel (forum posts start titled threads over REST)
+    ForumTopLevel,
+    /// Message type the server does not ingest (presence, test, unspecified)
+    UnsupportedType,
+    /// Edit, delete or reaction for a message that does not exist in the channel
+    UnknownMessage,
+}
+
+impl RejectReason {
+    pub const ALL: [RejectReason; 15] = [
+        RejectReason::Malformed,
+        RejectReason::InvalidSignature,
+        RejectReason::TopicMismatch,
+        RejectReason::UnknownChannel,
+        RejectReason::NotTextChannel,
+        RejectReason::UnknownSender,
+        RejectReason::Banned,
+        RejectReason::Muted,
+        RejectReason::MissingPermission,
+        RejectReason::ThreadClosed,
+        RejectReason::SlowMode,
+        RejectReason::Probation,
+        RejectReason::ForumTopLevel,
+        RejectReason::UnsupportedType,
+        RejectReason::UnknownMessage,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Malformed => "malformed",
+            Self::InvalidSignature => "invalid_signature",
+            Self::TopicMismatch => "topic_mismatch",
+            Self::UnknownChannel => "unknown_channel",
+            Self::NotTextChannel => "not_text_channel",
+            Self::UnknownSender => "unknown_sender",
+            Self::Banned => "banned",
+            Self::Muted => "muted",
+            Self::MissingPermission => "missing_permission",
+            Self::ThreadClosed => "thread_closed",
+            Self::SlowMode => "slowmode",
+            Self::Probation => "probation",
+            Self::ForumTopLevel => "forum_top_level",
+            Self::UnsupportedType => "unsupported_type",
+            Self::UnknownMessage => "unknown_message",
+        }
+    }
+}
+
+impl EnvelopeError {
+    /// Map an envelope error to a rejection reason.
+    /// Returns None for local failures (e.g. DB errors) that say nothing about the sender.
+    pub fn reject_reason(&self) -> Option<RejectReason> {
+        match self {
+            Self::DecodeError(_) | Self::InvalidTopic(_) => Some(RejectReason::Malformed),
+            Self::InvalidPublicKey | Self::InvalidSignature => Some(RejectReason::InvalidSignature),
+            Self::Unauthorized(reason) => Some(*reason),
+            Self::DbError(_) => None,
+        }
+    }
+}
+
+/// Per-reason counters of rejected gossip messages.
+#[derive(Debug, Default)]
+pub struct GossipRejectionCounters {
+    counts: [AtomicU64; RejectReason::ALL.len()],
+}
+
+impl GossipRejectionCounters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one rejection.
+    pub fn record(&self, reason: RejectReason) {
+        self.counts[reason as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Current count for a reason.
+    pub fn get(&self, reason: RejectReason) -> u64 {
+        self.counts[reason as usize].load(Ordering::Relaxed)
+    }
+
+    /// Snapshot of all counters as (reason, count) pairs.
+    pub fn snapshot(&self) -> Vec<(&'static str, u64)> {
+        RejectReason::ALL
+            .iter()
+            .map(|r| (r.as_str(), self.get(*r)))
+            .collect()
+    }
+}
+
+/// Server keys whose envelopes relay their users' actions.
+///
+/// Servers sign the events their REST clients create with their own identity key (see
+/// `p2p::publish`), so such an envelope is attributed to the user its payload names and
+/// authorized as that user. Envelopes from any other key act as the signer.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedServers {
+    keys: HashSet<Vec<u8>>,
+}
+
+impl TrustedServers {
+    /// Trust this server's own key plus the configured hex-encoded peer server keys.
+    /// Keys that are not 32 hex-encoded bytes are skipped with a warning.
+    pub fn new(own_key: &[u8], configured: &[String]) -> Self {
+        let mut keys = HashSet::from([own_key.to_vec()]);
+        for key in configured {
+            match hex::decode(key) {
+                Ok(bytes) if bytes.len() == 32 => {
+                    keys.insert(bytes);
+                }
+                _ => tracing::warn!("Ignoring invalid trusted server key: {}", key),
+            }
+        }
+        Self { keys }
+    }
+
+    pub fn contains(&self, key: &[u8]) -> bool {
+        self.keys.contains(key)
+    }
+
+    /// Public key of the user an envelope acts for: the signer, or for a trusted server
+    /// the user named in the payload.
+    pub fn actor(
+        &self,
+        envelope: &GossipEnvelope,
+        message_type: MessageType,
+    ) -> Result<Vec<u8>, EnvelopeError> {
+        if !self.contains(&envelope.sender_pubkey) {
+            return Ok(envelope.sender_pubkey.clone());
+        }
+        let payload = envelope.payload.as_slice();
+        let decode_err = |e: prost::DecodeError| EnvelopeError::DecodeError(e.to_string());
+        let actor = match message_type {
+            MessageType::Chat => proto_chat::ChatMessage::decode(payload)
+                .map_err(decode_err)?
+                .sender_pubkey,
+            MessageType::Edit => proto_chat::MessageEditedEvent::decode(payload)
+                .map_err(decode_err)?
+                .editor_pubkey,
+            MessageType::Delete => proto_chat::MessageDeletedEvent::decode(payload)
+                .map_err(decode_err)?
+                .deleted_by_pubkey,
+            MessageType::ReactionAdd => proto_chat::ReactionAddedEvent::decode(payload)
+                .map_err(decode_err)?
+                .reaction
+                .map(|reaction| reaction.user_pubkey)
+                .unwrap_or_default(),
+            MessageType::ReactionRemove => proto_chat::ReactionRemovedEvent::decode(payload)
+                .map_err(decode_err)?
+                .user_pubkey,
+            MessageType::Typing => proto_presence::TypingIndicator::decode(payload)
+                .map_err(decode_err)?
+                .user_pubkey,
+            MessageType::Unspecified | MessageType::Presence | MessageType::Test => {
+                return Err(EnvelopeError::Unauthorized(RejectReason::UnsupportedType))
+            }
+        };
+        hex::decode(&actor).map_err(|_| EnvelopeError::Unauthorized(RejectReason::UnknownSender))
+    }
+}
+
+/// Where an authorized envelope belongs: a channel, or a thread inside that channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GossipTarget {
+    pub message_type: MessageType,
+    pub channel_id: String,
+    pub thread_id: Option<String>,
+    /// Hex public key of the acting user (see `TrustedServers::actor`)
+    pub sender_pubkey: String,
+    /// Sender's effective permissions in the channel
+    pub sender_permissions: Permissions,
+}
+
+/// Channel permissions a sender needs for each ingested message type, or None for types
+/// the server does not ingest.
+pub fn required_permissions(message_type: MessageType) -> Option<Permissions> {
+    match message_type {
+        MessageType::Chat | MessageType::Typing | MessageType::Edit => {
+            Some(Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES)
+        }
+        MessageType::ReactionAdd => Some(Permissions::VIEW_CHANNEL | Permissions::ADD_REACTIONS),
+        // Authorship (or MANAGE_MESSAGES for deletes) is checked against the target message
+        MessageType::Delete | MessageType::ReactionRemove => Some(Permissions::VIEW_CHANNEL),
+        MessageType::Unspecified | MessageType::Presence | MessageType::Test => None,
+    }
+}
+
+/// Authorize a verified envelope against server state.
+///
+/// The sender (the signer, or the user a trusted server relays for) must be a registered, non-banned user holding the message type's
+/// `required_permissions` in the channel (overrides applied), and the topic must name an
+/// existing message channel or an open thread in one. Text mutes block everything but
+/// removing content, and probation limits apply to messages, edits and reactions as they
+/// do over REST. Chat messages to the channel itself also respect the channel
+/// type's posting rules and slow mode.
+pub fn authorize_envelope(
+    conn: &rusqlite::Connection,
+    trusted: &TrustedServers,
+    trust_config: Option<&TrustConfig>,
+    envelope: &GossipEnvelope,
+) -> Result<GossipTarget, EnvelopeError> {
+    let message_type = MessageType::try_from(envelope.message_type)
+        .map_err(|_| EnvelopeError::Unauthorized(RejectReason::UnsupportedType))?;
+    let required = required_permissions(message_type)
+        .ok_or(EnvelopeError::Unauthorized(RejectReason::UnsupportedType))?;
+
+    let topic_id = extract_channel_id(&envelope.topic)?;
+    let db_err = |e: rusqlite::Error| EnvelopeError::DbError(e.to_string());
+
+    // Thread topics resolve to their parent channel
+    let mut thread_locked = false;
+    let (channel_id, thread_id) = match extract_thread_id(&envelope.topic) {
+        Some(thread_id) => {
+            let thread: Option<(String, bool, bool)> = conn
+                .query_row(
+                    "SELECT channel_id, archived, locked FROM threads WHERE id = ?1",
+                    [&thread_id],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()
+                .map_err(db_err)?;
+            let (channel_id, archived, locked) =
+                thread.ok_or(EnvelopeError::Unauthorized(RejectReason::UnknownChannel))?;
+            if archived {
+                return Err(EnvelopeError::Unauthorized(RejectReason::ThreadClosed));
+            }
+            thread_locked = locked;
+            (channel_id, Some(thread_id))
+        }
+        None => (topic_id, None),
+    };
+
+    let channel_type = ChannelType::of(conn, &channel_id)
+        .map_err(db_err)?
+        .ok_or(EnvelopeError::Unauthorized(RejectReason::UnknownChannel))?;
+    if !channel_type.has_messages() {
+        return Err(EnvelopeError::Unauthorized(RejectReason::NotTextChannel));
+    }
+
+    let sender_pubkey = trusted.actor(envelope, message_type)?;
+    let sender: Option<(String, String, bool)> = conn
+        .query_row(
+            "SELECT id, fingerprint, is_owner FROM users WHERE public_key = ?1",
+            [&sender_pubkey],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(db_err)?;
+    let (user_id, fingerprint, is_owner) =
+        sender.ok_or(EnvelopeError::Unauthorized(RejectReason::UnknownSender))?;
+
+    if check_ban(conn, &fingerprint).is_some() {
+        return Err(EnvelopeError::Unauthorized(RejectReason::Banned));
+    }
+    let removes_content = matches!(
+        message_type,
+        MessageType::Delete | MessageType::ReactionRemove
+    );
+    if !removes_content
+        && active_mute(conn, &fingerprint, SanctionKind::TextMute)
+            .map_err(db_err)?
+            .is_some()
+    {
+        return Err(EnvelopeError::Unauthorized(RejectReason::Muted));
+    }
+
+    let perms = compute_channel_permissions(conn, &user_id, is_owner, &channel_id).map_err(db_err)?;
+    if !perms.contains(required) {
+        return Err(EnvelopeError::Unauthorized(RejectReason::MissingPermission));
+    }
+    if thread_locked && !perms.contains(Permissions::MANAGE_MESSAGES) {
+        return Err(EnvelopeError::Unauthorized(RejectReason::ThreadClosed));
+    }
+    let capabilities = envelope_capabilities(envelope, message_type);
+    match check_capabilities(conn, trust_config, &user_id, is_owner, &capabilities) {
+        Ok(()) => {}
+        Err((StatusCode::FORBIDDEN, _)) => {
+            return Err(EnvelopeError::Unauthorized(RejectReason::Probation))
+        }
+        Err((_, e)) => return Err(EnvelopeError::DbError(e)),
+    }
+    if thread_id.is_none() && message_type == MessageType::Chat {
+        if !channel_type.allows_top_level_post(perms) {
+            return Err(EnvelopeError::Unauthorized(RejectReason::MissingPermission));
+        }
+        // A gossiped post cannot carry a thread title, so forum posts must come over REST
+        if channel_type == ChannelType::Forum {
+            return Err(EnvelopeError::Unauthorized(RejectReason::ForumTopLevel));
+        }
+    }
+    if message_type == MessageType::Chat
+        && cooldown_remaining(
+            conn,
+            &channel_id,
+            thread_id.as_deref(),
+            &hex::encode(&sender_pubkey),
+            perms,
+        )
+        .map_err(db_err)?
+        .is_some()
+    {
+        return Err(EnvelopeError::Unauthorized(RejectReason::SlowMode));
+    }
+
+    Ok(GossipTarget {
+        message_type,
+        channel_id,
+        thread_id,
+        sender_pubkey: hex::encode(sender_pubkey),
+        sender_permissions: perms,
+    })
+}
+
+/// Probation-gated capabilities an envelope's payload uses (see `moderation::trust`).
+/// Payloads that do not decode use none; the handler rejects them or stores them opaque.
+fn envelope_capabilities(envelope: &GossipEnvelope, message_type: MessageType) -> Vec<Capability> {
+    let payload = envelope.payload.as_slice();
+    match message_type {
+        MessageType::Chat => proto_chat::ChatMessage::decode(payload)
+            .map(|msg| trust::message_capabilities(&msg.content, !msg.block_refs.is_empty()))
+            .unwrap_or_default(),
+        MessageType::Edit => proto_chat::MessageEditedEvent::decode(payload)
+            .map(|event| trust::message_capabilities(&event.new_content, false))
+            .unwrap_or_default(),
+        MessageType::ReactionAdd => vec![Capability::Reactions],
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,212 @@
+//! Per-user token-bucket rate limiting for REST routes and WebSocket requests.
+//!
+//! Requests are keyed by the authenticated user id, or by peer IP when there is no valid
+//! JWT, and charged to a bucket chosen by route (`RateClass`). Limits come from the
+//! `[rate_limits]` section of `united.toml`. Auth and public identity routes keep their
+//! own per-IP `tower_governor` limits in `routes::build_router`.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, MatchedPath, State},
+    http::{header, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+
+use crate::auth::middleware::claims_from_headers;
+use crate::config::{RateLimit, RateLimitConfig};
+use crate::state::AppState;
+
+/// Which bucket a request is charged to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateClass {
+    Messages,
+    Reactions,
+    Typing,
+    DirectMessages,
+    Uploads,
+    Api,
+    Ws,
+}
+
+impl RateClass {
+    /// Bucket for a REST request, from its method and matched route path.
+    pub fn for_route(method: &Method, path: &str) -> Self {
+        match (method.as_str(), path) {
+            ("POST", "/api/channels/{channel_id}/messages")
+            | ("PUT", "/api/channels/{channel_id}/messages/{message_id}")
+            | ("POST", "/api/threads/{thread_id}/messages") => Self::Messages,
+            ("POST", "/api/messages/{message_id}/reactions")
+            | ("DELETE", "/api/messages/{message_id}/reactions/{emoji}") => Self::Reactions,
+            ("POST", "/api/typing") => Self::Typing,
+            ("POST", "/api/dm/messages") | ("POST", "/api/dm/conversations") => {
+                Self::DirectMessages
+            }
+            ("PUT", "/api/blocks") => Self::Uploads,
+            _ => Self::Api,
+        }
+    }
+
+    fn limit(self, config: &RateLimitConfig) -> RateLimit {
+        match self {
+            Self::Messages => config.messages,
+            Self::Reactions => config.reactions,
+            Self::Typing => config.typing,
+            Self::DirectMessages => config.direct_messages,
+            Self::Uploads => config.uploads,
+            Self::Api => config.api,
+            Self::Ws => config.ws,
+        }
+    }
+}
+
+/// Rate-limit key for an authenticated user.
+pub fn user_key(user_id: &str) -> String {
+    format!("user:{}", user_id)
+}
+
+/// Rate-limit key for an unauthenticated peer.
+pub fn ip_key(addr: &SocketAddr) -> String {
+    format!("ip:{}", addr.ip())
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Add the tokens earned since the last update, capped at the burst size.
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second(limit)).min(burst(limit));
+        self.updated = now;
+    }
+}
+
+fn per_second(limit: RateLimit) -> f64 {
+    limit.per_minute as f64 / 60.0
+}
+
+fn burst(limit: RateLimit) -> f64 {
+    limit.burst.max(1) as f64
+}
+
+/// Token buckets per (class, key), created full on first use.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: DashMap<(RateClass, String), Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: DashMap::new(),
+        }
+    }
+
+    /// Take a token from `key`'s bucket for `class`, or return how long until the next
+    /// token is available.
+    pub fn check(&self, class: RateClass, key: &str) -> Result<(), Duration> {
+        self.check_at(class, key, Instant::now())
+    }
+
+    /// `check` at an explicit instant.
+    pub fn check_at(&self, class: RateClass, key: &str, now: Instant) -> Result<(), Duration> {
+        let limit = class.limit(&self.config);
+        if !self.config.enabled || limit.per_minute == 0 {
+            return Ok(());
+        }
+
+        let mut bucket = self
+            .buckets
+            .entry((class, key.to_string()))
+            .or_insert_with(|| Bucket {
+                tokens: burst(limit),
+                updated: now,
+            });
+        bucket.refill(limit, now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / per_second(limit),
+            ))
+        }
+    }
+
+    /// Drop buckets that have refilled completely, since a fresh bucket behaves the
+    /// same. Called periodically to bound memory.
+    pub fn retain_recent(&self) {
+        let now = Instant::now();
+        let config = &self.config;
+        self.buckets.retain(|(class, _), bucket| {
+            let limit = class.limit(config);
+            bucket.refill(limit, now);
+            bucket.tokens < burst(limit)
+        });
+    }
+
+    /// Number of buckets currently tracked.
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+}
+
+/// Spawn a background task that drops refilled buckets every minute.
+pub fn spawn_bucket_cleanup(limiter: Arc<RateLimiter>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            limiter.retain_recent();
+        }
+    });
+}
+
+/// 429 response with a Retry-After header in whole seconds (rounded up).
+pub fn too_many_requests(retry_after: Duration) -> Response {
+    let secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, secs.to_string())],
+        format!("Rate limit exceeded; retry after {} seconds", secs),
+    )
+        .into_response()
+}
+
+/// Middleware charging each REST request to its route's bucket.
+pub async fn enforce(State(state): State<AppState>, req: Request<Body>, next: Next) -> Response {
+    let path = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str())
+        .unwrap_or_else(|| req.uri().path());
+    let class = RateClass::for_route(req.method(), path);
+
+    let key = match claims_from_headers(req.headers(), &state.jwt_secret) {
+        Some(claims) => user_key(&claims.sub),
+        None => req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| ip_key(addr))
+            .unwrap_or_else(|| "ip:unknown".to_string()),
+    };
+
+    match state.rate_limiter.check(class, &key) {
+        Ok(()) => next.run(req).await,
+        Err(retry_after) => too_many_requests(retry_after),
+    }
+}
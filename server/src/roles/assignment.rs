@@ -7,9 +7,12 @@ use chrono::Utc;
 use serde::{Deserialize, Serialize};
 
 use crate::auth::middleware::Claims;
-use crate::roles::permissions::{require_permission, Permissions};
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::roles::hierarchy::{require_outranks, require_role_below};
+use crate::roles::permissions::{load_user_permissions, require_permission, Permissions};
 use crate::state::AppState;
 use crate::ws::broadcast::broadcast_to_all;
+use crate::ws::scope;
 use crate::proto::ws::{envelope::Payload, Envelope};
 use crate::proto::roles as proto_roles;
 
@@ -41,21 +44,24 @@ pub struct UserRolesResponse {
     pub roles: Vec<RoleResponse>,
 }
 
-/// POST /api/roles/assign — Assign a role to a user (requires ADMIN permission).
+/// POST /api/roles/assign — Assign a role to a user (requires MANAGE_ROLES and a
+/// role higher than both the one being assigned and the member's highest role).
 pub async fn assign_role(
     State(state): State<AppState>,
     claims: Claims,
     Json(req): Json<AssignRoleRequest>,
 ) -> Result<StatusCode, (StatusCode, String)> {
-    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::MANAGE_ROLES)
         .await
         .map_err(|s| (s, "Insufficient permissions".to_string()))?;
 
     let db = state.db.clone();
     let user_id = req.user_id.clone();
     let role_id = req.role_id.clone();
+    let caller_id = claims.sub.clone();
+    let caller_is_owner = claims.is_owner;
 
-    tokio::task::spawn_blocking(move || {
+    let entry = tokio::task::spawn_blocking(move || {
         let conn = db.lock().map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
 
         // Verify user exists
@@ -68,14 +74,29 @@ pub async fn assign_role(
             return Err((StatusCode::NOT_FOUND, "User not found".to_string()));
         }
 
-        // Verify role exists
-        let role_exists: bool = conn
-            .query_row("SELECT COUNT(*) FROM roles WHERE id = ?1", [&role_id], |row| {
-                row.get::<_, i64>(0).map(|c| c > 0)
-            })
-            .unwrap_or(false);
-        if !role_exists {
-            return Err((StatusCode::NOT_FOUND, "Role not found".to_string()));
+        // Verify role exists and sits below the caller's highest role
+        let (role_permissions, position): (u32, i64) = conn
+            .query_row(
+                "SELECT permissions, position FROM roles WHERE id = ?1",
+                [&role_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|_| (StatusCode::NOT_FOUND, "Role not found".to_string()))?;
+        require_role_below(&conn, &caller_id, caller_is_owner, position)?;
+        if user_id != caller_id {
+            require_outranks(&conn, &caller_id, caller_is_owner, &user_id)?;
+        }
+
+        // Callers cannot hand out permissions they do not hold themselves
+        if !caller_is_owner {
+            let held = load_user_permissions(&conn, &caller_id)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Load permissions: {}", e)))?;
+            if !held.contains(Permissions::from_bits_truncate(role_permissions)) {
+                return Err((
+                    StatusCode::FORBIDDEN,
+                    "Cannot assign a role with permissions you do not have".to_string(),
+                ));
+            }
         }
 
         let now = Utc::now().to_rfc3339();
@@ -87,48 +108,61 @@ pub async fn assign_role(
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Assign role: {}", e)))?;
 
-        Ok::<_, (StatusCode, String)>(())
+        audit::record(
+            &conn,
+            AuditRecord::new(&caller_id, AuditAction::MemberRoleAdd, &user_id)
+                .after(serde_json::json!({ "role_id": role_id })),
+        )
+        .map_err(audit::audit_error)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
+    // Channel visibility may have changed for the member's connections
+    scope::refresh_user_visibility(&state, &req.user_id).await;
+
     // Broadcast RoleAssignedEvent
     let event = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::RoleAssignedEvent(proto_roles::RoleAssignedEvent {
             user_id: req.user_id,
             role_id: req.role_id,
         })),
     };
     broadcast_to_all(&state.connections, &event);
+    audit::notify_admins(&state, entry);
 
     Ok(StatusCode::OK)
 }
 
-/// POST /api/roles/remove — Remove a role from a user (requires ADMIN permission).
+/// POST /api/roles/remove — Remove a role from a user (requires MANAGE_ROLES and a
+/// role higher than both the one being removed and the member's highest role).
 /// Cannot remove the @everyone (default) role from users.
 pub async fn remove_role(
     State(state): State<AppState>,
     claims: Claims,
     Json(req): Json<RemoveRoleRequest>,
 ) -> Result<StatusCode, (StatusCode, String)> {
-    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::MANAGE_ROLES)
         .await
         .map_err(|s| (s, "Insufficient permissions".to_string()))?;
 
     let db = state.db.clone();
     let user_id = req.user_id.clone();
     let role_id = req.role_id.clone();
+    let caller_id = claims.sub.clone();
+    let caller_is_owner = claims.is_owner;
 
-    tokio::task::spawn_blocking(move || {
+    let entry = tokio::task::spawn_blocking(move || {
         let conn = db.lock().map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
 
         // Check if role is default — cannot remove @everyone
-        let is_default: bool = conn
+        let (is_default, position): (bool, i64) = conn
             .query_row(
-                "SELECT is_default FROM roles WHERE id = ?1",
+                "SELECT is_default, position FROM roles WHERE id = ?1",
                 [&role_id],
-                |row| row.get::<_, bool>(0),
+                |row| Ok((row.get(0)?, row.get(1)?)),
             )
             .map_err(|_| (StatusCode::NOT_FOUND, "Role not found".to_string()))?;
 
@@ -139,26 +173,41 @@ pub async fn remove_role(
             ));
         }
 
+        require_role_below(&conn, &caller_id, caller_is_owner, position)?;
+        if user_id != caller_id {
+            require_outranks(&conn, &caller_id, caller_is_owner, &user_id)?;
+        }
+
         conn.execute(
             "DELETE FROM user_roles WHERE user_id = ?1 AND role_id = ?2",
             rusqlite::params![user_id, role_id],
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Remove role: {}", e)))?;
 
-        Ok::<_, (StatusCode, String)>(())
+        audit::record(
+            &conn,
+            AuditRecord::new(&caller_id, AuditAction::MemberRoleRemove, &user_id)
+                .before(serde_json::json!({ "role_id": role_id })),
+        )
+        .map_err(audit::audit_error)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
+    // Channel visibility may have changed for the member's connections
+    scope::refresh_user_visibility(&state, &req.user_id).await;
+
     // Broadcast RoleRemovedEvent
     let event = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::RoleRemovedEvent(proto_roles::RoleRemovedEvent {
             user_id: req.user_id,
             role_id: req.role_id,
         })),
     };
     broadcast_to_all(&state.connections, &event);
+    audit::notify_admins(&state, entry);
 
     Ok(StatusCode::OK)
 }
@@ -172,7 +221,7 @@ pub async fn list_members(
     let db = state.db.clone();
 
     let members = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+        let conn = db.read().map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
 
         // Get all users ordered by owner first, then display name
         let mut user_stmt = conn
@@ -232,7 +281,7 @@ pub async fn get_user_roles(
     let db = state.db.clone();
 
     let roles = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         // Get roles from user_roles + default roles (UNION to include @everyone)
         let mut stmt = conn
@@ -8,9 +8,12 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::auth::middleware::Claims;
-use crate::roles::permissions::{require_permission, Permissions};
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::roles::hierarchy::require_role_below;
+use crate::roles::permissions::{require_grantable, require_permission, Permissions};
 use crate::state::AppState;
 use crate::ws::broadcast::broadcast_to_all;
+use crate::ws::scope;
 use crate::proto::ws::{envelope::Payload, Envelope};
 use crate::proto::roles as proto_roles;
 
@@ -29,6 +32,20 @@ pub struct RoleListResponse {
     pub roles: Vec<RoleResponse>,
 }
 
+impl RoleResponse {
+    /// Convert to the protobuf Role used in events and WS responses.
+    pub fn to_proto(&self) -> proto_roles::Role {
+        proto_roles::Role {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            permissions: self.permissions,
+            color: self.color.clone(),
+            position: self.position,
+            is_default: self.is_default,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateRoleRequest {
     pub name: String,
@@ -44,6 +61,17 @@ pub struct UpdateRoleRequest {
     pub color: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoleReorderEntry {
+    pub id: String,
+    pub position: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReorderRolesRequest {
+    pub entries: Vec<RoleReorderEntry>,
+}
+
 /// GET /api/roles — List all roles ordered by position.
 pub async fn list_roles(
     State(state): State<AppState>,
@@ -52,27 +80,8 @@ pub async fn list_roles(
     let db = state.db.clone();
 
     let roles = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        let mut stmt = conn
-            .prepare("SELECT id, name, permissions, color, position, is_default FROM roles ORDER BY position ASC")
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-        let rows: Vec<RoleResponse> = stmt
-            .query_map([], |row| {
-                Ok(RoleResponse {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    permissions: row.get::<_, u32>(2)?,
-                    color: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
-                    position: row.get(4)?,
-                    is_default: row.get::<_, bool>(5)?,
-                })
-            })
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        Ok::<_, StatusCode>(rows)
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        query_roles(&conn).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
     })
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
@@ -80,14 +89,16 @@ pub async fn list_roles(
     Ok(Json(RoleListResponse { roles }))
 }
 
-/// POST /api/roles — Create a new role (requires ADMIN permission).
+/// POST /api/roles — Create a new role (requires MANAGE_ROLES).
+/// New roles start at the bottom of the hierarchy, just above @everyone, so the
+/// creator can manage them; existing roles move up one position.
 pub async fn create_role(
     State(state): State<AppState>,
     claims: Claims,
     Json(req): Json<CreateRoleRequest>,
 ) -> Result<(StatusCode, Json<RoleResponse>), (StatusCode, String)> {
     // Permission check
-    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::MANAGE_ROLES)
         .await
         .map_err(|s| (s, "Insufficient permissions".to_string()))?;
 
@@ -95,22 +106,27 @@ pub async fn create_role(
         return Err((StatusCode::BAD_REQUEST, "Role name cannot be empty".to_string()));
     }
 
+    require_grantable(&state.db, &claims.sub, claims.is_owner, req.permissions).await?;
+
     let db = state.db.clone();
     let name = req.name.clone();
     let permissions = req.permissions;
     let color = req.color.clone();
+    let actor_id = claims.sub.clone();
 
-    let role = tokio::task::spawn_blocking(move || {
+    let (role, roles, entry) = tokio::task::spawn_blocking(move || {
         let conn = db.lock().map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
 
-        // Get next position
-        let max_pos: i64 = conn
-            .query_row("SELECT COALESCE(MAX(position), 0) FROM roles", [], |row| row.get(0))
-            .unwrap_or(0);
-
         let role_id = Uuid::now_v7().to_string();
         let now = Utc::now().to_rfc3339();
-        let position = max_pos + 1;
+        let position = 1;
+
+        // Make room directly above @everyone
+        conn.execute(
+            "UPDATE roles SET position = position + 1 WHERE is_default = 0",
+            [],
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Shift roles: {}", e)))?;
 
         conn.execute(
             "INSERT INTO roles (id, name, permissions, color, position, is_default, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6, ?7)",
@@ -118,14 +134,24 @@ pub async fn create_role(
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Insert role: {}", e)))?;
 
-        Ok::<_, (StatusCode, String)>(RoleResponse {
+        let roles = query_roles(&conn)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query: {}", e)))?;
+
+        let role = RoleResponse {
             id: role_id,
             name,
             permissions,
             color,
             position,
             is_default: false,
-        })
+        };
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::RoleCreate, &role.id).after(&role),
+        )
+        .map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((role, roles, entry))
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
@@ -133,6 +159,7 @@ pub async fn create_role(
     // Broadcast RoleCreatedEvent
     let event = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::RoleCreatedEvent(proto_roles::RoleCreatedEvent {
             role: Some(proto_roles::Role {
                 id: role.id.clone(),
@@ -146,36 +173,47 @@ pub async fn create_role(
     };
     broadcast_to_all(&state.connections, &event);
 
+    // Existing roles shifted up, so clients also get the new order
+    let event = Envelope {
+        request_id: String::new(),
+        seq: 0,
+        payload: Some(Payload::RoleReorderedEvent(proto_roles::RoleReorderedEvent {
+            roles: roles.iter().map(|r| r.to_proto()).collect(),
+        })),
+    };
+    broadcast_to_all(&state.connections, &event);
+    audit::notify_admins(&state, entry);
+
     Ok((StatusCode::CREATED, Json(role)))
 }
 
-/// PUT /api/roles/{id} — Update a role (requires ADMIN permission).
+/// PUT /api/roles/{id} — Update a role (requires MANAGE_ROLES and a higher role).
 pub async fn update_role(
     State(state): State<AppState>,
     claims: Claims,
     Path(role_id): Path<String>,
     Json(req): Json<UpdateRoleRequest>,
 ) -> Result<Json<RoleResponse>, (StatusCode, String)> {
-    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::MANAGE_ROLES)
         .await
         .map_err(|s| (s, "Insufficient permissions".to_string()))?;
 
+    if let Some(permissions) = req.permissions {
+        require_grantable(&state.db, &claims.sub, claims.is_owner, permissions).await?;
+    }
+
     let db = state.db.clone();
     let rid = role_id.clone();
+    let actor_id = claims.sub.clone();
+    let actor_is_owner = claims.is_owner;
 
-    let role = tokio::task::spawn_blocking(move || {
+    let (role, entry) = tokio::task::spawn_blocking(move || {
         let conn = db.lock().map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
 
-        // Check role exists
-        let exists: bool = conn
-            .query_row("SELECT COUNT(*) FROM roles WHERE id = ?1", [&rid], |row| {
-                row.get::<_, i64>(0).map(|c| c > 0)
-            })
-            .unwrap_or(false);
-
-        if !exists {
-            return Err((StatusCode::NOT_FOUND, "Role not found".to_string()));
-        }
+        // Check role exists and sits below the caller's highest role
+        let before = load_role(&conn, &rid)
+            .map_err(|_| (StatusCode::NOT_FOUND, "Role not found".to_string()))?;
+        require_role_below(&conn, &actor_id, actor_is_owner, before.position)?;
 
         let now = Utc::now().to_rfc3339();
 
@@ -205,31 +243,29 @@ pub async fn update_role(
         }
 
         // Read back updated role
-        let role = conn
-            .query_row(
-                "SELECT id, name, permissions, color, position, is_default FROM roles WHERE id = ?1",
-                [&rid],
-                |row| {
-                    Ok(RoleResponse {
-                        id: row.get(0)?,
-                        name: row.get(1)?,
-                        permissions: row.get::<_, u32>(2)?,
-                        color: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
-                        position: row.get(4)?,
-                        is_default: row.get::<_, bool>(5)?,
-                    })
-                },
-            )
+        let role = load_role(&conn, &rid)
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Read role: {}", e)))?;
 
-        Ok::<_, (StatusCode, String)>(role)
+        let entry = audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::RoleUpdate, &rid)
+                .before(&before)
+                .after(&role),
+        )
+        .map_err(audit::audit_error)?;
+
+        Ok::<_, (StatusCode, String)>((role, entry))
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
+    // Channel visibility may have changed for connected users
+    scope::refresh_visibility(&state).await;
+
     // Broadcast RoleUpdatedEvent
     let event = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::RoleUpdatedEvent(proto_roles::RoleUpdatedEvent {
             role: Some(proto_roles::Role {
                 id: role.id.clone(),
@@ -242,62 +278,204 @@ pub async fn update_role(
         })),
     };
     broadcast_to_all(&state.connections, &event);
+    audit::notify_admins(&state, entry);
 
     Ok(Json(role))
 }
 
-/// DELETE /api/roles/{id} — Delete a role (requires ADMIN permission).
+/// DELETE /api/roles/{id} — Delete a role (requires MANAGE_ROLES and a higher role).
 /// Cannot delete the default (@everyone) role.
 pub async fn delete_role(
     State(state): State<AppState>,
     claims: Claims,
     Path(role_id): Path<String>,
 ) -> Result<StatusCode, (StatusCode, String)> {
-    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::MANAGE_ROLES)
         .await
         .map_err(|s| (s, "Insufficient permissions".to_string()))?;
 
     let db = state.db.clone();
     let rid = role_id.clone();
+    let actor_id = claims.sub.clone();
+    let actor_is_owner = claims.is_owner;
 
-    tokio::task::spawn_blocking(move || {
+    let entry = tokio::task::spawn_blocking(move || {
         let conn = db.lock().map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
 
         // Check if role is default
-        let is_default: bool = conn
-            .query_row(
-                "SELECT is_default FROM roles WHERE id = ?1",
-                [&rid],
-                |row| row.get::<_, bool>(0),
-            )
+        let before = load_role(&conn, &rid)
             .map_err(|_| (StatusCode::NOT_FOUND, "Role not found".to_string()))?;
 
-        if is_default {
+        if before.is_default {
             return Err((StatusCode::BAD_REQUEST, "Cannot delete the default role".to_string()));
         }
 
+        require_role_below(&conn, &actor_id, actor_is_owner, before.position)?;
+
         // Delete user_roles entries for this role first (cascade)
         conn.execute("DELETE FROM user_roles WHERE role_id = ?1", [&rid])
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Delete user_roles: {}", e)))?;
 
+        // Delete channel overrides targeting this role
+        conn.execute(
+            "DELETE FROM channel_permission_overrides WHERE target_type = 'role' AND target_id = ?1",
+            [&rid],
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Delete overrides: {}", e)))?;
+
         // Delete the role
         conn.execute("DELETE FROM roles WHERE id = ?1", [&rid])
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Delete role: {}", e)))?;
 
-        Ok::<_, (StatusCode, String)>(())
+        audit::record(
+            &conn,
+            AuditRecord::new(&actor_id, AuditAction::RoleDelete, &rid).before(&before),
+        )
+        .map_err(audit::audit_error)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
 
+    // Channel visibility may have changed for connected users
+    scope::refresh_visibility(&state).await;
+
     // Broadcast RoleDeletedEvent
     let event = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::RoleDeletedEvent(proto_roles::RoleDeletedEvent {
             role_id: role_id.clone(),
         })),
     };
     broadcast_to_all(&state.connections, &event);
+    audit::notify_admins(&state, entry);
 
     Ok(StatusCode::OK)
 }
 
+
+/// PUT /api/roles/reorder — Move roles to new positions (requires MANAGE_ROLES).
+/// Non-owners may only move roles below their highest role, to positions still below it.
+/// The @everyone role is pinned at position 0.
+pub async fn reorder_roles(
+    State(state): State<AppState>,
+    claims: Claims,
+    Json(req): Json<ReorderRolesRequest>,
+) -> Result<Json<RoleListResponse>, (StatusCode, String)> {
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::MANAGE_ROLES)
+        .await
+        .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let db = state.db.clone();
+    let actor_id = claims.sub.clone();
+    let actor_is_owner = claims.is_owner;
+
+    let (roles, entry) = tokio::task::spawn_blocking(move || {
+        let mut conn = db.lock().map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+
+        // Validate every entry before applying any of them
+        for entry in &req.entries {
+            let (is_default, position): (bool, i64) = conn
+                .query_row(
+                    "SELECT is_default, position FROM roles WHERE id = ?1",
+                    [&entry.id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .map_err(|_| (StatusCode::NOT_FOUND, "Role not found".to_string()))?;
+            if is_default {
+                return Err((StatusCode::BAD_REQUEST, "Cannot move the default role".to_string()));
+            }
+            if entry.position < 1 {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "Role positions must be above the default role".to_string(),
+                ));
+            }
+            require_role_below(&conn, &actor_id, actor_is_owner, position)?;
+            require_role_below(&conn, &actor_id, actor_is_owner, entry.position)?;
+        }
+
+        let before: Vec<RoleReorderEntry> = query_roles(&conn)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query: {}", e)))?
+            .into_iter()
+            .filter(|r| req.entries.iter().any(|e| e.id == r.id))
+            .map(|r| RoleReorderEntry { id: r.id, position: r.position })
+            .collect();
+
+        // The hierarchy depends on these positions: apply them all or none
+        let tx = conn
+            .transaction()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Begin: {}", e)))?;
+        let now = Utc::now().to_rfc3339();
+        for entry in &req.entries {
+            tx.execute(
+                "UPDATE roles SET position = ?1, updated_at = ?2 WHERE id = ?3",
+                rusqlite::params![entry.position, now, entry.id],
+            )
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Reorder role: {}", e)))?;
+        }
+
+        let roles = query_roles(&tx)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query: {}", e)))?;
+        let entry = audit::record(
+            &tx,
+            AuditRecord::new(&actor_id, AuditAction::RoleReorder, "")
+                .before(&before)
+                .after(&req.entries),
+        )
+        .map_err(audit::audit_error)?;
+        tx.commit()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Commit: {}", e)))?;
+
+        Ok::<_, (StatusCode, String)>((roles, entry))
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    // Broadcast RoleReorderedEvent with the resulting order
+    let event = Envelope {
+        request_id: String::new(),
+        seq: 0,
+        payload: Some(Payload::RoleReorderedEvent(proto_roles::RoleReorderedEvent {
+            roles: roles.iter().map(|r| r.to_proto()).collect(),
+        })),
+    };
+    broadcast_to_all(&state.connections, &event);
+    audit::notify_admins(&state, entry);
+
+    Ok(Json(RoleListResponse { roles }))
+}
+
+/// Load all roles ordered by position.
+fn query_roles(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<RoleResponse>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, permissions, color, position, is_default FROM roles ORDER BY position ASC",
+    )?;
+
+    let rows = stmt
+        .query_map([], role_from_row)?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(rows)
+}
+
+/// Load one role by ID.
+fn load_role(conn: &rusqlite::Connection, role_id: &str) -> rusqlite::Result<RoleResponse> {
+    conn.query_row(
+        "SELECT id, name, permissions, color, position, is_default FROM roles WHERE id = ?1",
+        [role_id],
+        role_from_row,
+    )
+}
+
+fn role_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<RoleResponse> {
+    Ok(RoleResponse {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        permissions: row.get::<_, u32>(2)?,
+        color: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+        position: row.get(4)?,
+        is_default: row.get::<_, bool>(5)?,
+    })
+}
@@ -0,0 +1,74 @@
+//! Role hierarchy: roles with a higher `position` outrank lower ones.
+//!
+//! A member's rank is the position of their highest role (@everyone sits at 0).
+//! Non-owners may only manage roles, and act on members, strictly below their own rank.
+//! The server owner outranks everyone.
+
+use axum::http::StatusCode;
+
+/// Position of the user's highest role (0 if they only hold @everyone).
+pub fn highest_role_position(conn: &rusqlite::Connection, user_id: &str) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(r.position), 0) FROM roles r
+         INNER JOIN user_roles ur ON ur.role_id = r.id
+         WHERE ur.user_id = ?1",
+        [user_id],
+        |row| row.get(0),
+    )
+}
+
+/// Require the role at `role_position` to sit strictly below the actor's highest role.
+pub fn require_role_below(
+    conn: &rusqlite::Connection,
+    actor_id: &str,
+    actor_is_owner: bool,
+    role_position: i64,
+) -> Result<(), (StatusCode, String)> {
+    if actor_is_owner {
+        return Ok(());
+    }
+    let actor_position = highest_role_position(conn, actor_id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Role hierarchy: {}", e)))?;
+    if role_position < actor_position {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::FORBIDDEN,
+            "Role is not below your highest role".to_string(),
+        ))
+    }
+}
+
+/// Require the actor to outrank `target_id` (the target's highest role sits strictly
+/// below the actor's). Nobody outranks the owner.
+pub fn require_outranks(
+    conn: &rusqlite::Connection,
+    actor_id: &str,
+    actor_is_owner: bool,
+    target_id: &str,
+) -> Result<(), (StatusCode, String)> {
+    let target_is_owner: bool = conn
+        .query_row("SELECT is_owner FROM users WHERE id = ?1", [target_id], |row| row.get(0))
+        .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+    if target_is_owner {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Cannot act on the server owner".to_string(),
+        ));
+    }
+    if actor_is_owner {
+        return Ok(());
+    }
+    let position = |user_id| {
+        highest_role_position(conn, user_id)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Role hierarchy: {}", e)))
+    };
+    if position(target_id)? < position(actor_id)? {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::FORBIDDEN,
+            "Target member's highest role is not below yours".to_string(),
+        ))
+    }
+}
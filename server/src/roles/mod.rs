@@ -1,3 +1,4 @@
 pub mod assignment;
 pub mod crud;
+pub mod hierarchy;
 pub mod permissions;
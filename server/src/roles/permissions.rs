@@ -11,10 +11,40 @@ bitflags! {
         const KICK_MEMBERS    = 1 << 2;  // 0x04
         const BAN_MEMBERS     = 1 << 3;  // 0x08
         const ADMIN           = 1 << 4;  // 0x10
+        const VIEW_CHANNEL    = 1 << 5;  // 0x20
+        const READ_HISTORY    = 1 << 6;  // 0x40
+        const MANAGE_MESSAGES = 1 << 7;  // 0x80
+        const MANAGE_ROLES    = 1 << 8;  // 0x100
+        const MANAGE_INVITES  = 1 << 9;  // 0x200
+        const MUTE_MEMBERS    = 1 << 10; // 0x400
+        const MOVE_MEMBERS    = 1 << 11; // 0x800
+        const ATTACH_FILES    = 1 << 12; // 0x1000
+        const ADD_REACTIONS   = 1 << 13; // 0x2000
+        const MENTION_EVERYONE = 1 << 14; // 0x4000
+        const MANAGE_SERVER   = 1 << 15; // 0x8000
     }
 }
 
 impl Permissions {
+    /// Permissions granted to @everyone on a new server.
+    pub const DEFAULT_EVERYONE: Permissions = Permissions::SEND_MESSAGES
+        .union(Permissions::VIEW_CHANNEL)
+        .union(Permissions::READ_HISTORY)
+        .union(Permissions::ATTACH_FILES)
+        .union(Permissions::ADD_REACTIONS);
+
+    /// Permissions that can be allowed/denied per channel via overrides.
+    /// Server-wide powers (ADMIN, role/invite/server management, kick/ban) are excluded.
+    pub const CHANNEL_SCOPED: Permissions = Permissions::SEND_MESSAGES
+        .union(Permissions::VIEW_CHANNEL)
+        .union(Permissions::READ_HISTORY)
+        .union(Permissions::MANAGE_MESSAGES)
+        .union(Permissions::MUTE_MEMBERS)
+        .union(Permissions::MOVE_MEMBERS)
+        .union(Permissions::ATTACH_FILES)
+        .union(Permissions::ADD_REACTIONS)
+        .union(Permissions::MENTION_EVERYONE);
+
     /// ADMIN implies all other permissions.
     pub fn effective(self) -> Permissions {
         if self.contains(Permissions::ADMIN) {
@@ -36,6 +66,50 @@ pub fn compute_user_permissions(is_owner: bool, role_permissions: &[u32]) -> Per
     Permissions::from_bits_truncate(combined).effective()
 }
 
+/// Load a non-owner user's effective permissions from their assigned roles plus @everyone.
+/// Synchronous — for callers already holding the DB lock.
+pub fn load_user_permissions(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+) -> rusqlite::Result<Permissions> {
+    // Get permission bits from all assigned roles + @everyone (is_default=1)
+    let mut stmt = conn.prepare(
+        "SELECT r.permissions FROM roles r
+         INNER JOIN user_roles ur ON ur.role_id = r.id
+         WHERE ur.user_id = ?1
+         UNION ALL
+         SELECT r.permissions FROM roles r WHERE r.is_default = 1",
+    )?;
+
+    let perms: Vec<u32> = stmt
+        .query_map([user_id], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(compute_user_permissions(false, &perms))
+}
+
+/// Load a user's effective server-wide permissions from the DB (owner has all).
+pub async fn user_permissions(
+    db: &DbPool,
+    user_id: &str,
+    is_owner: bool,
+) -> Result<Permissions, StatusCode> {
+    if is_owner {
+        return Ok(Permissions::all());
+    }
+
+    let db = db.clone();
+    let uid = user_id.to_string();
+
+    tokio::task::spawn_blocking(move || {
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        load_user_permissions(&conn, &uid).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+}
+
 /// Check if a user has the required permission.
 /// Reads current roles from DB (not JWT) to reflect real-time changes.
 /// Owner always passes. Returns Err(FORBIDDEN) on failure.
@@ -45,41 +119,152 @@ pub async fn require_permission(
     is_owner: bool,
     required: Permissions,
 ) -> Result<(), StatusCode> {
+    if user_permissions(db, user_id, is_owner).await?.contains(required) {
+        Ok(())
+    } else {
+        Err(StatusCode::FORBIDDEN)
+    }
+}
+
+/// Check that a user holds every permission in `granted` before letting them put
+/// those bits on a role, so MANAGE_ROLES cannot be used to escalate privileges.
+pub async fn require_grantable(
+    db: &DbPool,
+    user_id: &str,
+    is_owner: bool,
+    granted: u32,
+) -> Result<(), (StatusCode, String)> {
+    let held = user_permissions(db, user_id, is_owner)
+        .await
+        .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+    if held.contains(Permissions::from_bits_truncate(granted)) {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::FORBIDDEN,
+            "Cannot grant permissions you do not have".to_string(),
+        ))
+    }
+}
+
+/// Resolve a user's effective permissions in a specific channel.
+///
+/// Starts from the server-wide role permissions, then applies channel overrides in order:
+/// the @everyone override, the combined overrides of the user's other roles, and finally
+/// the user's own override. Each layer clears its deny bits, then sets its allow bits.
+/// Owner and ADMIN bypass overrides entirely.
+pub fn compute_channel_permissions(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+    is_owner: bool,
+    channel_id: &str,
+) -> rusqlite::Result<Permissions> {
     if is_owner {
-        return Ok(());
+        return Ok(Permissions::all());
+    }
+    let base = load_user_permissions(conn, user_id)?;
+    apply_channel_overrides(conn, user_id, base, channel_id)
+}
+
+/// Apply `channel_id`'s overrides to `base`, the user's server-wide permissions as
+/// returned by `load_user_permissions`, for callers resolving many channels for one user.
+/// ADMIN bypasses overrides.
+pub fn apply_channel_overrides(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+    base: Permissions,
+    channel_id: &str,
+) -> rusqlite::Result<Permissions> {
+    if base.contains(Permissions::ADMIN) {
+        return Ok(Permissions::all());
+    }
+
+    // (is_everyone, target_type, allow, deny) for overrides that apply to this user
+    let mut stmt = conn.prepare(
+        "SELECT COALESCE(r.is_default, 0), o.target_type, o.allow, o.deny
+         FROM channel_permission_overrides o
+         LEFT JOIN roles r ON o.target_type = 'role' AND r.id = o.target_id
+         WHERE o.channel_id = ?1
+           AND ((o.target_type = 'user' AND o.target_id = ?2)
+             OR (o.target_type = 'role' AND (r.is_default = 1
+                 OR o.target_id IN (SELECT role_id FROM user_roles WHERE user_id = ?2))))",
+    )?;
+    let overrides: Vec<(bool, String, u32, u32)> = stmt
+        .query_map(rusqlite::params![channel_id, user_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let apply = |bits: u32, allow: u32, deny: u32| (bits & !deny) | allow;
+    let mut bits = base.bits();
+
+    // 1. @everyone override
+    for (_, _, allow, deny) in overrides.iter().filter(|o| o.0) {
+        bits = apply(bits, *allow, *deny);
+    }
+
+    // 2. Other role overrides, combined
+    let (role_allow, role_deny) = overrides
+        .iter()
+        .filter(|o| !o.0 && o.1 == "role")
+        .fold((0u32, 0u32), |(a, d), o| (a | o.2, d | o.3));
+    bits = apply(bits, role_allow, role_deny);
+
+    // 3. User override
+    for (_, _, allow, deny) in overrides.iter().filter(|o| o.1 == "user") {
+        bits = apply(bits, *allow, *deny);
     }
 
+    Ok(Permissions::from_bits_truncate(bits))
+}
+
+/// Check if a user has the required permission in a channel (overrides applied).
+/// Returns Err(NOT_FOUND) if the channel does not exist, Err(FORBIDDEN) if the check fails.
+pub async fn require_channel_permission(
+    db: &DbPool,
+    user_id: &str,
+    is_owner: bool,
+    channel_id: &str,
+    required: Permissions,
+) -> Result<(), StatusCode> {
     let db = db.clone();
     let uid = user_id.to_string();
+    let cid = channel_id.to_string();
 
-    let has_permission = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-        // Get permission bits from all assigned roles + @everyone (is_default=1)
-        let mut stmt = conn
-            .prepare(
-                "SELECT r.permissions FROM roles r
-                 INNER JOIN user_roles ur ON ur.role_id = r.id
-                 WHERE ur.user_id = ?1
-                 UNION ALL
-                 SELECT r.permissions FROM roles r WHERE r.is_default = 1",
-            )
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-        let perms: Vec<u32> = stmt
-            .query_map([&uid], |row| row.get(0))
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        let effective = compute_user_permissions(false, &perms);
-        Ok::<bool, StatusCode>(effective.contains(required))
+    tokio::task::spawn_blocking(move || {
+        let conn = db.read().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        check_channel_permission(&conn, &uid, is_owner, &cid, required)
     })
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .map(|_| ())
+}
 
-    if has_permission {
-        Ok(())
+/// Synchronous form of `require_channel_permission`, for callers already holding the DB lock.
+/// Returns the caller's effective channel permissions on success, for finer-grained checks.
+pub fn check_channel_permission(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+    is_owner: bool,
+    channel_id: &str,
+    required: Permissions,
+) -> Result<Permissions, StatusCode> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT COUNT(*) FROM channels WHERE id = ?1",
+            [channel_id],
+            |row| row.get::<_, i64>(0).map(|c| c > 0),
+        )
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !exists {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let effective = compute_channel_permissions(conn, user_id, is_owner, channel_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if effective.contains(required) {
+        Ok(effective)
     } else {
         Err(StatusCode::FORBIDDEN)
     }
@@ -12,12 +12,14 @@ use crate::chat;
 use crate::dm;
 use crate::identity::{blob, registration, rotation};
 use crate::channels::crud as channel_crud;
-use crate::invite::{generate as invite_gen, landing as invite_landing};
-use crate::moderation::{ban, kick};
+use crate::channels::overrides as channel_overrides;
+use crate::invite::{generate as invite_gen, landing as invite_landing, tree as invite_tree};
+use crate::moderation::{abuse, audit, ban, ban_list, kick, sanctions, trust};
+use crate::rate_limit;
 use crate::roles::{assignment as role_assignment, crud as role_crud};
 use crate::state::AppState;
-use crate::voice;
 use crate::ws::handler as ws_handler;
+use crate::ws::outbound as ws_outbound;
 
 /// GET /api/p2p/info — Public endpoint returning the server's P2P connection info.
 /// Required by clients to construct the server's libp2p multiaddr for dialing.
@@ -32,6 +34,12 @@ async fn p2p_info(
             state.server_peer_id
         ),
         "libp2p_port": state.libp2p_port,
+        "gossip_rejections": state
+            .gossip_rejections
+            .snapshot()
+            .into_iter()
+            .map(|(reason, count)| (reason.to_string(), count.into()))
+            .collect::<serde_json::Map<String, serde_json::Value>>(),
     }))
 }
 
@@ -168,10 +176,9 @@ pub fn build_router(state: AppState) -> Router {
     );
 
     // WebSocket endpoint (auth via query param, not JWT header)
-    let ws_routes = Router::new().route(
-        "/ws",
-        axum::routing::get(ws_handler::ws_upgrade),
-    );
+    let ws_routes = Router::new()
+        .route("/ws", axum::routing::get(ws_handler::ws_upgrade))
+        .route("/api/ws/metrics", axum::routing::get(ws_outbound::get_outbound_metrics));
 
     // Health check
     let health = Router::new().route("/health", axum::routing::get(health_check));
@@ -184,12 +191,27 @@ pub fn build_router(state: AppState) -> Router {
         .route("/api/channels/reorder", axum::routing::put(channel_crud::reorder_channels))
         .route("/api/channels/{id}", axum::routing::put(channel_crud::update_channel))
         .route("/api/channels/{id}", axum::routing::delete(channel_crud::delete_channel))
+        .route("/api/channels/{id}/overrides", axum::routing::get(channel_overrides::list_overrides))
+        .route(
+            "/api/channels/{id}/overrides/{target_type}/{target_id}",
+            axum::routing::put(channel_overrides::set_override)
+                .delete(channel_overrides::delete_override),
+        )
         .route("/api/categories", axum::routing::post(channel_crud::create_category))
-        .route("/api/categories/{id}", axum::routing::delete(channel_crud::delete_category));
+        .route("/api/categories/reorder", axum::routing::put(channel_crud::reorder_categories))
+        .route("/api/categories/{id}", axum::routing::put(channel_crud::update_category))
+        .route("/api/categories/{id}", axum::routing::delete(channel_crud::delete_category))
+        .route("/api/channels/{id}/retention", axum::routing::put(chat::retention::set_channel_retention))
+        .route("/api/categories/{id}/retention", axum::routing::put(chat::retention::set_category_retention))
+        .route("/api/retention/report", axum::routing::get(chat::retention::get_retention_report));
     let role_routes = Router::new()
         .route("/api/members", axum::routing::get(role_assignment::list_members))
         .route("/api/roles", axum::routing::get(role_crud::list_roles))
         .route("/api/roles", axum::routing::post(role_crud::create_role))
+        .route(
+            "/api/roles/reorder",
+            axum::routing::put(role_crud::reorder_roles),
+        )
         .route(
             "/api/roles/{id}",
             axum::routing::put(role_crud::update_role),
@@ -214,12 +236,71 @@ pub fn build_router(state: AppState) -> Router {
         .route("/api/moderation/kick", axum::routing::post(kick::kick_user))
         .route("/api/moderation/ban", axum::routing::post(ban::ban_user))
         .route("/api/moderation/unban", axum::routing::post(ban::unban_user))
-        .route("/api/moderation/bans", axum::routing::get(ban::list_bans));
+        .route("/api/moderation/bans", axum::routing::get(ban::list_bans))
+        .route("/api/moderation/warn", axum::routing::post(sanctions::warn_user))
+        .route("/api/moderation/mute", axum::routing::post(sanctions::mute_user))
+        .route(
+            "/api/moderation/sanctions/me",
+            axum::routing::get(sanctions::my_sanctions),
+        )
+        .route(
+            "/api/moderation/sanctions/{sanction_id}",
+            axum::routing::delete(sanctions::revoke_sanction),
+        )
+        .route(
+            "/api/moderation/users/{user_id}/record",
+            axum::routing::get(sanctions::get_user_record),
+        )
+        .route(
+            "/api/moderation/users/{user_id}/fast-track",
+            axum::routing::post(trust::fast_track),
+        )
+        .route(
+            "/api/moderation/probation/me",
+            axum::routing::get(trust::my_probation),
+        )
+        // Signed export of this server's bans (public, fetched by other servers)
+        .route(
+            "/api/moderation/ban-list",
+            axum::routing::get(ban_list::export_ban_list),
+        )
+        .route(
+            "/api/moderation/ban-lists",
+            axum::routing::get(ban_list::list_ban_list_sources).post(ban_list::import_ban_list),
+        )
+        .route(
+            "/api/moderation/ban-lists/{source_id}",
+            axum::routing::get(ban_list::get_ban_list_source)
+                .delete(ban_list::delete_ban_list_source),
+        )
+        .route(
+            "/api/moderation/ban-lists/{source_id}/entries/{fingerprint}",
+            axum::routing::put(ban_list::decide_ban_list_entry),
+        )
+        .route("/api/moderation/flags", axum::routing::get(abuse::list_flags))
+        .route(
+            "/api/moderation/flags/{flag_id}",
+            axum::routing::put(abuse::review_flag),
+        )
+        .route("/api/audit-log", axum::routing::get(audit::get_audit_log));
     let invite_routes = Router::new()
         .route("/api/invites", axum::routing::post(invite_gen::create_invite))
         .route("/api/invites", axum::routing::get(invite_gen::list_invites))
         .route("/api/invites/{code}", axum::routing::get(invite_gen::get_invite)
-            .delete(invite_gen::delete_invite));
+            .delete(invite_gen::delete_invite))
+        .route("/api/invites/{code}/uses", axum::routing::get(invite_tree::list_invite_uses))
+        .route(
+            "/api/members/{user_id}/invite-tree",
+            axum::routing::get(invite_tree::get_invite_tree),
+        )
+        .route(
+            "/api/members/{user_id}/invite-tree/review",
+            axum::routing::post(invite_tree::review_invite_tree),
+        )
+        .route(
+            "/api/members/{user_id}/invite-privileges",
+            axum::routing::put(invite_tree::set_invite_privileges),
+        );
     // Public invite landing page (no auth required)
     let invite_landing_routes = Router::new()
         .route("/invite/{code}", axum::routing::get(invite_landing::invite_landing_page));
@@ -242,6 +323,10 @@ pub fn build_router(state: AppState) -> Router {
             axum::routing::put(chat::messages::edit_message)
                 .delete(chat::messages::delete_message),
         )
+        .route(
+            "/api/channels/{channel_id}/messages/{message_id}/revisions",
+            axum::routing::get(chat::revisions::get_revisions),
+        )
         .route(
             "/api/channels/{channel_id}/last-read",
             axum::routing::put(chat::messages::update_last_read)
@@ -255,7 +340,29 @@ pub fn build_router(state: AppState) -> Router {
         .route(
             "/api/messages/{message_id}/reactions/{emoji}",
             axum::routing::delete(chat::reactions::remove_reaction),
-        );
+        )
+        .route(
+            "/api/channels/{channel_id}/threads",
+            axum::routing::get(chat::threads::list_threads).post(chat::threads::create_thread),
+        )
+        .route(
+            "/api/threads/{thread_id}",
+            axum::routing::put(chat::threads::update_thread),
+        )
+        .route(
+            "/api/threads/{thread_id}/messages",
+            axum::routing::get(chat::threads::get_thread_messages)
+                .post(chat::threads::create_thread_message),
+        )
+        .route(
+            "/api/channels/{channel_id}/pins",
+            axum::routing::get(chat::pins::list_pins),
+        )
+        .route(
+            "/api/channels/{channel_id}/pins/{message_id}",
+            axum::routing::put(chat::pins::pin_message).delete(chat::pins::unpin_message),
+        )
+        .route("/api/search", axum::routing::get(chat::search::search_messages));
 
     // Phase 5: DM routes (key exchange, conversations, messages, offline delivery)
     let dm_routes = Router::new()
@@ -299,9 +406,9 @@ pub fn build_router(state: AppState) -> Router {
             axum::routing::get(get_voice_participants),
         );
 
-    Router::new()
-        .merge(auth_routes)
-        .merge(public_identity_routes)
+    // Per-user token buckets ([rate_limits] in united.toml) for everything except the
+    // governor-limited auth/identity routes and the health check
+    let rate_limited_routes = Router::new()
         .merge(public_routes)
         .merge(authenticated_routes)
         .merge(admin_routes)
@@ -316,6 +423,15 @@ pub fn build_router(state: AppState) -> Router {
         .merge(block_storage_routes)
         .merge(voice_routes)
         .merge(ws_routes)
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit::enforce,
+        ));
+
+    Router::new()
+        .merge(auth_routes)
+        .merge(public_identity_routes)
+        .merge(rate_limited_routes)
         .merge(health)
         .layer(middleware::from_fn_with_state(
             state.clone(),
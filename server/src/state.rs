@@ -1,12 +1,17 @@
 use dashmap::DashMap;
+use ed25519_dalek::SigningKey;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 
 use crate::chat::presence::PresenceInfo;
-use crate::config::TurnConfig;
+use crate::config::{TrustConfig, TurnConfig};
 use crate::db::DbPool;
+use crate::moderation::abuse::AbuseMonitor;
+use crate::p2p::validation::GossipRejectionCounters;
 use crate::p2p::{PeerDirectory, SwarmCommand};
+use crate::rate_limit::RateLimiter;
 use crate::voice::state::VoiceState;
+use crate::ws::outbound::OutboundMetrics;
 use crate::ws::ConnectionRegistry;
 
 /// Challenge stored in memory with expiry
@@ -29,6 +34,8 @@ pub struct AppState {
     pub encryption_key: Vec<u8>,
     /// Active WebSocket connections per user
     pub connections: ConnectionRegistry,
+    /// Outbound WebSocket queue counters (dropped events, slow-consumer disconnects)
+    pub outbound_metrics: Arc<OutboundMetrics>,
     /// Server config
     pub registration_mode: String,
     /// Channel for sending commands to the libp2p Swarm event loop
@@ -37,6 +44,11 @@ pub struct AppState {
     pub peer_directory: Arc<PeerDirectory>,
     /// Server's libp2p PeerId as a string
     pub server_peer_id: String,
+    /// Ed25519 signing key of the server's libp2p identity (signs server-originated gossip
+    /// and the exported ban list)
+    pub server_signing_key: Arc<SigningKey>,
+    /// Per-reason counters of gossip messages rejected at ingestion
+    pub gossip_rejections: Arc<GossipRejectionCounters>,
     /// Configured libp2p port (for P2P info endpoint)
     pub libp2p_port: u16,
     /// In-memory presence tracking: user_pubkey -> PresenceInfo
@@ -53,4 +65,10 @@ pub struct AppState {
     pub voice_state: Arc<VoiceState>,
     /// TURN relay configuration for voice channel NAT traversal
     pub turn_config: Option<TurnConfig>,
+    /// New-member probation gates (None disables probation)
+    pub trust_config: Option<TrustConfig>,
+    /// Background behavioral analyzer that flags suspicious accounts for admin review
+    pub abuse: AbuseMonitor,
+    /// Per-user token buckets for REST routes and WebSocket requests
+    pub rate_limiter: Arc<RateLimiter>,
 }
@@ -1,13 +1,17 @@
 use axum::extract::ws::Message;
+use axum::http::StatusCode;
 use prost::Message as ProstMessage;
-use tokio::sync::mpsc;
 
+use crate::moderation::audit::{self, AuditAction, AuditRecord};
+use crate::moderation::sanctions::{active_mute, SanctionKind};
 use crate::proto::voice_proto;
 use crate::proto::ws::{envelope::Payload, Envelope};
+use crate::roles::permissions::{require_channel_permission, Permissions};
 use crate::state::AppState;
 use crate::voice::state::VoiceParticipantInfo;
 use crate::voice::turn;
 use crate::ws::broadcast::send_to_user;
+use crate::ws::ConnectionSender;
 
 /// Handle a VoiceJoinRequest: add user to voice channel, return participants + ICE servers,
 /// broadcast join event to existing participants.
@@ -16,27 +20,60 @@ use crate::ws::broadcast::send_to_user;
 pub async fn handle_voice_join(
     req: voice_proto::VoiceJoinRequest,
     request_id: &str,
-    tx: &mpsc::UnboundedSender<Message>,
+    tx: &ConnectionSender,
     state: &AppState,
     user_id: &str,
+    is_owner: bool,
 ) {
+    // Joining requires seeing the channel (channel overrides applied)
+    if let Err(status) = require_channel_permission(
+        &state.db,
+        user_id,
+        is_owner,
+        &req.channel_id,
+        Permissions::VIEW_CHANNEL,
+    )
+    .await
+    {
+        let message = if status == StatusCode::NOT_FOUND {
+            "Voice channel not found"
+        } else {
+            "Insufficient permissions"
+        };
+        send_error(tx, request_id, status.as_u16() as u32, message);
+        return;
+    }
+
     // Look up user's display_name and pubkey from DB
     let db = state.db.clone();
     let uid = user_id.to_string();
     let user_info = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().ok()?;
-        conn.query_row(
-            "SELECT display_name, lower(hex(public_key)) FROM users WHERE id = ?1",
-            rusqlite::params![uid],
-            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
-        )
-        .ok()
+        let conn = db.read().ok()?;
+        let (display_name, pubkey, fingerprint) = conn
+            .query_row(
+                "SELECT display_name, lower(hex(public_key)), fingerprint FROM users WHERE id = ?1",
+                rusqlite::params![uid],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                },
+            )
+            .ok()?;
+        // Voice-muted users join server-muted
+        let muted = active_mute(&conn, &fingerprint, SanctionKind::VoiceMute)
+            .ok()
+            .flatten()
+            .is_some();
+        Some((display_name, pubkey, muted))
     })
     .await
     .ok()
     .flatten();
 
-    let (display_name, pubkey) = match user_info {
+    let (display_name, pubkey, muted) = match user_info {
         Some(info) => info,
         None => {
             send_error(tx, request_id, 404, "User not found");
@@ -54,7 +91,7 @@ pub async fn handle_voice_join(
     let db = state.db.clone();
     let channel_id = req.channel_id.clone();
     let max_participants = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().ok()?;
+        let conn = db.read().ok()?;
         conn.query_row(
             "SELECT max_participants FROM channels WHERE id = ?1",
             rusqlite::params![channel_id],
@@ -71,7 +108,7 @@ pub async fn handle_voice_join(
         user_id: user_id.to_string(),
         display_name: display_name.clone(),
         pubkey: pubkey.clone(),
-        muted: false,
+        muted,
         deafened: false,
     };
 
@@ -106,6 +143,7 @@ pub async fn handle_voice_join(
     // Send VoiceJoinResponse to the joiner
     let response = Envelope {
         request_id: request_id.to_string(),
+        seq: 0,
         payload: Some(Payload::VoiceJoinResponse(voice_proto::VoiceJoinResponse {
             participants,
             ice_servers,
@@ -116,6 +154,7 @@ pub async fn handle_voice_join(
     // Broadcast VoiceParticipantJoinedEvent to existing participants
     let joined_event = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::VoiceParticipantJoinedEvent(
             voice_proto::VoiceParticipantJoinedEvent {
                 channel_id: req.channel_id.clone(),
@@ -123,7 +162,7 @@ pub async fn handle_voice_join(
                     user_id: user_id.to_string(),
                     display_name,
                     pubkey,
-                    muted: false,
+                    muted,
                     deafened: false,
                 }),
             },
@@ -136,18 +175,39 @@ pub async fn handle_voice_join(
 }
 
 /// Handle a VoiceLeaveRequest: remove user from voice channel, broadcast leave event.
+///
+/// A non-empty `user_id` naming someone else disconnects that participant instead,
+/// which requires MOVE_MEMBERS in the channel.
 pub async fn handle_voice_leave(
     req: voice_proto::VoiceLeaveRequest,
-    _request_id: &str,
-    _tx: &mpsc::UnboundedSender<Message>,
+    request_id: &str,
+    tx: &ConnectionSender,
     state: &AppState,
     user_id: &str,
+    is_owner: bool,
 ) {
+    let target_id = if req.user_id.is_empty() { user_id } else { req.user_id.as_str() };
+    let is_moderation = target_id != user_id;
+    if is_moderation
+        && !require_voice_moderation(
+            state,
+            tx,
+            request_id,
+            user_id,
+            is_owner,
+            &req.channel_id,
+            Permissions::MOVE_MEMBERS,
+        )
+        .await
+    {
+        return;
+    }
+
     // Look up display_name for the leave broadcast
     let db = state.db.clone();
-    let uid = user_id.to_string();
+    let uid = target_id.to_string();
     let display_name = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().ok()?;
+        let conn = db.read().ok()?;
         conn.query_row(
             "SELECT display_name FROM users WHERE id = ?1",
             rusqlite::params![uid],
@@ -160,20 +220,41 @@ pub async fn handle_voice_leave(
     .flatten()
     .unwrap_or_else(|| "Unknown".to_string());
 
-    state.voice_state.leave_channel(&req.channel_id, user_id);
-    broadcast_leave_event(state, &req.channel_id, user_id, &display_name);
+    state.voice_state.leave_channel(&req.channel_id, target_id);
+    broadcast_leave_event(state, &req.channel_id, target_id, &display_name);
+
+    // A disconnected participant is no longer in the channel, so tell them directly
+    if is_moderation {
+        audit_voice_moderation(
+            state,
+            AuditRecord::new(user_id, AuditAction::VoiceMove, target_id)
+                .before(serde_json::json!({ "channel_id": req.channel_id })),
+        )
+        .await;
+        let envelope = Envelope {
+            request_id: String::new(),
+            seq: 0,
+            payload: Some(Payload::VoiceLeaveEvent(voice_proto::VoiceLeaveEvent {
+                channel_id: req.channel_id,
+                user_id: target_id.to_string(),
+                display_name,
+            })),
+        };
+        send_to_user(&state.connections, target_id, &envelope);
+    }
 }
 
 /// Handle a VoiceSdpOffer: relay to target user with sender's user_id.
 pub async fn handle_voice_sdp_offer(
     req: voice_proto::VoiceSdpOffer,
     _request_id: &str,
-    _tx: &mpsc::UnboundedSender<Message>,
+    _tx: &ConnectionSender,
     state: &AppState,
     user_id: &str,
 ) {
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::VoiceSdpOffer(voice_proto::VoiceSdpOffer {
             target_user_id: req.target_user_id.clone(),
             sdp: req.sdp,
@@ -188,12 +269,13 @@ pub async fn handle_voice_sdp_offer(
 pub async fn handle_voice_sdp_answer(
     req: voice_proto::VoiceSdpAnswer,
     _request_id: &str,
-    _tx: &mpsc::UnboundedSender<Message>,
+    _tx: &ConnectionSender,
     state: &AppState,
     user_id: &str,
 ) {
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::VoiceSdpAnswer(voice_proto::VoiceSdpAnswer {
             target_user_id: req.target_user_id.clone(),
             sdp: req.sdp,
@@ -208,12 +290,13 @@ pub async fn handle_voice_sdp_answer(
 pub async fn handle_voice_ice_candidate(
     req: voice_proto::VoiceIceCandidate,
     _request_id: &str,
-    _tx: &mpsc::UnboundedSender<Message>,
+    _tx: &ConnectionSender,
     state: &AppState,
     user_id: &str,
 ) {
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::VoiceIceCandidate(voice_proto::VoiceIceCandidate {
             target_user_id: req.target_user_id.clone(),
             candidate_json: req.candidate_json,
@@ -225,25 +308,55 @@ pub async fn handle_voice_ice_candidate(
 }
 
 /// Handle a VoiceStateUpdate: update voice state and broadcast to all participants.
+///
+/// A non-empty `user_id` naming someone else server-mutes/deafens that participant,
+/// which requires MUTE_MEMBERS in the channel.
 pub async fn handle_voice_state_update(
     req: voice_proto::VoiceStateUpdate,
-    _request_id: &str,
-    _tx: &mpsc::UnboundedSender<Message>,
+    request_id: &str,
+    tx: &ConnectionSender,
     state: &AppState,
     user_id: &str,
+    is_owner: bool,
 ) {
+    let target_id = if req.user_id.is_empty() { user_id } else { req.user_id.as_str() };
+    let is_moderation = target_id != user_id;
+    if is_moderation
+        && !require_voice_moderation(
+            state,
+            tx,
+            request_id,
+            user_id,
+            is_owner,
+            &req.channel_id,
+            Permissions::MUTE_MEMBERS,
+        )
+        .await
+    {
+        return;
+    }
+
+    // Nobody can lift a voice mute sanction by unmuting, including moderators
+    if !req.muted {
+        if let Some(until) = voice_muted_until(state, target_id).await {
+            send_error(tx, request_id, 403, &format!("Voice muted until {}", until));
+            return;
+        }
+    }
+
     // Update in-memory state
     state
         .voice_state
-        .update_state(&req.channel_id, user_id, req.muted, req.deafened);
+        .update_state(&req.channel_id, target_id, req.muted, req.deafened);
 
-    // Broadcast to all participants in the channel
+    // Broadcast to all participants in the channel (including the target when moderated)
     let participants = state.voice_state.get_participants(&req.channel_id);
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::VoiceStateUpdate(voice_proto::VoiceStateUpdate {
-            channel_id: req.channel_id,
-            user_id: user_id.to_string(),
+            channel_id: req.channel_id.clone(),
+            user_id: target_id.to_string(),
             muted: req.muted,
             deafened: req.deafened,
         })),
@@ -254,19 +367,32 @@ pub async fn handle_voice_state_update(
             send_to_user(&state.connections, &p.user_id, &envelope);
         }
     }
+
+    if is_moderation {
+        audit_voice_moderation(
+            state,
+            AuditRecord::new(user_id, AuditAction::VoiceMute, target_id).after(serde_json::json!({
+                "channel_id": req.channel_id,
+                "muted": req.muted,
+                "deafened": req.deafened,
+            })),
+        )
+        .await;
+    }
 }
 
 /// Handle a VoiceSpeakingEvent: broadcast to all participants in the channel.
 pub async fn handle_voice_speaking(
     req: voice_proto::VoiceSpeakingEvent,
     _request_id: &str,
-    _tx: &mpsc::UnboundedSender<Message>,
+    _tx: &ConnectionSender,
     state: &AppState,
     user_id: &str,
 ) {
     let participants = state.voice_state.get_participants(&req.channel_id);
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::VoiceSpeakingEvent(
             voice_proto::VoiceSpeakingEvent {
                 channel_id: req.channel_id,
@@ -283,6 +409,86 @@ pub async fn handle_voice_speaking(
     }
 }
 
+/// Server-mute a user who just received a voice mute sanction, in whatever voice
+/// channel they are in, and tell the channel's participants (including the user).
+pub fn enforce_voice_mute(state: &AppState, user_id: &str) {
+    for (channel_id, deafened) in state.voice_state.force_mute(user_id) {
+        let envelope = Envelope {
+            request_id: String::new(),
+            seq: 0,
+            payload: Some(Payload::VoiceStateUpdate(voice_proto::VoiceStateUpdate {
+                channel_id: channel_id.clone(),
+                user_id: user_id.to_string(),
+                muted: true,
+                deafened,
+            })),
+        };
+        for p in state.voice_state.get_participants(&channel_id) {
+            send_to_user(&state.connections, &p.user_id, &envelope);
+        }
+    }
+}
+
+/// When the user's active voice mute sanction ends, or None if they are not voice muted.
+async fn voice_muted_until(state: &AppState, user_id: &str) -> Option<String> {
+    let db = state.db.clone();
+    let uid = user_id.to_string();
+    tokio::task::spawn_blocking(move || {
+        let conn = db.read().ok()?;
+        let fingerprint: String = conn
+            .query_row(
+                "SELECT fingerprint FROM users WHERE id = ?1",
+                rusqlite::params![uid],
+                |row| row.get(0),
+            )
+            .ok()?;
+        active_mute(&conn, &fingerprint, SanctionKind::VoiceMute)
+            .ok()
+            .flatten()
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// Check a channel-scoped voice moderation permission, replying with an error on failure.
+/// Returns true if the caller may proceed.
+async fn require_voice_moderation(
+    state: &AppState,
+    tx: &ConnectionSender,
+    request_id: &str,
+    user_id: &str,
+    is_owner: bool,
+    channel_id: &str,
+    required: Permissions,
+) -> bool {
+    match require_channel_permission(&state.db, user_id, is_owner, channel_id, required).await {
+        Ok(()) => true,
+        Err(status) => {
+            send_error(tx, request_id, status.as_u16() as u32, "Insufficient permissions");
+            false
+        }
+    }
+}
+
+/// Write a moderator's action on another participant to the audit log. The action has
+/// already taken effect, so a failed write is only logged.
+async fn audit_voice_moderation(state: &AppState, record: AuditRecord) {
+    let db = state.db.clone();
+    let entry = tokio::task::spawn_blocking(move || {
+        let conn = db.lock().ok()?;
+        audit::record(&conn, record)
+            .inspect_err(|e| tracing::warn!("Voice moderation audit failed: {}", e))
+            .ok()
+    })
+    .await
+    .ok()
+    .flatten();
+    if let Some(entry) = entry {
+        audit::notify_admins(state, entry);
+    }
+}
+
 /// Broadcast a VoiceLeaveEvent to remaining participants in a channel.
 pub fn broadcast_leave_event(
     state: &AppState,
@@ -293,6 +499,7 @@ pub fn broadcast_leave_event(
     let participants = state.voice_state.get_participants(channel_id);
     let envelope = Envelope {
         request_id: String::new(),
+        seq: 0,
         payload: Some(Payload::VoiceLeaveEvent(voice_proto::VoiceLeaveEvent {
             channel_id: channel_id.to_string(),
             user_id: user_id.to_string(),
@@ -306,7 +513,7 @@ pub fn broadcast_leave_event(
 }
 
 /// Encode and send an Envelope as a binary WebSocket message.
-fn send_envelope(tx: &mpsc::UnboundedSender<Message>, envelope: &Envelope) {
+fn send_envelope(tx: &ConnectionSender, envelope: &Envelope) {
     let mut buf = Vec::with_capacity(envelope.encoded_len());
     if envelope.encode(&mut buf).is_ok() {
         let _ = tx.send(Message::Binary(buf.into()));
@@ -314,13 +521,15 @@ fn send_envelope(tx: &mpsc::UnboundedSender<Message>, envelope: &Envelope) {
 }
 
 /// Send an error response envelope.
-fn send_error(tx: &mpsc::UnboundedSender<Message>, request_id: &str, code: u32, message: &str) {
+fn send_error(tx: &ConnectionSender, request_id: &str, code: u32, message: &str) {
     let envelope = Envelope {
         request_id: request_id.to_string(),
+        seq: 0,
         payload: Some(Payload::Error(crate::proto::ws::ErrorResponse {
             code,
             message: message.to_string(),
             request_id: request_id.to_string(),
+            retry_after_ms: 0,
         })),
     };
     send_envelope(tx, &envelope);
@@ -52,6 +52,12 @@ pub struct VoiceState {
 
 const SOFT_CAP: usize = 8;
 
+impl Default for VoiceState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl VoiceState {
     pub fn new() -> Self {
         Self {
@@ -116,6 +122,14 @@ impl VoiceState {
             .unwrap_or_default()
     }
 
+    /// Every occupied voice channel and its participants.
+    pub fn snapshot(&self) -> Vec<(String, Vec<VoiceParticipantInfo>)> {
+        self.channels
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().participants.clone()))
+            .collect()
+    }
+
     /// Update a participant's muted/deafened state.
     pub fn update_state(&self, channel_id: &str, user_id: &str, muted: bool, deafened: bool) {
         if let Some(mut entry) = self.channels.get_mut(channel_id) {
@@ -129,6 +143,23 @@ impl VoiceState {
         }
     }
 
+    /// Mute a user in every voice channel they are in.
+    ///
+    /// Returns (channel_id, deafened) for each channel where they were unmuted before.
+    pub fn force_mute(&self, user_id: &str) -> Vec<(String, bool)> {
+        let mut changed = Vec::new();
+        for mut entry in self.channels.iter_mut() {
+            let channel_id = entry.key().clone();
+            for p in entry.value_mut().participants.iter_mut() {
+                if p.user_id == user_id && !p.muted {
+                    p.muted = true;
+                    changed.push((channel_id.clone(), p.deafened));
+                }
+            }
+        }
+        changed
+    }
+
     /// Remove a user from all voice channels they are in.
     ///
     /// Returns the list of channel_ids the user was in (for broadcasting leave events).
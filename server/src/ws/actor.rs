@@ -1,15 +1,21 @@
 use axum::extract::ws::{CloseFrame, Message, WebSocket};
 use futures_util::{SinkExt, StreamExt};
 use prost::Message as ProstMessage;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::time::{interval, timeout};
 
+use crate::auth::middleware::Claims;
 use crate::chat::presence::{self, PresenceStatus};
-use crate::proto::ws::Envelope;
+use crate::proto::ws::{envelope::Payload, Envelope, ResumedEvent};
 use crate::state::AppState;
 use crate::ws::protocol;
-use crate::ws::ConnectionSender;
+use crate::ws::ready;
+use crate::ws::scope;
+use crate::ws::session::Session;
+use crate::ws::outbound::{self, ConnectionReceiver, Outbound, OUTBOUND_QUEUE_LEN};
+use crate::ws::{Connection, ConnectionSender};
 
 /// Ping interval: server sends WebSocket ping every 30 seconds.
 /// Per Pitfall 5: prevents connection leaks from abrupt disconnects.
@@ -18,32 +24,72 @@ const PING_INTERVAL: Duration = Duration::from_secs(30);
 /// Pong timeout: if pong not received within 10 seconds after ping, close.
 const PONG_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// A reconnecting client's request to resume a session.
+#[derive(Debug, Clone)]
+pub struct Resume {
+    pub session_id: String,
+    /// Seq of the last event the client received
+    pub last_seq: u64,
+}
+
 /// Run the actor-per-connection pattern for an authenticated WebSocket.
 ///
 /// Splits the WebSocket into reader and writer halves:
-/// - Writer task: owns the sink, forwards messages from an mpsc channel
+/// - Writer task: owns the sink, forwards messages from the bounded outbound queue
 /// - Reader task: processes incoming messages, dispatches to protocol handlers
 ///
-/// The mpsc channel allows any part of the system to send messages to this client
-/// by cloning the sender.
+/// The outbound queue allows any part of the system to send messages to this client
+/// by cloning the sender. The connection's JWT claims are kept for the lifetime of
+/// the actor so request envelopes go through the same permission checks as REST.
 pub async fn run_connection(
     socket: WebSocket,
     state: AppState,
-    user_id: String,
-    fingerprint: String,
+    claims: Claims,
+    resume: Option<Resume>,
 ) {
+    let user_id = claims.sub.clone();
+    let fingerprint = claims.fingerprint.clone();
     let (ws_sender, mut ws_receiver) = socket.split();
-    let (tx, rx) = mpsc::unbounded_channel::<Message>();
+    let (tx, rx) = outbound::channel(OUTBOUND_QUEUE_LEN, state.outbound_metrics.clone());
 
-    // Register this connection in the connection registry
-    register_connection(&state, &user_id, tx.clone());
+    // Spawn writer task first: it drains the bounded queue while the snapshot or replay
+    // below is queued
+    let mut writer_handle = tokio::spawn(writer_task(ws_sender, rx));
+
+    // Resume the client's previous session if it is still buffering, otherwise start
+    // a new one with a ReadyEvent snapshot
+    let resumed = match &resume {
+        Some(resume) => resume_session(&state, &user_id, resume, &tx),
+        None => None,
+    };
+    let is_new_session = resumed.is_none();
+    let connection = match resumed {
+        Some(connection) => connection,
+        None => {
+            let connection = Connection {
+                scope: scope::connection_scope(&state, &user_id, claims.is_owner).await,
+                session: Arc::new(Session::new()),
+            };
+            register_connection(&state, &user_id, connection.clone());
+            let ready = ready::ready_event(
+                &state,
+                &claims,
+                &connection.scope,
+                &connection.session.id,
+                resume.is_some(),
+            )
+            .await;
+            connection.session.start(tx.clone(), &ready);
+            connection
+        }
+    };
 
     // Look up user's pubkey and display_name for presence broadcast
     let (user_pubkey, display_name) = {
         let db = state.db.clone();
         let uid = user_id.clone();
         tokio::task::spawn_blocking(move || {
-            let conn = db.lock().ok()?;
+            let conn = db.read().ok()?;
             conn.query_row(
                 "SELECT lower(hex(public_key)), display_name FROM users WHERE id = ?1",
                 rusqlite::params![uid],
@@ -60,8 +106,9 @@ pub async fn run_connection(
     // Broadcast ONLINE presence to all clients
     presence::set_user_presence(&state, &user_pubkey, &display_name, PresenceStatus::Online);
 
-    // Send the current presence snapshot to the newly connected client
-    {
+    // Send the current presence snapshot to the newly connected client (a resumed
+    // session already received the updates it missed)
+    if is_new_session {
         let all_presence = presence::get_all_presence(&state);
         for info in &all_presence {
             let update = crate::proto::presence::PresenceUpdateEvent {
@@ -74,11 +121,12 @@ pub async fn run_connection(
             };
             let envelope = Envelope {
                 request_id: String::new(),
+                seq: 0,
                 payload: Some(crate::proto::ws::envelope::Payload::PresenceUpdateEvent(update)),
             };
             let mut buf = Vec::with_capacity(envelope.encoded_len());
             if envelope.encode(&mut buf).is_ok() {
-                let _ = tx.send(Message::Binary(buf.into()));
+                let _ = tx.send_droppable(Message::Binary(buf.into()));
             }
         }
     }
@@ -89,9 +137,6 @@ pub async fn run_connection(
         "WebSocket actor started"
     );
 
-    // Spawn writer task: forwards mpsc messages to WebSocket sink
-    let writer_handle = tokio::spawn(writer_task(ws_sender, rx));
-
     // Track pong reception
     let (pong_tx, mut pong_rx) = mpsc::unbounded_channel::<()>();
 
@@ -131,11 +176,22 @@ pub async fn run_connection(
 
     // Reader loop: process incoming WebSocket messages
     loop {
-        match ws_receiver.next().await {
+        let next = tokio::select! {
+            next = ws_receiver.next() => next,
+            // The writer stopped: the socket broke or the client fell too far behind
+            _ = &mut writer_handle => break,
+        };
+        match next {
             Some(Ok(msg)) => match msg {
                 Message::Binary(data) => {
                     // Decode protobuf envelope and dispatch
-                    protocol::handle_binary_message(&data, &tx, &state, &user_id).await;
+                    protocol::handle_binary_message(
+                        &data,
+                        &tx,
+                        &connection.scope,
+                        &state,
+                        &claims,
+                    ).await;
                 }
                 Message::Text(text) => {
                     // We use binary protobuf, but handle text gracefully
@@ -182,8 +238,8 @@ pub async fn run_connection(
     writer_handle.abort();
     ping_handle.abort();
 
-    // Remove this connection from the registry
-    unregister_connection(&state, &user_id, &tx);
+    // Keep the session registered (and buffering) so the client can resume it
+    unregister_connection(&state, &user_id, &connection, &tx);
 
     // Voice disconnect cleanup: remove user from all voice channels and broadcast leave events
     let voice_left_channels = state.voice_state.leave_all_channels(&user_id);
@@ -202,7 +258,7 @@ pub async fn run_connection(
     let has_remaining = state
         .connections
         .get(&user_id)
-        .map(|v| !v.is_empty())
+        .map(|v| v.iter().any(|c| c.session.is_attached()))
         .unwrap_or(false);
 
     if !has_remaining {
@@ -216,12 +272,21 @@ pub async fn run_connection(
     );
 }
 
-/// Writer task: receives messages from mpsc channel and forwards them to the WebSocket sink.
+/// Writer task: drains the connection's outbound queue into the WebSocket sink. Stops
+/// after sending the slow-consumer close frame if the queue overflows.
 async fn writer_task(
     mut ws_sender: futures_util::stream::SplitSink<WebSocket, Message>,
-    mut rx: mpsc::UnboundedReceiver<Message>,
+    mut rx: ConnectionReceiver,
 ) {
-    while let Some(msg) = rx.recv().await {
+    while let Some(outbound) = rx.recv().await {
+        let msg = match outbound {
+            Outbound::Message(msg) => msg,
+            Outbound::Close(frame) => {
+                tracing::warn!(reason = %frame.reason, "Outbound queue overflowed, closing connection");
+                let _ = ws_sender.send(Message::Close(Some(frame))).await;
+                break;
+            }
+        };
         if ws_sender.send(msg).await.is_err() {
             // WebSocket send failed — connection is broken
             break;
@@ -230,12 +295,12 @@ async fn writer_task(
 }
 
 /// Register a connection sender in the connection registry.
-fn register_connection(state: &AppState, user_id: &str, tx: ConnectionSender) {
+fn register_connection(state: &AppState, user_id: &str, connection: Connection) {
     state
         .connections
         .entry(user_id.to_string())
         .or_default()
-        .push(tx);
+        .push(connection);
 
     let conn_count = state
         .connections
@@ -249,15 +314,20 @@ fn register_connection(state: &AppState, user_id: &str, tx: ConnectionSender) {
     );
 }
 
-/// Remove closed connections from the registry for a user.
-/// After the reader loop exits, the tx sender is dropped, so any
-/// corresponding receivers are closed. We remove senders that are closed.
-fn unregister_connection(state: &AppState, user_id: &str, _tx: &ConnectionSender) {
-    let mut remove_user = false;
+/// Detach this socket from its session. The session stays in the registry until it
+/// expires; expired sessions of the user are dropped here and by the cleanup task.
+fn unregister_connection(
+    state: &AppState,
+    user_id: &str,
+    connection: &Connection,
+    tx: &ConnectionSender,
+) {
+    connection.session.detach(tx);
 
+    let now = Instant::now();
+    let mut remove_user = false;
     if let Some(mut connections) = state.connections.get_mut(user_id) {
-        // Remove senders that are closed (the receiver has been dropped)
-        connections.retain(|sender| !sender.is_closed());
+        connections.retain(|c| !c.session.is_expired(now));
         if connections.is_empty() {
             remove_user = true;
         }
@@ -269,6 +339,55 @@ fn unregister_connection(state: &AppState, user_id: &str, _tx: &ConnectionSender
 
     tracing::debug!(
         user_id = %user_id,
-        "Connection unregistered"
+        session_id = %connection.session.id,
+        "Connection detached"
+    );
+}
+
+/// Attach `tx` to the user's session named in `resume`. Returns None if there is no
+/// such session or it can no longer replay everything after `last_seq`; a session
+/// that failed to resume is dropped.
+fn resume_session(
+    state: &AppState,
+    user_id: &str,
+    resume: &Resume,
+    tx: &ConnectionSender,
+) -> Option<Connection> {
+    let connection = state
+        .connections
+        .get(user_id)?
+        .iter()
+        .find(|c| c.session.id == resume.session_id)
+        .cloned()?;
+
+    let session_id = connection.session.id.clone();
+    let resumed = connection
+        .session
+        .resume(tx.clone(), resume.last_seq, |replayed| Envelope {
+            request_id: String::new(),
+            seq: 0,
+            payload: Some(Payload::ResumedEvent(ResumedEvent {
+                session_id,
+                replayed,
+            })),
+        });
+    if resumed {
+        tracing::debug!(
+            user_id = %user_id,
+            session_id = %resume.session_id,
+            "Session resumed"
+        );
+        return Some(connection);
+    }
+
+    if let Some(mut connections) = state.connections.get_mut(user_id) {
+        connections.retain(|c| !Arc::ptr_eq(&c.session, &connection.session));
+    }
+    tracing::debug!(
+        user_id = %user_id,
+        session_id = %resume.session_id,
+        last_seq = resume.last_seq,
+        "Session resume failed"
     );
+    None
 }
@@ -1,53 +1,105 @@
 use prost::Message as ProstMessage;
 
-use crate::proto::ws::Envelope;
-use super::ConnectionRegistry;
+use crate::proto::ws::{envelope::Payload, Envelope};
+use super::outbound::Delivery;
+use super::scope::EventScope;
+use super::{Connection, ConnectionRegistry};
 
-/// Broadcast a protobuf envelope to all connected users.
-pub fn broadcast_to_all(registry: &ConnectionRegistry, envelope: &Envelope) {
+/// Send a protobuf envelope to every connection in `scope`, numbered and buffered for
+/// replay by each connection's session.
+pub fn publish(registry: &ConnectionRegistry, scope: EventScope<'_>, envelope: &Envelope) {
     let mut buf = Vec::with_capacity(envelope.encoded_len());
     if envelope.encode(&mut buf).is_err() {
         return;
     }
-    let msg = axum::extract::ws::Message::Binary(buf.into());
+    let delivery = delivery_of(envelope);
 
-    for entry in registry.iter() {
-        for sender in entry.value().iter() {
-            let _ = sender.send(msg.clone());
+    // A user's connections can be found directly
+    if let EventScope::User(user_id) = scope {
+        if let Some(connections) = registry.get(user_id) {
+            for connection in connections.value().iter() {
+                connection.session.deliver(&buf, delivery);
+            }
         }
+        return;
     }
+
+    deliver_where(registry, &buf, delivery, |user_id, connection| {
+        connection
+            .scope
+            .read()
+            .map(|s| s.receives(user_id, scope))
+            .unwrap_or(false)
+    });
 }
 
-/// Send a protobuf envelope to a specific user (all their connections).
-pub fn send_to_user(registry: &ConnectionRegistry, user_id: &str, envelope: &Envelope) {
+/// Send a protobuf envelope to every connection `filter` accepts.
+pub fn publish_where(
+    registry: &ConnectionRegistry,
+    envelope: &Envelope,
+    filter: impl Fn(&str, &Connection) -> bool,
+) {
     let mut buf = Vec::with_capacity(envelope.encoded_len());
     if envelope.encode(&mut buf).is_err() {
         return;
     }
-    let msg = axum::extract::ws::Message::Binary(buf.into());
+    deliver_where(registry, &buf, delivery_of(envelope), filter);
+}
 
-    if let Some(connections) = registry.get(user_id) {
-        for sender in connections.value().iter() {
-            let _ = sender.send(msg.clone());
+fn deliver_where(
+    registry: &ConnectionRegistry,
+    buf: &[u8],
+    delivery: Delivery,
+    filter: impl Fn(&str, &Connection) -> bool,
+) {
+    for entry in registry.iter() {
+        for connection in entry.value().iter() {
+            if filter(entry.key(), connection) {
+                connection.session.deliver(buf, delivery);
+            }
         }
     }
 }
 
+/// Typing indicators and presence updates are shed first when a client falls behind;
+/// the next update supersedes a lost one.
+fn delivery_of(envelope: &Envelope) -> Delivery {
+    match envelope.payload {
+        Some(Payload::TypingEvent(_)) | Some(Payload::PresenceUpdateEvent(_)) => {
+            Delivery::Droppable
+        }
+        _ => Delivery::Required,
+    }
+}
+
+/// Broadcast a protobuf envelope to all connected users.
+pub fn broadcast_to_all(registry: &ConnectionRegistry, envelope: &Envelope) {
+    publish(registry, EventScope::Server, envelope);
+}
+
+/// Send a protobuf envelope to a specific user (all their connections).
+pub fn send_to_user(registry: &ConnectionRegistry, user_id: &str, envelope: &Envelope) {
+    publish(registry, EventScope::User(user_id), envelope);
+}
+
 /// Force-close all connections for a user (kick/ban).
-/// Sends a WebSocket Close frame with the given code and reason.
+/// Sends a WebSocket Close frame with the given code and reason, and drops the user's
+/// sessions so the closed sockets cannot be resumed.
 pub fn force_close_user(
     registry: &ConnectionRegistry,
     user_id: &str,
     close_code: u16,
     reason: &str,
 ) {
-    if let Some(connections) = registry.get(user_id) {
+    if let Some((_, connections)) = registry.remove(user_id) {
         let close_frame = axum::extract::ws::CloseFrame {
             code: close_code,
             reason: reason.into(),
         };
-        for sender in connections.value().iter() {
-            let _ = sender.send(axum::extract::ws::Message::Close(Some(close_frame.clone())));
+        for connection in connections.iter() {
+            connection
+                .session
+                .send(axum::extract::ws::Message::Close(Some(close_frame.clone())));
         }
     }
 }
@@ -8,26 +8,31 @@ use axum::{
 use serde::Deserialize;
 
 use crate::auth::jwt;
+use crate::auth::middleware::Claims;
 use crate::moderation::ban::check_ban;
 use crate::state::AppState;
 use crate::ws::actor;
 
 /// Query parameters for WebSocket connection.
-/// Auth is via query param ?token=JWT per CONTEXT.md decision.
+/// Auth is via query param ?token=JWT per CONTEXT.md decision. A reconnecting client
+/// adds `session` and `last_seq` to resume its previous session.
 #[derive(Debug, Deserialize)]
 pub struct WsAuthQuery {
     pub token: String,
+    pub session: Option<String>,
+    pub last_seq: Option<u64>,
 }
 
 /// WebSocket close codes per CONTEXT.md:
 /// 4001 = token expired
 /// 4002 = token invalid
 /// 4003 = banned
+/// (4004 = kicked, 4005 = slow consumer, see `outbound::CLOSE_SLOW_CONSUMER`)
 const CLOSE_TOKEN_EXPIRED: u16 = 4001;
 const CLOSE_TOKEN_INVALID: u16 = 4002;
 const CLOSE_BANNED: u16 = 4003;
 
-/// GET /ws?token=JWT
+/// GET /ws?token=JWT[&session=ID&last_seq=N]
 /// WebSocket upgrade endpoint. Authenticates via query parameter.
 /// On auth failure, upgrades then immediately closes with appropriate close code.
 /// On success, spawns an actor for the connection.
@@ -45,7 +50,7 @@ pub async fn ws_upgrade(
             let fingerprint_for_check = claims.fingerprint.clone();
             let db = state.db.clone();
             let ban_reason = tokio::task::spawn_blocking(move || {
-                let conn = db.lock().ok()?;
+                let conn = db.read().ok()?;
                 check_ban(&conn, &fingerprint_for_check)
             })
             .await
@@ -76,9 +81,13 @@ pub async fn ws_upgrade(
                 fingerprint = %claims.fingerprint,
                 "WebSocket connection authenticated"
             );
-            ws.on_upgrade(move |socket| {
-                handle_authenticated(socket, state, claims.sub, claims.fingerprint)
-            })
+            let resume = params
+                .session
+                .map(|session_id| actor::Resume {
+                    session_id,
+                    last_seq: params.last_seq.unwrap_or(0),
+                });
+            ws.on_upgrade(move |socket| handle_authenticated(socket, state, claims, resume))
         }
         Err(err) => {
             // Determine close code based on error type
@@ -112,8 +121,8 @@ pub async fn ws_upgrade(
 async fn handle_authenticated(
     socket: WebSocket,
     state: AppState,
-    user_id: String,
-    fingerprint: String,
+    claims: Claims,
+    resume: Option<actor::Resume>,
 ) {
-    actor::run_connection(socket, state, user_id, fingerprint).await;
+    actor::run_connection(socket, state, claims, resume).await;
 }
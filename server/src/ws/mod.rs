@@ -1,20 +1,30 @@
 pub mod actor;
 pub mod broadcast;
 pub mod handler;
+pub mod outbound;
 pub mod protocol;
+pub mod ready;
+pub mod requests;
+pub mod scope;
+pub mod session;
 
 use dashmap::DashMap;
 use std::sync::Arc;
-use tokio::sync::mpsc;
 
-/// Type alias for the sender half of a WebSocket connection's channel.
-/// Other parts of the system can clone this to push messages to a specific client.
-pub type ConnectionSender = mpsc::UnboundedSender<axum::extract::ws::Message>;
+pub use outbound::ConnectionSender;
+
+/// A WebSocket session: which events it receives and its (resumable) outbound stream.
+/// Stays registered for a while after its socket drops so the client can resume it.
+#[derive(Clone)]
+pub struct Connection {
+    pub scope: scope::SharedScope,
+    pub session: Arc<session::Session>,
+}
 
 /// Connection registry: tracks all active WebSocket connections per user.
 /// A user can have multiple concurrent connections (multiple devices/tabs).
-/// Arc<DashMap<UserId, Vec<ConnectionSender>>>
-pub type ConnectionRegistry = Arc<DashMap<String, Vec<ConnectionSender>>>;
+/// Arc<DashMap<UserId, Vec<Connection>>>
+pub type ConnectionRegistry = Arc<DashMap<String, Vec<Connection>>>;
 
 /// Create a new empty connection registry.
 pub fn new_connection_registry() -> ConnectionRegistry {
@@ -0,0 +1,326 @@
+//! Bounded outbound queues for WebSocket connections.
+//!
+//! Everything sent to a client goes through its connection's queue, drained by the
+//! connection's writer task. A client that stops reading fills its queue; the queue never
+//! grows past its capacity:
+//!
+//! 1. Droppable events (typing indicators and presence updates) are discarded first: a
+//!    new droppable event is not queued, and a new regular message evicts the oldest
+//!    queued droppable event.
+//! 2. If the queue is full of regular messages, the connection is closed with
+//!    `CLOSE_SLOW_CONSUMER`. The close reason names the session so the client can resume
+//!    it and get the missed events from the replay buffer.
+//!
+//! Dropped events keep their sequence numbers, so a client sees a gap in `Envelope.seq`.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use axum::extract::ws::{CloseFrame, Message};
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use tokio::sync::Notify;
+
+use crate::auth::middleware::Claims;
+use crate::roles::permissions::{require_permission, Permissions};
+use crate::state::AppState;
+use crate::ws::session::REPLAY_BUFFER_LEN;
+
+/// Messages queued per connection. Larger than the replay buffer so a resume, which
+/// replays the whole buffer at once, always fits.
+pub const OUTBOUND_QUEUE_LEN: usize = REPLAY_BUFFER_LEN + 24;
+
+/// Close code sent to a client whose queue overflowed.
+pub const CLOSE_SLOW_CONSUMER: u16 = 4005;
+
+/// Whether a message may be discarded when the client falls behind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Required,
+    Droppable,
+}
+
+/// The queue is closed (the writer is gone or the connection overflowed).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueClosed;
+
+/// Server-wide outbound queue counters.
+#[derive(Debug, Default)]
+pub struct OutboundMetrics {
+    dropped_events: AtomicU64,
+    slow_consumer_disconnects: AtomicU64,
+    peak_depth: AtomicUsize,
+}
+
+impl OutboundMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Droppable events discarded because a client fell behind.
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped_events.load(Ordering::Relaxed)
+    }
+
+    /// Connections closed because their queue overflowed.
+    pub fn slow_consumer_disconnects(&self) -> u64 {
+        self.slow_consumer_disconnects.load(Ordering::Relaxed)
+    }
+
+    /// Deepest any queue has been since startup.
+    pub fn peak_depth(&self) -> usize {
+        self.peak_depth.load(Ordering::Relaxed)
+    }
+}
+
+struct QueueState {
+    items: VecDeque<(Message, Delivery)>,
+    /// Receiver dropped
+    closed: bool,
+    /// Set when a required message did not fit; the writer closes the connection
+    overflowed: bool,
+    /// Session named in the slow-consumer close reason
+    resume_session: Option<String>,
+}
+
+struct Queue {
+    capacity: usize,
+    state: Mutex<QueueState>,
+    notify: Notify,
+    metrics: Arc<OutboundMetrics>,
+}
+
+/// Sending half of a connection's outbound queue. Other parts of the system clone this
+/// to push messages to a specific client.
+#[derive(Clone)]
+pub struct ConnectionSender {
+    queue: Arc<Queue>,
+}
+
+/// Receiving half, owned by the connection's writer task.
+pub struct ConnectionReceiver {
+    queue: Arc<Queue>,
+}
+
+/// What the writer task should do next.
+#[derive(Debug)]
+pub enum Outbound {
+    Message(Message),
+    /// The queue overflowed: send this close frame and stop.
+    Close(CloseFrame),
+}
+
+/// Create a connection queue holding at most `capacity` messages.
+pub fn channel(
+    capacity: usize,
+    metrics: Arc<OutboundMetrics>,
+) -> (ConnectionSender, ConnectionReceiver) {
+    let queue = Arc::new(Queue {
+        capacity,
+        state: Mutex::new(QueueState {
+            items: VecDeque::new(),
+            closed: false,
+            overflowed: false,
+            resume_session: None,
+        }),
+        notify: Notify::new(),
+        metrics,
+    });
+    (
+        ConnectionSender {
+            queue: queue.clone(),
+        },
+        ConnectionReceiver { queue },
+    )
+}
+
+impl ConnectionSender {
+    /// Queue a message that must be delivered. If the queue is full and holds no
+    /// droppable events, the connection is marked for a slow-consumer close.
+    pub fn send(&self, msg: Message) -> Result<(), QueueClosed> {
+        self.push(msg, Delivery::Required)
+    }
+
+    /// Queue a message, dropping it rather than growing the queue if the client is behind.
+    pub fn send_droppable(&self, msg: Message) -> Result<(), QueueClosed> {
+        self.push(msg, Delivery::Droppable)
+    }
+
+    /// Queue a message with the given delivery class.
+    pub fn push(&self, msg: Message, delivery: Delivery) -> Result<(), QueueClosed> {
+        let queue = &self.queue;
+        let mut state = queue.state.lock().map_err(|_| QueueClosed)?;
+        if state.closed || state.overflowed {
+            return Err(QueueClosed);
+        }
+
+        if state.items.len() >= queue.capacity {
+            if delivery == Delivery::Droppable {
+                queue.metrics.dropped_events.fetch_add(1, Ordering::Relaxed);
+                return Ok(());
+            }
+            let droppable = state
+                .items
+                .iter()
+                .position(|(_, d)| *d == Delivery::Droppable);
+            match droppable {
+                Some(index) => {
+                    state.items.remove(index);
+                    queue.metrics.dropped_events.fetch_add(1, Ordering::Relaxed);
+                }
+                None => {
+                    // Nothing left to shed: free the backlog and have the writer close
+                    state.items.clear();
+                    state.overflowed = true;
+                    queue
+                        .metrics
+                        .slow_consumer_disconnects
+                        .fetch_add(1, Ordering::Relaxed);
+                    drop(state);
+                    queue.notify.notify_one();
+                    return Err(QueueClosed);
+                }
+            }
+        }
+
+        state.items.push_back((msg, delivery));
+        queue
+            .metrics
+            .peak_depth
+            .fetch_max(state.items.len(), Ordering::Relaxed);
+        drop(state);
+        queue.notify.notify_one();
+        Ok(())
+    }
+
+    /// Name the session in the close reason if this connection overflows.
+    pub fn set_resume_session(&self, session_id: &str) {
+        if let Ok(mut state) = self.queue.state.lock() {
+            state.resume_session = Some(session_id.to_string());
+        }
+    }
+
+    /// Messages currently queued.
+    pub fn len(&self) -> usize {
+        self.queue
+            .state
+            .lock()
+            .map(|state| state.items.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the writer is gone or the connection overflowed.
+    pub fn is_closed(&self) -> bool {
+        self.queue
+            .state
+            .lock()
+            .map(|state| state.closed || state.overflowed)
+            .unwrap_or(true)
+    }
+
+    /// Whether both senders feed the same connection.
+    pub fn same_channel(&self, other: &ConnectionSender) -> bool {
+        Arc::ptr_eq(&self.queue, &other.queue)
+    }
+}
+
+impl ConnectionReceiver {
+    /// Wait for the next thing to write. Returns None after the slow-consumer close.
+    pub async fn recv(&mut self) -> Option<Outbound> {
+        loop {
+            {
+                let mut state = self.queue.state.lock().ok()?;
+                if state.closed {
+                    return None;
+                }
+                if state.overflowed {
+                    state.closed = true;
+                    let reason = match &state.resume_session {
+                        Some(session_id) => format!("Slow consumer; resume session {}", session_id),
+                        None => "Slow consumer".to_string(),
+                    };
+                    return Some(Outbound::Close(CloseFrame {
+                        code: CLOSE_SLOW_CONSUMER,
+                        reason: reason.into(),
+                    }));
+                }
+                if let Some((msg, _)) = state.items.pop_front() {
+                    return Some(Outbound::Message(msg));
+                }
+            }
+            // Single consumer: notify_one stores a permit if a push lands before we wait
+            self.queue.notify.notified().await;
+        }
+    }
+}
+
+impl Drop for ConnectionReceiver {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.queue.state.lock() {
+            state.closed = true;
+            state.items.clear();
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct OutboundMetricsResponse {
+    /// Sessions with a socket attached
+    pub connections: usize,
+    /// Sessions waiting to be resumed
+    pub detached_sessions: usize,
+    pub queue_capacity: usize,
+    /// Messages queued across all connections
+    pub queue_depth_total: usize,
+    /// Deepest queue right now
+    pub queue_depth_max: usize,
+    /// Deepest queue since startup
+    pub queue_depth_peak: usize,
+    pub dropped_events: u64,
+    pub slow_consumer_disconnects: u64,
+}
+
+/// GET /api/ws/metrics
+/// Outbound queue depth and slow-consumer counters. Requires ADMIN.
+pub async fn get_outbound_metrics(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<Json<OutboundMetricsResponse>, (StatusCode, String)> {
+    require_permission(&state.db, &claims.sub, claims.is_owner, Permissions::ADMIN)
+        .await
+        .map_err(|s| (s, "Insufficient permissions".to_string()))?;
+
+    let mut connections = 0;
+    let mut detached_sessions = 0;
+    let mut queue_depth_total = 0;
+    let mut queue_depth_max = 0;
+    for entry in state.connections.iter() {
+        for connection in entry.value().iter() {
+            match connection.session.queue_depth() {
+                Some(depth) => {
+                    connections += 1;
+                    queue_depth_total += depth;
+                    queue_depth_max = queue_depth_max.max(depth);
+                }
+                None => detached_sessions += 1,
+            }
+        }
+    }
+
+    let metrics = &state.outbound_metrics;
+    Ok(Json(OutboundMetricsResponse {
+        connections,
+        detached_sessions,
+        queue_capacity: OUTBOUND_QUEUE_LEN,
+        queue_depth_total,
+        queue_depth_max,
+        queue_depth_peak: metrics.peak_depth(),
+        dropped_events: metrics.dropped_events(),
+        slow_consumer_disconnects: metrics.slow_consumer_disconnects(),
+    }))
+}
@@ -1,23 +1,33 @@
 use axum::extract::ws::Message;
 use libp2p::PeerId;
 use prost::Message as ProstMessage;
-use tokio::sync::mpsc;
 
+use crate::auth::middleware::Claims;
+use crate::p2p::messages::{extract_channel_id, extract_thread_id};
 use crate::proto::p2p_proto;
 use crate::proto::ws::{
-    envelope::Payload, ErrorResponse, Envelope, ServerInfoResponse,
+    envelope::Payload, ErrorResponse, Envelope, ServerInfoResponse, SubscribeChannelsRequest,
+    SubscribeChannelsResponse,
 };
 use crate::proto::server::ServerInfo;
+use crate::rate_limit::{self, RateClass};
+use crate::roles::permissions::{check_channel_permission, Permissions};
 use crate::state::AppState;
+use crate::ws::requests::{self, respond};
+use crate::ws::scope::SharedScope;
+use crate::ws::ConnectionSender;
 
 /// Handle an incoming binary (protobuf) message.
 /// Decodes the Envelope, dispatches based on payload type, sends response.
 pub async fn handle_binary_message(
     data: &[u8],
-    tx: &mpsc::UnboundedSender<Message>,
+    tx: &ConnectionSender,
+    scope: &SharedScope,
     state: &AppState,
-    user_id: &str,
+    claims: &Claims,
 ) {
+    let user_id = claims.sub.as_str();
+
     // Decode the protobuf Envelope
     let envelope = match Envelope::decode(data) {
         Ok(env) => env,
@@ -34,10 +44,18 @@ pub async fn handle_binary_message(
 
     let request_id = envelope.request_id.clone();
 
+    if let Err(retry_after) = state
+        .rate_limiter
+        .check(RateClass::Ws, &rate_limit::user_key(user_id))
+    {
+        send_rate_limited(tx, &request_id, retry_after);
+        return;
+    }
+
     // Dispatch based on payload type
     match envelope.payload {
         Some(payload) => {
-            dispatch_payload(payload, &request_id, tx, state, user_id).await;
+            dispatch_payload(payload, &request_id, tx, scope, state, claims).await;
         }
         None => {
             send_error(tx, &request_id, 400, "Empty payload");
@@ -46,29 +64,151 @@ pub async fn handle_binary_message(
 }
 
 /// Dispatch a decoded payload to the appropriate handler.
+/// Request variants backed by REST endpoints are routed through `ws::requests`,
+/// which reuses the REST handlers with the connection's claims.
 async fn dispatch_payload(
     payload: Payload,
     request_id: &str,
-    tx: &mpsc::UnboundedSender<Message>,
+    tx: &ConnectionSender,
+    scope: &SharedScope,
     state: &AppState,
-    user_id: &str,
+    claims: &Claims,
 ) {
+    let user_id = claims.sub.as_str();
     match payload {
+        // --- Auth ---
+        Payload::ChallengeRequest(req) => {
+            respond(tx, request_id, requests::challenge_request(req, state).await);
+        }
+        Payload::VerifyRequest(req) => {
+            respond(tx, request_id, requests::verify_request(req, state).await);
+        }
+        // --- Identity blobs and key rotation ---
+        Payload::StoreBlobRequest(req) => {
+            respond(tx, request_id, requests::store_blob(req, state, claims).await);
+        }
+        Payload::GetBlobRequest(req) => {
+            respond(tx, request_id, requests::get_blob(req, state).await);
+        }
+        Payload::RotateKeyRequest(req) => {
+            respond(tx, request_id, requests::rotate_key(req, state, claims).await);
+        }
+        Payload::CancelRotationRequest(req) => {
+            respond(tx, request_id, requests::cancel_rotation(req, state, claims).await);
+        }
+        // --- Channels and categories ---
+        Payload::CreateChannelRequest(req) => {
+            respond(tx, request_id, requests::create_channel(req, state, claims).await);
+        }
+        Payload::RenameChannelRequest(req) => {
+            respond(tx, request_id, requests::rename_channel(req, state, claims).await);
+        }
+        Payload::DeleteChannelRequest(req) => {
+            respond(tx, request_id, requests::delete_channel(req, state, claims).await);
+        }
+        Payload::ReorderChannelsRequest(req) => {
+            respond(tx, request_id, requests::reorder_channels(req, state, claims).await);
+        }
+        Payload::CreateCategoryRequest(req) => {
+            respond(tx, request_id, requests::create_category(req, state, claims).await);
+        }
+        Payload::RenameCategoryRequest(req) => {
+            respond(tx, request_id, requests::rename_category(req, state, claims).await);
+        }
+        Payload::DeleteCategoryRequest(req) => {
+            respond(tx, request_id, requests::delete_category(req, state, claims).await);
+        }
+        Payload::ReorderCategoriesRequest(req) => {
+            respond(tx, request_id, requests::reorder_categories(req, state, claims).await);
+        }
+        // --- Channel permission overrides ---
+        Payload::ListChannelOverridesRequest(req) => {
+            respond(tx, request_id, requests::list_channel_overrides(req, state, claims).await);
+        }
+        Payload::SetChannelOverrideRequest(req) => {
+            respond(tx, request_id, requests::set_channel_override(req, state, claims).await);
+        }
+        Payload::DeleteChannelOverrideRequest(req) => {
+            respond(tx, request_id, requests::delete_channel_override(req, state, claims).await);
+        }
+        // --- Roles ---
+        Payload::CreateRoleRequest(req) => {
+            respond(tx, request_id, requests::create_role(req, state, claims).await);
+        }
+        Payload::UpdateRoleRequest(req) => {
+            respond(tx, request_id, requests::update_role(req, state, claims).await);
+        }
+        Payload::DeleteRoleRequest(req) => {
+            respond(tx, request_id, requests::delete_role(req, state, claims).await);
+        }
+        Payload::AssignRoleRequest(req) => {
+            respond(tx, request_id, requests::assign_role(req, state, claims).await);
+        }
+        Payload::RemoveRoleRequest(req) => {
+            respond(tx, request_id, requests::remove_role(req, state, claims).await);
+        }
+        Payload::ReorderRolesRequest(req) => {
+            respond(tx, request_id, requests::reorder_roles(req, state, claims).await);
+        }
+        // --- Moderation ---
+        Payload::KickRequest(req) => {
+            respond(tx, request_id, requests::kick(req, state, claims).await);
+        }
+        Payload::BanRequest(req) => {
+            respond(tx, request_id, requests::ban(req, state, claims).await);
+        }
+        Payload::UnbanRequest(req) => {
+            respond(tx, request_id, requests::unban(req, state, claims).await);
+        }
+        // --- Invites ---
+        Payload::CreateInviteRequest(req) => {
+            respond(tx, request_id, requests::create_invite(req, state, claims).await);
+        }
+        Payload::DeleteInviteRequest(req) => {
+            respond(tx, request_id, requests::delete_invite(req, state, claims).await);
+        }
+        Payload::JoinServerRequest(req) => {
+            respond(tx, request_id, requests::join_server(req, state, claims).await);
+        }
+        // --- Chat and DM history ---
+        Payload::FetchHistoryRequest(req) => {
+            respond(tx, request_id, requests::fetch_history(req, state, claims).await);
+        }
+        Payload::SearchRequest(req) => {
+            respond(tx, request_id, requests::search(req, state, claims).await);
+        }
+        Payload::DmHistoryRequest(req) => {
+            respond(tx, request_id, requests::dm_history(req, state, claims).await);
+        }
+        Payload::DmConversationListRequest(req) => {
+            respond(tx, request_id, requests::dm_conversation_list(req, state, claims).await);
+        }
+        // --- Blocks ---
+        Payload::BlockRequest(req) => {
+            respond(tx, request_id, requests::block_request(req, state, claims).await);
+        }
+        // --- Server and P2P ---
         Payload::ServerInfoRequest(_) => {
             handle_server_info_request(request_id, tx, state).await;
         }
         Payload::PeerDirectoryRequest(req) => {
-            handle_peer_directory_request(req, request_id, tx, state).await;
+            handle_peer_directory_request(req, request_id, tx, state, claims).await;
         }
         Payload::RegisterPeerIdRequest(req) => {
             handle_register_peer_id(req, request_id, tx, state, user_id).await;
         }
+        // --- Event subscriptions ---
+        Payload::SubscribeChannelsRequest(req) => {
+            handle_subscribe_channels(req, request_id, tx, scope);
+        }
         // --- Phase 8: Voice Channels ---
         Payload::VoiceJoinRequest(req) => {
-            crate::voice::signaling::handle_voice_join(req, request_id, tx, state, user_id).await;
+            crate::voice::signaling::handle_voice_join(req, request_id, tx, state, user_id, claims.is_owner)
+                .await;
         }
         Payload::VoiceLeaveRequest(req) => {
-            crate::voice::signaling::handle_voice_leave(req, request_id, tx, state, user_id).await;
+            crate::voice::signaling::handle_voice_leave(req, request_id, tx, state, user_id, claims.is_owner)
+                .await;
         }
         Payload::VoiceSdpOffer(req) => {
             crate::voice::signaling::handle_voice_sdp_offer(req, request_id, tx, state, user_id)
@@ -83,21 +223,21 @@ async fn dispatch_payload(
                 .await;
         }
         Payload::VoiceStateUpdate(req) => {
-            crate::voice::signaling::handle_voice_state_update(req, request_id, tx, state, user_id)
+            crate::voice::signaling::handle_voice_state_update(req, request_id, tx, state, user_id, claims.is_owner)
                 .await;
         }
         Payload::VoiceSpeakingEvent(req) => {
             crate::voice::signaling::handle_voice_speaking(req, request_id, tx, state, user_id)
                 .await;
         }
-        // Unhandled payload types
+        // Server-to-client payloads (responses and events) are not valid requests
         _ => {
             tracing::debug!(
                 user_id = %user_id,
                 request_id = %request_id,
-                "Unhandled payload type"
+                "Unexpected server-to-client payload type"
             );
-            send_error(tx, request_id, 501, "Payload type not yet implemented");
+            send_error(tx, request_id, 400, "Payload type is not a request");
         }
     }
 }
@@ -105,14 +245,14 @@ async fn dispatch_payload(
 /// Handle a ServerInfoRequest: return server name, description, version.
 async fn handle_server_info_request(
     request_id: &str,
-    tx: &mpsc::UnboundedSender<Message>,
+    tx: &ConnectionSender,
     state: &AppState,
 ) {
     let db = state.db.clone();
     let req_id = request_id.to_string();
 
     let info = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().ok()?;
+        let conn = db.read().ok()?;
 
         let name = conn
             .query_row(
@@ -164,6 +304,7 @@ async fn handle_server_info_request(
         Some(server_info) => {
             let response = Envelope {
                 request_id: req_id,
+                seq: 0,
                 payload: Some(Payload::ServerInfoResponse(ServerInfoResponse {
                     info: Some(server_info),
                 })),
@@ -177,13 +318,50 @@ async fn handle_server_info_request(
 }
 
 /// Handle a PeerDirectoryRequest: query the peer directory for peers in the requested channels.
+/// Channels the caller cannot view (per channel overrides) are silently dropped from the query.
 async fn handle_peer_directory_request(
     req: p2p_proto::PeerDirectoryRequest,
     request_id: &str,
-    tx: &mpsc::UnboundedSender<Message>,
+    tx: &ConnectionSender,
     state: &AppState,
+    claims: &Claims,
 ) {
-    let peers = state.peer_directory.get_peers_for_channels(&req.channel_ids);
+    let db = state.db.clone();
+    let user_id = claims.sub.clone();
+    let is_owner = claims.is_owner;
+    let channel_ids = req.channel_ids;
+    let visible_channels = tokio::task::spawn_blocking(move || {
+        let conn = match db.read() {
+            Ok(conn) => conn,
+            Err(_) => return Vec::new(),
+        };
+        channel_ids
+            .into_iter()
+            .filter(|entry| {
+                // Entries may be bare channel UUIDs or full gossipsub topics: prefix/uuid
+                // for a channel, prefix/thread/uuid for a thread, which is visible to
+                // whoever can see its parent channel
+                let channel_id = match extract_thread_id(entry) {
+                    Some(thread_id) => conn
+                        .query_row(
+                            "SELECT channel_id FROM threads WHERE id = ?1",
+                            [&thread_id],
+                            |row| row.get::<_, String>(0),
+                        )
+                        .ok(),
+                    None => Some(extract_channel_id(entry).unwrap_or_else(|_| entry.clone())),
+                };
+                channel_id.is_some_and(|channel_id| {
+                    check_channel_permission(&conn, &user_id, is_owner, &channel_id, Permissions::VIEW_CHANNEL)
+                        .is_ok()
+                })
+            })
+            .collect::<Vec<String>>()
+    })
+    .await
+    .unwrap_or_default();
+
+    let peers = state.peer_directory.get_peers_for_channels(&visible_channels);
 
     let peer_infos: Vec<p2p_proto::PeerInfo> = peers
         .into_iter()
@@ -198,6 +376,7 @@ async fn handle_peer_directory_request(
 
     let response = Envelope {
         request_id: request_id.to_string(),
+        seq: 0,
         payload: Some(Payload::PeerDirectoryResponse(
             p2p_proto::PeerDirectoryResponse {
                 peers: peer_infos,
@@ -212,7 +391,7 @@ async fn handle_peer_directory_request(
 async fn handle_register_peer_id(
     req: p2p_proto::RegisterPeerIdRequest,
     request_id: &str,
-    tx: &mpsc::UnboundedSender<Message>,
+    tx: &ConnectionSender,
     state: &AppState,
     user_id: &str,
 ) {
@@ -230,7 +409,7 @@ async fn handle_register_peer_id(
     let db = state.db.clone();
     let uid = user_id.to_string();
     let fingerprint = tokio::task::spawn_blocking(move || {
-        let conn = db.lock().ok()?;
+        let conn = db.read().ok()?;
         conn.query_row(
             "SELECT fingerprint FROM users WHERE id = ?1",
             [&uid],
@@ -253,6 +432,7 @@ async fn handle_register_peer_id(
             );
             let response = Envelope {
                 request_id: request_id.to_string(),
+                seq: 0,
                 payload: Some(Payload::RegisterPeerIdResponse(
                     p2p_proto::RegisterPeerIdResponse { success: true },
                 )),
@@ -266,8 +446,37 @@ async fn handle_register_peer_id(
     }
 }
 
+/// Handle a SubscribeChannelsRequest: narrow (or restore) the channels whose events
+/// this connection receives, and reply with the channels it now gets.
+fn handle_subscribe_channels(
+    req: SubscribeChannelsRequest,
+    request_id: &str,
+    tx: &ConnectionSender,
+    scope: &SharedScope,
+) {
+    let Ok(mut scope) = scope.write() else {
+        send_error(tx, request_id, 500, "Connection state unavailable");
+        return;
+    };
+    scope.subscribed = if req.all {
+        None
+    } else {
+        Some(req.channel_ids.into_iter().collect())
+    };
+
+    let response = Envelope {
+        request_id: request_id.to_string(),
+        seq: 0,
+        payload: Some(Payload::SubscribeChannelsResponse(SubscribeChannelsResponse {
+            channel_ids: scope.delivered_channels(),
+            all: scope.subscribed.is_none(),
+        })),
+    };
+    send_envelope(tx, &response);
+}
+
 /// Encode and send an Envelope as a binary WebSocket message.
-fn send_envelope(tx: &mpsc::UnboundedSender<Message>, envelope: &Envelope) {
+pub(crate) fn send_envelope(tx: &ConnectionSender, envelope: &Envelope) {
     let mut buf = Vec::with_capacity(envelope.encoded_len());
     if envelope.encode(&mut buf).is_ok() {
         let _ = tx.send(Message::Binary(buf.into()));
@@ -275,18 +484,40 @@ fn send_envelope(tx: &mpsc::UnboundedSender<Message>, envelope: &Envelope) {
 }
 
 /// Send an error response envelope.
-fn send_error(
-    tx: &mpsc::UnboundedSender<Message>,
+pub(crate) fn send_error(
+    tx: &ConnectionSender,
     request_id: &str,
     code: u32,
     message: &str,
 ) {
     let envelope = Envelope {
         request_id: request_id.to_string(),
+        seq: 0,
         payload: Some(Payload::Error(ErrorResponse {
             code,
             message: message.to_string(),
             request_id: request_id.to_string(),
+            retry_after_ms: 0,
+        })),
+    };
+    send_envelope(tx, &envelope);
+}
+
+/// Send a 429 error response carrying how long to wait before retrying.
+fn send_rate_limited(
+    tx: &ConnectionSender,
+    request_id: &str,
+    retry_after: std::time::Duration,
+) {
+    let retry_after_ms = retry_after.as_millis().clamp(1, u32::MAX as u128) as u32;
+    let envelope = Envelope {
+        request_id: request_id.to_string(),
+        seq: 0,
+        payload: Some(Payload::Error(ErrorResponse {
+            code: 429,
+            message: format!("Rate limit exceeded; retry after {} ms", retry_after_ms),
+            request_id: request_id.to_string(),
+            retry_after_ms,
         })),
     };
     send_envelope(tx, &envelope);
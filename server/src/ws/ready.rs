@@ -0,0 +1,127 @@
+//! The `ReadyEvent` snapshot sent at the start of every new WebSocket session.
+
+use axum::extract::State;
+
+use crate::auth::middleware::Claims;
+use crate::channels::crud as channel_crud;
+use crate::chat::presence;
+use crate::proto::presence as proto_presence;
+use crate::proto::voice_proto;
+use crate::proto::ws::{
+    envelope::Payload, ChannelUnread, Envelope, ReadyEvent, VoiceChannelParticipants,
+};
+use crate::roles::crud as role_crud;
+use crate::state::AppState;
+use crate::ws::scope::SharedScope;
+
+/// Build the ReadyEvent for a new session: the channels, roles, presence, voice
+/// participants and unread counts the user can see.
+pub async fn ready_event(
+    state: &AppState,
+    claims: &Claims,
+    scope: &SharedScope,
+    session_id: &str,
+    resume_failed: bool,
+) -> Envelope {
+    let visible = scope
+        .read()
+        .map(|s| s.visible_channels.clone())
+        .unwrap_or_default();
+
+    let categories = channel_crud::list_channels(State(state.clone()), claims.clone())
+        .await
+        .map(|json| json.0.to_proto().categories)
+        .unwrap_or_default();
+    let roles = role_crud::list_roles(State(state.clone()), claims.clone())
+        .await
+        .map(|json| json.0.roles.iter().map(|r| r.to_proto()).collect())
+        .unwrap_or_default();
+
+    let presence = presence::get_all_presence(state)
+        .iter()
+        .map(|info| proto_presence::PresenceUpdate {
+            user_pubkey: info.user_pubkey.clone(),
+            display_name: info.display_name.clone(),
+            status: info.status.as_proto_i32(),
+            timestamp: 0,
+        })
+        .collect();
+
+    let voice_channels = state
+        .voice_state
+        .snapshot()
+        .into_iter()
+        .filter(|(channel_id, _)| visible.contains(channel_id))
+        .map(|(channel_id, participants)| VoiceChannelParticipants {
+            channel_id,
+            participants: participants
+                .into_iter()
+                .map(|p| voice_proto::VoiceParticipant {
+                    user_id: p.user_id,
+                    display_name: p.display_name,
+                    pubkey: p.pubkey,
+                    muted: p.muted,
+                    deafened: p.deafened,
+                })
+                .collect(),
+        })
+        .collect();
+
+    let db = state.db.clone();
+    let user_id = claims.sub.clone();
+    let unread = tokio::task::spawn_blocking(move || {
+        let conn = db.read().map_err(|e| e.to_string())?;
+        unread_counts(&conn, &user_id).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r)
+    .unwrap_or_else(|e| {
+        tracing::error!("Failed to load unread counts: {}", e);
+        Vec::new()
+    })
+    .into_iter()
+    .filter(|u| visible.contains(&u.channel_id))
+    .collect();
+
+    Envelope {
+        request_id: String::new(),
+        seq: 0,
+        payload: Some(Payload::ReadyEvent(ReadyEvent {
+            session_id: session_id.to_string(),
+            resume_failed,
+            user_id: claims.sub.clone(),
+            categories,
+            roles,
+            presence,
+            voice_channels,
+            unread,
+        })),
+    }
+}
+
+/// Read state of every message channel's top-level messages for `user_id`.
+pub fn unread_counts(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+) -> rusqlite::Result<Vec<ChannelUnread>> {
+    let mut stmt = conn.prepare(
+        "SELECT c.id, COALESCE(lr.last_sequence, 0), COALESCE(cs.last_sequence, 0),
+                (SELECT COUNT(*) FROM messages m
+                 WHERE m.channel_id = c.id AND m.thread_id IS NULL AND m.deleted = 0
+                   AND m.server_sequence > COALESCE(lr.last_sequence, 0))
+         FROM channels c
+         LEFT JOIN last_read lr ON lr.channel_id = c.id AND lr.user_id = ?1
+         LEFT JOIN channel_sequences cs ON cs.channel_id = c.id AND cs.thread_id = ''
+         WHERE c.channel_type != 'voice'",
+    )?;
+    let rows = stmt.query_map([user_id], |row| {
+        Ok(ChannelUnread {
+            channel_id: row.get(0)?,
+            last_read_sequence: row.get::<_, i64>(1)? as u64,
+            latest_sequence: row.get::<_, i64>(2)? as u64,
+            unread_count: row.get::<_, i64>(3)? as u32,
+        })
+    })?;
+    rows.collect()
+}
@@ -0,0 +1,848 @@
+//! WebSocket request handlers for the REST-backed Envelope request variants.
+//!
+//! Each handler converts the protobuf request into the input of the matching REST
+//! handler and calls it with the connection's JWT claims. Permission checks,
+//! validation and event broadcasts are therefore identical on both transports.
+//! The result is converted back into a protobuf payload and sent to the caller
+//! with the original request_id; failures become an ErrorResponse carrying the
+//! REST status code.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::auth::challenge;
+use crate::auth::middleware::Claims;
+use crate::channels::crud as channel_crud;
+use crate::channels::overrides as channel_overrides;
+use crate::chat::messages as chat_messages;
+use crate::chat::search as chat_search;
+use crate::dm::{conversations as dm_conversations, messages as dm_messages};
+use crate::identity::{blob, rotation};
+use crate::invite::generate as invite_gen;
+use crate::moderation::{ban, kick};
+use crate::proto::{
+    auth as proto_auth, blocks as proto_blocks, channels as proto_channels, chat as proto_chat,
+    dm as proto_dm, identity as proto_identity, invite as proto_invite,
+    moderation as proto_mod, roles as proto_roles,
+};
+use crate::proto::ws::{envelope::Payload, Envelope};
+use crate::roles::{assignment as role_assignment, crud as role_crud};
+use crate::state::AppState;
+use crate::ws::protocol::{send_envelope, send_error};
+use crate::ws::ConnectionSender;
+
+/// Error returned by a request handler: HTTP-equivalent status and message.
+pub type RequestError = (StatusCode, String);
+
+/// Result of a request handler: the response payload to correlate with the request.
+pub type RequestResult = Result<Payload, RequestError>;
+
+/// Send a handler result to the caller, echoing the request_id.
+pub fn respond(tx: &ConnectionSender, request_id: &str, result: RequestResult) {
+    match result {
+        Ok(payload) => {
+            let envelope = Envelope {
+                request_id: request_id.to_string(),
+                seq: 0,
+                payload: Some(payload),
+            };
+            send_envelope(tx, &envelope);
+        }
+        Err((status, message)) => {
+            send_error(tx, request_id, status.as_u16() as u32, &message);
+        }
+    }
+}
+
+/// Map a bare status code (from handlers that return `StatusCode` errors) to a RequestError.
+fn status_error(status: StatusCode) -> RequestError {
+    (
+        status,
+        status.canonical_reason().unwrap_or("Request failed").to_string(),
+    )
+}
+
+/// Treat an empty proto3 string as "not set".
+fn non_empty(value: String) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+// --- Auth (challenge-response re-authentication over the socket) ---
+
+pub async fn challenge_request(
+    _req: proto_auth::ChallengeRequest,
+    state: &AppState,
+) -> RequestResult {
+    let Json(resp) = challenge::issue_challenge(State(state.clone()))
+        .await
+        .map_err(status_error)?;
+
+    Ok(Payload::ChallengeResponse(proto_auth::ChallengeResponse {
+        challenge_id: resp.challenge_id,
+        challenge_bytes: hex::decode(&resp.challenge_bytes).unwrap_or_default(),
+    }))
+}
+
+pub async fn verify_request(req: proto_auth::VerifyRequest, state: &AppState) -> RequestResult {
+    let Json(resp) = challenge::verify_challenge(
+        State(state.clone()),
+        Json(challenge::VerifyApiRequest {
+            challenge_id: req.challenge_id,
+            public_key: hex::encode(&req.public_key),
+            signature: hex::encode(&req.signature),
+            fingerprint: req.fingerprint,
+        }),
+    )
+    .await
+    .map_err(status_error)?;
+
+    Ok(Payload::VerifyResponse(proto_auth::VerifyResponse {
+        access_token: resp.access_token,
+        refresh_token: resp.refresh_token,
+    }))
+}
+
+// --- Identity blobs and key rotation ---
+
+/// The REST API stores the blob as opaque bytes; over WS the stored bytes are the
+/// encoded IdentityBlob message so it round-trips through GetBlobRequest.
+pub async fn store_blob(
+    req: proto_identity::StoreBlobRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    use prost::Message as _;
+
+    let blob = req
+        .blob
+        .ok_or((StatusCode::BAD_REQUEST, "Missing identity blob".to_string()))?;
+
+    let Json(resp) = blob::put_blob(
+        State(state.clone()),
+        claims.clone(),
+        Json(blob::PutBlobRequest {
+            encrypted_blob: hex::encode(blob.encode_to_vec()),
+        }),
+    )
+    .await?;
+
+    Ok(Payload::StoreBlobResponse(proto_identity::StoreBlobResponse {
+        success: resp.success,
+    }))
+}
+
+pub async fn get_blob(req: proto_identity::GetBlobRequest, state: &AppState) -> RequestResult {
+    use prost::Message as _;
+
+    let Json(resp) = blob::get_blob(State(state.clone()), Path(req.fingerprint))
+        .await
+        .map_err(status_error)?;
+
+    let bytes = hex::decode(&resp.encrypted_blob).unwrap_or_default();
+    // Blobs uploaded over REST are raw ciphertext rather than an encoded IdentityBlob
+    let identity_blob = proto_identity::IdentityBlob::decode(bytes.as_slice()).unwrap_or_else(|_| {
+        proto_identity::IdentityBlob {
+            fingerprint: resp.fingerprint.clone(),
+            encrypted_private_key: bytes,
+            created_at: resp.created_at.clone(),
+            ..Default::default()
+        }
+    });
+
+    Ok(Payload::GetBlobResponse(proto_identity::GetBlobResponse {
+        blob: Some(identity_blob),
+    }))
+}
+
+pub async fn rotate_key(
+    req: proto_identity::RotateKeyRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let record = req
+        .rotation
+        .ok_or((StatusCode::BAD_REQUEST, "Missing rotation record".to_string()))?;
+
+    let Json(resp) = rotation::rotate_key(
+        State(state.clone()),
+        claims.clone(),
+        Json(rotation::RotateKeyRequest {
+            prev_key: hex::encode(&record.prev_key),
+            new_key: hex::encode(&record.new_key),
+            reason: record.reason,
+            signature_old: hex::encode(&record.signature_old),
+            signature_new: hex::encode(&record.signature_new),
+        }),
+    )
+    .await?;
+
+    Ok(Payload::RotateKeyResponse(proto_identity::RotateKeyResponse {
+        accepted: resp.accepted,
+        cancellation_deadline: resp.cancellation_deadline,
+    }))
+}
+
+pub async fn cancel_rotation(
+    req: proto_identity::CancelRotationRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    // Rotation is always cancelled for the authenticated identity
+    if !req.fingerprint.is_empty() && req.fingerprint != claims.fingerprint {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Cannot cancel another identity's rotation".to_string(),
+        ));
+    }
+
+    let Json(resp) = rotation::cancel_rotation(
+        State(state.clone()),
+        claims.clone(),
+        Json(rotation::CancelRotationRequest {
+            signature_old_key: hex::encode(&req.signature_old_key),
+        }),
+    )
+    .await?;
+
+    Ok(Payload::CancelRotationResponse(
+        proto_identity::CancelRotationResponse {
+            cancelled: resp.cancelled,
+        },
+    ))
+}
+
+// --- Channels and categories ---
+
+pub async fn create_channel(
+    req: proto_channels::CreateChannelRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let (_, Json(channel)) = channel_crud::create_channel(
+        State(state.clone()),
+        claims.clone(),
+        Json(channel_crud::CreateChannelRequest {
+            name: req.name,
+            channel_type: req.channel_type,
+            category_id: req.category_id,
+        }),
+    )
+    .await?;
+
+    Ok(Payload::ChannelCreatedEvent(proto_channels::ChannelCreatedEvent {
+        channel: Some(channel.to_proto()),
+    }))
+}
+
+pub async fn rename_channel(
+    req: proto_channels::RenameChannelRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let Json(channel) = channel_crud::update_channel(
+        State(state.clone()),
+        claims.clone(),
+        Path(req.channel_id),
+        Json(channel_crud::UpdateChannelRequest {
+            name: Some(req.name),
+            slowmode_secs: None,
+        }),
+    )
+    .await?;
+
+    Ok(Payload::ChannelUpdatedEvent(proto_channels::ChannelUpdatedEvent {
+        channel: Some(channel.to_proto()),
+    }))
+}
+
+pub async fn delete_channel(
+    req: proto_channels::DeleteChannelRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    channel_crud::delete_channel(State(state.clone()), claims.clone(), Path(req.channel_id.clone()))
+        .await?;
+
+    Ok(Payload::ChannelDeletedEvent(proto_channels::ChannelDeletedEvent {
+        channel_id: req.channel_id,
+    }))
+}
+
+/// Reorders reply with the full channel list so the caller sees the resulting order.
+pub async fn reorder_channels(
+    req: proto_channels::ReorderChannelsRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let entries = req
+        .entries
+        .into_iter()
+        .map(|e| channel_crud::ReorderEntry {
+            id: e.id,
+            position: e.position,
+        })
+        .collect();
+    channel_crud::reorder_channels(
+        State(state.clone()),
+        claims.clone(),
+        Json(channel_crud::ReorderRequest { entries }),
+    )
+    .await?;
+
+    channel_list(state, claims).await
+}
+
+pub async fn create_category(
+    req: proto_channels::CreateCategoryRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let (_, Json(category)) = channel_crud::create_category(
+        State(state.clone()),
+        claims.clone(),
+        Json(channel_crud::CreateCategoryRequest { name: req.name }),
+    )
+    .await?;
+
+    Ok(Payload::CategoryCreatedEvent(proto_channels::CategoryCreatedEvent {
+        category: Some(category.to_proto()),
+    }))
+}
+
+pub async fn rename_category(
+    req: proto_channels::RenameCategoryRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let Json(category) = channel_crud::update_category(
+        State(state.clone()),
+        claims.clone(),
+        Path(req.category_id),
+        Json(channel_crud::UpdateCategoryRequest { name: req.name }),
+    )
+    .await?;
+
+    Ok(Payload::CategoryUpdatedEvent(proto_channels::CategoryUpdatedEvent {
+        category: Some(category.to_proto()),
+    }))
+}
+
+pub async fn delete_category(
+    req: proto_channels::DeleteCategoryRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    channel_crud::delete_category(
+        State(state.clone()),
+        claims.clone(),
+        Path(req.category_id.clone()),
+    )
+    .await?;
+
+    Ok(Payload::CategoryDeletedEvent(proto_channels::CategoryDeletedEvent {
+        category_id: req.category_id,
+    }))
+}
+
+pub async fn reorder_categories(
+    req: proto_channels::ReorderCategoriesRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let entries = req
+        .entries
+        .into_iter()
+        .map(|e| channel_crud::ReorderEntry {
+            id: e.id,
+            position: e.position,
+        })
+        .collect();
+    channel_crud::reorder_categories(
+        State(state.clone()),
+        claims.clone(),
+        Json(channel_crud::ReorderRequest { entries }),
+    )
+    .await?;
+
+    channel_list(state, claims).await
+}
+
+async fn channel_list(state: &AppState, claims: &Claims) -> RequestResult {
+    let Json(list) = channel_crud::list_channels(State(state.clone()), claims.clone())
+        .await
+        .map_err(status_error)?;
+    Ok(Payload::ChannelListResponse(list.to_proto()))
+}
+
+// --- Channel permission overrides ---
+
+pub async fn list_channel_overrides(
+    req: proto_channels::ListChannelOverridesRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let Json(overrides) = channel_overrides::list_overrides(
+        State(state.clone()),
+        claims.clone(),
+        Path(req.channel_id),
+    )
+    .await?;
+
+    Ok(Payload::ChannelOverridesResponse(proto_channels::ChannelOverridesResponse {
+        overrides: overrides.iter().map(|o| o.to_proto()).collect(),
+    }))
+}
+
+pub async fn set_channel_override(
+    req: proto_channels::SetChannelOverrideRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let Json(entry) = channel_overrides::set_override(
+        State(state.clone()),
+        claims.clone(),
+        Path((req.channel_id, req.target_type, req.target_id)),
+        Json(channel_overrides::SetOverrideRequest {
+            allow: req.allow,
+            deny: req.deny,
+        }),
+    )
+    .await?;
+
+    Ok(Payload::ChannelOverrideUpdatedEvent(proto_channels::ChannelOverrideUpdatedEvent {
+        permission_override: Some(entry.to_proto()),
+    }))
+}
+
+pub async fn delete_channel_override(
+    req: proto_channels::DeleteChannelOverrideRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    channel_overrides::delete_override(
+        State(state.clone()),
+        claims.clone(),
+        Path((req.channel_id.clone(), req.target_type.clone(), req.target_id.clone())),
+    )
+    .await?;
+
+    Ok(Payload::ChannelOverrideDeletedEvent(proto_channels::ChannelOverrideDeletedEvent {
+        channel_id: req.channel_id,
+        target_type: req.target_type,
+        target_id: req.target_id,
+    }))
+}
+
+// --- Roles ---
+
+pub async fn create_role(
+    req: proto_roles::CreateRoleRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let (_, Json(role)) = role_crud::create_role(
+        State(state.clone()),
+        claims.clone(),
+        Json(role_crud::CreateRoleRequest {
+            name: req.name,
+            permissions: req.permissions,
+            color: req.color,
+        }),
+    )
+    .await?;
+
+    Ok(Payload::RoleCreatedEvent(proto_roles::RoleCreatedEvent {
+        role: Some(role.to_proto()),
+    }))
+}
+
+/// Empty name/color leave those fields unchanged; permissions are always replaced
+/// since proto3 cannot distinguish an unset bitfield from zero.
+pub async fn update_role(
+    req: proto_roles::UpdateRoleRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let Json(role) = role_crud::update_role(
+        State(state.clone()),
+        claims.clone(),
+        Path(req.role_id),
+        Json(role_crud::UpdateRoleRequest {
+            name: non_empty(req.name),
+            permissions: Some(req.permissions),
+            color: non_empty(req.color),
+        }),
+    )
+    .await?;
+
+    Ok(Payload::RoleUpdatedEvent(proto_roles::RoleUpdatedEvent {
+        role: Some(role.to_proto()),
+    }))
+}
+
+pub async fn delete_role(
+    req: proto_roles::DeleteRoleRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    role_crud::delete_role(State(state.clone()), claims.clone(), Path(req.role_id.clone())).await?;
+
+    Ok(Payload::RoleDeletedEvent(proto_roles::RoleDeletedEvent {
+        role_id: req.role_id,
+    }))
+}
+
+pub async fn assign_role(
+    req: proto_roles::AssignRoleRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    role_assignment::assign_role(
+        State(state.clone()),
+        claims.clone(),
+        Json(role_assignment::AssignRoleRequest {
+            user_id: req.user_id.clone(),
+            role_id: req.role_id.clone(),
+        }),
+    )
+    .await?;
+
+    Ok(Payload::RoleAssignedEvent(proto_roles::RoleAssignedEvent {
+        user_id: req.user_id,
+        role_id: req.role_id,
+    }))
+}
+
+pub async fn remove_role(
+    req: proto_roles::RemoveRoleRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    role_assignment::remove_role(
+        State(state.clone()),
+        claims.clone(),
+        Json(role_assignment::RemoveRoleRequest {
+            user_id: req.user_id.clone(),
+            role_id: req.role_id.clone(),
+        }),
+    )
+    .await?;
+
+    Ok(Payload::RoleRemovedEvent(proto_roles::RoleRemovedEvent {
+        user_id: req.user_id,
+        role_id: req.role_id,
+    }))
+}
+
+/// Reorders reply with the full role list so the caller sees the resulting order.
+pub async fn reorder_roles(
+    req: proto_roles::ReorderRolesRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let entries = req
+        .entries
+        .into_iter()
+        .map(|e| role_crud::RoleReorderEntry {
+            id: e.role_id,
+            position: e.position,
+        })
+        .collect();
+    let Json(list) = role_crud::reorder_roles(
+        State(state.clone()),
+        claims.clone(),
+        Json(role_crud::ReorderRolesRequest { entries }),
+    )
+    .await?;
+
+    Ok(Payload::RoleListResponse(proto_roles::RoleListResponse {
+        roles: list.roles.iter().map(|r| r.to_proto()).collect(),
+    }))
+}
+
+async fn role_list(state: &AppState, claims: &Claims) -> Result<proto_roles::RoleListResponse, RequestError> {
+    let Json(list) = role_crud::list_roles(State(state.clone()), claims.clone())
+        .await
+        .map_err(status_error)?;
+    Ok(proto_roles::RoleListResponse {
+        roles: list.roles.iter().map(|r| r.to_proto()).collect(),
+    })
+}
+
+// --- Moderation ---
+
+pub async fn kick(req: proto_mod::KickRequest, state: &AppState, claims: &Claims) -> RequestResult {
+    kick::kick_user(
+        State(state.clone()),
+        claims.clone(),
+        Json(kick::KickRequest {
+            user_id: req.user_id.clone(),
+            reason: req.reason.clone(),
+            evidence_message_ids: req.evidence_message_ids,
+        }),
+    )
+    .await?;
+
+    Ok(Payload::UserKickedEvent(proto_mod::UserKickedEvent {
+        user_id: req.user_id,
+        reason: req.reason,
+    }))
+}
+
+pub async fn ban(req: proto_mod::BanRequest, state: &AppState, claims: &Claims) -> RequestResult {
+    let Json(_ban) = ban::ban_user(
+        State(state.clone()),
+        claims.clone(),
+        Json(ban::BanRequest {
+            user_id: req.user_id.clone(),
+            reason: req.reason.clone(),
+            expires_at: req.expires_at,
+            duration_secs: None,
+            evidence_message_ids: req.evidence_message_ids,
+            review_invitees: false,
+            admin_signature: Some(req.admin_signature).filter(|s| !s.is_empty()),
+            signed_at: Some(req.signed_at).filter(|s| !s.is_empty()),
+        }),
+    )
+    .await?;
+
+    Ok(Payload::UserBannedEvent(proto_mod::UserBannedEvent {
+        user_id: req.user_id,
+        reason: req.reason,
+    }))
+}
+
+pub async fn unban(req: proto_mod::UnbanRequest, state: &AppState, claims: &Claims) -> RequestResult {
+    ban::unban_user(
+        State(state.clone()),
+        claims.clone(),
+        Json(ban::UnbanRequest {
+            fingerprint: req.fingerprint.clone(),
+        }),
+    )
+    .await?;
+
+    Ok(Payload::UserUnbannedEvent(proto_mod::UserUnbannedEvent {
+        fingerprint: req.fingerprint,
+    }))
+}
+
+// --- Invites ---
+
+/// Replies with an InviteListResponse containing only the newly created invite.
+pub async fn create_invite(
+    req: proto_invite::CreateInviteRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let (_, Json(invite)) = invite_gen::create_invite(
+        State(state.clone()),
+        claims.clone(),
+        Json(invite_gen::CreateInviteRequest {
+            max_uses: req.max_uses,
+            expires_at: req.expires_at,
+        }),
+    )
+    .await?;
+
+    Ok(Payload::InviteListResponse(proto_invite::InviteListResponse {
+        invites: vec![invite.to_proto()],
+    }))
+}
+
+/// Replies with the remaining invites after deletion.
+pub async fn delete_invite(
+    req: proto_invite::DeleteInviteRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    invite_gen::delete_invite(State(state.clone()), claims.clone(), Path(req.code)).await?;
+
+    let Json(list) = invite_gen::list_invites(State(state.clone()), claims.clone()).await?;
+    Ok(Payload::InviteListResponse(proto_invite::InviteListResponse {
+        invites: list.invites.iter().map(|i| i.to_proto()).collect(),
+    }))
+}
+
+/// Redeem an invite as an already-registered member and return the server's
+/// channel and role lists, mirroring what a fresh join would receive.
+pub async fn join_server(
+    req: proto_invite::JoinServerRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let db = state.db.clone();
+    let code = req.invite_code;
+    let user_id = claims.sub.clone();
+    tokio::task::spawn_blocking(move || {
+        let conn = db
+            .lock()
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "DB lock".to_string()))?;
+        // Members re-sending a join just get the lists back; only a newcomer
+        // spends a use of the invite.
+        let is_member: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM users WHERE id = ?1)",
+                [&user_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Query user: {}", e)))?;
+        if is_member {
+            return Ok(());
+        }
+        let inviter_id = crate::invite::validate::consume_invite(&conn, &code)?;
+        crate::invite::validate::record_invite_use(&conn, &code, &inviter_id, &user_id)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Task join: {}", e)))??;
+
+    let Json(channels) = channel_crud::list_channels(State(state.clone()), claims.clone())
+        .await
+        .map_err(status_error)?;
+    let roles = role_list(state, claims).await?;
+
+    Ok(Payload::JoinServerResponse(proto_invite::JoinServerResponse {
+        success: true,
+        channel_list: Some(channels.to_proto()),
+        role_list: Some(roles),
+    }))
+}
+
+// --- Chat and DM history ---
+
+fn optional_sequence(before_sequence: u64) -> Option<u64> {
+    if before_sequence == 0 {
+        None
+    } else {
+        Some(before_sequence)
+    }
+}
+
+fn optional_limit(limit: u32) -> Option<u32> {
+    if limit == 0 {
+        None
+    } else {
+        Some(limit)
+    }
+}
+
+pub async fn fetch_history(
+    req: proto_chat::FetchHistoryRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let Json(history) = chat_messages::get_channel_messages(
+        State(state.clone()),
+        claims.clone(),
+        Path(req.channel_id),
+        Query(chat_messages::HistoryQuery {
+            before: optional_sequence(req.before_sequence),
+            limit: optional_limit(req.limit),
+        }),
+    )
+    .await
+    .map_err(status_error)?;
+
+    Ok(Payload::FetchHistoryResponse(proto_chat::FetchHistoryResponse {
+        messages: history.messages.iter().map(|m| m.to_proto()).collect(),
+        has_more: history.has_more,
+    }))
+}
+
+pub async fn search(
+    req: proto_chat::SearchRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let Json(results) = chat_search::search_messages(
+        State(state.clone()),
+        claims.clone(),
+        Query(chat_search::SearchQuery {
+            q: req.query,
+            limit: optional_limit(req.limit),
+            offset: Some(req.offset),
+        }),
+    )
+    .await
+    .map_err(status_error)?;
+
+    Ok(Payload::SearchResponse(proto_chat::SearchResponse {
+        messages: results.messages.iter().map(|m| m.to_proto()).collect(),
+        has_more: results.has_more,
+    }))
+}
+
+pub async fn dm_history(
+    req: proto_dm::DmHistoryRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let Json(history) = dm_messages::get_dm_messages(
+        State(state.clone()),
+        claims.clone(),
+        Path(req.conversation_id),
+        Query(dm_messages::DmHistoryQuery {
+            before: optional_sequence(req.before_sequence),
+            limit: optional_limit(req.limit),
+        }),
+    )
+    .await
+    .map_err(status_error)?;
+
+    Ok(Payload::DmHistoryResponse(proto_dm::DmHistoryResponse {
+        messages: history.messages.iter().map(|m| m.to_proto()).collect(),
+        has_more: history.has_more,
+    }))
+}
+
+pub async fn dm_conversation_list(
+    _req: proto_dm::DmConversationListRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let Json(conversations) =
+        dm_conversations::list_conversations(State(state.clone()), claims.clone())
+            .await
+            .map_err(status_error)?;
+
+    Ok(Payload::DmConversationListResponse(
+        proto_dm::DmConversationListResponse {
+            conversations: conversations.iter().map(|c| c.to_proto()).collect(),
+        },
+    ))
+}
+
+// --- Blocks ---
+
+pub async fn block_request(
+    req: proto_blocks::BlockRequest,
+    state: &AppState,
+    claims: &Claims,
+) -> RequestResult {
+    let result = crate::blocks::routes::get_block_route(
+        State(state.clone()),
+        claims.clone(),
+        Path(req.hash.clone()),
+    )
+    .await;
+
+    // A missing block is an expected outcome, reported in-band like the P2P exchange
+    let (data, not_found) = match result {
+        Ok((_, _, data)) => (data, false),
+        Err((StatusCode::NOT_FOUND, _)) => (vec![], true),
+        Err(e) => return Err(e),
+    };
+
+    Ok(Payload::BlockResponse(proto_blocks::BlockResponse {
+        hash: req.hash.to_lowercase(),
+        data,
+        not_found,
+    }))
+}
@@ -0,0 +1,314 @@
+//! Who receives which WebSocket events.
+//!
+//! Every event is published with an `EventScope`. Each connection keeps a
+//! `ConnectionScope`: the channels its user may view (VIEW_CHANNEL with overrides
+//! applied), the user's roles, and an optional explicit channel subscription set. Channel
+//! events only reach connections that can view the channel and, if they narrowed their
+//! subscriptions, subscribed to it.
+//!
+//! Visibility is computed when a connection opens and recomputed after anything that can
+//! change it: for every connection by `refresh_visibility` when a role changes, for one
+//! user by `refresh_user_visibility` when their roles change, and for one channel by
+//! `publish_channel_change` when a channel or its overrides change. Events announcing a
+//! channel change go through `publish_channel_change`, so users who just lost access to
+//! the channel still get them.
+
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+use crate::proto::ws::Envelope;
+use crate::roles::permissions::{
+    apply_channel_overrides, compute_channel_permissions, load_user_permissions, Permissions,
+};
+use crate::state::AppState;
+use crate::ws::broadcast::publish_where;
+use crate::ws::session::Session;
+
+/// Audience of a WebSocket event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventScope<'a> {
+    /// Every connected user
+    Server,
+    /// Connections that can view and are subscribed to the channel
+    Channel(&'a str),
+    /// Every connection of one user
+    User(&'a str),
+    /// Connections of users holding any of the roles, and of the owner
+    Roles(&'a [String]),
+}
+
+/// Per-connection delivery state.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionScope {
+    pub is_owner: bool,
+    /// Channels the user has VIEW_CHANNEL in
+    pub visible_channels: HashSet<String>,
+    /// Roles the user holds, including @everyone
+    pub role_ids: HashSet<String>,
+    /// Explicit channel subscriptions (None receives every visible channel)
+    pub subscribed: Option<HashSet<String>>,
+}
+
+pub type SharedScope = Arc<RwLock<ConnectionScope>>;
+
+impl ConnectionScope {
+    /// Whether a connection of `user_id` with this scope receives an event.
+    pub fn receives(&self, user_id: &str, scope: EventScope<'_>) -> bool {
+        match scope {
+            EventScope::Server => true,
+            EventScope::User(target) => target == user_id,
+            EventScope::Roles(role_ids) => {
+                self.is_owner || role_ids.iter().any(|r| self.role_ids.contains(r))
+            }
+            EventScope::Channel(channel_id) => {
+                self.visible_channels.contains(channel_id)
+                    && self
+                        .subscribed
+                        .as_ref()
+                        .is_none_or(|s| s.contains(channel_id))
+            }
+        }
+    }
+
+    /// Channels this connection receives events for.
+    pub fn delivered_channels(&self) -> Vec<String> {
+        let mut channels: Vec<String> = match &self.subscribed {
+            Some(subscribed) => subscribed
+                .intersection(&self.visible_channels)
+                .cloned()
+                .collect(),
+            None => self.visible_channels.iter().cloned().collect(),
+        };
+        channels.sort();
+        channels
+    }
+}
+
+/// Channels `user_id` can view and the roles they hold.
+pub fn load_visibility(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+    is_owner: bool,
+) -> rusqlite::Result<(HashSet<String>, HashSet<String>)> {
+    let channel_ids: Vec<String> = conn
+        .prepare("SELECT id FROM channels")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    // Role permissions are the same for every channel; only the overrides differ
+    let base = if is_owner {
+        Permissions::all()
+    } else {
+        load_user_permissions(conn, user_id)?
+    };
+    let mut visible = HashSet::new();
+    for channel_id in channel_ids {
+        if apply_channel_overrides(conn, user_id, base, &channel_id)?
+            .contains(Permissions::VIEW_CHANNEL)
+        {
+            visible.insert(channel_id);
+        }
+    }
+
+    let role_ids = conn
+        .prepare(
+            "SELECT role_id FROM user_roles WHERE user_id = ?1
+             UNION SELECT id FROM roles WHERE is_default = 1",
+        )?
+        .query_map([user_id], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+
+    Ok((visible, role_ids))
+}
+
+/// Build the scope for a new connection.
+pub async fn connection_scope(state: &AppState, user_id: &str, is_owner: bool) -> SharedScope {
+    let db = state.db.clone();
+    let uid = user_id.to_string();
+    let loaded = tokio::task::spawn_blocking(move || {
+        let conn = db.read().map_err(|e| e.to_string())?;
+        load_visibility(&conn, &uid, is_owner).map_err(|e| e.to_string())
+    })
+    .await;
+
+    let (visible_channels, role_ids) = match loaded {
+        Ok(Ok(visibility)) => visibility,
+        Ok(Err(e)) => {
+            tracing::error!(user_id = %user_id, "Failed to load channel visibility: {}", e);
+            Default::default()
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user_id, "Channel visibility task join error: {}", e);
+            Default::default()
+        }
+    };
+
+    Arc::new(RwLock::new(ConnectionScope {
+        is_owner,
+        visible_channels,
+        role_ids,
+        subscribed: None,
+    }))
+}
+
+/// (user_id, is_owner) of each connected user, or only of `only_user` when set.
+fn connected_users(state: &AppState, only_user: Option<&str>) -> Vec<(String, bool)> {
+    state
+        .connections
+        .iter()
+        .filter(|entry| only_user.is_none_or(|u| u == entry.key()))
+        .filter_map(|entry| {
+            let is_owner = entry.value().first()?.scope.read().ok()?.is_owner;
+            Some((entry.key().clone(), is_owner))
+        })
+        .collect()
+}
+
+/// Run `load` against the DB on the blocking pool, logging failures.
+async fn load_blocking<T: Send + 'static>(
+    state: &AppState,
+    load: impl FnOnce(&rusqlite::Connection) -> rusqlite::Result<T> + Send + 'static,
+) -> Option<T> {
+    let db = state.db.clone();
+    let loaded = tokio::task::spawn_blocking(move || {
+        let conn = db.read().map_err(|e| e.to_string())?;
+        load(&conn).map_err(|e| e.to_string())
+    })
+    .await;
+
+    match loaded {
+        Ok(Ok(loaded)) => Some(loaded),
+        Ok(Err(e)) => {
+            tracing::error!("Failed to refresh channel visibility: {}", e);
+            None
+        }
+        Err(e) => {
+            tracing::error!("Channel visibility refresh task join error: {}", e);
+            None
+        }
+    }
+}
+
+/// Recompute channel visibility and roles for every open connection.
+pub async fn refresh_visibility(state: &AppState) {
+    refresh_users(state, connected_users(state, None)).await;
+}
+
+/// Recompute channel visibility and roles for the open connections of one user.
+pub async fn refresh_user_visibility(state: &AppState, user_id: &str) {
+    refresh_users(state, connected_users(state, Some(user_id))).await;
+}
+
+async fn refresh_users(state: &AppState, users: Vec<(String, bool)>) {
+    if users.is_empty() {
+        return;
+    }
+
+    let loaded = load_blocking(state, move |conn| {
+        users
+            .into_iter()
+            .map(|(user_id, is_owner)| {
+                let visibility = load_visibility(conn, &user_id, is_owner)?;
+                Ok((user_id, visibility))
+            })
+            .collect::<rusqlite::Result<Vec<_>>>()
+    })
+    .await;
+    let Some(refreshed) = loaded else {
+        return;
+    };
+
+    for (user_id, (visible_channels, role_ids)) in refreshed {
+        if let Some(connections) = state.connections.get(&user_id) {
+            for connection in connections.value().iter() {
+                if let Ok(mut scope) = connection.scope.write() {
+                    scope.visible_channels = visible_channels.clone();
+                    scope.role_ids = role_ids.clone();
+                }
+            }
+        }
+    }
+}
+
+/// Recompute whether each open connection can view `channel_id`, leaving its other
+/// channels as they are. A deleted channel is dropped from every connection.
+async fn refresh_channel_visibility(state: &AppState, channel_id: &str) {
+    let users = connected_users(state, None);
+    if users.is_empty() {
+        return;
+    }
+
+    let cid = channel_id.to_string();
+    let loaded = load_blocking(state, move |conn| {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM channels WHERE id = ?1)",
+            [&cid],
+            |row| row.get(0),
+        )?;
+        users
+            .into_iter()
+            .map(|(user_id, is_owner)| {
+                let visible = exists
+                    && compute_channel_permissions(conn, &user_id, is_owner, &cid)?
+                        .contains(Permissions::VIEW_CHANNEL);
+                Ok((user_id, visible))
+            })
+            .collect::<rusqlite::Result<Vec<_>>>()
+    })
+    .await;
+    let Some(refreshed) = loaded else {
+        return;
+    };
+
+    for (user_id, visible) in refreshed {
+        if let Some(connections) = state.connections.get(&user_id) {
+            for connection in connections.value().iter() {
+                if let Ok(mut scope) = connection.scope.write() {
+                    if visible {
+                        scope.visible_channels.insert(channel_id.to_string());
+                    } else {
+                        scope.visible_channels.remove(channel_id);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Sessions currently receiving events for `channel_id`. Take this before applying a
+/// change that can revoke access to the channel and pass it to `publish_channel_change`.
+pub fn channel_audience(state: &AppState, channel_id: &str) -> Vec<Arc<Session>> {
+    let mut audience = Vec::new();
+    for entry in state.connections.iter() {
+        for connection in entry.value().iter() {
+            let receives = connection
+                .scope
+                .read()
+                .map(|s| s.receives(entry.key(), EventScope::Channel(channel_id)))
+                .unwrap_or(false);
+            if receives {
+                audience.push(connection.session.clone());
+            }
+        }
+    }
+    audience
+}
+
+/// Recompute visibility after a change to who may view `channel_id`, then publish
+/// `envelope` to the channel's audience from before the change (see `channel_audience`)
+/// and to every connection that can view it now.
+pub async fn publish_channel_change(
+    state: &AppState,
+    channel_id: &str,
+    audience: &[Arc<Session>],
+    envelope: &Envelope,
+) {
+    refresh_channel_visibility(state, channel_id).await;
+    publish_where(&state.connections, envelope, |user_id, connection| {
+        audience.iter().any(|s| Arc::ptr_eq(s, &connection.session))
+            || connection
+                .scope
+                .read()
+                .map(|s| s.receives(user_id, EventScope::Channel(channel_id)))
+                .unwrap_or(false)
+    });
+}
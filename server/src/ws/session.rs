@@ -0,0 +1,225 @@
+//! Resumable WebSocket sessions.
+//!
+//! Every event published to a connection is numbered with a per-session sequence
+//! number (`Envelope.seq`) and kept in a bounded replay buffer. When the socket drops,
+//! the session stays registered and keeps buffering for `RESUME_WINDOW`; a client that
+//! reconnects with `/ws?token=..&session=..&last_seq=..` gets the events it missed
+//! followed by a `ResumedEvent`. If the session has expired or the buffer no longer
+//! reaches back to `last_seq`, the client gets a fresh session and a `ReadyEvent`
+//! snapshot with `resume_failed` set.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::body::Bytes;
+use axum::extract::ws::Message;
+use prost::encoding::{encode_key, encode_varint, WireType};
+use prost::Message as ProstMessage;
+
+use crate::proto::ws::Envelope;
+use crate::ws::outbound::Delivery;
+use crate::ws::{ConnectionRegistry, ConnectionSender};
+
+/// Events kept per session for replay.
+pub const REPLAY_BUFFER_LEN: usize = 1000;
+
+/// How long a disconnected session can be resumed.
+pub const RESUME_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// Field number of `Envelope.seq`.
+const SEQ_FIELD: u32 = 2;
+
+struct SessionInner {
+    /// Outbound channel of the attached socket (None while disconnected)
+    sender: Option<ConnectionSender>,
+    detached_at: Option<Instant>,
+    last_seq: u64,
+    /// (seq, encoded envelope) of the most recent events, oldest first
+    replay: VecDeque<(u64, Bytes)>,
+}
+
+pub struct Session {
+    pub id: String,
+    inner: Mutex<SessionInner>,
+}
+
+impl Session {
+    /// A new session, detached until `start` attaches its first socket.
+    pub fn new() -> Self {
+        Self {
+            id: uuid::Uuid::now_v7().to_string(),
+            inner: Mutex::new(SessionInner {
+                sender: None,
+                detached_at: None,
+                last_seq: 0,
+                replay: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// Number an encoded event envelope, buffer it, and send it if a socket is attached.
+    pub fn deliver(&self, encoded: &[u8], delivery: Delivery) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        inner.last_seq += 1;
+        let frame = with_seq(encoded, inner.last_seq);
+
+        if inner.replay.len() == REPLAY_BUFFER_LEN {
+            inner.replay.pop_front();
+        }
+        let seq = inner.last_seq;
+        inner.replay.push_back((seq, frame.clone()));
+
+        if let Some(sender) = &inner.sender {
+            let _ = sender.push(Message::Binary(frame), delivery);
+        }
+    }
+
+    /// Send an unnumbered message (e.g. a close frame) to the attached socket, if any.
+    pub fn send(&self, msg: Message) {
+        if let Ok(inner) = self.inner.lock() {
+            if let Some(sender) = &inner.sender {
+                let _ = sender.send(msg);
+            }
+        }
+    }
+
+    /// Attach the first socket: send `ready`, then any events buffered while the
+    /// snapshot in it was being built.
+    pub fn start(&self, sender: ConnectionSender, ready: &Envelope) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        sender.set_resume_session(&self.id);
+        let _ = sender.send(Message::Binary(ready.encode_to_vec().into()));
+        for (_, frame) in inner.replay.iter() {
+            let _ = sender.send(Message::Binary(frame.clone()));
+        }
+        inner.sender = Some(sender);
+        inner.detached_at = None;
+    }
+
+    /// Attach a reconnecting socket: replay the events after `last_seq`, then send the
+    /// envelope built by `resumed` from the number replayed. Returns false, leaving the
+    /// session untouched, if some of those events are no longer buffered.
+    pub fn resume(
+        &self,
+        sender: ConnectionSender,
+        last_seq: u64,
+        resumed: impl FnOnce(u32) -> Envelope,
+    ) -> bool {
+        let Ok(mut inner) = self.inner.lock() else {
+            return false;
+        };
+        if last_seq > inner.last_seq {
+            return false;
+        }
+        let oldest = inner
+            .replay
+            .front()
+            .map(|(seq, _)| *seq)
+            .unwrap_or(inner.last_seq + 1);
+        if last_seq + 1 < oldest {
+            return false;
+        }
+
+        sender.set_resume_session(&self.id);
+        let mut replayed = 0;
+        for (_, frame) in inner.replay.iter().filter(|(seq, _)| *seq > last_seq) {
+            let _ = sender.send(Message::Binary(frame.clone()));
+            replayed += 1;
+        }
+        let _ = sender.send(Message::Binary(resumed(replayed).encode_to_vec().into()));
+
+        // A socket still attached here is a stale one the client has abandoned
+        if let Some(stale) = inner.sender.replace(sender) {
+            let _ = stale.send(Message::Close(None));
+        }
+        inner.detached_at = None;
+        true
+    }
+
+    /// Mark the session disconnected if `sender` is still its socket. Events keep
+    /// being buffered until the session expires.
+    pub fn detach(&self, sender: &ConnectionSender) {
+        if let Ok(mut inner) = self.inner.lock() {
+            if inner
+                .sender
+                .as_ref()
+                .is_some_and(|s| s.same_channel(sender))
+            {
+                inner.sender = None;
+                inner.detached_at = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Whether a live socket is attached.
+    pub fn is_attached(&self) -> bool {
+        self.inner
+            .lock()
+            .map(|inner| inner.sender.as_ref().is_some_and(|s| !s.is_closed()))
+            .unwrap_or(false)
+    }
+
+    /// Messages waiting in the attached socket's outbound queue (None while detached).
+    pub fn queue_depth(&self) -> Option<usize> {
+        let inner = self.inner.lock().ok()?;
+        inner
+            .sender
+            .as_ref()
+            .filter(|s| !s.is_closed())
+            .map(|s| s.len())
+    }
+
+    /// Whether the session has been disconnected for longer than `RESUME_WINDOW`.
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.inner
+            .lock()
+            .map(|inner| {
+                inner
+                    .detached_at
+                    .is_some_and(|at| now.saturating_duration_since(at) > RESUME_WINDOW)
+            })
+            .unwrap_or(true)
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Append `Envelope.seq` to an encoded envelope. Envelopes are encoded once per event
+/// and shared by every recipient; protobuf decoders accept fields in any order, so the
+/// per-session number is added to the encoded bytes instead of re-encoding.
+fn with_seq(encoded: &[u8], seq: u64) -> Bytes {
+    let mut buf = Vec::with_capacity(encoded.len() + 11);
+    buf.extend_from_slice(encoded);
+    encode_key(SEQ_FIELD, WireType::Varint, &mut buf);
+    encode_varint(seq, &mut buf);
+    buf.into()
+}
+
+/// Drop sessions that have been disconnected for longer than `RESUME_WINDOW`.
+pub fn prune_expired(registry: &ConnectionRegistry) {
+    let now = Instant::now();
+    registry.retain(|_, connections| {
+        connections.retain(|c| !c.session.is_expired(now));
+        !connections.is_empty()
+    });
+}
+
+/// Spawn a background task that prunes expired sessions every minute.
+pub fn spawn_session_cleanup(registry: ConnectionRegistry) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            prune_expired(&registry);
+        }
+    });
+}
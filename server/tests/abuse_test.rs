@@ -0,0 +1,278 @@
+//! Integration tests for behavioral abuse detection.
+//! Tests cover: rapid registrations from one IP (loopback exempt), duplicate messages
+//! across channels, link-heavy first messages, machine-regular posting, mass DMs, and
+//! flag deduplication in `user_flags`.
+
+use std::net::IpAddr;
+
+use chrono::{DateTime, Duration, Utc};
+use united_server::moderation::abuse::{
+    record_flag, AbuseDetector, ActivityEvent, FlagCandidate, FlagKind,
+};
+
+fn registered(user_id: &str, ip: &str) -> ActivityEvent {
+    ActivityEvent::Registered {
+        user_id: user_id.to_string(),
+        ip: ip.parse::<IpAddr>().unwrap(),
+    }
+}
+
+fn message(user_id: &str, channel_id: &str, content: &str) -> ActivityEvent {
+    ActivityEvent::ChannelMessage {
+        user_id: user_id.to_string(),
+        channel_id: channel_id.to_string(),
+        content: content.to_string(),
+    }
+}
+
+fn kinds(flags: &[FlagCandidate]) -> Vec<FlagKind> {
+    flags.iter().map(|f| f.kind).collect()
+}
+
+fn start() -> DateTime<Utc> {
+    "2026-01-01T12:00:00Z".parse().unwrap()
+}
+
+#[test]
+fn test_rapid_registrations_from_one_ip_flag_every_account() {
+    let mut detector = AbuseDetector::new();
+    let t = start();
+
+    assert!(detector
+        .observe(&registered("a", "203.0.113.7"), t)
+        .is_empty());
+    assert!(detector
+        .observe(&registered("b", "203.0.113.7"), t + Duration::minutes(2))
+        .is_empty());
+    // A different address does not count towards the burst
+    assert!(detector
+        .observe(&registered("x", "198.51.100.1"), t + Duration::minutes(3))
+        .is_empty());
+
+    let flags = detector.observe(&registered("c", "203.0.113.7"), t + Duration::minutes(4));
+    let mut users: Vec<&str> = flags.iter().map(|f| f.user_id.as_str()).collect();
+    users.sort();
+    assert_eq!(users, vec!["a", "b", "c"]);
+    assert!(flags.iter().all(|f| f.kind == FlagKind::RapidRegistration));
+    assert!(flags[0].reason.contains("203.0.113.7"));
+
+    // Outside the window the count starts over
+    let later = t + Duration::minutes(30);
+    assert!(detector
+        .observe(&registered("d", "203.0.113.7"), later)
+        .is_empty());
+
+    // Loopback is shared by everyone behind a local proxy and never flags
+    let mut detector = AbuseDetector::new();
+    for (i, user) in ["p", "q", "r", "s"].iter().enumerate() {
+        let flags = detector.observe(
+            &registered(user, "127.0.0.1"),
+            t + Duration::seconds(i as i64),
+        );
+        assert!(flags.is_empty());
+    }
+}
+
+#[test]
+fn test_duplicate_messages_across_channels() {
+    let mut detector = AbuseDetector::new();
+    let t = start();
+    let spam = "Join my server for FREE nitro";
+
+    assert!(detector.observe(&message("u", "c1", spam), t).is_empty());
+    // Same channel again does not count as a new channel
+    assert!(detector
+        .observe(&message("u", "c1", spam), t + Duration::seconds(10))
+        .is_empty());
+    // Case and whitespace differences are normalized away
+    assert!(detector
+        .observe(
+            &message("u", "c2", "join my  server for free NITRO "),
+            t + Duration::seconds(20)
+        )
+        .is_empty());
+    let flags = detector.observe(&message("u", "c3", spam), t + Duration::seconds(30));
+    assert_eq!(kinds(&flags), vec![FlagKind::DuplicateMessages]);
+    assert_eq!(flags[0].user_id, "u");
+
+    // Short messages are never compared
+    let mut detector = AbuseDetector::new();
+    for (i, channel) in ["c1", "c2", "c3", "c4"].iter().enumerate() {
+        let flags = detector.observe(
+            &message("v", channel, "hello"),
+            t + Duration::seconds(i as i64 * 60),
+        );
+        assert!(flags.is_empty());
+    }
+
+    // Spread out beyond the window, the same text is fine
+    let mut detector = AbuseDetector::new();
+    for (i, channel) in ["c1", "c2", "c3"].iter().enumerate() {
+        let flags = detector.observe(
+            &message("w", channel, spam),
+            t + Duration::minutes(i as i64 * 10),
+        );
+        assert!(flags.is_empty());
+    }
+}
+
+#[test]
+fn test_link_heavy_first_messages_after_registration() {
+    let t = start();
+
+    let mut detector = AbuseDetector::new();
+    detector.observe(&registered("new", "127.0.0.1"), t);
+    assert!(detector
+        .observe(
+            &message("new", "c1", "check https://spam.example"),
+            t + Duration::minutes(1)
+        )
+        .is_empty());
+    assert!(detector
+        .observe(&message("new", "c1", "hi all"), t + Duration::minutes(3))
+        .is_empty());
+    let flags = detector.observe(
+        &message("new", "c2", "visit www.spam.example"),
+        t + Duration::minutes(5),
+    );
+    assert_eq!(kinds(&flags), vec![FlagKind::LinkHeavyFirstMessages]);
+
+    // Mostly conversation with the odd link is fine
+    let mut detector = AbuseDetector::new();
+    detector.observe(&registered("chatty", "127.0.0.1"), t);
+    let contents = [
+        "hello everyone",
+        "https://docs.example is useful",
+        "thanks!",
+        "see you later",
+        "http://a.example http://b.example",
+    ];
+    for (i, content) in contents.iter().enumerate() {
+        let flags = detector.observe(
+            &message("chatty", "c1", content),
+            t + Duration::minutes(i as i64 * 7),
+        );
+        assert!(flags.is_empty());
+    }
+
+    // Accounts registered before the analyzer started are not judged on links
+    let mut detector = AbuseDetector::new();
+    for (i, channel) in ["c1", "c2", "c3"].iter().enumerate() {
+        let link = format!("https://example.org/{}", i);
+        let flags = detector.observe(
+            &message("old", channel, &link),
+            t + Duration::minutes(i as i64 * 7),
+        );
+        assert!(flags.is_empty());
+    }
+}
+
+#[test]
+fn test_machine_regular_posting_cadence() {
+    let t = start();
+
+    let mut detector = AbuseDetector::new();
+    let mut flags = Vec::new();
+    for i in 0..8 {
+        let content = format!("update number {}", i);
+        flags = detector.observe(
+            &message("bot", &format!("c{}", i % 2), &content),
+            t + Duration::seconds(i * 30),
+        );
+        if i < 7 {
+            assert!(flags.is_empty());
+        }
+    }
+    assert_eq!(kinds(&flags), vec![FlagKind::RegularCadence]);
+
+    // Human-irregular spacing is fine
+    let mut detector = AbuseDetector::new();
+    let offsets = [0, 12, 50, 61, 140, 150, 230, 300, 302];
+    for (i, offset) in offsets.iter().enumerate() {
+        let content = format!("thought {}", i);
+        let flags = detector.observe(
+            &message("human", "c1", &content),
+            t + Duration::seconds(*offset),
+        );
+        assert!(flags.is_empty());
+    }
+}
+
+#[test]
+fn test_mass_dm_creation() {
+    let t = start();
+    let mut detector = AbuseDetector::new();
+
+    for i in 0..9 {
+        let flags = detector.observe(
+            &ActivityEvent::DmConversationCreated {
+                user_id: "dmer".to_string(),
+                recipient_pubkey: format!("pk{}", i),
+            },
+            t + Duration::seconds(i * 17),
+        );
+        assert!(flags.is_empty());
+    }
+    // Repeat messages to an existing recipient do not add up
+    let flags = detector.observe(
+        &ActivityEvent::DirectMessage {
+            user_id: "dmer".to_string(),
+            recipient_pubkey: "pk0".to_string(),
+        },
+        t + Duration::seconds(200),
+    );
+    assert!(flags.is_empty());
+
+    let flags = detector.observe(
+        &ActivityEvent::DmConversationCreated {
+            user_id: "dmer".to_string(),
+            recipient_pubkey: "pk9".to_string(),
+        },
+        t + Duration::seconds(211),
+    );
+    assert_eq!(kinds(&flags), vec![FlagKind::MassDm]);
+}
+
+#[test]
+fn test_record_flag_bumps_open_flag_instead_of_duplicating() {
+    let tmp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let db =
+        united_server::db::init_db(tmp_dir.path().to_str().unwrap()).expect("Failed to init DB");
+    let conn = db.lock().unwrap();
+    conn.execute(
+        "INSERT INTO users (id, public_key, fingerprint, display_name, created_at, updated_at)
+         VALUES ('u1', X'01', 'fp1', 'Spammer', '', '')",
+        [],
+    )
+    .unwrap();
+
+    let flag = |reason: &str| FlagCandidate {
+        user_id: "u1".to_string(),
+        kind: FlagKind::DuplicateMessages,
+        reason: reason.to_string(),
+    };
+    record_flag(&conn, &flag("first")).unwrap();
+    record_flag(&conn, &flag("second")).unwrap();
+
+    let (count, occurrences, reason): (i64, i64, String) = conn
+        .query_row(
+            "SELECT COUNT(*), MAX(occurrences), MAX(reason) FROM user_flags WHERE user_id = 'u1'",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .unwrap();
+    assert_eq!((count, occurrences, reason.as_str()), (1, 2, "second"));
+
+    // Once reviewed, a repeat opens a fresh flag
+    conn.execute("UPDATE user_flags SET status = 'reviewed'", [])
+        .unwrap();
+    record_flag(&conn, &flag("third")).unwrap();
+    let open: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM user_flags WHERE user_id = 'u1' AND status = 'open'",
+            [],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert_eq!(open, 1);
+}
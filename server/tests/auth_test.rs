@@ -39,6 +39,7 @@ async fn start_test_server() -> (String, SocketAddr) {
     let connections = united_server::ws::new_connection_registry();
 
     let (swarm_cmd_tx, _swarm_cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+    let abuse = united_server::moderation::abuse::AbuseMonitor::spawn(db.clone());
     let state = united_server::state::AppState {
         db,
         challenges: Arc::new(dashmap::DashMap::new()),
@@ -49,12 +50,22 @@ async fn start_test_server() -> (String, SocketAddr) {
         swarm_cmd_tx,
         peer_directory: Arc::new(united_server::p2p::PeerDirectory::new()),
         server_peer_id: "test-peer-id".to_string(),
+        server_signing_key: Arc::new(ed25519_dalek::SigningKey::from_bytes(&[7u8; 32])),
+        gossip_rejections: Arc::new(united_server::p2p::validation::GossipRejectionCounters::new()),
+        outbound_metrics: Arc::new(united_server::ws::outbound::OutboundMetrics::new()),
         libp2p_port: 0,
         presence: Arc::new(dashmap::DashMap::new()),
         data_dir: data_dir.clone(),
         block_retention_days: None,
         block_cleanup_interval_secs: None,
         max_upload_size_mb: None,
+        voice_state: Arc::new(united_server::voice::state::VoiceState::new()),
+        turn_config: None,
+        trust_config: None,
+        abuse,
+        rate_limiter: Arc::new(united_server::rate_limit::RateLimiter::new(
+            united_server::config::RateLimitConfig::default(),
+        )),
     };
 
     let app = united_server::routes::build_router(state);
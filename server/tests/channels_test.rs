@@ -1,6 +1,9 @@
 //! Integration tests for channel and category CRUD operations.
 //! Tests cover: starter template seeding, create/rename/delete channels,
-//! create/delete categories, reorder channels, and permission checks.
+//! create/delete categories, reorder channels, permission checks, channel
+//! permission overrides and who may set them, server-signed gossip publishing of REST
+//! chat events and their ingestion by a peer server that trusts the publisher, threads,
+//! message search, pinned messages, message retention policies, and edit history.
 
 use ed25519_dalek::{SigningKey, Signer};
 use rand::Rng;
@@ -18,6 +21,18 @@ fn random_signing_key() -> SigningKey {
 
 /// Helper: start the server on a random port and return (base_url, setup_token, addr).
 async fn start_test_server() -> (String, String, SocketAddr) {
+    let (base_url, setup_token, addr, _swarm_cmd_rx) = start_test_server_with_swarm().await;
+    (base_url, setup_token, addr)
+}
+
+/// Helper: like `start_test_server`, but also returns the swarm command receiver
+/// so tests can inspect what the server publishes to gossipsub.
+async fn start_test_server_with_swarm() -> (
+    String,
+    String,
+    SocketAddr,
+    tokio::sync::mpsc::UnboundedReceiver<united_server::p2p::SwarmCommand>,
+) {
     let tmp_dir = tempfile::tempdir().expect("Failed to create temp dir");
     let data_dir = tmp_dir.path().to_str().unwrap().to_string();
 
@@ -32,7 +47,8 @@ async fn start_test_server() -> (String, String, SocketAddr) {
 
     let connections = united_server::ws::new_connection_registry();
 
-    let (swarm_cmd_tx, _swarm_cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (swarm_cmd_tx, swarm_cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+    let abuse = united_server::moderation::abuse::AbuseMonitor::spawn(db.clone());
     let state = united_server::state::AppState {
         db,
         challenges: Arc::new(dashmap::DashMap::new()),
@@ -43,12 +59,22 @@ async fn start_test_server() -> (String, String, SocketAddr) {
         swarm_cmd_tx,
         peer_directory: Arc::new(united_server::p2p::PeerDirectory::new()),
         server_peer_id: "test-peer-id".to_string(),
+        server_signing_key: Arc::new(ed25519_dalek::SigningKey::from_bytes(&[7u8; 32])),
+        gossip_rejections: Arc::new(united_server::p2p::validation::GossipRejectionCounters::new()),
+        outbound_metrics: Arc::new(united_server::ws::outbound::OutboundMetrics::new()),
         libp2p_port: 0,
         presence: Arc::new(dashmap::DashMap::new()),
         data_dir: data_dir.clone(),
         block_retention_days: None,
         block_cleanup_interval_secs: None,
         max_upload_size_mb: None,
+        voice_state: Arc::new(united_server::voice::state::VoiceState::new()),
+        turn_config: None,
+        trust_config: None,
+        abuse,
+        rate_limiter: Arc::new(united_server::rate_limit::RateLimiter::new(
+            united_server::config::RateLimitConfig::default(),
+        )),
     };
 
     let app = united_server::routes::build_router(state);
@@ -66,7 +92,7 @@ async fn start_test_server() -> (String, String, SocketAddr) {
     });
 
     let base_url = format!("http://{}", addr);
-    (base_url, setup_token, addr)
+    (base_url, setup_token, addr, swarm_cmd_rx)
 }
 
 /// Register a user as owner (with setup_token) and return (access_token, user_id).
@@ -105,6 +131,11 @@ async fn register_owner(base_url: &str, setup_token: &str) -> (String, String) {
 
 /// Register a non-owner user (open registration, no setup_token) and return access_token.
 async fn register_regular_user(base_url: &str, name: &str) -> String {
+    register_regular_user_with_id(base_url, name).await.0
+}
+
+/// Register a non-owner user and return (access_token, user_id).
+async fn register_regular_user_with_id(base_url: &str, name: &str) -> (String, String) {
     let client = reqwest::Client::new();
     let signing_key = random_signing_key();
     let verifying_key = signing_key.verifying_key();
@@ -131,7 +162,10 @@ async fn register_regular_user(base_url: &str, name: &str) -> String {
 
     assert_eq!(resp.status(), 200, "User registration failed for {}", name);
     let body: serde_json::Value = resp.json().await.unwrap();
-    body["access_token"].as_str().unwrap().to_string()
+    (
+        body["access_token"].as_str().unwrap().to_string(),
+        body["user_id"].as_str().unwrap().to_string(),
+    )
 }
 
 // =============================================================================
@@ -514,3 +548,1711 @@ async fn test_channel_crud_requires_manage_channels_permission() {
         "Non-admin user should get 403 FORBIDDEN for channel creation"
     );
 }
+
+#[tokio::test]
+async fn test_rest_chat_events_are_published_as_signed_gossip() {
+    use united_server::p2p::messages::decode_and_verify_gossip_envelope;
+    use united_server::p2p::publish::channel_topic;
+    use united_server::p2p::SwarmCommand;
+    use united_server::proto::p2p_proto::MessageType;
+
+    let (base_url, setup_token, _addr, mut swarm_cmd_rx) = start_test_server_with_swarm().await;
+    let (token, _user_id) = register_owner(&base_url, &setup_token).await;
+    let client = reqwest::Client::new();
+
+    // Find #general from the starter template
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let channel_id = body["categories"][0]["channels"][0]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // Ignore anything published before the chat events (e.g. topic subscriptions)
+    while swarm_cmd_rx.try_recv().is_ok() {}
+
+    let resp = client
+        .post(format!("{}/api/channels/{}/messages", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "content": "hello mesh" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let message: serde_json::Value = resp.json().await.unwrap();
+    let message_id = message["id"].as_str().unwrap().to_string();
+
+    let resp = client
+        .post(format!("{}/api/messages/{}/reactions", base_url, message_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "emoji": "👍" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+
+    let resp = client
+        .delete(format!(
+            "{}/api/channels/{}/messages/{}",
+            base_url, channel_id, message_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let expected_topic = channel_topic("test-peer-id", &channel_id);
+    let server_pubkey = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32])
+        .verifying_key()
+        .to_bytes()
+        .to_vec();
+
+    let mut published_types = Vec::new();
+    while let Ok(cmd) = swarm_cmd_rx.try_recv() {
+        if let SwarmCommand::Publish { topic, data } = cmd {
+            assert_eq!(topic, expected_topic);
+            let envelope = decode_and_verify_gossip_envelope(&data)
+                .expect("Server-published envelope should verify");
+            assert_eq!(envelope.topic, expected_topic);
+            assert_eq!(envelope.sender_pubkey, server_pubkey);
+            published_types.push(envelope.message_type);
+        }
+    }
+
+    assert_eq!(
+        published_types,
+        vec![
+            MessageType::Chat as i32,
+            MessageType::ReactionAdd as i32,
+            MessageType::Delete as i32,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_published_rest_events_are_applied_by_a_trusting_peer() {
+    use united_server::p2p::messages::{
+        decode_and_verify_gossip_envelope, handle_gossip_message, GossipOutcome,
+    };
+    use united_server::p2p::validation::{RejectReason, TrustedServers};
+    use united_server::p2p::SwarmCommand;
+    use united_server::proto::chat as proto_chat;
+
+    let (base_url, setup_token, _addr, mut swarm_cmd_rx) = start_test_server_with_swarm().await;
+    let (token, _user_id) = register_owner(&base_url, &setup_token).await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let channel_id = body["categories"][0]["channels"][0]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    while swarm_cmd_rx.try_recv().is_ok() {}
+
+    // Post, edit, react to and delete a message over REST
+    let resp = client
+        .post(format!("{}/api/channels/{}/messages", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "content": "hello peers" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let message: serde_json::Value = resp.json().await.unwrap();
+    let message_id = message["id"].as_str().unwrap().to_string();
+    let message_url = format!("{}/api/channels/{}/messages/{}", base_url, channel_id, message_id);
+
+    let resp = client
+        .put(&message_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "content": "hello again" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .post(format!("{}/api/messages/{}/reactions", base_url, message_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "emoji": "👍" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let resp = client
+        .delete(&message_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let mut envelopes = Vec::new();
+    while let Ok(cmd) = swarm_cmd_rx.try_recv() {
+        if let SwarmCommand::Publish { data, .. } = cmd {
+            envelopes.push(decode_and_verify_gossip_envelope(&data).unwrap());
+        }
+    }
+    assert_eq!(envelopes.len(), 4);
+    let owner_hex = prost::Message::decode(envelopes[0].payload.as_slice())
+        .map(|message: proto_chat::ChatMessage| message.sender_pubkey)
+        .unwrap();
+
+    // A peer server that knows the channel and the owner, but has its own identity
+    let peer_dir = tempfile::tempdir().unwrap();
+    let peer_db = united_server::db::init_db(peer_dir.path().to_str().unwrap()).unwrap();
+    {
+        let conn = peer_db.lock().unwrap();
+        conn.execute(
+            "INSERT INTO categories (id, name, position, created_at) VALUES ('cat', 'Text', 0, '')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO channels (id, name, channel_type, category_id, position, created_at)
+             VALUES (?1, 'general', 'text', 'cat', 0, '')",
+            [&channel_id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO roles (id, name, permissions, position, is_default, created_at, updated_at)
+             VALUES ('everyone', '@everyone', ?1, 0, 1, '', '')",
+            [united_server::roles::permissions::Permissions::DEFAULT_EVERYONE.bits()],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO users (id, public_key, fingerprint, display_name, created_at, updated_at)
+             VALUES ('owner', ?1, 'FP-owner', 'Owner', '', '')",
+            [hex::decode(&owner_hex).unwrap()],
+        )
+        .unwrap();
+    }
+    let peer_key = random_signing_key().verifying_key();
+    let publisher_hex = hex::encode(envelopes[0].sender_pubkey.as_slice());
+
+    // Without trusting the publisher, its envelopes name no registered sender
+    let untrusting = TrustedServers::new(peer_key.as_bytes(), &[]);
+    match handle_gossip_message(&peer_db, &untrusting, None, &envelopes[0]) {
+        Err(e) => assert_eq!(e.reject_reason(), Some(RejectReason::UnknownSender)),
+        Ok(_) => panic!("Expected the untrusted publisher to be rejected"),
+    }
+
+    // Trusting it, each event is applied as the owner who made it
+    let trusting = TrustedServers::new(peer_key.as_bytes(), &[publisher_hex]);
+    let outcomes: Vec<GossipOutcome> = envelopes
+        .iter()
+        .map(|envelope| handle_gossip_message(&peer_db, &trusting, None, envelope).unwrap())
+        .collect();
+    match &outcomes[..] {
+        [
+            GossipOutcome::Message(stored),
+            GossipOutcome::Edited(edited),
+            GossipOutcome::ReactionAdded { event, .. },
+            GossipOutcome::Deleted(deleted),
+        ] => {
+            assert_eq!(stored.chat_message.as_ref().unwrap().sender_pubkey, owner_hex);
+            assert_eq!(edited.editor_pubkey, owner_hex);
+            assert_eq!(event.reaction.as_ref().unwrap().user_pubkey, owner_hex);
+            assert_eq!(deleted.deleted_by_pubkey, owner_hex);
+        }
+        _ => panic!("Expected a message, edit, reaction and delete"),
+    }
+
+    let conn = peer_db.lock().unwrap();
+    let (sender, content, edited, was_deleted): (String, String, bool, bool) = conn
+        .query_row(
+            "SELECT sender_pubkey, content_text, edited, deleted FROM messages",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+        )
+        .unwrap();
+    assert_eq!(sender, owner_hex);
+    assert_eq!((content.as_str(), edited, was_deleted), ("hello again", true, true));
+    let reactor: String = conn
+        .query_row("SELECT user_pubkey FROM reactions", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(reactor, owner_hex);
+}
+
+#[tokio::test]
+async fn test_channel_overrides_make_private_channels() {
+    let (base_url, setup_token, _addr) = start_test_server().await;
+    let (owner_token, _owner_id) = register_owner(&base_url, &setup_token).await;
+    let (user_token, _user_id) = register_regular_user_with_id(&base_url, "Member").await;
+    let (staff_token, staff_id) = register_regular_user_with_id(&base_url, "Staff").await;
+    let client = reqwest::Client::new();
+
+    // Create a text channel and find the @everyone role
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let category_id = body["categories"][0]["category"]["id"].as_str().unwrap().to_string();
+
+    let resp = client
+        .post(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "name": "staff", "channel_type": "text", "category_id": category_id }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let channel: serde_json::Value = resp.json().await.unwrap();
+    let channel_id = channel["id"].as_str().unwrap().to_string();
+
+    let resp = client
+        .get(format!("{}/api/roles", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let everyone_id = body["roles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["is_default"].as_bool() == Some(true))
+        .unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // Regular users cannot manage overrides
+    let resp = client
+        .put(format!("{}/api/channels/{}/overrides/role/{}", base_url, channel_id, everyone_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "deny": 0x20 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    // A bit cannot be both allowed and denied
+    let resp = client
+        .put(format!("{}/api/channels/{}/overrides/role/{}", base_url, channel_id, everyone_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "allow": 0x20, "deny": 0x20 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    // Hide the channel from @everyone, then let Staff back in
+    let resp = client
+        .put(format!("{}/api/channels/{}/overrides/role/{}", base_url, channel_id, everyone_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "deny": 0x20 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .put(format!("{}/api/channels/{}/overrides/user/{}", base_url, channel_id, staff_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "allow": 0x21 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .get(format!("{}/api/channels/{}/overrides", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let overrides: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(overrides.as_array().unwrap().len(), 2);
+
+    // Hidden from the regular member: not listed, no history, no posting
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let listed: Vec<&str> = body["categories"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|c| c["channels"].as_array().unwrap())
+        .map(|ch| ch["id"].as_str().unwrap())
+        .collect();
+    assert!(!listed.contains(&channel_id.as_str()));
+
+    let resp = client
+        .get(format!("{}/api/channels/{}/messages", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    let resp = client
+        .post(format!("{}/api/channels/{}/messages", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "content": "let me in" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    // Staff user override wins over the @everyone deny
+    let resp = client
+        .post(format!("{}/api/channels/{}/messages", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", staff_token))
+        .json(&json!({ "content": "staff only" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+
+    let resp = client
+        .get(format!("{}/api/channels/{}/messages", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", staff_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Removing the @everyone override makes the channel public again
+    let resp = client
+        .delete(format!("{}/api/channels/{}/overrides/role/{}", base_url, channel_id, everyone_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .get(format!("{}/api/channels/{}/messages", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_channel_managers_cannot_override_beyond_their_authority() {
+    let (base_url, setup_token, _addr) = start_test_server().await;
+    let (owner_token, owner_id) = register_owner(&base_url, &setup_token).await;
+    let (manager_token, manager_id) = register_regular_user_with_id(&base_url, "Manager").await;
+    let (_member_token, member_id) = register_regular_user_with_id(&base_url, "Member").await;
+    let client = reqwest::Client::new();
+
+    // Manager (MANAGE_CHANNELS) ends up above the later-created Helper role
+    let mut role_ids = Vec::new();
+    for (name, permissions) in [("Manager", 0x02), ("Helper", 0)] {
+        let resp = client
+            .post(format!("{}/api/roles", base_url))
+            .header("Authorization", format!("Bearer {}", owner_token))
+            .json(&json!({ "name": name, "permissions": permissions }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 201);
+        let role: serde_json::Value = resp.json().await.unwrap();
+        role_ids.push(role["id"].as_str().unwrap().to_string());
+    }
+    let (manager_role, helper_role) = (&role_ids[0], &role_ids[1]);
+    let resp = client
+        .post(format!("{}/api/roles/assign", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "user_id": manager_id, "role_id": manager_role }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let channel_id = body["categories"][0]["channels"][0]["id"].as_str().unwrap().to_string();
+    let overrides_url = format!("{}/api/channels/{}/overrides", base_url, channel_id);
+
+    let set = |token: &str, target: String, body: serde_json::Value| {
+        client
+            .put(format!("{}/{}", overrides_url, target))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&body)
+            .send()
+    };
+    let delete = |token: &str, target: String| {
+        client
+            .delete(format!("{}/{}", overrides_url, target))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+
+    // No granting bits the manager lacks in the channel, to anyone including themselves
+    let resp = set(&manager_token, format!("user/{}", manager_id), json!({ "allow": 0x80 }))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp = set(&manager_token, format!("user/{}", member_id), json!({ "allow": 0x80 }))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    // No overriding their own role, roles above it, or the owner
+    let resp = set(&manager_token, format!("role/{}", manager_role), json!({ "allow": 0x01 }))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp = set(&manager_token, format!("user/{}", owner_id), json!({ "deny": 0x20 }))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    // Held bits on a lower role are fine
+    let resp = set(&manager_token, format!("role/{}", helper_role), json!({ "deny": 0x20 }))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Removing an override needs the same authority as setting it
+    let resp = set(&owner_token, format!("role/{}", manager_role), json!({ "deny": 0x01 }))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = set(&owner_token, format!("user/{}", member_id), json!({ "allow": 0x80 }))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = delete(&manager_token, format!("role/{}", manager_role)).await.unwrap();
+    assert_eq!(resp.status(), 403);
+    assert_eq!(delete(&manager_token, format!("user/{}", member_id)).await.unwrap().status(), 403);
+    let resp = delete(&manager_token, format!("role/{}", helper_role)).await.unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_slowmode_throttles_members_but_not_message_managers() {
+    let (base_url, setup_token, _addr) = start_test_server().await;
+    let (owner_token, _owner_id) = register_owner(&base_url, &setup_token).await;
+    let (user_token, _user_id) = register_regular_user_with_id(&base_url, "Member").await;
+    let (staff_token, staff_id) = register_regular_user_with_id(&base_url, "Staff").await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let category_id = body["categories"][0]["category"]["id"].as_str().unwrap().to_string();
+
+    let resp = client
+        .post(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "name": "busy", "channel_type": "text", "category_id": category_id }))
+        .send()
+        .await
+        .unwrap();
+    let channel: serde_json::Value = resp.json().await.unwrap();
+    let channel_id = channel["id"].as_str().unwrap().to_string();
+    assert_eq!(channel["slowmode_secs"], 0);
+
+    let channel_url = format!("{}/api/channels/{}", base_url, channel_id);
+    let set_slowmode = |token: &str, secs: u32| {
+        client
+            .put(&channel_url)
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "slowmode_secs": secs }))
+            .send()
+    };
+
+    // Only channel managers can set it, and within the limit
+    assert_eq!(set_slowmode(&user_token, 60).await.unwrap().status(), 403);
+    assert_eq!(set_slowmode(&owner_token, 6 * 60 * 60 + 1).await.unwrap().status(), 400);
+    let resp = set_slowmode(&owner_token, 60).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["slowmode_secs"], 60);
+    assert_eq!(body["name"], "busy", "Name is unchanged when omitted");
+
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let listed = body["categories"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|c| c["channels"].as_array().unwrap())
+        .find(|c| c["id"] == channel_id.as_str())
+        .unwrap()
+        .clone();
+    assert_eq!(listed["slowmode_secs"], 60);
+
+    let post = |token: &str, content: &str| {
+        client
+            .post(format!("{}/api/channels/{}/messages", base_url, channel_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "content": content }))
+            .send()
+    };
+
+    // A member gets one message per interval and is told how long to wait
+    assert_eq!(post(&user_token, "first").await.unwrap().status(), 201);
+    let resp = post(&user_token, "second").await.unwrap();
+    assert_eq!(resp.status(), 429);
+    let error = resp.text().await.unwrap();
+    assert!(error.starts_with("slowmode:"), "{}", error);
+    assert!(
+        error.contains("try again in 60 seconds") || error.contains("try again in 59 seconds"),
+        "{}",
+        error
+    );
+
+    // The owner and members with MANAGE_MESSAGES in the channel are exempt
+    let resp = client
+        .put(format!("{}/overrides/user/{}", channel_url, staff_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "allow": 0x80 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    for token in [&owner_token, &owner_token, &staff_token, &staff_token] {
+        assert_eq!(post(token, "moderating").await.unwrap().status(), 201);
+    }
+
+    // Threads inherit the channel's interval, with a cooldown of their own
+    let resp = post(&owner_token, "thread root").await.unwrap();
+    let root: serde_json::Value = resp.json().await.unwrap();
+    let resp = client
+        .post(format!("{}/api/channels/{}/threads", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "root_message_id": root["id"], "name": "Side topic" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let thread: serde_json::Value = resp.json().await.unwrap();
+    let reply = |token: &str, content: &str| {
+        client
+            .post(format!("{}/api/threads/{}/messages", base_url, thread["id"].as_str().unwrap()))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "content": content }))
+            .send()
+    };
+    assert_eq!(reply(&user_token, "first reply").await.unwrap().status(), 201);
+    let resp = reply(&user_token, "second reply").await.unwrap();
+    assert_eq!(resp.status(), 429);
+    assert!(resp.text().await.unwrap().starts_with("slowmode:"));
+    assert_eq!(reply(&staff_token, "moderating").await.unwrap().status(), 201);
+
+    // Turning slow mode off lifts the cooldown
+    assert_eq!(set_slowmode(&owner_token, 0).await.unwrap().status(), 200);
+    assert_eq!(post(&user_token, "second").await.unwrap().status(), 201);
+}
+
+#[tokio::test]
+async fn test_announcement_and_forum_channels() {
+    let (base_url, setup_token, _addr) = start_test_server().await;
+    let (owner_token, _owner_id) = register_owner(&base_url, &setup_token).await;
+    let user_token = register_regular_user(&base_url, "Reader").await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let category_id = body["categories"][0]["category"]["id"].as_str().unwrap().to_string();
+
+    let create = |name: &str, channel_type: &str| {
+        client
+            .post(format!("{}/api/channels", base_url))
+            .header("Authorization", format!("Bearer {}", owner_token))
+            .json(&json!({ "name": name, "channel_type": channel_type, "category_id": category_id }))
+            .send()
+    };
+    assert_eq!(create("bogus", "gallery").await.unwrap().status(), 400);
+
+    let resp = create("news", "announcement").await.unwrap();
+    assert_eq!(resp.status(), 201);
+    let news: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(news["channel_type"], "announcement");
+    let news_id = news["id"].as_str().unwrap();
+
+    let resp = create("help", "forum").await.unwrap();
+    assert_eq!(resp.status(), 201);
+    let forum: serde_json::Value = resp.json().await.unwrap();
+    let forum_id = forum["id"].as_str().unwrap();
+
+    let post = |token: &str, channel_id: &str, body: serde_json::Value| {
+        client
+            .post(format!("{}/api/channels/{}/messages", base_url, channel_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&body)
+            .send()
+    };
+
+    // Announcements: moderators post, everyone else reads and reacts
+    let resp = post(&user_token, news_id, json!({ "content": "me too" })).await.unwrap();
+    assert_eq!(resp.status(), 403);
+    assert!(resp.text().await.unwrap().starts_with("announcement_channel:"));
+
+    let resp = post(&owner_token, news_id, json!({ "content": "v2 is out" })).await.unwrap();
+    assert_eq!(resp.status(), 201);
+    let announcement: serde_json::Value = resp.json().await.unwrap();
+    assert!(announcement.get("started_thread").is_none());
+
+    let resp = client
+        .post(format!(
+            "{}/api/messages/{}/reactions",
+            base_url,
+            announcement["id"].as_str().unwrap()
+        ))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "emoji": "🎉" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(resp.status().is_success());
+
+    let resp = client
+        .get(format!("{}/api/channels/{}/messages", base_url, news_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    let history: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(history["messages"].as_array().unwrap().len(), 1);
+
+    // Forums: every top-level post needs a title and starts a thread
+    let resp = post(&user_token, forum_id, json!({ "content": "it broke" })).await.unwrap();
+    assert_eq!(resp.status(), 400);
+    assert!(resp.text().await.unwrap().starts_with("forum_title_required:"));
+
+    let resp = post(
+        &user_token,
+        forum_id,
+        json!({ "content": "it broke", "title": "Crash on startup" }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), 201);
+    let forum_post: serde_json::Value = resp.json().await.unwrap();
+    let thread = &forum_post["started_thread"];
+    assert_eq!(thread["name"], "Crash on startup");
+    assert_eq!(thread["root_message_id"], forum_post["id"]);
+    let thread_id = thread["id"].as_str().unwrap();
+
+    let resp = client
+        .post(format!("{}/api/threads/{}/messages", base_url, thread_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "content": "Which version?" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+
+    let resp = client
+        .get(format!("{}/api/channels/{}/threads", base_url, forum_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    let threads: serde_json::Value = resp.json().await.unwrap();
+    let threads = threads["threads"].as_array().unwrap();
+    assert_eq!(threads.len(), 1);
+    assert_eq!(threads[0]["message_count"], 1);
+}
+
+#[tokio::test]
+async fn test_threads_are_separate_sub_conversations() {
+    use united_server::p2p::publish::thread_topic;
+    use united_server::p2p::SwarmCommand;
+
+    let (base_url, setup_token, _addr, mut swarm_cmd_rx) = start_test_server_with_swarm().await;
+    let (owner_token, _owner_id) = register_owner(&base_url, &setup_token).await;
+    let (user_token, _user_id) = register_regular_user_with_id(&base_url, "Member").await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let channel_id = body["categories"][0]["channels"][0]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let resp = client
+        .post(format!("{}/api/channels/{}/messages", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "content": "long discussion starts here" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let root: serde_json::Value = resp.json().await.unwrap();
+    let root_id = root["id"].as_str().unwrap().to_string();
+
+    while swarm_cmd_rx.try_recv().is_ok() {}
+
+    // Start a thread on the message; a second thread on the same root conflicts
+    let resp = client
+        .post(format!("{}/api/channels/{}/threads", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "root_message_id": root_id, "name": "Side topic" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let thread: serde_json::Value = resp.json().await.unwrap();
+    let thread_id = thread["id"].as_str().unwrap().to_string();
+    assert!(!thread["archived"].as_bool().unwrap());
+
+    let resp = client
+        .post(format!("{}/api/channels/{}/threads", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "root_message_id": root_id, "name": "Duplicate" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 409);
+
+    // Thread messages have their own sequence and stay out of channel history
+    for (i, content) in ["first reply", "second reply"].iter().enumerate() {
+        let resp = client
+            .post(format!("{}/api/threads/{}/messages", base_url, thread_id))
+            .header("Authorization", format!("Bearer {}", user_token))
+            .json(&json!({ "content": content }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 201);
+        let msg: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(msg["server_sequence"].as_u64().unwrap(), i as u64 + 1);
+        assert_eq!(msg["thread_id"].as_str().unwrap(), thread_id);
+    }
+
+    let resp = client
+        .get(format!("{}/api/threads/{}/messages", base_url, thread_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let history: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(history["messages"].as_array().unwrap().len(), 2);
+
+    let resp = client
+        .get(format!("{}/api/channels/{}/messages", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    let history: serde_json::Value = resp.json().await.unwrap();
+    let contents: Vec<&str> = history["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["content"].as_str().unwrap())
+        .collect();
+    assert_eq!(contents, vec!["long discussion starts here"]);
+
+    let resp = client
+        .get(format!("{}/api/channels/{}/threads", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    let list: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(list["threads"][0]["message_count"].as_u64().unwrap(), 2);
+
+    // Only MANAGE_MESSAGES holders can lock; a locked thread rejects regular posts
+    let resp = client
+        .put(format!("{}/api/threads/{}", base_url, thread_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "locked": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    let resp = client
+        .put(format!("{}/api/threads/{}", base_url, thread_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "locked": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .post(format!("{}/api/threads/{}/messages", base_url, thread_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "content": "still here?" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    // Archiving makes the thread read-only and leaves its gossip topic
+    let resp = client
+        .put(format!("{}/api/threads/{}", base_url, thread_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "archived": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let updated: serde_json::Value = resp.json().await.unwrap();
+    assert!(updated["archived"].as_bool().unwrap());
+
+    let resp = client
+        .post(format!("{}/api/threads/{}/messages", base_url, thread_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "content": "after archive" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 409);
+
+    let expected_topic = thread_topic("test-peer-id", &thread_id);
+    let mut subscribed = false;
+    let mut published = 0;
+    let mut unsubscribed = false;
+    while let Ok(cmd) = swarm_cmd_rx.try_recv() {
+        match cmd {
+            SwarmCommand::SubscribeTopic(topic) if topic == expected_topic => subscribed = true,
+            SwarmCommand::Publish { topic, .. } if topic == expected_topic => published += 1,
+            SwarmCommand::UnsubscribeTopic(topic) if topic == expected_topic => unsubscribed = true,
+            _ => {}
+        }
+    }
+    assert!(subscribed, "Server should join the thread topic on creation");
+    assert_eq!(published, 2, "Thread messages should be published on the thread topic");
+    assert!(unsubscribed, "Server should leave the thread topic when archived");
+}
+
+#[tokio::test]
+async fn test_search_respects_filters_and_channel_visibility() {
+    let (base_url, setup_token, _addr) = start_test_server().await;
+    let (owner_token, _owner_id) = register_owner(&base_url, &setup_token).await;
+    let user_token = register_regular_user(&base_url, "Member").await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let category_id = body["categories"][0]["category"]["id"].as_str().unwrap().to_string();
+    let general_id = body["categories"][0]["channels"][0]["id"].as_str().unwrap().to_string();
+
+    // A channel hidden from @everyone
+    let resp = client
+        .post(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "name": "secret", "channel_type": "text", "category_id": category_id }))
+        .send()
+        .await
+        .unwrap();
+    let channel: serde_json::Value = resp.json().await.unwrap();
+    let secret_id = channel["id"].as_str().unwrap().to_string();
+
+    let resp = client
+        .get(format!("{}/api/roles", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let everyone_id = body["roles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["is_default"].as_bool() == Some(true))
+        .unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let resp = client
+        .put(format!("{}/api/channels/{}/overrides/role/{}", base_url, secret_id, everyone_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "deny": 0x20 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let post = |token: &str, channel_id: &str, content: &str| {
+        client
+            .post(format!("{}/api/channels/{}/messages", base_url, channel_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "content": content }))
+            .send()
+    };
+    let resp = post(&owner_token, &secret_id, "launch plans are ready").await.unwrap();
+    assert_eq!(resp.status(), 201);
+    let resp = post(&user_token, &general_id, "launch party tonight").await.unwrap();
+    assert_eq!(resp.status(), 201);
+    let party: serde_json::Value = resp.json().await.unwrap();
+    let party_id = party["id"].as_str().unwrap().to_string();
+    let resp = post(&user_token, &general_id, "unrelated chatter").await.unwrap();
+    assert_eq!(resp.status(), 201);
+
+    let search = |token: &str, q: &str| {
+        client
+            .get(format!("{}/api/search", base_url))
+            .query(&[("q", q)])
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+    let count = |body: serde_json::Value| body["messages"].as_array().unwrap().len();
+
+    // Hidden channel results are filtered out for the member
+    let resp = search(&owner_token, "launch").await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(count(resp.json().await.unwrap()), 2);
+    let resp = search(&user_token, "launch").await.unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(count(body.clone()), 1);
+    assert_eq!(body["messages"][0]["id"].as_str().unwrap(), party_id);
+    assert_eq!(body["messages"][0]["sender_display_name"], "Member");
+
+    // Filters
+    let resp = search(&user_token, "from:member").await.unwrap();
+    assert_eq!(count(resp.json().await.unwrap()), 2);
+    let resp = search(&owner_token, "launch in:#secret").await.unwrap();
+    assert_eq!(count(resp.json().await.unwrap()), 1);
+    let resp = search(&user_token, "launch has:attachment").await.unwrap();
+    assert_eq!(count(resp.json().await.unwrap()), 0);
+    let resp = search(&user_token, "launch after:2000-01-01 before:2999-01-01").await.unwrap();
+    assert_eq!(count(resp.json().await.unwrap()), 1);
+    let resp = search(&user_token, "launch before:2000-01-01").await.unwrap();
+    assert_eq!(count(resp.json().await.unwrap()), 0);
+
+    // Malformed queries
+    for q in ["", "   ", "launch before:yesterday", "has:pizza"] {
+        let resp = search(&user_token, q).await.unwrap();
+        assert_eq!(resp.status(), 400, "query {:?}", q);
+    }
+
+    // Edits and deletes keep the index in sync
+    let resp = client
+        .put(format!("{}/api/channels/{}/messages/{}", base_url, general_id, party_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "content": "picnic tonight" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = search(&user_token, "launch").await.unwrap();
+    assert_eq!(count(resp.json().await.unwrap()), 0);
+    let resp = search(&user_token, "picnic").await.unwrap();
+    assert_eq!(count(resp.json().await.unwrap()), 1);
+
+    let resp = client
+        .delete(format!("{}/api/channels/{}/messages/{}", base_url, general_id, party_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    assert!(resp.status().is_success());
+    let resp = search(&user_token, "picnic").await.unwrap();
+    assert_eq!(count(resp.json().await.unwrap()), 0);
+}
+
+#[tokio::test]
+async fn test_pin_and_unpin_messages() {
+    let (base_url, setup_token, _addr) = start_test_server().await;
+    let (owner_token, _owner_id) = register_owner(&base_url, &setup_token).await;
+    let (user_token, user_id) = register_regular_user_with_id(&base_url, "Member").await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let channel_id = body["categories"][0]["channels"][0]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let resp = client
+        .post(format!("{}/api/channels/{}/messages", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "content": "house rules: be kind" }))
+        .send()
+        .await
+        .unwrap();
+    let message: serde_json::Value = resp.json().await.unwrap();
+    let message_id = message["id"].as_str().unwrap().to_string();
+    let pin_url = format!("{}/api/channels/{}/pins/{}", base_url, channel_id, message_id);
+
+    // Pinning requires MANAGE_MESSAGES
+    let resp = client
+        .put(&pin_url)
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    let resp = client
+        .put(&pin_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let pin: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(pin["message"]["id"].as_str().unwrap(), message_id);
+    assert_ne!(pin["pinned_by"].as_str().unwrap(), user_id);
+
+    // Pinning again is a no-op; an unknown message is not found
+    let resp = client
+        .put(&pin_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .put(format!("{}/api/channels/{}/pins/999999", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+
+    // Any member who can read the channel can list pins
+    let resp = client
+        .get(format!("{}/api/channels/{}/pins", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let pins = body["pins"].as_array().unwrap();
+    assert_eq!(pins.len(), 1);
+    assert_eq!(pins[0]["message"]["content"], "house rules: be kind");
+
+    let resp = client
+        .delete(&pin_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .delete(&pin_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+
+    let resp = client
+        .get(format!("{}/api/channels/{}/pins", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert!(body["pins"].as_array().unwrap().is_empty());
+
+    // The pin and the unpin are audited once each, citing the message
+    let resp = client
+        .get(format!("{}/api/audit-log?target_id={}", base_url, message_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let log: serde_json::Value = resp.json().await.unwrap();
+    let actions: Vec<&str> = log["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["action"].as_str().unwrap())
+        .collect();
+    assert_eq!(actions, vec!["message_unpin", "message_pin"]);
+    assert_eq!(log["entries"][0]["evidence_message_ids"], json!([message_id]));
+}
+
+#[tokio::test]
+async fn test_deleting_another_members_message_is_audited() {
+    let (base_url, setup_token, _addr) = start_test_server().await;
+    let (owner_token, owner_id) = register_owner(&base_url, &setup_token).await;
+    let user_token = register_regular_user(&base_url, "Member").await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let channel_id = body["categories"][0]["channels"][0]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let mut message_ids = Vec::new();
+    for content in ["my own typo", "something rude"] {
+        let resp = client
+            .post(format!("{}/api/channels/{}/messages", base_url, channel_id))
+            .header("Authorization", format!("Bearer {}", user_token))
+            .json(&json!({ "content": content }))
+            .send()
+            .await
+            .unwrap();
+        let message: serde_json::Value = resp.json().await.unwrap();
+        message_ids.push(message["id"].as_str().unwrap().to_string());
+    }
+
+    // The author deletes their own message, the owner removes the other one
+    for (token, message_id) in [(&user_token, &message_ids[0]), (&owner_token, &message_ids[1])] {
+        let resp = client
+            .delete(format!(
+                "{}/api/channels/{}/messages/{}",
+                base_url, channel_id, message_id
+            ))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+
+    let resp = client
+        .get(format!("{}/api/audit-log?action=message_delete", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let log: serde_json::Value = resp.json().await.unwrap();
+    let entries = log["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["actor_id"].as_str().unwrap(), owner_id);
+    assert_eq!(entries[0]["target_type"], "message");
+    assert_eq!(entries[0]["target_id"].as_str().unwrap(), message_ids[1]);
+    assert_eq!(entries[0]["evidence_message_ids"], json!([message_ids[1]]));
+    assert_eq!(entries[0]["before"]["channel_id"].as_str().unwrap(), channel_id);
+}
+
+#[test]
+fn test_blocks_of_pinned_messages_survive_retention() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let data_dir = tmp_dir.path().to_str().unwrap().to_string();
+    let db = united_server::db::init_db(&data_dir).unwrap();
+
+    db.lock()
+        .unwrap()
+        .execute_batch(
+            "INSERT INTO categories (id, name, position, created_at) VALUES ('cat', 'General', 0, '2000-01-01');
+             INSERT INTO channels (id, name, category_id, created_at) VALUES ('chan', 'general', 'cat', '2000-01-01');
+             INSERT INTO messages (id, channel_id, sender_pubkey, timestamp, server_sequence, signature, block_refs_json)
+                 VALUES (1, 'chan', 'aa', 0, 1, X'', '[{\"hash\":\"pinned\"}]'),
+                        (2, 'chan', 'aa', 0, 2, X'', '[{\"hash\":\"unpinned\"}]');
+             INSERT INTO pins (message_id, channel_id, pinned_by, pinned_at) VALUES (1, 'chan', 'owner', '2000-01-01');
+             INSERT INTO blocks (hash, size, encrypted_size, channel_id, expires_at)
+                 VALUES ('pinned', 1, 1, 'chan', '2000-01-01'),
+                        ('unpinned', 1, 1, 'chan', '2000-01-01'),
+                        ('loose', 1, 1, NULL, '2000-01-01');",
+        )
+        .unwrap();
+
+    let purged = united_server::blocks::store::delete_expired_blocks(&db, &data_dir).unwrap();
+    assert_eq!(purged, 2);
+
+    let remaining: Vec<String> = {
+        let conn = db.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT hash FROM blocks").unwrap();
+        stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+    };
+    assert_eq!(remaining, vec!["pinned".to_string()]);
+}
+
+#[test]
+fn test_message_retention_report_and_apply() {
+    use chrono::{Duration, Utc};
+    use united_server::chat::retention::{apply_retention, retention_report};
+
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let data_dir = tmp_dir.path().to_str().unwrap().to_string();
+    let db = united_server::db::init_db(&data_dir).unwrap();
+    let now = Utc::now();
+    let old = (now - Duration::days(40)).to_rfc3339();
+    let recent = (now - Duration::days(1)).to_rfc3339();
+
+    {
+        let conn = db.lock().unwrap();
+        // 'cat' keeps 30 days (tombstone); 'tomb' inherits it, 'gone' hard-deletes after
+        // 30 days, 'kept' opts out with 0
+        conn.execute_batch(
+            "INSERT INTO categories (id, name, position, created_at, retention_days, retention_mode)
+                 VALUES ('cat', 'General', 0, '2000-01-01', 30, 'tombstone');
+             INSERT INTO channels (id, name, category_id, position, created_at) VALUES ('tomb', 'tomb', 'cat', 0, '2000-01-01');
+             INSERT INTO channels (id, name, category_id, position, created_at, retention_days, retention_mode)
+                 VALUES ('gone', 'gone', 'cat', 1, '2000-01-01', 30, 'delete'),
+                        ('kept', 'kept', 'cat', 2, '2000-01-01', 0, NULL);",
+        )
+        .unwrap();
+        let mut insert = conn
+            .prepare(
+                "INSERT INTO messages (id, channel_id, sender_pubkey, timestamp, server_sequence, signature, content_text, created_at)
+                 VALUES (?1, ?2, 'aa', 0, ?1, X'', 'hello', ?3)",
+            )
+            .unwrap();
+        for (id, channel, created_at) in [
+            (1, "tomb", &old),
+            (2, "tomb", &old),
+            (3, "tomb", &recent),
+            (4, "gone", &old),
+            (5, "gone", &old),
+            (6, "gone", &recent),
+            (7, "kept", &old),
+        ] {
+            insert
+                .execute(rusqlite::params![id, channel, created_at])
+                .unwrap();
+        }
+        // Pinned messages and roots of active threads are exempt
+        conn.execute_batch(&format!(
+            "INSERT INTO pins (message_id, channel_id, pinned_by, pinned_at) VALUES (2, 'tomb', 'owner', '2000-01-01');
+             INSERT INTO threads (id, channel_id, root_message_id, name, created_by, last_message_at, created_at, updated_at)
+                 VALUES ('t', 'gone', 5, 'still going', 'owner', '{recent}', '{old}', '{recent}');
+             INSERT INTO reactions (message_id, user_pubkey, emoji, created_at) VALUES (1, 'aa', 'x', '{old}');
+             INSERT INTO message_revisions (message_id, content_text, written_at, replaced_at)
+                 VALUES (1, 'hi', '{old}', '{old}');"
+        ))
+        .unwrap();
+    }
+
+    let report = retention_report(&db.lock().unwrap(), now).unwrap();
+    let entry = |id: &str| report.channels.iter().find(|c| c.retention.channel_id == id).unwrap();
+    assert_eq!(entry("tomb").due, 1);
+    assert_eq!(entry("tomb").pinned_exempt, 1);
+    assert_eq!(entry("tomb").retention.channel_policy, None);
+    assert_eq!(entry("tomb").retention.effective.unwrap().days, 30);
+    assert_eq!(entry("gone").due, 1);
+    assert_eq!(entry("kept").due, 0);
+    assert!(entry("kept").cutoff.is_none());
+    assert_eq!(report.total_due, 2);
+
+    // The report is a dry run
+    let count: i64 = db
+        .lock()
+        .unwrap()
+        .query_row("SELECT COUNT(*) FROM messages WHERE deleted = 0", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, 7);
+
+    assert_eq!(apply_retention(&db.lock().unwrap(), now).unwrap(), 2);
+    let conn = db.lock().unwrap();
+    let rows: Vec<(i64, bool, Option<String>)> = conn
+        .prepare("SELECT id, deleted, content_text FROM messages ORDER BY id")
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+    let hello = Some("hello".to_string());
+    assert_eq!(
+        rows,
+        vec![
+            (1, true, None),
+            (2, false, hello.clone()),
+            (3, false, hello.clone()),
+            (5, false, hello.clone()),
+            (6, false, hello.clone()),
+            (7, false, hello),
+        ]
+    );
+    let reactions: i64 = conn
+        .query_row("SELECT COUNT(*) FROM reactions", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(reactions, 0);
+    let revisions: i64 = conn
+        .query_row("SELECT COUNT(*) FROM message_revisions", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(revisions, 0);
+    drop(conn);
+
+    // Already tombstoned messages are not counted again
+    assert_eq!(apply_retention(&db.lock().unwrap(), now).unwrap(), 0);
+}
+
+#[test]
+fn test_retention_compares_sqlite_timestamps_as_times() {
+    use chrono::{Duration, Utc};
+    use united_server::chat::retention::{apply_retention, retention_report};
+
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let data_dir = tmp_dir.path().to_str().unwrap().to_string();
+    let db = united_server::db::init_db(&data_dir).unwrap();
+    let now = Utc::now();
+    let cutoff = now - Duration::days(30);
+    // Messages default to datetime('now') text ("YYYY-MM-DD HH:MM:SS"), which sorts
+    // before an RFC 3339 cutoff on the same day even when it is later
+    let sqlite_time = |t: chrono::DateTime<Utc>| t.format("%Y-%m-%d %H:%M:%S").to_string();
+
+    {
+        let conn = db.lock().unwrap();
+        conn.execute_batch(
+            "INSERT INTO categories (id, name, position, created_at) VALUES ('cat', 'General', 0, '2000-01-01');
+             INSERT INTO channels (id, name, category_id, position, created_at, retention_days, retention_mode)
+                 VALUES ('gone', 'gone', 'cat', 0, '2000-01-01', 30, 'delete');",
+        )
+        .unwrap();
+        for (id, created_at) in [
+            (1, sqlite_time(cutoff - Duration::days(1))),
+            (2, sqlite_time(cutoff + Duration::minutes(1))),
+        ] {
+            conn.execute(
+                "INSERT INTO messages (id, channel_id, sender_pubkey, timestamp, server_sequence, signature, content_text, created_at)
+                 VALUES (?1, 'gone', 'aa', 0, ?1, X'', 'hello', ?2)",
+                rusqlite::params![id, created_at],
+            )
+            .unwrap();
+        }
+    }
+
+    let report = retention_report(&db.lock().unwrap(), now).unwrap();
+    assert_eq!(report.total_due, 1);
+    assert_eq!(apply_retention(&db.lock().unwrap(), now).unwrap(), 1);
+    let remaining: Vec<i64> = db
+        .lock()
+        .unwrap()
+        .prepare("SELECT id FROM messages")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(remaining, vec![2]);
+}
+
+#[tokio::test]
+async fn test_retention_endpoints_require_permissions() {
+    let (base_url, setup_token, _addr) = start_test_server().await;
+    let (owner_token, _owner_id) = register_owner(&base_url, &setup_token).await;
+    let user_token = register_regular_user(&base_url, "Member").await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let category_id = body["categories"][0]["category"]["id"].as_str().unwrap().to_string();
+    let channel_id = body["categories"][0]["channels"][0]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let channel_url = format!("{}/api/channels/{}/retention", base_url, channel_id);
+    let category_url = format!("{}/api/categories/{}/retention", base_url, category_id);
+    let report_url = format!("{}/api/retention/report", base_url);
+
+    for (url, token) in [(&channel_url, &user_token), (&category_url, &user_token)] {
+        let resp = client
+            .put(url)
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "days": 7 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 403);
+    }
+    let resp = client
+        .get(&report_url)
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    let resp = client
+        .put(&channel_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "days": 100000 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    // A category policy is inherited until the channel sets its own
+    let resp = client
+        .put(&category_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "days": 90, "mode": "delete" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["policy"]["days"], 90);
+    assert_eq!(body["policy"]["mode"], "delete");
+
+    let resp = client
+        .put(&channel_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "days": 7 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["effective"]["days"], 7);
+    assert_eq!(body["effective"]["mode"], "tombstone");
+    assert_eq!(body["category_policy"]["days"], 90);
+
+    let resp = client
+        .put(&channel_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "days": null }))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert!(body["channel_policy"].is_null());
+    assert_eq!(body["effective"]["days"], 90);
+
+    let resp = client
+        .get(&report_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let entry = body["channels"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["channel_id"] == channel_id.as_str())
+        .unwrap();
+    assert_eq!(entry["effective"]["mode"], "delete");
+    assert_eq!(entry["due"], 0);
+    assert_eq!(body["total_due"], 0);
+
+    let resp = client
+        .put(format!("{}/api/categories/nope/retention", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "days": 1 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn test_edit_history_visible_to_author_and_moderators() {
+    let (base_url, setup_token, _addr) = start_test_server().await;
+    let (owner_token, _owner_id) = register_owner(&base_url, &setup_token).await;
+    let author_token = register_regular_user(&base_url, "Author").await;
+    let other_token = register_regular_user(&base_url, "Other").await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", author_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let channel_id = body["categories"][0]["channels"][0]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let resp = client
+        .post(format!("{}/api/channels/{}/messages", base_url, channel_id))
+        .header("Authorization", format!("Bearer {}", author_token))
+        .json(&json!({ "content": "first" }))
+        .send()
+        .await
+        .unwrap();
+    let message: serde_json::Value = resp.json().await.unwrap();
+    let message_url = format!(
+        "{}/api/channels/{}/messages/{}",
+        base_url,
+        channel_id,
+        message["id"].as_str().unwrap()
+    );
+    let revisions_url = format!("{}/revisions", message_url);
+
+    for content in ["second", "third"] {
+        let resp = client
+            .put(&message_url)
+            .header("Authorization", format!("Bearer {}", author_token))
+            .json(&json!({ "content": content }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+
+    for token in [&author_token, &owner_token] {
+        let resp = client
+            .get(&revisions_url)
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        let revisions = body["revisions"].as_array().unwrap();
+        let contents: Vec<&str> = revisions
+            .iter()
+            .map(|r| r["content"].as_str().unwrap())
+            .collect();
+        assert_eq!(contents, vec!["first", "second"]);
+        // Each version was written before it was replaced, and replaced when the next
+        // version was written
+        assert!(revisions[0]["written_at"].as_str() <= revisions[0]["replaced_at"].as_str());
+        assert_eq!(revisions[0]["replaced_at"], revisions[1]["written_at"]);
+    }
+
+    // Other members cannot see the history
+    let resp = client
+        .get(&revisions_url)
+        .header("Authorization", format!("Bearer {}", other_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    let resp = client
+        .get(format!(
+            "{}/api/channels/{}/messages/999999/revisions",
+            base_url, channel_id
+        ))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
+#[test]
+fn test_revision_pruning() {
+    use chrono::{Duration, Utc};
+    use united_server::chat::revisions::prune_revisions;
+
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let data_dir = tmp_dir.path().to_str().unwrap().to_string();
+    let db = united_server::db::init_db(&data_dir).unwrap();
+    let now = Utc::now();
+    let conn = db.lock().unwrap();
+    conn.execute_batch(&format!(
+        "INSERT INTO categories (id, name, position, created_at) VALUES ('cat', 'General', 0, '2000-01-01');
+         INSERT INTO channels (id, name, category_id, created_at) VALUES ('chan', 'general', 'cat', '2000-01-01');
+         INSERT INTO messages (id, channel_id, sender_pubkey, timestamp, server_sequence, signature, content_text)
+             VALUES (1, 'chan', 'aa', 0, 1, X'', 'current');
+         INSERT INTO message_revisions (message_id, content_text, written_at, replaced_at)
+             VALUES (1, 'old', '2000-01-01', '{old}'), (1, 'recent', '2000-01-01', '{recent}');",
+        old = (now - Duration::days(100)).to_rfc3339(),
+        recent = (now - Duration::days(10)).to_rfc3339(),
+    ))
+    .unwrap();
+
+    assert_eq!(prune_revisions(&conn, now, 0).unwrap(), 0);
+    assert_eq!(prune_revisions(&conn, now, 90).unwrap(), 1);
+    let remaining: String = conn
+        .query_row("SELECT content_text FROM message_revisions", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(remaining, "recent");
+}
+
+#[test]
+fn test_history_reads_do_not_wait_for_the_writer() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let db = united_server::db::init_db(tmp_dir.path().to_str().unwrap()).unwrap();
+
+    db.lock()
+        .unwrap()
+        .execute_batch(
+            "INSERT INTO categories (id, name, position, created_at) VALUES ('cat', 'General', 0, '2000-01-01');
+             INSERT INTO channels (id, name, category_id, created_at) VALUES ('chan', 'general', 'cat', '2000-01-01');
+             INSERT INTO messages (id, channel_id, sender_pubkey, timestamp, server_sequence, signature, content_text)
+                 VALUES (1, 'chan', 'aa', 0, 1, X'', 'first');",
+        )
+        .unwrap();
+    let history = |conn: &rusqlite::Connection| {
+        conn.query_row(
+            "SELECT COUNT(*) FROM messages WHERE channel_id = 'chan'",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .unwrap()
+    };
+
+    // A writer in the middle of a transaction doesn't block readers; they see the last commit
+    let mut writer = db.lock().unwrap();
+    let tx = writer.transaction().unwrap();
+    tx.execute(
+        "INSERT INTO messages (id, channel_id, sender_pubkey, timestamp, server_sequence, signature, content_text)
+         VALUES (2, 'chan', 'aa', 0, 2, X'', 'second')",
+        [],
+    )
+    .unwrap();
+    let readers: Vec<_> = (0..united_server::db::READ_CONNECTIONS)
+        .map(|_| db.read().unwrap())
+        .collect();
+    for reader in &readers {
+        assert_eq!(history(reader), 1);
+    }
+    drop(readers);
+    tx.commit().unwrap();
+    drop(writer);
+    assert_eq!(history(&db.read().unwrap()), 2);
+
+    // Pool connections are read-only
+    assert!(db
+        .read()
+        .unwrap()
+        .execute("DELETE FROM messages", [])
+        .is_err());
+}
@@ -0,0 +1,678 @@
+//! Integration tests for gossipsub ingestion authorization.
+//! Tests cover: accepted envelopes from authorized senders, and rejection of unknown
+//! senders, banned and muted senders, senders without SEND_MESSAGES, non-text/unknown
+//! channels, announcement and forum posting rules, senders inside a channel's or a
+//! thread's slow mode cooldown, and new members using capabilities withheld during probation, plus
+//! edits, deletes and reactions applied from gossip and how other message types are
+//! handled.
+
+use ed25519_dalek::SigningKey;
+use rand::Rng;
+use united_server::db::DbPool;
+use prost::Message as ProstMessage;
+use united_server::p2p::messages::{
+    decode_and_verify_gossip_envelope, encode_gossip_envelope, handle_gossip_message,
+    EnvelopeError, GossipOutcome, GossipPersistResult,
+};
+use united_server::proto::chat as proto_chat;
+use united_server::p2p::validation::{GossipRejectionCounters, RejectReason, TrustedServers};
+use united_server::proto::p2p_proto::{GossipEnvelope, MessageType};
+use united_server::roles::permissions::Permissions;
+
+/// Generate a signing key from random bytes (avoids rand_core version conflict).
+fn random_signing_key() -> SigningKey {
+    let secret: [u8; 32] = rand::rng().random();
+    SigningKey::from_bytes(&secret)
+}
+
+/// Ingest an envelope the way the gossip loop does, with no trusted peer servers.
+fn ingest(db: &DbPool, envelope: &GossipEnvelope) -> Result<GossipOutcome, EnvelopeError> {
+    handle_gossip_message(db, &TrustedServers::default(), None, envelope)
+}
+
+/// Helper: create a fresh DB with the starter template and an @everyone role
+/// granting `everyone_permissions`. Returns (db, text_channel_id, voice_channel_id, tmp_dir).
+fn setup_db(everyone_permissions: u32) -> (DbPool, String, String, tempfile::TempDir) {
+    let tmp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let db = united_server::db::init_db(tmp_dir.path().to_str().unwrap()).expect("Failed to init DB");
+
+    let (text_id, voice_id) = {
+        let conn = db.lock().unwrap();
+        united_server::channels::seed::seed_starter_template(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO roles (id, name, permissions, position, is_default, created_at, updated_at)
+             VALUES ('everyone', '@everyone', ?1, 0, 1, '', '')",
+            [everyone_permissions],
+        )
+        .unwrap();
+        let text_id: String = conn
+            .query_row("SELECT id FROM channels WHERE channel_type = 'text' LIMIT 1", [], |r| r.get(0))
+            .unwrap();
+        let voice_id: String = conn
+            .query_row("SELECT id FROM channels WHERE channel_type = 'voice' LIMIT 1", [], |r| r.get(0))
+            .unwrap();
+        (text_id, voice_id)
+    };
+
+    (db, text_id, voice_id, tmp_dir)
+}
+
+/// Insert a registered user for `key` and return its fingerprint.
+fn register_user(db: &DbPool, key: &SigningKey, name: &str) -> String {
+    let fingerprint = format!("FP-{}", name);
+    let conn = db.lock().unwrap();
+    conn.execute(
+        "INSERT INTO users (id, public_key, fingerprint, display_name, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, '', '')",
+        rusqlite::params![
+            format!("user-{}", name),
+            key.verifying_key().as_bytes().to_vec(),
+            fingerprint,
+            name
+        ],
+    )
+    .unwrap();
+    fingerprint
+}
+
+/// Build a signed CHAT envelope for `channel_id` from `key`.
+fn chat_envelope(key: &SigningKey, channel_id: &str) -> GossipEnvelope {
+    let data = encode_gossip_envelope(
+        key.verifying_key().as_bytes(),
+        key,
+        &format!("test-peer-id/{}", channel_id),
+        MessageType::Chat,
+        0,
+        b"",
+    );
+    decode_and_verify_gossip_envelope(&data).unwrap()
+}
+
+/// Build a signed envelope of any type carrying `payload` for `channel_id` from `key`.
+fn event_envelope(
+    key: &SigningKey,
+    channel_id: &str,
+    message_type: MessageType,
+    payload: &impl ProstMessage,
+) -> GossipEnvelope {
+    let data = encode_gossip_envelope(
+        key.verifying_key().as_bytes(),
+        key,
+        &format!("test-peer-id/{}", channel_id),
+        message_type,
+        0,
+        &payload.encode_to_vec(),
+    );
+    decode_and_verify_gossip_envelope(&data).unwrap()
+}
+
+/// Handle a CHAT envelope from `key` and return what was stored.
+fn persist_chat(db: &DbPool, key: &SigningKey, channel_id: &str) -> GossipPersistResult {
+    match ingest(db, &chat_envelope(key, channel_id)).unwrap() {
+        GossipOutcome::Message(result) => *result,
+        _ => panic!("Expected a stored chat message"),
+    }
+}
+
+fn assert_rejected(result: Result<impl Sized, EnvelopeError>, expected: RejectReason) {
+    match result {
+        Err(e) => assert_eq!(e.reject_reason(), Some(expected), "unexpected error: {}", e),
+        Ok(_) => panic!("Expected rejection: {:?}", expected),
+    }
+}
+
+fn message_count(db: &DbPool) -> i64 {
+    let conn = db.lock().unwrap();
+    conn.query_row("SELECT COUNT(*) FROM messages", [], |r| r.get(0)).unwrap()
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[test]
+fn test_authorized_sender_is_persisted() {
+    let (db, text_id, _voice_id, _tmp) = setup_db(Permissions::DEFAULT_EVERYONE.bits());
+    let key = random_signing_key();
+    register_user(&db, &key, "alice");
+
+    let result = persist_chat(&db, &key, &text_id);
+    assert_eq!(result.channel_id, text_id);
+    assert_eq!(result.server_sequence, 1);
+    assert_eq!(message_count(&db), 1);
+}
+
+#[test]
+fn test_unknown_sender_is_rejected() {
+    let (db, text_id, _voice_id, _tmp) = setup_db(Permissions::DEFAULT_EVERYONE.bits());
+    let key = random_signing_key();
+
+    assert_rejected(
+        ingest(&db, &chat_envelope(&key, &text_id)),
+        RejectReason::UnknownSender,
+    );
+    assert_eq!(message_count(&db), 0);
+}
+
+#[test]
+fn test_banned_sender_is_rejected() {
+    let (db, text_id, _voice_id, _tmp) = setup_db(Permissions::DEFAULT_EVERYONE.bits());
+    let key = random_signing_key();
+    let fingerprint = register_user(&db, &key, "mallory");
+    register_user(&db, &random_signing_key(), "mod");
+    {
+        let conn = db.lock().unwrap();
+        conn.execute(
+            "INSERT INTO bans (id, fingerprint, banned_by, reason, created_at) VALUES ('b1', ?1, 'user-mod', 'spam', '')",
+            [&fingerprint],
+        )
+        .unwrap();
+    }
+
+    assert_rejected(
+        ingest(&db, &chat_envelope(&key, &text_id)),
+        RejectReason::Banned,
+    );
+    assert_eq!(message_count(&db), 0);
+}
+
+#[test]
+fn test_muted_sender_is_rejected_until_mute_expires() {
+    let (db, text_id, _voice_id, _tmp) = setup_db(Permissions::DEFAULT_EVERYONE.bits());
+    let key = random_signing_key();
+    let fingerprint = register_user(&db, &key, "loud");
+    {
+        let conn = db.lock().unwrap();
+        conn.execute(
+            "INSERT INTO sanctions (id, fingerprint, user_id, kind, issued_by, expires_at, created_at)
+             VALUES ('s1', ?1, 'user-loud', 'text_mute', 'user-mod', '2999-01-01T00:00:00+00:00', '')",
+            [&fingerprint],
+        )
+        .unwrap();
+    }
+
+    assert_rejected(
+        ingest(&db, &chat_envelope(&key, &text_id)),
+        RejectReason::Muted,
+    );
+    assert_eq!(message_count(&db), 0);
+
+    // An expired mute no longer applies
+    {
+        let conn = db.lock().unwrap();
+        conn.execute(
+            "UPDATE sanctions SET expires_at = '2000-01-01T00:00:00+00:00' WHERE id = 's1'",
+            [],
+        )
+        .unwrap();
+    }
+    ingest(&db, &chat_envelope(&key, &text_id)).unwrap();
+    assert_eq!(message_count(&db), 1);
+}
+
+#[test]
+fn test_sender_without_send_messages_is_rejected() {
+    let (db, text_id, _voice_id, _tmp) = setup_db(Permissions::VIEW_CHANNEL.bits());
+    let key = random_signing_key();
+    register_user(&db, &key, "muted");
+
+    assert_rejected(
+        ingest(&db, &chat_envelope(&key, &text_id)),
+        RejectReason::MissingPermission,
+    );
+    assert_eq!(message_count(&db), 0);
+}
+
+#[test]
+fn test_voice_and_unknown_channels_are_rejected() {
+    let (db, _text_id, voice_id, _tmp) = setup_db(Permissions::DEFAULT_EVERYONE.bits());
+    let key = random_signing_key();
+    register_user(&db, &key, "bob");
+
+    assert_rejected(
+        ingest(&db, &chat_envelope(&key, &voice_id)),
+        RejectReason::NotTextChannel,
+    );
+    assert_rejected(
+        ingest(&db, &chat_envelope(&key, "no-such-channel")),
+        RejectReason::UnknownChannel,
+    );
+    assert_eq!(message_count(&db), 0);
+}
+
+#[test]
+fn test_announcement_and_forum_top_level_posts() {
+    let (db, text_id, _voice_id, _tmp) = setup_db(Permissions::DEFAULT_EVERYONE.bits());
+    let key = random_signing_key();
+    register_user(&db, &key, "alice");
+
+    let conn = db.lock().unwrap();
+    conn.execute(
+        "UPDATE channels SET channel_type = 'announcement' WHERE id = ?1",
+        [&text_id],
+    )
+    .unwrap();
+    drop(conn);
+    assert_rejected(
+        ingest(&db, &chat_envelope(&key, &text_id)),
+        RejectReason::MissingPermission,
+    );
+
+    // MANAGE_MESSAGES may post announcements
+    let conn = db.lock().unwrap();
+    conn.execute(
+        "UPDATE roles SET permissions = ?1 WHERE id = 'everyone'",
+        [(Permissions::DEFAULT_EVERYONE | Permissions::MANAGE_MESSAGES).bits()],
+    )
+    .unwrap();
+    conn.execute(
+        "UPDATE channels SET channel_type = 'forum' WHERE id = ?1",
+        [&text_id],
+    )
+    .unwrap();
+    drop(conn);
+    assert_rejected(
+        ingest(&db, &chat_envelope(&key, &text_id)),
+        RejectReason::ForumTopLevel,
+    );
+
+    let conn = db.lock().unwrap();
+    conn.execute(
+        "UPDATE channels SET channel_type = 'announcement' WHERE id = ?1",
+        [&text_id],
+    )
+    .unwrap();
+    drop(conn);
+    ingest(&db, &chat_envelope(&key, &text_id)).unwrap();
+    assert_eq!(message_count(&db), 1);
+}
+
+#[test]
+fn test_slowmode_rejects_messages_inside_the_cooldown() {
+    let (db, text_id, _voice_id, _tmp) = setup_db(Permissions::DEFAULT_EVERYONE.bits());
+    let key = random_signing_key();
+    register_user(&db, &key, "alice");
+    let other = random_signing_key();
+    register_user(&db, &other, "bob");
+    db.lock()
+        .unwrap()
+        .execute(
+            "UPDATE channels SET slowmode_secs = 30 WHERE id = ?1",
+            [&text_id],
+        )
+        .unwrap();
+
+    ingest(&db, &chat_envelope(&key, &text_id)).unwrap();
+    assert_rejected(
+        ingest(&db, &chat_envelope(&key, &text_id)),
+        RejectReason::SlowMode,
+    );
+    // Each sender has their own cooldown
+    ingest(&db, &chat_envelope(&other, &text_id)).unwrap();
+    assert_eq!(message_count(&db), 2);
+
+    // Once the interval has passed the sender may post again
+    db.lock()
+        .unwrap()
+        .execute(
+            "UPDATE messages SET created_at = '2020-01-01T00:00:00+00:00'",
+            [],
+        )
+        .unwrap();
+    ingest(&db, &chat_envelope(&key, &text_id)).unwrap();
+    assert_eq!(message_count(&db), 3);
+}
+
+#[test]
+fn test_slowmode_applies_to_thread_posts_per_thread() {
+    let (db, text_id, _voice_id, _tmp) = setup_db(Permissions::DEFAULT_EVERYONE.bits());
+    let key = random_signing_key();
+    register_user(&db, &key, "alice");
+    db.lock()
+        .unwrap()
+        .execute(
+            "UPDATE channels SET slowmode_secs = 30 WHERE id = ?1",
+            [&text_id],
+        )
+        .unwrap();
+
+    // The thread's root message starts alice's cooldown in the channel itself
+    persist_chat(&db, &key, &text_id);
+    let conn = db.lock().unwrap();
+    let root_id: i64 = conn
+        .query_row("SELECT MAX(id) FROM messages", [], |r| r.get(0))
+        .unwrap();
+    conn.execute(
+        "INSERT INTO threads (id, channel_id, root_message_id, name, created_by, created_at, updated_at)
+         VALUES ('thread-1', ?1, ?2, 'Side topic', 'user-alice', '', '')",
+        rusqlite::params![text_id, root_id],
+    )
+    .unwrap();
+    drop(conn);
+
+    // The thread inherits the channel's interval but keeps its own cooldown
+    ingest(&db, &chat_envelope(&key, "thread/thread-1")).unwrap();
+    assert_rejected(
+        ingest(&db, &chat_envelope(&key, "thread/thread-1")),
+        RejectReason::SlowMode,
+    );
+    assert_eq!(message_count(&db), 2);
+}
+
+#[test]
+fn test_probation_limits_apply_to_gossip() {
+    let (db, text_id, _voice_id, _tmp) = setup_db(Permissions::DEFAULT_EVERYONE.bits());
+    let key = random_signing_key();
+    register_user(&db, &key, "newcomer");
+    persist_chat(&db, &key, &text_id);
+    let message_id: i64 = db
+        .lock()
+        .unwrap()
+        .query_row("SELECT id FROM messages", [], |r| r.get(0))
+        .unwrap();
+    // Registered just now, so on probation
+    db.lock()
+        .unwrap()
+        .execute("UPDATE users SET created_at = ?1", [chrono::Utc::now().to_rfc3339()])
+        .unwrap();
+
+    let trust = united_server::config::TrustConfig {
+        reactions: true,
+        ..Default::default()
+    };
+    let ingest_on_probation = |message_type: MessageType, payload: Vec<u8>| {
+        let data = encode_gossip_envelope(
+            key.verifying_key().as_bytes(),
+            &key,
+            &format!("test-peer-id/{}", text_id),
+            message_type,
+            0,
+            &payload,
+        );
+        let envelope = decode_and_verify_gossip_envelope(&data).unwrap();
+        handle_gossip_message(&db, &TrustedServers::default(), Some(&trust), &envelope)
+    };
+    let chat = |content: &str| proto_chat::ChatMessage {
+        content: content.to_string(),
+        ..Default::default()
+    };
+    let edit = |content: &str| proto_chat::MessageEditedEvent {
+        message_id: message_id.to_string(),
+        channel_id: text_id.clone(),
+        new_content: content.to_string(),
+        ..Default::default()
+    };
+    let reaction = proto_chat::ReactionAddedEvent {
+        reaction: Some(proto_chat::Reaction {
+            message_id: message_id.to_string(),
+            emoji: "👍".to_string(),
+            ..Default::default()
+        }),
+    };
+
+    assert_rejected(
+        ingest_on_probation(MessageType::Chat, chat("see https://example.com").encode_to_vec()),
+        RejectReason::Probation,
+    );
+    assert_rejected(
+        ingest_on_probation(MessageType::Edit, edit("@role:mods look").encode_to_vec()),
+        RejectReason::Probation,
+    );
+    assert_rejected(
+        ingest_on_probation(MessageType::ReactionAdd, reaction.encode_to_vec()),
+        RejectReason::Probation,
+    );
+
+    // Ungated content still goes through
+    assert!(matches!(
+        ingest_on_probation(MessageType::Edit, edit("plain words").encode_to_vec()),
+        Ok(GossipOutcome::Edited(_))
+    ));
+    assert!(matches!(
+        ingest_on_probation(MessageType::Chat, chat("hello").encode_to_vec()),
+        Ok(GossipOutcome::Message(_))
+    ));
+}
+
+#[test]
+fn test_rejection_counters_track_each_reason() {
+    let counters = GossipRejectionCounters::new();
+    counters.record(RejectReason::Banned);
+    counters.record(RejectReason::Banned);
+    counters.record(RejectReason::UnknownSender);
+
+    assert_eq!(counters.get(RejectReason::Banned), 2);
+    assert_eq!(counters.get(RejectReason::UnknownSender), 1);
+    assert_eq!(counters.get(RejectReason::MissingPermission), 0);
+
+    let snapshot = counters.snapshot();
+    assert_eq!(snapshot.len(), RejectReason::ALL.len());
+    assert!(snapshot.contains(&("banned", 2)));
+}
+
+#[test]
+fn test_sequence_numbers_are_never_reused() {
+    use united_server::db::sequences::next_channel_sequence;
+
+    let (db, text_id, _voice_id, _tmp) = setup_db(Permissions::DEFAULT_EVERYONE.bits());
+    let key = random_signing_key();
+    register_user(&db, &key, "alice");
+
+    for expected in 1..=3 {
+        let result = persist_chat(&db, &key, &text_id);
+        assert_eq!(result.server_sequence, expected);
+    }
+
+    // Hard-deleting the timeline (e.g. by retention) doesn't restart the numbering
+    db.lock().unwrap().execute("DELETE FROM messages", []).unwrap();
+    let result = persist_chat(&db, &key, &text_id);
+    assert_eq!(result.server_sequence, 4);
+
+    let conn = db.lock().unwrap();
+    // Threads are numbered separately from their channel
+    assert_eq!(next_channel_sequence(&conn, &text_id, Some("thread-1")).unwrap(), 1);
+    assert_eq!(next_channel_sequence(&conn, &text_id, None).unwrap(), 5);
+
+    // A sequence number can only be used once per timeline
+    let insert = |seq: i64, thread_id: Option<&str>| {
+        conn.execute(
+            "INSERT INTO messages (channel_id, sender_pubkey, timestamp, server_sequence, signature, thread_id)
+             VALUES (?1, 'aa', 0, ?2, X'', ?3)",
+            rusqlite::params![text_id, seq, thread_id],
+        )
+    };
+    assert!(insert(4, None).is_err());
+    insert(4, Some("thread-1")).unwrap();
+}
+
+#[test]
+fn test_sequence_counters_are_backfilled() {
+    use united_server::db::sequences::{next_channel_sequence, next_dm_sequence};
+
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let mut conn = rusqlite::Connection::open(tmp_dir.path().join("united.db")).unwrap();
+    conn.pragma_update(None, "foreign_keys", "ON").unwrap();
+    let migrations = united_server::db::migrations::migrations();
+    migrations.to_version(&mut conn, 22).unwrap();
+
+    conn.execute_batch(
+        "INSERT INTO categories (id, name, position, created_at) VALUES ('cat', 'General', 0, '');
+         INSERT INTO channels (id, name, category_id, created_at) VALUES ('chan', 'general', 'cat', '');
+         INSERT INTO messages (channel_id, sender_pubkey, timestamp, server_sequence, signature, thread_id)
+             VALUES ('chan', 'aa', 0, 1, X'', NULL), ('chan', 'aa', 0, 7, X'', NULL),
+                    ('chan', 'aa', 0, 2, X'', 'thread-1');
+         INSERT INTO dm_conversations (id, participant_a, participant_b) VALUES ('conv', 'aa', 'bb');
+         INSERT INTO dm_messages (id, conversation_id, sender_pubkey, encrypted_payload, nonce, timestamp, server_sequence)
+             VALUES ('dm-1', 'conv', 'aa', X'00', X'00', 0, 1), ('dm-2', 'conv', 'bb', X'00', X'00', 0, 3);",
+    )
+    .unwrap();
+    migrations.to_latest(&mut conn).unwrap();
+
+    assert_eq!(next_channel_sequence(&conn, "chan", None).unwrap(), 8);
+    assert_eq!(next_channel_sequence(&conn, "chan", Some("thread-1")).unwrap(), 3);
+    assert_eq!(next_dm_sequence(&conn, "conv").unwrap(), 4);
+}
+
+#[test]
+fn test_inbound_edit_envelope_is_applied() {
+    let (db, text_id, _voice_id, _tmp) = setup_db(Permissions::DEFAULT_EVERYONE.bits());
+    let alice = random_signing_key();
+    let bob = random_signing_key();
+    register_user(&db, &alice, "alice");
+    register_user(&db, &bob, "bob");
+
+    persist_chat(&db, &alice, &text_id);
+    let message_id: i64 = db
+        .lock()
+        .unwrap()
+        .query_row("SELECT id FROM messages", [], |r| r.get(0))
+        .unwrap();
+    let edit = |content: &str, message_id: String| proto_chat::MessageEditedEvent {
+        message_id,
+        channel_id: text_id.clone(),
+        new_content: content.to_string(),
+        edit_timestamp: 0,
+        editor_pubkey: String::new(),
+    };
+
+    // Only the author can edit, and only messages that exist
+    assert_rejected(
+        ingest(
+            &db,
+            &event_envelope(&bob, &text_id, MessageType::Edit, &edit("hijacked", message_id.to_string())),
+        ),
+        RejectReason::MissingPermission,
+    );
+    assert_rejected(
+        ingest(
+            &db,
+            &event_envelope(&alice, &text_id, MessageType::Edit, &edit("lost", "999".to_string())),
+        ),
+        RejectReason::UnknownMessage,
+    );
+
+    let outcome = ingest(
+        &db,
+        &event_envelope(&alice, &text_id, MessageType::Edit, &edit(" fixed ", message_id.to_string())),
+    )
+    .unwrap();
+    match outcome {
+        GossipOutcome::Edited(event) => {
+            assert_eq!(event.message_id, message_id.to_string());
+            assert_eq!(event.new_content, "fixed");
+        }
+        _ => panic!("Expected an applied edit"),
+    }
+
+    // The edit changed the message in place: no new row, no sequence number taken
+    assert_eq!(message_count(&db), 1);
+    {
+        let conn = db.lock().unwrap();
+        let (content, edited): (String, bool) = conn
+            .query_row("SELECT content_text, edited FROM messages WHERE id = ?1", [message_id], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((content.as_str(), edited), ("fixed", true));
+        let revisions: i64 = conn
+            .query_row("SELECT COUNT(*) FROM message_revisions WHERE message_id = ?1", [message_id], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(revisions, 1);
+    }
+    assert_eq!(persist_chat(&db, &alice, &text_id).server_sequence, 2);
+
+    // Typing indicators are accepted but not stored; presence is not ingested
+    assert!(matches!(
+        ingest(&db, &event_envelope(&alice, &text_id, MessageType::Typing, &edit("", String::new()))),
+        Ok(GossipOutcome::Typing)
+    ));
+    assert_rejected(
+        ingest(&db, &event_envelope(&alice, &text_id, MessageType::Presence, &edit("", String::new()))),
+        RejectReason::UnsupportedType,
+    );
+    assert_eq!(message_count(&db), 2);
+}
+
+#[test]
+fn test_inbound_delete_and_reaction_envelopes_are_applied() {
+    let (db, text_id, _voice_id, _tmp) = setup_db(Permissions::DEFAULT_EVERYONE.bits());
+    let alice = random_signing_key();
+    let bob = random_signing_key();
+    register_user(&db, &alice, "alice");
+    register_user(&db, &bob, "bob");
+
+    persist_chat(&db, &alice, &text_id);
+    let message_id: i64 = db
+        .lock()
+        .unwrap()
+        .query_row("SELECT id FROM messages", [], |r| r.get(0))
+        .unwrap();
+    let bob_hex = hex::encode(bob.verifying_key().as_bytes());
+    let reaction_count = |db: &DbPool| -> i64 {
+        db.lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM reactions", [], |r| r.get(0))
+            .unwrap()
+    };
+
+    // Reactions are recorded for the envelope's signer, whatever the payload claims
+    let added = proto_chat::ReactionAddedEvent {
+        reaction: Some(proto_chat::Reaction {
+            message_id: message_id.to_string(),
+            user_pubkey: "someone-else".to_string(),
+            emoji: "👍".to_string(),
+            timestamp: 0,
+        }),
+    };
+    match ingest(&db, &event_envelope(&bob, &text_id, MessageType::ReactionAdd, &added))
+        .unwrap()
+    {
+        GossipOutcome::ReactionAdded { channel_id, event } => {
+            assert_eq!(channel_id, text_id);
+            assert_eq!(event.reaction.unwrap().user_pubkey, bob_hex);
+        }
+        _ => panic!("Expected an applied reaction"),
+    }
+    assert_eq!(reaction_count(&db), 1);
+
+    let removed = proto_chat::ReactionRemovedEvent {
+        message_id: message_id.to_string(),
+        user_pubkey: String::new(),
+        emoji: "👍".to_string(),
+    };
+    assert!(matches!(
+        ingest(&db, &event_envelope(&bob, &text_id, MessageType::ReactionRemove, &removed)),
+        Ok(GossipOutcome::ReactionRemoved { .. })
+    ));
+    assert_eq!(reaction_count(&db), 0);
+
+    // Only the author (or a MANAGE_MESSAGES holder) can delete
+    let delete = proto_chat::MessageDeletedEvent {
+        message_id: message_id.to_string(),
+        channel_id: text_id.clone(),
+        deleted_by_pubkey: String::new(),
+    };
+    assert_rejected(
+        ingest(&db, &event_envelope(&bob, &text_id, MessageType::Delete, &delete)),
+        RejectReason::MissingPermission,
+    );
+    assert!(matches!(
+        ingest(&db, &event_envelope(&alice, &text_id, MessageType::Delete, &delete)),
+        Ok(GossipOutcome::Deleted(_))
+    ));
+    let deleted: bool = db
+        .lock()
+        .unwrap()
+        .query_row("SELECT deleted FROM messages WHERE id = ?1", [message_id], |r| r.get(0))
+        .unwrap();
+    assert!(deleted);
+    assert_eq!(message_count(&db), 1);
+
+    // Deleted messages cannot be reacted to
+    assert_rejected(
+        ingest(&db, &event_envelope(&bob, &text_id, MessageType::ReactionAdd, &added)),
+        RejectReason::UnknownMessage,
+    );
+}
@@ -1,4 +1,5 @@
-//! Integration tests for invite system: creation, consumption, landing page, limits.
+//! Integration tests for invite system: creation, consumption, landing page, limits,
+//! and invitation chains.
 
 use ed25519_dalek::{Signer, SigningKey};
 use rand::Rng;
@@ -29,6 +30,7 @@ async fn start_test_server() -> (String, String, SocketAddr) {
     let connections = united_server::ws::new_connection_registry();
 
     let (swarm_cmd_tx, _swarm_cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+    let abuse = united_server::moderation::abuse::AbuseMonitor::spawn(db.clone());
     let state = united_server::state::AppState {
         db,
         challenges: Arc::new(dashmap::DashMap::new()),
@@ -39,12 +41,22 @@ async fn start_test_server() -> (String, String, SocketAddr) {
         swarm_cmd_tx,
         peer_directory: Arc::new(united_server::p2p::PeerDirectory::new()),
         server_peer_id: "test-peer-id".to_string(),
+        server_signing_key: Arc::new(ed25519_dalek::SigningKey::from_bytes(&[7u8; 32])),
+        gossip_rejections: Arc::new(united_server::p2p::validation::GossipRejectionCounters::new()),
+        outbound_metrics: Arc::new(united_server::ws::outbound::OutboundMetrics::new()),
         libp2p_port: 0,
         presence: Arc::new(dashmap::DashMap::new()),
         data_dir: data_dir.clone(),
         block_retention_days: None,
         block_cleanup_interval_secs: None,
         max_upload_size_mb: None,
+        voice_state: Arc::new(united_server::voice::state::VoiceState::new()),
+        turn_config: None,
+        trust_config: None,
+        abuse,
+        rate_limiter: Arc::new(united_server::rate_limit::RateLimiter::new(
+            united_server::config::RateLimitConfig::default(),
+        )),
     };
 
     let app = united_server::routes::build_router(state);
@@ -82,6 +94,7 @@ async fn start_invite_only_server() -> (String, String, SocketAddr) {
     let connections = united_server::ws::new_connection_registry();
 
     let (swarm_cmd_tx, _swarm_cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+    let abuse = united_server::moderation::abuse::AbuseMonitor::spawn(db.clone());
     let state = united_server::state::AppState {
         db,
         challenges: Arc::new(dashmap::DashMap::new()),
@@ -92,12 +105,22 @@ async fn start_invite_only_server() -> (String, String, SocketAddr) {
         swarm_cmd_tx,
         peer_directory: Arc::new(united_server::p2p::PeerDirectory::new()),
         server_peer_id: "test-peer-id".to_string(),
+        server_signing_key: Arc::new(ed25519_dalek::SigningKey::from_bytes(&[7u8; 32])),
+        gossip_rejections: Arc::new(united_server::p2p::validation::GossipRejectionCounters::new()),
+        outbound_metrics: Arc::new(united_server::ws::outbound::OutboundMetrics::new()),
         libp2p_port: 0,
         presence: Arc::new(dashmap::DashMap::new()),
         data_dir: data_dir.clone(),
         block_retention_days: None,
         block_cleanup_interval_secs: None,
         max_upload_size_mb: None,
+        voice_state: Arc::new(united_server::voice::state::VoiceState::new()),
+        turn_config: None,
+        trust_config: None,
+        abuse,
+        rate_limiter: Arc::new(united_server::rate_limit::RateLimiter::new(
+            united_server::config::RateLimitConfig::default(),
+        )),
     };
 
     let app = united_server::routes::build_router(state);
@@ -435,3 +458,172 @@ async fn test_delete_invite() {
         .unwrap();
     assert_eq!(landing.status(), 404, "Deleted invite landing page should return 404");
 }
+
+#[tokio::test]
+async fn test_invite_tree_and_inviter_accountability() {
+    let (base_url, setup_token, _) = start_test_server().await;
+    let (owner_token, owner_id) = register_owner(&base_url, &setup_token).await;
+    let client = reqwest::Client::new();
+    let auth = |token: &str| format!("Bearer {}", token);
+
+    let create_invite = |token: String| {
+        let client = client.clone();
+        let url = format!("{}/api/invites", base_url);
+        async move {
+            client
+                .post(url)
+                .header("Authorization", format!("Bearer {}", token))
+                .json(&json!({}))
+                .send()
+                .await
+                .unwrap()
+        }
+    };
+
+    // Owner invites Alice; Alice (given MANAGE_INVITES) invites Bob and Carol
+    let resp = create_invite(owner_token.clone()).await;
+    let owner_code = resp.json::<serde_json::Value>().await.unwrap()["code"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let (alice_token, alice_id) = register_with_invite(&base_url, "Alice", Some(&owner_code))
+        .await
+        .unwrap();
+
+    let resp = client
+        .post(format!("{}/api/roles", base_url))
+        .header("Authorization", auth(&owner_token))
+        .json(&json!({ "name": "Recruiter", "permissions": 0x200 }))
+        .send()
+        .await
+        .unwrap();
+    let role: serde_json::Value = resp.json().await.unwrap();
+    let resp = client
+        .post(format!("{}/api/roles/assign", base_url))
+        .header("Authorization", auth(&owner_token))
+        .json(&json!({ "user_id": alice_id, "role_id": role["id"] }))
+        .send()
+        .await
+        .unwrap();
+    assert!(resp.status().is_success());
+
+    let resp = create_invite(alice_token.clone()).await;
+    assert_eq!(resp.status(), 201);
+    let alice_code = resp.json::<serde_json::Value>().await.unwrap()["code"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let (bob_token, bob_id) = register_with_invite(&base_url, "Bob", Some(&alice_code))
+        .await
+        .unwrap();
+    let (_, carol_id) = register_with_invite(&base_url, "Carol", Some(&alice_code))
+        .await
+        .unwrap();
+
+    // Bob's chain leads back through Alice to the owner
+    let resp = client
+        .get(format!("{}/api/members/{}/invite-tree", base_url, bob_id))
+        .header("Authorization", auth(&owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let tree: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(tree["invited_by_invite"].as_str().unwrap(), alice_code);
+    let chain = tree["chain"].as_array().unwrap();
+    assert_eq!(chain.len(), 2);
+    assert_eq!(chain[0]["user_id"].as_str().unwrap(), alice_id);
+    assert_eq!(chain[0]["invite_code"].as_str().unwrap(), alice_code);
+    assert_eq!(chain[1]["user_id"].as_str().unwrap(), owner_id);
+
+    let resp = client
+        .get(format!("{}/api/members/{}/invite-tree", base_url, alice_id))
+        .header("Authorization", auth(&owner_token))
+        .send()
+        .await
+        .unwrap();
+    let tree: serde_json::Value = resp.json().await.unwrap();
+    let invitees: Vec<&str> = tree["invitees"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["user_id"].as_str().unwrap())
+        .collect();
+    assert_eq!(invitees, vec![bob_id.as_str(), carol_id.as_str()]);
+
+    let resp = client
+        .get(format!("{}/api/invites/{}/uses", base_url, alice_code))
+        .header("Authorization", auth(&owner_token))
+        .send()
+        .await
+        .unwrap();
+    let uses: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(uses["created_by"].as_str().unwrap(), alice_id);
+    assert_eq!(uses["uses"].as_array().unwrap().len(), 2);
+    assert_eq!(uses["uses"][0]["display_name"], "Bob");
+
+    // Members without moderation or invite permissions cannot inspect trees
+    let resp = client
+        .get(format!("{}/api/members/{}/invite-tree", base_url, alice_id))
+        .header("Authorization", auth(&bob_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    // Revoking Alice's invite privileges disables her codes and new invites
+    let resp = client
+        .put(format!("{}/api/members/{}/invite-privileges", base_url, alice_id))
+        .header("Authorization", auth(&owner_token))
+        .json(&json!({ "revoked": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let privileges: serde_json::Value = resp.json().await.unwrap();
+    assert!(privileges["invites_revoked_at"].is_string());
+    let resp = create_invite(alice_token.clone()).await;
+    assert_eq!(resp.status(), 403);
+    assert_eq!(
+        register_with_invite(&base_url, "Dave", Some(&alice_code)).await,
+        Err(400)
+    );
+
+    // Banning Alice with review_invitees queues everyone she brought in
+    let resp = client
+        .post(format!("{}/api/moderation/ban", base_url))
+        .header("Authorization", auth(&owner_token))
+        .json(&json!({ "user_id": alice_id, "reason": "raid", "review_invitees": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .get(format!("{}/api/moderation/flags?kind=invite_chain", base_url))
+        .header("Authorization", auth(&owner_token))
+        .send()
+        .await
+        .unwrap();
+    let flags: serde_json::Value = resp.json().await.unwrap();
+    let mut flagged: Vec<&str> = flags["flags"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["user_id"].as_str().unwrap())
+        .collect();
+    flagged.sort();
+    let mut expected = vec![bob_id.as_str(), carol_id.as_str()];
+    expected.sort();
+    assert_eq!(flagged, expected);
+
+    let resp = client
+        .get(format!("{}/api/members/{}/invite-tree", base_url, owner_id))
+        .header("Authorization", auth(&owner_token))
+        .send()
+        .await
+        .unwrap();
+    let tree: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(tree["invitees"][0]["user_id"].as_str().unwrap(), alice_id);
+    assert_eq!(tree["invitees"][0]["banned"], true);
+    assert_eq!(tree["invitees"][0]["invited_count"], 2);
+}
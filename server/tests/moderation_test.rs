@@ -1,4 +1,6 @@
-//! Integration tests for moderation: kick and ban.
+//! Integration tests for moderation: kick, ban (and the end of the member's resumable
+//! sessions), the audit log, graduated sanctions, shared ban lists between servers, the
+//! abuse flag review queue, and new-member probation.
 
 use ed25519_dalek::{Signer, SigningKey};
 use rand::Rng;
@@ -14,6 +16,12 @@ fn random_signing_key() -> SigningKey {
 }
 
 async fn start_test_server() -> (String, String, SocketAddr) {
+    start_test_server_with_trust(None).await
+}
+
+async fn start_test_server_with_trust(
+    trust_config: Option<united_server::config::TrustConfig>,
+) -> (String, String, SocketAddr) {
     let tmp_dir = tempfile::tempdir().expect("Failed to create temp dir");
     let data_dir = tmp_dir.path().to_str().unwrap().to_string();
 
@@ -29,6 +37,7 @@ async fn start_test_server() -> (String, String, SocketAddr) {
     let connections = united_server::ws::new_connection_registry();
 
     let (swarm_cmd_tx, _swarm_cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+    let abuse = united_server::moderation::abuse::AbuseMonitor::spawn(db.clone());
     let state = united_server::state::AppState {
         db,
         challenges: Arc::new(dashmap::DashMap::new()),
@@ -39,12 +48,22 @@ async fn start_test_server() -> (String, String, SocketAddr) {
         swarm_cmd_tx,
         peer_directory: Arc::new(united_server::p2p::PeerDirectory::new()),
         server_peer_id: "test-peer-id".to_string(),
+        server_signing_key: Arc::new(random_signing_key()),
+        gossip_rejections: Arc::new(united_server::p2p::validation::GossipRejectionCounters::new()),
+        outbound_metrics: Arc::new(united_server::ws::outbound::OutboundMetrics::new()),
         libp2p_port: 0,
         presence: Arc::new(dashmap::DashMap::new()),
         data_dir: data_dir.clone(),
         block_retention_days: None,
         block_cleanup_interval_secs: None,
         max_upload_size_mb: None,
+        voice_state: Arc::new(united_server::voice::state::VoiceState::new()),
+        turn_config: None,
+        trust_config,
+        abuse,
+        rate_limiter: Arc::new(united_server::rate_limit::RateLimiter::new(
+            united_server::config::RateLimitConfig::default(),
+        )),
     };
 
     let app = united_server::routes::build_router(state);
@@ -66,8 +85,15 @@ async fn start_test_server() -> (String, String, SocketAddr) {
 }
 
 async fn register_owner(base_url: &str, setup_token: &str) -> (String, String) {
+    register_owner_with_key(base_url, setup_token, &random_signing_key()).await
+}
+
+async fn register_owner_with_key(
+    base_url: &str,
+    setup_token: &str,
+    signing_key: &SigningKey,
+) -> (String, String) {
     let client = reqwest::Client::new();
-    let signing_key = random_signing_key();
     let verifying_key = signing_key.verifying_key();
     let public_key_hex = hex::encode(verifying_key.as_bytes());
 
@@ -100,8 +126,17 @@ async fn register_owner(base_url: &str, setup_token: &str) -> (String, String) {
 
 /// Register a regular user and return (access_token, user_id, fingerprint).
 async fn register_user(base_url: &str, name: &str) -> (String, String, String) {
+    register_user_with_key(base_url, name, &random_signing_key()).await
+}
+
+/// Register a regular user with a given identity key (the same identity can join
+/// several servers).
+async fn register_user_with_key(
+    base_url: &str,
+    name: &str,
+    signing_key: &SigningKey,
+) -> (String, String, String) {
     let client = reqwest::Client::new();
-    let signing_key = random_signing_key();
     let verifying_key = signing_key.verifying_key();
     let public_key_hex = hex::encode(verifying_key.as_bytes());
 
@@ -171,6 +206,94 @@ async fn test_kick_requires_kick_members_permission() {
     assert_eq!(resp.status(), 403, "Non-admin kick should return 403");
 }
 
+#[tokio::test]
+async fn test_kicked_or_banned_sessions_cannot_be_resumed() {
+    use futures_util::StreamExt;
+    use prost::Message as ProstMessage;
+    use tokio_tungstenite::tungstenite::Message;
+    use united_server::proto::ws::{envelope::Payload, Envelope};
+
+    let (base_url, setup_token, addr) = start_test_server().await;
+    let (owner_token, _) = register_owner(&base_url, &setup_token).await;
+    let (user_token, user_id, _) = register_user(&base_url, "Kicked").await;
+    let client = reqwest::Client::new();
+
+    let connect = |query: String| async move {
+        let (ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws?{}", addr, query))
+            .await
+            .expect("Failed to connect");
+        ws.split().1
+    };
+    // First envelope on a socket (the ReadyEvent), or the close code it was closed with
+    let first_event = |mut read: futures_util::stream::SplitStream<_>| async move {
+        loop {
+            let next = tokio::time::timeout(std::time::Duration::from_secs(2), read.next())
+                .await
+                .expect("Timed out waiting for the server");
+            match next {
+                Some(Ok(Message::Binary(data))) => {
+                    return (Some(Envelope::decode(data.as_ref()).unwrap()), read, None)
+                }
+                Some(Ok(Message::Close(frame))) => {
+                    return (None, read, frame.map(|f| u16::from(f.code)))
+                }
+                Some(Ok(_)) => continue,
+                other => panic!("Socket ended without a close frame: {:?}", other),
+            }
+        }
+    };
+    let ready_session = |envelope: Option<Envelope>| match envelope.and_then(|e| e.payload) {
+        Some(Payload::ReadyEvent(ready)) => ready,
+        other => panic!("Expected ReadyEvent, got: {:?}", other),
+    };
+    let wait_for_close = |mut read: futures_util::stream::SplitStream<_>| async move {
+        loop {
+            match tokio::time::timeout(std::time::Duration::from_secs(2), read.next())
+                .await
+                .expect("Timed out waiting for the close frame")
+            {
+                Some(Ok(Message::Close(frame))) => return frame.map(|f| u16::from(f.code)),
+                Some(Ok(_)) => continue,
+                other => panic!("Socket ended without a close frame: {:?}", other),
+            }
+        }
+    };
+
+    let (ready, read, _) = first_event(connect(format!("token={}", user_token)).await).await;
+    let ready = ready_session(ready);
+
+    let resp = client
+        .post(format!("{}/api/moderation/kick", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "user_id": user_id }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(wait_for_close(read).await, Some(4004));
+
+    // The kicked session is gone: resuming it starts a fresh one
+    let resume = |session_id: &str| format!("token={}&session={}&last_seq=0", user_token, session_id);
+    let (fresh, read, _) = first_event(connect(resume(&ready.session_id)).await).await;
+    let fresh = ready_session(fresh);
+    assert!(fresh.resume_failed);
+    assert_ne!(fresh.session_id, ready.session_id);
+
+    let resp = client
+        .post(format!("{}/api/moderation/ban", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "user_id": user_id, "reason": "spam" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(wait_for_close(read).await, Some(4003));
+
+    let (envelope, _, code) = first_event(connect(resume(&fresh.session_id)).await).await;
+    assert!(envelope.is_none(), "Banned user resumed: {:?}", envelope);
+    assert_eq!(code, Some(4003));
+}
+
 #[tokio::test]
 async fn test_ban_user() {
     let (base_url, setup_token, _) = start_test_server().await;
@@ -333,3 +456,927 @@ async fn test_ban_includes_reason() {
         .expect("Should find ban");
     assert_eq!(ban["reason"].as_str().unwrap(), "spamming");
 }
+
+#[tokio::test]
+async fn test_audit_log_records_privileged_actions() {
+    let (base_url, setup_token, _) = start_test_server().await;
+    let (owner_token, owner_id) = register_owner(&base_url, &setup_token).await;
+    let (user_token, user_id, fingerprint) = register_user(&base_url, "Troublemaker").await;
+    let client = reqwest::Client::new();
+
+    // Kick with a reason and evidence, then ban and unban
+    let resp = client
+        .post(format!("{}/api/moderation/kick", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "user_id": user_id, "reason": "flooding", "evidence_message_ids": ["41", "42"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .post(format!("{}/api/moderation/ban", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "user_id": user_id, "reason": "kept flooding" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .post(format!("{}/api/moderation/unban", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "fingerprint": fingerprint }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // A role change records before and after snapshots
+    let resp = client
+        .post(format!("{}/api/roles", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "name": "Helper", "permissions": 1 }))
+        .send()
+        .await
+        .unwrap();
+    let role: serde_json::Value = resp.json().await.unwrap();
+    let role_id = role["id"].as_str().unwrap().to_string();
+    let resp = client
+        .put(format!("{}/api/roles/{}", base_url, role_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "name": "Moderator" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Only admins can read the log
+    let resp = client
+        .get(format!("{}/api/audit-log", base_url))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    let resp = client
+        .get(format!("{}/api/audit-log", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let actions: Vec<&str> = body["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["action"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        actions,
+        vec!["role_update", "role_create", "member_unban", "member_ban", "member_kick"],
+        "Newest entries come first"
+    );
+
+    let entries = body["entries"].as_array().unwrap();
+    assert_eq!(entries[0]["before"]["name"], "Helper");
+    assert_eq!(entries[0]["after"]["name"], "Moderator");
+    assert_eq!(entries[2]["target_type"], "fingerprint");
+    assert_eq!(entries[2]["before"]["reason"], "kept flooding");
+    let kick = &entries[4];
+    assert_eq!(kick["actor_id"].as_str().unwrap(), owner_id);
+    assert_eq!(kick["target_id"].as_str().unwrap(), user_id);
+    assert_eq!(kick["reason"], "flooding");
+    assert_eq!(kick["evidence_message_ids"], json!(["41", "42"]));
+
+    // Filtering and pagination
+    let resp = client
+        .get(format!("{}/api/audit-log?target_id={}&limit=1", base_url, user_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["entries"].as_array().unwrap().len(), 1);
+    assert_eq!(body["entries"][0]["action"], "member_ban");
+    assert!(body["has_more"].as_bool().unwrap());
+    let cursor = body["entries"][0]["id"].as_str().unwrap().to_string();
+
+    let resp = client
+        .get(format!(
+            "{}/api/audit-log?target_id={}&before={}",
+            base_url, user_id, cursor
+        ))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["entries"].as_array().unwrap().len(), 1);
+    assert_eq!(body["entries"][0]["action"], "member_kick");
+    assert!(!body["has_more"].as_bool().unwrap());
+}
+
+#[tokio::test]
+async fn test_sanctions_escalate_and_mutes_are_enforced() {
+    let (base_url, setup_token, _) = start_test_server().await;
+    let (owner_token, _owner_id) = register_owner(&base_url, &setup_token).await;
+    let (user_token, user_id, fingerprint) = register_user(&base_url, "Heckler").await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let channel_id = body["categories"][0]["channels"][0]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let messages_url = format!("{}/api/channels/{}/messages", base_url, channel_id);
+    let record_url = format!("{}/api/moderation/users/{}/record", base_url, user_id);
+
+    // A clean record suggests a warning; members cannot open records
+    let resp = client
+        .get(&record_url)
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp = client
+        .get(&record_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let record: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(record["fingerprint"].as_str().unwrap(), fingerprint);
+    assert_eq!(record["suggested_next_step"]["action"], "warning");
+
+    // Warnings are visible to the target
+    let resp = client
+        .post(format!("{}/api/moderation/warn", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "user_id": user_id, "reason": "language" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .get(format!("{}/api/moderation/sanctions/me", base_url))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    let mine: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(mine["sanctions"][0]["kind"], "warning");
+    assert_eq!(mine["sanctions"][0]["reason"], "language");
+
+    let resp = client
+        .get(&record_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let record: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(record["suggested_next_step"]["action"], "text_mute");
+    assert_eq!(record["suggested_next_step"]["duration_secs"], 3600);
+
+    // Invalid mutes are rejected
+    for body in [
+        json!({ "user_id": user_id, "kind": "video", "duration_secs": 60 }),
+        json!({ "user_id": user_id, "kind": "text", "duration_secs": 0 }),
+    ] {
+        let resp = client
+            .post(format!("{}/api/moderation/mute", base_url))
+            .header("Authorization", format!("Bearer {}", owner_token))
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 400);
+    }
+
+    // A text mute blocks messages, edits and reactions until revoked
+    let resp = client
+        .post(&messages_url)
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "content": "before the mute" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let message: serde_json::Value = resp.json().await.unwrap();
+    let message_id = message["id"].as_str().unwrap().to_string();
+
+    let resp = client
+        .post(format!("{}/api/moderation/mute", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "user_id": user_id, "kind": "text", "duration_secs": 600, "reason": "cool off" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let mute: serde_json::Value = resp.json().await.unwrap();
+    let mute_id = mute["id"].as_str().unwrap().to_string();
+    assert!(mute["expires_at"].is_string());
+
+    let resp = client
+        .post(&messages_url)
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "content": "still talking" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp = client
+        .post(format!("{}/api/messages/{}/reactions", base_url, message_id))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "emoji": "👍" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    let message_url = format!("{}/{}", messages_url, message_id);
+    let resp = client
+        .put(&message_url)
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "content": "rewritten while muted" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    let resp = client
+        .get(&record_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let record: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(record["text_muted_until"], mute["expires_at"]);
+    assert!(record["voice_muted_until"].is_null());
+    assert_eq!(record["suggested_next_step"]["action"], "kick");
+
+    let resp = client
+        .delete(format!("{}/api/moderation/sanctions/{}", base_url, mute_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .delete(format!("{}/api/moderation/sanctions/{}", base_url, mute_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 409);
+
+    let resp = client
+        .post(&messages_url)
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "content": "sorry" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let resp = client
+        .put(&message_url)
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "content": "before the mute (edited)" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Kicks and temporary bans land on the record too
+    let resp = client
+        .post(format!("{}/api/moderation/kick", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "user_id": user_id, "reason": "again" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .get(&record_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let record: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(record["suggested_next_step"]["action"], "temporary_ban");
+
+    let resp = client
+        .post(format!("{}/api/moderation/ban", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "user_id": user_id, "duration_secs": 86400 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .get(&record_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let record: serde_json::Value = resp.json().await.unwrap();
+    assert!(record["banned"].as_bool().unwrap());
+    assert_eq!(record["suggested_next_step"]["action"], "ban");
+    let kinds: Vec<&str> = record["sanctions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, vec!["ban", "kick", "text_mute", "warning"]);
+    assert!(record["sanctions"][0]["expires_at"].is_string());
+    assert!(record["sanctions"][2]["revoked_at"].is_string());
+}
+
+#[tokio::test]
+async fn test_ban_list_export_and_reviewed_import_between_servers() {
+    let (url_a, setup_a, _) = start_test_server().await;
+    let (url_b, setup_b, _) = start_test_server().await;
+    let (owner_a, _) = register_owner(&url_a, &setup_a).await;
+    let (owner_b, _) = register_owner(&url_b, &setup_b).await;
+    let client = reqwest::Client::new();
+
+    // Two troublemakers are banned on server A; one of them is also a member of B
+    let spammer_key = random_signing_key();
+    let (_, spammer_a, spammer_fp) = register_user_with_key(&url_a, "Spammer", &spammer_key).await;
+    let (_, spammer_b, _) = register_user_with_key(&url_b, "Spammer", &spammer_key).await;
+    let (_, raider_a, raider_fp) = register_user(&url_a, "Raider").await;
+    for (user_id, reason) in [(&spammer_a, "spam"), (&raider_a, "raids")] {
+        let resp = client
+            .post(format!("{}/api/moderation/ban", url_a))
+            .header("Authorization", format!("Bearer {}", owner_a))
+            .json(&json!({ "user_id": user_id, "reason": reason }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+
+    // The export is public and signed
+    let resp = client
+        .get(format!("{}/api/moderation/ban-list", url_a))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let exported: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(exported["entries"].as_array().unwrap().len(), 2);
+    assert!(exported["signature"].is_string());
+
+    // A tampered list is rejected
+    let mut tampered = exported.clone();
+    tampered["entries"][0]["reason"] = json!("something else");
+    let resp = client
+        .post(format!("{}/api/moderation/ban-lists", url_b))
+        .header("Authorization", format!("Bearer {}", owner_b))
+        .json(&json!({ "list": tampered }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    // Server B imports by URL; nothing is banned until reviewed
+    let resp = client
+        .post(format!("{}/api/moderation/ban-lists", url_b))
+        .header("Authorization", format!("Bearer {}", owner_b))
+        .json(&json!({ "url": format!("{}/api/moderation/ban-list", url_a) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let import: serde_json::Value = resp.json().await.unwrap();
+    let source_id = import["source"]["id"].as_str().unwrap().to_string();
+    assert_eq!(import["added"].as_array().unwrap().len(), 2);
+    assert_eq!(import["source"]["pending_count"], 2);
+    let spammer_entry = import["added"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["fingerprint"] == spammer_fp.as_str())
+        .unwrap();
+    assert_eq!(spammer_entry["decision"], "pending");
+    assert_eq!(spammer_entry["local_user_id"].as_str().unwrap(), spammer_b);
+
+    let resp = client
+        .get(format!("{}/api/moderation/bans", url_b))
+        .header("Authorization", format!("Bearer {}", owner_b))
+        .send()
+        .await
+        .unwrap();
+    let bans: serde_json::Value = resp.json().await.unwrap();
+    assert!(bans["bans"].as_array().unwrap().is_empty());
+
+    // Watch the member, import the other entry
+    let decide = |fingerprint: &str, decision: &str| {
+        client
+            .put(format!(
+                "{}/api/moderation/ban-lists/{}/entries/{}",
+                url_b, source_id, fingerprint
+            ))
+            .header("Authorization", format!("Bearer {}", owner_b))
+            .json(&json!({ "decision": decision }))
+            .send()
+    };
+    let resp = decide(&spammer_fp, "watch").await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = decide(&raider_fp, "import").await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let entry: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(entry["decision"], "import");
+    assert!(entry["locally_banned"].as_bool().unwrap());
+    let resp = decide(&raider_fp, "auto").await.unwrap();
+    assert_eq!(resp.status(), 400);
+
+    let resp = client
+        .get(format!("{}/api/moderation/bans", url_b))
+        .header("Authorization", format!("Bearer {}", owner_b))
+        .send()
+        .await
+        .unwrap();
+    let bans: serde_json::Value = resp.json().await.unwrap();
+    let banned: Vec<&str> = bans["bans"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| b["fingerprint"].as_str().unwrap())
+        .collect();
+    assert_eq!(banned, vec![raider_fp.as_str()]);
+
+    let resp = client
+        .get(format!("{}/api/moderation/users/{}/record", url_b, spammer_b))
+        .header("Authorization", format!("Bearer {}", owner_b))
+        .send()
+        .await
+        .unwrap();
+    let record: serde_json::Value = resp.json().await.unwrap();
+    assert!(!record["banned"].as_bool().unwrap());
+    assert_eq!(record["watchlist"][0]["source_id"].as_str().unwrap(), source_id);
+    assert_eq!(record["watchlist"][0]["reason"], "spam");
+
+    // After an unban on A, re-importing reports the removal and keeps B's decisions
+    let resp = client
+        .post(format!("{}/api/moderation/unban", url_a))
+        .header("Authorization", format!("Bearer {}", owner_a))
+        .json(&json!({ "fingerprint": raider_fp }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .post(format!("{}/api/moderation/ban-lists", url_b))
+        .header("Authorization", format!("Bearer {}", owner_b))
+        .json(&json!({ "url": format!("{}/api/moderation/ban-list", url_a) }))
+        .send()
+        .await
+        .unwrap();
+    let import: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(import["source"]["id"].as_str().unwrap(), source_id);
+    assert!(import["added"].as_array().unwrap().is_empty());
+    assert_eq!(import["removed"], json!([raider_fp]));
+
+    let resp = client
+        .get(format!("{}/api/moderation/ban-lists/{}", url_b, source_id))
+        .header("Authorization", format!("Bearer {}", owner_b))
+        .send()
+        .await
+        .unwrap();
+    let detail: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(detail["entries"].as_array().unwrap().len(), 1);
+    assert_eq!(detail["entries"][0]["decision"], "watch");
+}
+
+#[tokio::test]
+async fn test_ban_list_entries_carry_verified_admin_signatures() {
+    use united_server::moderation::ban_list::{ban_signing_bytes, BanList, BanListEntry};
+
+    let (url_a, setup_a, _) = start_test_server().await;
+    let (url_b, setup_b, _) = start_test_server().await;
+    let owner_key = random_signing_key();
+    let (owner_a, _) = register_owner_with_key(&url_a, &setup_a, &owner_key).await;
+    let (owner_b, _) = register_owner(&url_b, &setup_b).await;
+    let spammer_key = random_signing_key();
+    let (_, spammer_id, spammer_fp) = register_user_with_key(&url_a, "Spammer", &spammer_key).await;
+    let (_, raider_id, raider_fp) = register_user(&url_a, "Raider").await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{}/api/moderation/ban-list", url_a))
+        .send()
+        .await
+        .unwrap();
+    let exported: serde_json::Value = resp.json().await.unwrap();
+    let server_key = exported["server_public_key"].as_str().unwrap().to_string();
+    let spammer_pk = hex::encode(spammer_key.verifying_key().as_bytes());
+    let signed_at = chrono::Utc::now().to_rfc3339();
+    let signed = ban_signing_bytes(&server_key, &spammer_pk, "spam", &signed_at);
+
+    // A signature from any key but the caller's is rejected
+    let forged = hex::encode(random_signing_key().sign(&signed).to_bytes());
+    let resp = client
+        .post(format!("{}/api/moderation/ban", url_a))
+        .header("Authorization", format!("Bearer {}", owner_a))
+        .json(&json!({
+            "user_id": spammer_id,
+            "reason": "spam",
+            "admin_signature": forged,
+            "signed_at": signed_at,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    let signature = hex::encode(owner_key.sign(&signed).to_bytes());
+    let resp = client
+        .post(format!("{}/api/moderation/ban", url_a))
+        .header("Authorization", format!("Bearer {}", owner_a))
+        .json(&json!({
+            "user_id": spammer_id,
+            "reason": "spam",
+            "admin_signature": signature,
+            "signed_at": signed_at,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .post(format!("{}/api/moderation/ban", url_a))
+        .header("Authorization", format!("Bearer {}", owner_a))
+        .json(&json!({ "user_id": raider_id, "reason": "raids" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // The signed ban is exported with its signature and the banned key
+    let resp = client
+        .get(format!("{}/api/moderation/ban-list", url_a))
+        .send()
+        .await
+        .unwrap();
+    let exported: serde_json::Value = resp.json().await.unwrap();
+    let entries = exported["entries"].as_array().unwrap();
+    let spammer_entry = entries.iter().find(|e| e["fingerprint"] == spammer_fp.as_str()).unwrap();
+    assert_eq!(spammer_entry["admin_signature"].as_str().unwrap(), signature);
+    assert_eq!(spammer_entry["banned_key"].as_str().unwrap(), spammer_pk);
+    assert_eq!(spammer_entry["banned_at"].as_str().unwrap(), signed_at);
+    let raider_entry = entries.iter().find(|e| e["fingerprint"] == raider_fp.as_str()).unwrap();
+    assert!(raider_entry.get("admin_signature").is_none());
+
+    // Server B verifies the entry signature on import
+    let resp = client
+        .post(format!("{}/api/moderation/ban-lists", url_b))
+        .header("Authorization", format!("Bearer {}", owner_b))
+        .json(&json!({ "list": exported }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let import: serde_json::Value = resp.json().await.unwrap();
+    let signed_by: Vec<(String, bool)> = import["added"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            (
+                e["fingerprint"].as_str().unwrap().to_string(),
+                e["admin_signed"].as_bool().unwrap(),
+            )
+        })
+        .collect();
+    assert!(signed_by.contains(&(spammer_fp.clone(), true)));
+    assert!(signed_by.contains(&(raider_fp.clone(), false)));
+
+    // A server-signed list whose entry claims an admin signature it does not have is
+    // rejected, even though the list signature itself is valid
+    let rogue_server = random_signing_key();
+    let rogue_key = hex::encode(rogue_server.verifying_key().as_bytes());
+    let list = BanList {
+        version: 1,
+        server_name: "Rogue".to_string(),
+        server_public_key: rogue_key.clone(),
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        entries: vec![BanListEntry {
+            fingerprint: spammer_fp.clone(),
+            reason: "spam".to_string(),
+            banned_at: signed_at.clone(),
+            expires_at: None,
+            banned_by: hex::encode(owner_key.verifying_key().as_bytes()),
+            banned_key: Some(spammer_pk.clone()),
+            // Signed for server A, not for the rogue server
+            admin_signature: Some(signature.clone()),
+        }],
+    };
+    let resp = client
+        .post(format!("{}/api/moderation/ban-lists", url_b))
+        .header("Authorization", format!("Bearer {}", owner_b))
+        .json(&json!({ "list": list.sign(&rogue_server) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    assert!(resp.text().await.unwrap().contains(&spammer_fp));
+}
+
+#[tokio::test]
+async fn test_abuse_flags_queue_for_admin_review() {
+    let (base_url, setup_token, _) = start_test_server().await;
+    let (owner_token, _owner_id) = register_owner(&base_url, &setup_token).await;
+    let (user_token, user_id, _) = register_user(&base_url, "Spammer").await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let category = &body["categories"][0];
+    let mut channel_ids: Vec<String> = category["channels"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|c| c["channel_type"] == "text")
+        .map(|c| c["id"].as_str().unwrap().to_string())
+        .collect();
+    let resp = client
+        .post(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({
+            "name": "off-topic",
+            "channel_type": "text",
+            "category_id": category["category"]["id"],
+        }))
+        .send()
+        .await
+        .unwrap();
+    let created: serde_json::Value = resp.json().await.unwrap();
+    channel_ids.push(created["id"].as_str().unwrap().to_string());
+    assert!(channel_ids.len() >= 3);
+
+    // The same message in three channels is flagged, but not punished
+    for channel_id in &channel_ids[..3] {
+        let resp = client
+            .post(format!("{}/api/channels/{}/messages", base_url, channel_id))
+            .header("Authorization", format!("Bearer {}", user_token))
+            .json(&json!({ "content": "Cheap followers at spam dot example" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 201);
+    }
+
+    let flags_url = format!("{}/api/moderation/flags", base_url);
+    let mut flags = serde_json::Value::Null;
+    for _ in 0..50 {
+        let resp = client
+            .get(&flags_url)
+            .header("Authorization", format!("Bearer {}", owner_token))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        flags = resp.json().await.unwrap();
+        if !flags["flags"].as_array().unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    let flag = &flags["flags"][0];
+    assert_eq!(flag["user_id"].as_str().unwrap(), user_id);
+    assert_eq!(flag["display_name"], "Spammer");
+    assert_eq!(flag["kind"], "duplicate_messages");
+    assert_eq!(flag["status"], "open");
+    let flag_id = flag["id"].as_str().unwrap().to_string();
+
+    let resp = client
+        .get(&flags_url)
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    // Dismissing closes the flag and is audited
+    let resp = client
+        .put(format!("{}/{}", flags_url, flag_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "status": "dismissed" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let reviewed: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(reviewed["status"], "dismissed");
+
+    let resp = client
+        .get(&flags_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let open: serde_json::Value = resp.json().await.unwrap();
+    assert!(open["flags"].as_array().unwrap().is_empty());
+    let resp = client
+        .get(format!("{}?status=all&user_id={}", flags_url, user_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let all: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(all["flags"][0]["status"], "dismissed");
+
+    let resp = client
+        .get(format!("{}/api/audit-log?action=flag_review", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let log: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(log["entries"][0]["target_id"].as_str().unwrap(), flag_id);
+
+    // The flagged user can still post
+    let resp = client
+        .post(format!("{}/api/channels/{}/messages", base_url, channel_ids[0]))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "content": "sorry" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+}
+
+#[tokio::test]
+async fn test_probation_gates_new_members_until_fast_tracked() {
+    let trust = united_server::config::TrustConfig::default();
+    let (base_url, setup_token, _) = start_test_server_with_trust(Some(trust)).await;
+    let (owner_token, _owner_id) = register_owner(&base_url, &setup_token).await;
+    let (user_token, user_id, _) = register_user(&base_url, "Newcomer").await;
+    let friend_key = random_signing_key();
+    let (friend_token, _, _) = register_user_with_key(&base_url, "Friend", &friend_key).await;
+    let friend_pubkey = hex::encode(friend_key.verifying_key().to_bytes());
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{}/api/channels", base_url))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let channel_id = body["categories"][0]["channels"][0]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let messages_url = format!("{}/api/channels/{}/messages", base_url, channel_id);
+
+    let resp = client
+        .get(format!("{}/api/moderation/probation/me", base_url))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .send()
+        .await
+        .unwrap();
+    let status: serde_json::Value = resp.json().await.unwrap();
+    assert!(status["probation_until"].is_string());
+    let gated: Vec<&str> = status["gated"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|g| g.as_str().unwrap())
+        .collect();
+    assert_eq!(gated, vec!["links", "attachments", "direct_messages", "mentions"]);
+
+    // Plain messages and reactions are allowed; links and role mentions are not
+    let resp = client
+        .post(&messages_url)
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "content": "hello there" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let message: serde_json::Value = resp.json().await.unwrap();
+    let resp = client
+        .post(format!("{}/api/messages/{}/reactions", base_url, message["id"].as_str().unwrap()))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "emoji": "👋" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+
+    for (content, code) in [
+        ("see https://example.com", "probation_links"),
+        ("@role:moderators look", "probation_mentions"),
+    ] {
+        let resp = client
+            .post(&messages_url)
+            .header("Authorization", format!("Bearer {}", user_token))
+            .json(&json!({ "content": content }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 403);
+        assert!(resp.text().await.unwrap().starts_with(code));
+    }
+
+    // Editing a link into an earlier message is held to the same limits
+    let resp = client
+        .put(format!("{}/{}", messages_url, message["id"].as_str().unwrap()))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "content": "hello https://example.com" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    assert!(resp.text().await.unwrap().starts_with("probation_links"));
+
+    let resp = client
+        .put(format!("{}/api/blocks", base_url))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .header("X-Block-Hash", hex::encode(Sha256::digest(b"payload")))
+        .body(b"payload".to_vec())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    assert!(resp.text().await.unwrap().starts_with("probation_attachments"));
+
+    let resp = client
+        .get(format!("{}/api/moderation/users/{}/record", base_url, user_id))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let record: serde_json::Value = resp.json().await.unwrap();
+    assert!(record["probation_until"].is_string());
+    let resp = client
+        .post(format!("{}/api/dm/conversations", base_url))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "recipient_pubkey": friend_pubkey }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    assert!(resp.text().await.unwrap().starts_with("probation_direct_messages"));
+
+    // Members cannot fast-track; moderators can, and the gates lift immediately
+    let fast_track_url = format!("{}/api/moderation/users/{}/fast-track", base_url, user_id);
+    let resp = client
+        .post(&fast_track_url)
+        .header("Authorization", format!("Bearer {}", friend_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp = client
+        .post(&fast_track_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let status: serde_json::Value = resp.json().await.unwrap();
+    assert!(status["probation_until"].is_null());
+    assert!(status["trusted_at"].is_string());
+
+    let resp = client
+        .post(&messages_url)
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "content": "see https://example.com" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let resp = client
+        .post(format!("{}/api/dm/conversations", base_url))
+        .header("Authorization", format!("Bearer {}", user_token))
+        .json(&json!({ "recipient_pubkey": friend_pubkey }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+
+    let resp = client
+        .get(format!("{}/api/audit-log?action=member_fast_track", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    let log: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(log["entries"][0]["target_id"].as_str().unwrap(), user_id);
+}
@@ -0,0 +1,149 @@
+//! Tests for the per-user token buckets behind REST and WebSocket rate limiting.
+//! Tests cover: burst then refill, per-key and per-class isolation, route classification,
+//! disabled limits, and pruning of idle buckets.
+
+use std::time::{Duration, Instant};
+
+use axum::http::Method;
+use united_server::config::{RateLimit, RateLimitConfig};
+use united_server::rate_limit::{RateClass, RateLimiter};
+
+fn config_with_messages(per_minute: u32, burst: u32) -> RateLimitConfig {
+    RateLimitConfig {
+        messages: RateLimit { per_minute, burst },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_bucket_allows_burst_then_refills() {
+    let limiter = RateLimiter::new(config_with_messages(60, 3));
+    let t = Instant::now();
+
+    for _ in 0..3 {
+        assert!(limiter.check_at(RateClass::Messages, "user:a", t).is_ok());
+    }
+    let retry_after = limiter
+        .check_at(RateClass::Messages, "user:a", t)
+        .unwrap_err();
+    assert!(retry_after <= Duration::from_secs(1) && retry_after > Duration::ZERO);
+
+    // One token per second at 60/min
+    let later = t + Duration::from_millis(1000);
+    assert!(limiter
+        .check_at(RateClass::Messages, "user:a", later)
+        .is_ok());
+    assert!(limiter
+        .check_at(RateClass::Messages, "user:a", later)
+        .is_err());
+
+    // Idle time refills up to the burst, not beyond it
+    let much_later = t + Duration::from_secs(600);
+    for _ in 0..3 {
+        assert!(limiter
+            .check_at(RateClass::Messages, "user:a", much_later)
+            .is_ok());
+    }
+    assert!(limiter
+        .check_at(RateClass::Messages, "user:a", much_later)
+        .is_err());
+}
+
+#[test]
+fn test_buckets_are_per_key_and_per_class() {
+    let limiter = RateLimiter::new(config_with_messages(6, 1));
+    let t = Instant::now();
+
+    assert!(limiter.check_at(RateClass::Messages, "user:a", t).is_ok());
+    assert!(limiter.check_at(RateClass::Messages, "user:a", t).is_err());
+    assert!(limiter.check_at(RateClass::Messages, "user:b", t).is_ok());
+    assert!(limiter.check_at(RateClass::Api, "user:a", t).is_ok());
+    assert!(limiter
+        .check_at(RateClass::Messages, "ip:127.0.0.1", t)
+        .is_ok());
+}
+
+#[test]
+fn test_disabled_and_unlimited_buckets() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        enabled: false,
+        ..config_with_messages(1, 1)
+    });
+    let t = Instant::now();
+    for _ in 0..10 {
+        assert!(limiter.check_at(RateClass::Messages, "user:a", t).is_ok());
+    }
+    assert!(limiter.is_empty());
+
+    let limiter = RateLimiter::new(config_with_messages(0, 1));
+    for _ in 0..10 {
+        assert!(limiter.check_at(RateClass::Messages, "user:a", t).is_ok());
+    }
+}
+
+#[test]
+fn test_route_classification() {
+    let cases = [
+        (
+            Method::POST,
+            "/api/channels/{channel_id}/messages",
+            RateClass::Messages,
+        ),
+        (
+            Method::GET,
+            "/api/channels/{channel_id}/messages",
+            RateClass::Api,
+        ),
+        (
+            Method::PUT,
+            "/api/channels/{channel_id}/messages/{message_id}",
+            RateClass::Messages,
+        ),
+        (
+            Method::POST,
+            "/api/threads/{thread_id}/messages",
+            RateClass::Messages,
+        ),
+        (
+            Method::POST,
+            "/api/messages/{message_id}/reactions",
+            RateClass::Reactions,
+        ),
+        (Method::POST, "/api/typing", RateClass::Typing),
+        (Method::POST, "/api/dm/messages", RateClass::DirectMessages),
+        (
+            Method::POST,
+            "/api/dm/conversations",
+            RateClass::DirectMessages,
+        ),
+        (Method::PUT, "/api/blocks", RateClass::Uploads),
+        (Method::GET, "/api/blocks/{hash}", RateClass::Api),
+    ];
+    for (method, path, class) in cases {
+        assert_eq!(
+            RateClass::for_route(&method, path),
+            class,
+            "{} {}",
+            method,
+            path
+        );
+    }
+}
+
+#[test]
+fn test_retain_recent_drops_only_full_buckets() {
+    let limiter = RateLimiter::new(config_with_messages(1, 5));
+    let t = Instant::now();
+
+    // A drained bucket refills slowly (1/min) and is kept
+    for _ in 0..5 {
+        let _ = limiter.check_at(RateClass::Messages, "user:busy", t);
+    }
+    // A fast API bucket is back to full almost immediately and is dropped
+    limiter.check_at(RateClass::Api, "user:idle", t).unwrap();
+    assert_eq!(limiter.len(), 2);
+
+    std::thread::sleep(Duration::from_millis(300));
+    limiter.retain_recent();
+    assert_eq!(limiter.len(), 1);
+}
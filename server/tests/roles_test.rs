@@ -32,6 +32,7 @@ async fn start_test_server() -> (String, SocketAddr) {
     let connections = united_server::ws::new_connection_registry();
 
     let (swarm_cmd_tx, _swarm_cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+    let abuse = united_server::moderation::abuse::AbuseMonitor::spawn(db.clone());
     let state = united_server::state::AppState {
         db,
         challenges: Arc::new(dashmap::DashMap::new()),
@@ -42,12 +43,22 @@ async fn start_test_server() -> (String, SocketAddr) {
         swarm_cmd_tx,
         peer_directory: Arc::new(united_server::p2p::PeerDirectory::new()),
         server_peer_id: "test-peer-id".to_string(),
+        server_signing_key: Arc::new(ed25519_dalek::SigningKey::from_bytes(&[7u8; 32])),
+        gossip_rejections: Arc::new(united_server::p2p::validation::GossipRejectionCounters::new()),
+        outbound_metrics: Arc::new(united_server::ws::outbound::OutboundMetrics::new()),
         libp2p_port: 0,
         presence: Arc::new(dashmap::DashMap::new()),
         data_dir: data_dir.clone(),
         block_retention_days: None,
         block_cleanup_interval_secs: None,
         max_upload_size_mb: None,
+        voice_state: Arc::new(united_server::voice::state::VoiceState::new()),
+        turn_config: None,
+        trust_config: None,
+        abuse,
+        rate_limiter: Arc::new(united_server::rate_limit::RateLimiter::new(
+            united_server::config::RateLimitConfig::default(),
+        )),
     };
 
     let app = united_server::routes::build_router(state);
@@ -150,8 +161,33 @@ async fn test_everyone_role_created_on_seed() {
         .find(|r| r["is_default"].as_bool() == Some(true))
         .expect("Expected @everyone role");
     assert_eq!(everyone["name"].as_str().unwrap(), "everyone");
-    // SEND_MESSAGES = 0x01 = 1
-    assert_eq!(everyone["permissions"].as_u64().unwrap(), 1);
+    // SEND_MESSAGES | VIEW_CHANNEL | READ_HISTORY | ATTACH_FILES | ADD_REACTIONS = 12385
+    assert_eq!(everyone["permissions"].as_u64().unwrap(), 12385);
+}
+
+/// Test 1b: An existing server's @everyone ends up with the same permissions as a new one.
+#[test]
+fn test_migrated_everyone_matches_new_server_default() {
+    use united_server::roles::permissions::Permissions;
+
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let mut conn = rusqlite::Connection::open(tmp_dir.path().join("united.db")).unwrap();
+    let migrations = united_server::db::migrations::migrations();
+    migrations.to_version(&mut conn, 9).unwrap();
+
+    // Before the expanded bits, @everyone only had SEND_MESSAGES
+    conn.execute(
+        "INSERT INTO roles (id, name, permissions, position, is_default, created_at, updated_at)
+         VALUES ('everyone', 'everyone', 1, 0, 1, '', '')",
+        [],
+    )
+    .unwrap();
+    migrations.to_latest(&mut conn).unwrap();
+
+    let permissions: u32 = conn
+        .query_row("SELECT permissions FROM roles WHERE id = 'everyone'", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(permissions, Permissions::DEFAULT_EVERYONE.bits());
 }
 
 /// Test 2: Create a role with name, permissions, and color.
@@ -532,3 +568,297 @@ async fn test_new_user_gets_everyone_role() {
         .any(|r| r["is_default"].as_bool() == Some(true));
     assert!(has_everyone, "New user should have the @everyone role");
 }
+
+/// Test 11: MANAGE_ROLES works without ADMIN, but cannot grant or assign bits the caller lacks.
+#[tokio::test]
+async fn test_manage_roles_cannot_escalate() {
+    let (combined, _) = start_test_server().await;
+    let (base_url, setup_token) = parse_server_info(&combined);
+    let client = reqwest::Client::new();
+
+    let (_owner_id, owner_token) = register_user(&client, base_url, "Owner", Some(setup_token)).await;
+    let (manager_id, manager_token) = register_user(&client, base_url, "Manager", None).await;
+
+    // Owner creates a role with MANAGE_ROLES (0x100) and assigns it to the manager
+    let resp = client
+        .post(format!("{}/api/roles", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "name": "Role Manager", "permissions": 256, "color": "" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let manager_role: serde_json::Value = resp.json().await.unwrap();
+
+    let resp = client
+        .post(format!("{}/api/roles/assign", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "user_id": manager_id, "role_id": manager_role["id"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Manager can create a role with bits they hold (SEND_MESSAGES | MANAGE_ROLES)
+    let resp = client
+        .post(format!("{}/api/roles", base_url))
+        .header("Authorization", format!("Bearer {}", manager_token))
+        .json(&json!({ "name": "Helper", "permissions": 257, "color": "" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201, "MANAGE_ROLES should allow role creation");
+    let helper_role: serde_json::Value = resp.json().await.unwrap();
+
+    // ...but not one carrying ADMIN (0x10)
+    let resp = client
+        .post(format!("{}/api/roles", base_url))
+        .header("Authorization", format!("Bearer {}", manager_token))
+        .json(&json!({ "name": "Sneaky", "permissions": 16, "color": "" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403, "Granting ADMIN without holding it should be forbidden");
+
+    // ...nor raise an existing role to BAN_MEMBERS (0x08)
+    let resp = client
+        .put(format!("{}/api/roles/{}", base_url, helper_role["id"].as_str().unwrap()))
+        .header("Authorization", format!("Bearer {}", manager_token))
+        .json(&json!({ "permissions": 8 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403, "Granting BAN_MEMBERS without holding it should be forbidden");
+
+    // ...nor assign an owner-created role with ADMIN to themselves
+    let resp = client
+        .post(format!("{}/api/roles", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "name": "Admins", "permissions": 16, "color": "" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let admin_role: serde_json::Value = resp.json().await.unwrap();
+
+    let resp = client
+        .post(format!("{}/api/roles/assign", base_url))
+        .header("Authorization", format!("Bearer {}", manager_token))
+        .json(&json!({ "user_id": manager_id, "role_id": admin_role["id"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403, "Assigning an ADMIN role without holding ADMIN should be forbidden");
+}
+
+/// Test 12: Role hierarchy — moderators only act on roles and members below their highest role.
+#[tokio::test]
+async fn test_role_hierarchy_and_reorder() {
+    let (combined, _) = start_test_server().await;
+    let (base_url, setup_token) = parse_server_info(&combined);
+    let client = reqwest::Client::new();
+
+    let (_owner_id, owner_token) = register_user(&client, base_url, "Owner", Some(setup_token)).await;
+    let (mod_id, mod_token) = register_user(&client, base_url, "Moderator", None).await;
+    let (senior_id, _senior_token) = register_user(&client, base_url, "Senior", None).await;
+
+    // New roles start at the bottom, so "Senior" ends up above "Moderator"
+    let mut role_ids = Vec::new();
+    for (name, permissions) in [("Senior", 1), ("Moderator", 260)] {
+        let resp = client
+            .post(format!("{}/api/roles", base_url))
+            .header("Authorization", format!("Bearer {}", owner_token))
+            .json(&json!({ "name": name, "permissions": permissions, "color": "" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 201);
+        let role: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(role["position"].as_i64().unwrap(), 1, "New roles start just above @everyone");
+        role_ids.push(role["id"].as_str().unwrap().to_string());
+    }
+    let (senior_role, mod_role) = (&role_ids[0], &role_ids[1]);
+
+    for (user_id, role_id) in [(&senior_id, senior_role), (&mod_id, mod_role)] {
+        let resp = client
+            .post(format!("{}/api/roles/assign", base_url))
+            .header("Authorization", format!("Bearer {}", owner_token))
+            .json(&json!({ "user_id": user_id, "role_id": role_id }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+
+    // Moderator cannot kick someone ranked above them
+    let resp = client
+        .post(format!("{}/api/moderation/kick", base_url))
+        .header("Authorization", format!("Bearer {}", mod_token))
+        .json(&json!({ "user_id": senior_id }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403, "Kicking a higher-ranked member should be forbidden");
+
+    // ...nor assign, edit, or move a role above their own
+    let resp = client
+        .post(format!("{}/api/roles/assign", base_url))
+        .header("Authorization", format!("Bearer {}", mod_token))
+        .json(&json!({ "user_id": mod_id, "role_id": senior_role }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403, "Assigning a higher role should be forbidden");
+
+    let resp = client
+        .put(format!("{}/api/roles/{}", base_url, senior_role))
+        .header("Authorization", format!("Bearer {}", mod_token))
+        .json(&json!({ "name": "Demoted" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403, "Editing a higher role should be forbidden");
+
+    let resp = client
+        .put(format!("{}/api/roles/reorder", base_url))
+        .header("Authorization", format!("Bearer {}", mod_token))
+        .json(&json!({ "entries": [{ "id": senior_role, "position": 1 }] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403, "Reordering a higher role should be forbidden");
+
+    // Owner moves Moderator to the top
+    let resp = client
+        .put(format!("{}/api/roles/reorder", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "entries": [{ "id": mod_role, "position": 3 }] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let last = body["roles"].as_array().unwrap().last().unwrap().clone();
+    assert_eq!(last["id"].as_str().unwrap(), mod_role, "Roles should be returned in position order");
+
+    // The @everyone role cannot be moved
+    let everyone_id = body["roles"][0]["id"].as_str().unwrap();
+    let resp = client
+        .put(format!("{}/api/roles/reorder", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&json!({ "entries": [{ "id": everyone_id, "position": 5 }] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    // Now the kick succeeds
+    let resp = client
+        .post(format!("{}/api/moderation/kick", base_url))
+        .header("Authorization", format!("Bearer {}", mod_token))
+        .json(&json!({ "user_id": senior_id }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200, "Kicking a lower-ranked member should succeed");
+}
+
+#[tokio::test]
+async fn test_role_changes_require_outranking_the_member() {
+    let (combined, _) = start_test_server().await;
+    let (base_url, setup_token) = parse_server_info(&combined);
+    let client = reqwest::Client::new();
+
+    let (owner_id, owner_token) = register_user(&client, base_url, "Owner", Some(setup_token)).await;
+    let (mod_id, mod_token) = register_user(&client, base_url, "Moderator", None).await;
+    let (senior_id, _senior_token) = register_user(&client, base_url, "Senior", None).await;
+    let (member_id, _member_token) = register_user(&client, base_url, "Member", None).await;
+
+    // Created bottom-up: Helper < Moderator < Senior
+    let mut role_ids = Vec::new();
+    for (name, permissions) in [("Senior", 1), ("Moderator", 260), ("Helper", 0)] {
+        let resp = client
+            .post(format!("{}/api/roles", base_url))
+            .header("Authorization", format!("Bearer {}", owner_token))
+            .json(&json!({ "name": name, "permissions": permissions, "color": "" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 201);
+        let role: serde_json::Value = resp.json().await.unwrap();
+        role_ids.push(role["id"].as_str().unwrap().to_string());
+    }
+    let (senior_role, mod_role, helper_role) = (&role_ids[0], &role_ids[1], &role_ids[2]);
+
+    for (user_id, role_id) in [
+        (&senior_id, senior_role),
+        (&senior_id, helper_role),
+        (&mod_id, mod_role),
+    ] {
+        let resp = client
+            .post(format!("{}/api/roles/assign", base_url))
+            .header("Authorization", format!("Bearer {}", owner_token))
+            .json(&json!({ "user_id": user_id, "role_id": role_id }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+
+    // The Helper role is below the moderator, but the members are not
+    for (target, path) in [
+        (&senior_id, "assign"),
+        (&senior_id, "remove"),
+        (&owner_id, "assign"),
+    ] {
+        let resp = client
+            .post(format!("{}/api/roles/{}", base_url, path))
+            .header("Authorization", format!("Bearer {}", mod_token))
+            .json(&json!({ "user_id": target, "role_id": helper_role }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.status(),
+            403,
+            "Changing roles of a member ranked above the caller should be forbidden ({})",
+            path
+        );
+    }
+
+    let members: serde_json::Value = client
+        .get(format!("{}/api/members", base_url))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let senior = members
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["id"].as_str() == Some(senior_id.as_str()))
+        .unwrap();
+    assert!(
+        senior["role_ids"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|r| r.as_str() == Some(helper_role.as_str())),
+        "Senior should keep the Helper role"
+    );
+
+    // Lower-ranked members can still be managed
+    for path in ["assign", "remove"] {
+        let resp = client
+            .post(format!("{}/api/roles/{}", base_url, path))
+            .header("Authorization", format!("Bearer {}", mod_token))
+            .json(&json!({ "user_id": member_id, "role_id": helper_role }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200, "Managing a lower-ranked member should succeed ({})", path);
+    }
+}
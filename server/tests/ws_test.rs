@@ -27,19 +27,21 @@ async fn drain_presence_messages(
         >,
     >,
 ) {
-    loop {
-        match tokio::time::timeout(Duration::from_millis(200), read.next()).await {
-            Ok(Some(Ok(Message::Binary(_)))) => {
-                // Presence snapshot message — keep draining
-                continue;
-            }
-            _ => break, // Timeout or no more messages
-        }
-    }
+    // Presence snapshot messages — keep draining until timeout or end of stream
+    while let Ok(Some(Ok(Message::Binary(_)))) =
+        tokio::time::timeout(Duration::from_millis(200), read.next()).await
+    {}
 }
 
 /// Helper: start the server on a random port and return (base_url, setup_token, addr).
 async fn start_test_server() -> (String, String, SocketAddr) {
+    start_test_server_with_rate_limits(united_server::config::RateLimitConfig::default()).await
+}
+
+/// Helper: start the server with the given `[rate_limits]` configuration.
+async fn start_test_server_with_rate_limits(
+    rate_limits: united_server::config::RateLimitConfig,
+) -> (String, String, SocketAddr) {
     let tmp_dir = tempfile::tempdir().expect("Failed to create temp dir");
     let data_dir = tmp_dir.path().to_str().unwrap().to_string();
 
@@ -55,6 +57,7 @@ async fn start_test_server() -> (String, String, SocketAddr) {
     let connections = united_server::ws::new_connection_registry();
 
     let (swarm_cmd_tx, _swarm_cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+    let abuse = united_server::moderation::abuse::AbuseMonitor::spawn(db.clone());
     let state = united_server::state::AppState {
         db,
         challenges: Arc::new(dashmap::DashMap::new()),
@@ -65,12 +68,20 @@ async fn start_test_server() -> (String, String, SocketAddr) {
         swarm_cmd_tx,
         peer_directory: Arc::new(united_server::p2p::PeerDirectory::new()),
         server_peer_id: "test-peer-id".to_string(),
+        server_signing_key: Arc::new(ed25519_dalek::SigningKey::from_bytes(&[7u8; 32])),
+        gossip_rejections: Arc::new(united_server::p2p::validation::GossipRejectionCounters::new()),
+        outbound_metrics: Arc::new(united_server::ws::outbound::OutboundMetrics::new()),
         libp2p_port: 0,
         presence: Arc::new(dashmap::DashMap::new()),
         data_dir: data_dir.clone(),
         block_retention_days: None,
         block_cleanup_interval_secs: None,
         max_upload_size_mb: None,
+        voice_state: Arc::new(united_server::voice::state::VoiceState::new()),
+        turn_config: None,
+        trust_config: None,
+        abuse,
+        rate_limiter: Arc::new(united_server::rate_limit::RateLimiter::new(rate_limits)),
     };
 
     let app = united_server::routes::build_router(state);
@@ -250,6 +261,7 @@ async fn test_ws_protobuf_server_info_request() {
     // Send a ServerInfoRequest via protobuf envelope
     let envelope = united_server::proto::ws::Envelope {
         request_id: "test-req-1".to_string(),
+        seq: 0,
         payload: Some(
             united_server::proto::ws::envelope::Payload::ServerInfoRequest(
                 united_server::proto::ws::ServerInfoRequest {},
@@ -331,3 +343,902 @@ async fn test_ws_connection_cleanup_on_disconnect() {
     let result = tokio::time::timeout(Duration::from_millis(300), read2.next()).await;
     assert!(result.is_err(), "Expected timeout after presence drain (connection alive)");
 }
+
+/// Send a request envelope and wait for the response carrying the same request_id,
+/// skipping broadcast events (which have an empty request_id).
+async fn ws_request(
+    write: &mut futures_util::stream::SplitSink<
+        tokio_tungstenite::WebSocketStream<
+            tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+        >,
+        Message,
+    >,
+    read: &mut futures_util::stream::SplitStream<
+        tokio_tungstenite::WebSocketStream<
+            tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+        >,
+    >,
+    request_id: &str,
+    payload: united_server::proto::ws::envelope::Payload,
+) -> united_server::proto::ws::envelope::Payload {
+    let envelope = united_server::proto::ws::Envelope {
+        request_id: request_id.to_string(),
+        seq: 0,
+        payload: Some(payload),
+    };
+    write
+        .send(Message::Binary(envelope.encode_to_vec().into()))
+        .await
+        .expect("Failed to send protobuf");
+
+    loop {
+        let msg = tokio::time::timeout(Duration::from_secs(2), read.next())
+            .await
+            .expect("Expected response within timeout");
+        if let Some(Ok(Message::Binary(data))) = msg {
+            let response = united_server::proto::ws::Envelope::decode(data.as_ref())
+                .expect("Failed to decode protobuf response");
+            if response.request_id == request_id {
+                return response.payload.expect("Response should have a payload");
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_ws_channel_requests_use_rest_logic() {
+    use united_server::proto::channels as proto_channels;
+    use united_server::proto::ws::envelope::Payload;
+
+    let (base_url, setup_token, addr) = start_test_server().await;
+    let (owner_token, _fingerprint, _signing_key) =
+        register_user(&base_url, &setup_token, "WsOwner").await;
+    let (member_token, _fingerprint, _signing_key) =
+        register_user(&base_url, "", "WsMember").await;
+
+    let (owner_ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws?token={}", addr, owner_token))
+        .await
+        .expect("Failed to connect");
+    let (mut owner_write, mut owner_read) = owner_ws.split();
+    drain_presence_messages(&mut owner_read).await;
+
+    // Owner creates a category and a channel over the socket
+    let category = match ws_request(
+        &mut owner_write,
+        &mut owner_read,
+        "cat-1",
+        Payload::CreateCategoryRequest(proto_channels::CreateCategoryRequest {
+            name: "WS Category".to_string(),
+        }),
+    )
+    .await
+    {
+        Payload::CategoryCreatedEvent(evt) => evt.category.expect("Category in response"),
+        other => panic!("Expected CategoryCreatedEvent, got: {:?}", other),
+    };
+
+    let channel = match ws_request(
+        &mut owner_write,
+        &mut owner_read,
+        "chan-1",
+        Payload::CreateChannelRequest(proto_channels::CreateChannelRequest {
+            name: "ws-general".to_string(),
+            channel_type: "text".to_string(),
+            category_id: category.id.clone(),
+        }),
+    )
+    .await
+    {
+        Payload::ChannelCreatedEvent(evt) => evt.channel.expect("Channel in response"),
+        other => panic!("Expected ChannelCreatedEvent, got: {:?}", other),
+    };
+    assert_eq!(channel.name, "ws-general");
+    assert_eq!(channel.category_id, category.id);
+
+    // The channel is visible through REST, proving both transports share state
+    let client = reqwest::Client::new();
+    let body: serde_json::Value = client
+        .get(format!("{}/api/channels", base_url))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let found = body["categories"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|c| c["channels"].as_array().unwrap().iter())
+        .any(|ch| ch["id"] == channel.id.as_str());
+    assert!(found, "Channel created over WS should be listed by REST");
+
+    // A member without MANAGE_CHANNELS gets a correlated 403 error
+    let (member_ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws?token={}", addr, member_token))
+        .await
+        .expect("Failed to connect");
+    let (mut member_write, mut member_read) = member_ws.split();
+    drain_presence_messages(&mut member_read).await;
+
+    match ws_request(
+        &mut member_write,
+        &mut member_read,
+        "chan-2",
+        Payload::DeleteChannelRequest(proto_channels::DeleteChannelRequest {
+            channel_id: channel.id.clone(),
+        }),
+    )
+    .await
+    {
+        Payload::Error(err) => {
+            assert_eq!(err.code, 403);
+            assert_eq!(err.request_id, "chan-2");
+        }
+        other => panic!("Expected Error, got: {:?}", other),
+    }
+
+    // History over WS for the new channel is empty but well-formed
+    match ws_request(
+        &mut member_write,
+        &mut member_read,
+        "hist-1",
+        Payload::FetchHistoryRequest(united_server::proto::chat::FetchHistoryRequest {
+            channel_id: channel.id.clone(),
+            before_sequence: 0,
+            limit: 10,
+        }),
+    )
+    .await
+    {
+        Payload::FetchHistoryResponse(resp) => {
+            assert!(resp.messages.is_empty());
+            assert!(!resp.has_more);
+        }
+        other => panic!("Expected FetchHistoryResponse, got: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_ws_join_server_does_not_spend_invite_for_members() {
+    use united_server::proto::invite as proto_invite;
+    use united_server::proto::ws::envelope::Payload;
+
+    let (base_url, setup_token, addr) = start_test_server().await;
+    let (owner_token, _fingerprint, _signing_key) =
+        register_user(&base_url, &setup_token, "JoinOwner").await;
+    let (member_token, _fingerprint, _signing_key) =
+        register_user(&base_url, "", "JoinMember").await;
+
+    let client = reqwest::Client::new();
+    let invite: serde_json::Value = client
+        .post(format!("{}/api/invites", base_url))
+        .bearer_auth(&owner_token)
+        .json(&json!({ "max_uses": 1 }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let code = invite["code"].as_str().unwrap().to_string();
+
+    let (member_ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws?token={}", addr, member_token))
+        .await
+        .expect("Failed to connect");
+    let (mut member_write, mut member_read) = member_ws.split();
+    drain_presence_messages(&mut member_read).await;
+
+    // An existing member joining twice gets the lists back both times
+    for request_id in ["join-1", "join-2"] {
+        match ws_request(
+            &mut member_write,
+            &mut member_read,
+            request_id,
+            Payload::JoinServerRequest(proto_invite::JoinServerRequest {
+                invite_code: code.clone(),
+            }),
+        )
+        .await
+        {
+            Payload::JoinServerResponse(resp) => {
+                assert!(resp.success);
+                assert!(resp.channel_list.is_some());
+                assert!(resp.role_list.is_some());
+            }
+            other => panic!("Expected JoinServerResponse, got: {:?}", other),
+        }
+    }
+
+    // ...without using up the single-use invite or showing up in its join list
+    let resp = client
+        .get(format!("{}/api/invites/{}", base_url, code))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200, "Single-use invite should still be redeemable");
+
+    let uses: serde_json::Value = client
+        .get(format!("{}/api/invites/{}/uses", base_url, code))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(uses["uses"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_rate_limits_per_user_over_rest_and_ws() {
+    let rate_limits = united_server::config::RateLimitConfig {
+        typing: united_server::config::RateLimit {
+            per_minute: 6,
+            burst: 2,
+        },
+        ws: united_server::config::RateLimit {
+            per_minute: 6,
+            burst: 2,
+        },
+        ..Default::default()
+    };
+    let (base_url, setup_token, addr) = start_test_server_with_rate_limits(rate_limits).await;
+    let (alice_token, _, _) = register_user(&base_url, &setup_token, "RateAlice").await;
+    let (bob_token, _, _) = register_user(&base_url, "", "RateBob").await;
+    let client = reqwest::Client::new();
+
+    let typing = |token: &str| {
+        client
+            .post(format!("{}/api/typing", base_url))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "channel_id": "general" }))
+            .send()
+    };
+
+    // The burst is spent, then the third request is refused with Retry-After
+    for _ in 0..2 {
+        assert_eq!(typing(&alice_token).await.unwrap().status(), 200);
+    }
+    let resp = typing(&alice_token).await.unwrap();
+    assert_eq!(resp.status(), 429);
+    let retry_after: u64 = resp.headers()["retry-after"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=10).contains(&retry_after), "retry-after {}", retry_after);
+
+    // Buckets are per user and per route class
+    assert_eq!(typing(&bob_token).await.unwrap().status(), 200);
+    let resp = client
+        .get(format!("{}/api/presence", base_url))
+        .header("Authorization", format!("Bearer {}", alice_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // WS requests share the user's ws bucket and are refused with an ErrorResponse
+    let ws_url = format!("ws://{}/ws?token={}", addr, alice_token);
+    let (ws_stream, _) = tokio_tungstenite::connect_async(&ws_url)
+        .await
+        .expect("Failed to connect");
+    let (mut write, mut read) = ws_stream.split();
+    drain_presence_messages(&mut read).await;
+
+    let mut responses = Vec::new();
+    for i in 0..3 {
+        let envelope = united_server::proto::ws::Envelope {
+            request_id: format!("rl-{}", i),
+            seq: 0,
+            payload: Some(
+                united_server::proto::ws::envelope::Payload::ServerInfoRequest(
+                    united_server::proto::ws::ServerInfoRequest {},
+                ),
+            ),
+        };
+        let mut buf = Vec::new();
+        envelope.encode(&mut buf).unwrap();
+        write.send(Message::Binary(buf.into())).await.unwrap();
+
+        loop {
+            let msg = tokio::time::timeout(Duration::from_secs(2), read.next())
+                .await
+                .expect("Expected response within timeout");
+            let Some(Ok(Message::Binary(data))) = msg else {
+                panic!("Expected Binary message, got: {:?}", msg);
+            };
+            let response = united_server::proto::ws::Envelope::decode(data.as_ref()).unwrap();
+            if response.request_id == format!("rl-{}", i) {
+                responses.push(response.payload);
+                break;
+            }
+        }
+    }
+
+    assert!(matches!(
+        responses[0],
+        Some(united_server::proto::ws::envelope::Payload::ServerInfoResponse(_))
+    ));
+    assert!(matches!(
+        responses[1],
+        Some(united_server::proto::ws::envelope::Payload::ServerInfoResponse(_))
+    ));
+    match &responses[2] {
+        Some(united_server::proto::ws::envelope::Payload::Error(err)) => {
+            assert_eq!(err.code, 429);
+            assert_eq!(err.request_id, "rl-2");
+            assert!(
+                (1..=10_000).contains(&err.retry_after_ms),
+                "retry_after_ms {}",
+                err.retry_after_ms
+            );
+        }
+        other => panic!("Expected rate-limit ErrorResponse, got: {:?}", other),
+    }
+}
+
+/// Collect the content of NewMessageEvents received until the socket goes quiet.
+async fn collect_new_messages(
+    read: &mut futures_util::stream::SplitStream<
+        tokio_tungstenite::WebSocketStream<
+            tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+        >,
+    >,
+) -> Vec<String> {
+    use united_server::proto::ws::envelope::Payload;
+
+    let mut contents = Vec::new();
+    while let Ok(Some(Ok(Message::Binary(data)))) =
+        tokio::time::timeout(Duration::from_millis(500), read.next()).await
+    {
+        let envelope = united_server::proto::ws::Envelope::decode(data.as_ref()).unwrap();
+        if let Some(Payload::NewMessageEvent(event)) = envelope.payload {
+            contents.push(event.message.unwrap().content);
+        }
+    }
+    contents
+}
+
+#[tokio::test]
+async fn test_channel_events_reach_only_authorized_subscribers() {
+    use united_server::proto::ws::envelope::Payload;
+    use united_server::proto::ws::SubscribeChannelsRequest;
+
+    let (base_url, setup_token, addr) = start_test_server().await;
+    let (owner_token, _, _) = register_user(&base_url, &setup_token, "ScopeOwner").await;
+    let (member_token, _, _) = register_user(&base_url, "", "ScopeMember").await;
+    let client = reqwest::Client::new();
+
+    let body: serde_json::Value = client
+        .get(format!("{}/api/channels", base_url))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let category_id = body["categories"][0]["category"]["id"].as_str().unwrap().to_string();
+    let general_id = body["categories"][0]["channels"][0]["id"].as_str().unwrap().to_string();
+    let intro_id = body["categories"][0]["channels"][1]["id"].as_str().unwrap().to_string();
+
+    let staff: serde_json::Value = client
+        .post(format!("{}/api/channels", base_url))
+        .bearer_auth(&owner_token)
+        .json(&json!({ "name": "staff", "channel_type": "text", "category_id": category_id }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let staff_id = staff["id"].as_str().unwrap().to_string();
+
+    let (member_ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws?token={}", addr, member_token))
+        .await
+        .expect("Failed to connect");
+    let (mut member_write, mut member_read) = member_ws.split();
+    drain_presence_messages(&mut member_read).await;
+
+    // Make the staff channel private while the member is connected
+    let roles: serde_json::Value = client
+        .get(format!("{}/api/roles", base_url))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let everyone_id = roles["roles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["is_default"].as_bool() == Some(true))
+        .unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let resp = client
+        .put(format!("{}/api/channels/{}/overrides/role/{}", base_url, staff_id, everyone_id))
+        .bearer_auth(&owner_token)
+        .json(&json!({ "deny": 0x20 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let post = |channel_id: &str, content: &str| {
+        client
+            .post(format!("{}/api/channels/{}/messages", base_url, channel_id))
+            .bearer_auth(&owner_token)
+            .json(&json!({ "content": content }))
+            .send()
+    };
+
+    // Events for a channel the member cannot see are not delivered
+    assert_eq!(post(&staff_id, "staff only").await.unwrap().status(), 201);
+    assert_eq!(post(&general_id, "hello all").await.unwrap().status(), 201);
+    assert_eq!(collect_new_messages(&mut member_read).await, vec!["hello all"]);
+
+    // Narrowing subscriptions drops other channels; invisible channels stay excluded
+    match ws_request(
+        &mut member_write,
+        &mut member_read,
+        "sub-1",
+        Payload::SubscribeChannelsRequest(SubscribeChannelsRequest {
+            channel_ids: vec![intro_id.clone(), staff_id.clone()],
+            all: false,
+        }),
+    )
+    .await
+    {
+        Payload::SubscribeChannelsResponse(resp) => {
+            assert_eq!(resp.channel_ids, vec![intro_id.clone()]);
+            assert!(!resp.all);
+        }
+        other => panic!("Expected SubscribeChannelsResponse, got: {:?}", other),
+    }
+    assert_eq!(post(&general_id, "general again").await.unwrap().status(), 201);
+    assert_eq!(post(&intro_id, "hi, I'm new").await.unwrap().status(), 201);
+    assert_eq!(collect_new_messages(&mut member_read).await, vec!["hi, I'm new"]);
+
+    // Subscribing to everything again restores every visible channel
+    match ws_request(
+        &mut member_write,
+        &mut member_read,
+        "sub-2",
+        Payload::SubscribeChannelsRequest(SubscribeChannelsRequest {
+            channel_ids: vec![],
+            all: true,
+        }),
+    )
+    .await
+    {
+        Payload::SubscribeChannelsResponse(resp) => {
+            assert!(resp.all);
+            assert!(resp.channel_ids.contains(&general_id));
+            assert!(!resp.channel_ids.contains(&staff_id));
+        }
+        other => panic!("Expected SubscribeChannelsResponse, got: {:?}", other),
+    }
+    assert_eq!(post(&general_id, "back").await.unwrap().status(), 201);
+    assert_eq!(collect_new_messages(&mut member_read).await, vec!["back"]);
+}
+
+#[tokio::test]
+async fn test_private_channel_events_reach_only_viewers() {
+    use united_server::proto::ws::envelope::Payload;
+
+    let (base_url, setup_token, addr) = start_test_server().await;
+    let (owner_token, _, _) = register_user(&base_url, &setup_token, "PrivateOwner").await;
+    let (member_token, _, _) = register_user(&base_url, "", "PrivateMember").await;
+    let client = reqwest::Client::new();
+
+    let body: serde_json::Value = client
+        .get(format!("{}/api/channels", base_url))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let category_id = body["categories"][0]["category"]["id"].as_str().unwrap().to_string();
+    let staff: serde_json::Value = client
+        .post(format!("{}/api/channels", base_url))
+        .bearer_auth(&owner_token)
+        .json(&json!({ "name": "staff", "channel_type": "text", "category_id": category_id }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let staff_id = staff["id"].as_str().unwrap().to_string();
+    let roles: serde_json::Value = client
+        .get(format!("{}/api/roles", base_url))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let everyone_id = roles["roles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["is_default"].as_bool() == Some(true))
+        .unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let (owner_ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws?token={}", addr, owner_token))
+        .await
+        .expect("Failed to connect");
+    let (_owner_write, mut owner_read) = owner_ws.split();
+    drain_presence_messages(&mut owner_read).await;
+    let (member_ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws?token={}", addr, member_token))
+        .await
+        .expect("Failed to connect");
+    let (_member_write, mut member_read) = member_ws.split();
+    drain_presence_messages(&mut member_read).await;
+    read_envelopes_until(&mut owner_read, |_| false).await;
+
+    let event_names = |envelopes: Vec<united_server::proto::ws::Envelope>| -> Vec<&'static str> {
+        envelopes
+            .into_iter()
+            .filter_map(|e| match e.payload? {
+                Payload::ChannelOverrideUpdatedEvent(_) => Some("override_updated"),
+                Payload::ChannelUpdatedEvent(_) => Some("channel_updated"),
+                Payload::ChannelDeletedEvent(_) => Some("channel_deleted"),
+                Payload::AuditLogEntryCreatedEvent(_) => Some("audit"),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    // Audit events are pushed from a background task, so compare without ordering
+    let sorted = |mut names: Vec<&'static str>| {
+        names.sort_unstable();
+        names
+    };
+
+    // Hiding the channel: the member just lost access and still hears about it
+    let resp = client
+        .put(format!("{}/api/channels/{}/overrides/role/{}", base_url, staff_id, everyone_id))
+        .bearer_auth(&owner_token)
+        .json(&json!({ "deny": 0x20 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        event_names(read_envelopes_until(&mut member_read, |_| false).await),
+        vec!["override_updated"]
+    );
+    assert_eq!(
+        sorted(event_names(read_envelopes_until(&mut owner_read, |_| false).await)),
+        vec!["audit", "override_updated"]
+    );
+
+    // Being given a role that may view the channel lets the member hear from it again,
+    // and losing the role hides it once more
+    let role: serde_json::Value = client
+        .post(format!("{}/api/roles", base_url))
+        .bearer_auth(&owner_token)
+        .json(&json!({ "name": "Staff", "permissions": 0 }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let role_id = role["id"].as_str().unwrap().to_string();
+    let resp = client
+        .put(format!("{}/api/channels/{}/overrides/role/{}", base_url, staff_id, role_id))
+        .bearer_auth(&owner_token)
+        .json(&json!({ "allow": 0x20 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let members: serde_json::Value = client
+        .get(format!("{}/api/members", base_url))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let member_id = members
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["display_name"] == "PrivateMember")
+        .unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let post_staff = |content: &str| {
+        client
+            .post(format!("{}/api/channels/{}/messages", base_url, staff_id))
+            .bearer_auth(&owner_token)
+            .json(&json!({ "content": content }))
+            .send()
+    };
+    for (action, expected) in [("assign", vec!["staff news"]), ("remove", vec![])] {
+        let resp = client
+            .post(format!("{}/api/roles/{}", base_url, action))
+            .bearer_auth(&owner_token)
+            .json(&json!({ "user_id": member_id, "role_id": role_id }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        read_envelopes_until(&mut member_read, |_| false).await;
+        assert_eq!(post_staff("staff news").await.unwrap().status(), 201);
+        assert_eq!(collect_new_messages(&mut member_read).await, expected);
+    }
+    read_envelopes_until(&mut owner_read, |_| false).await;
+
+    // Renaming and deleting the hidden channel only reach its viewers
+    let resp = client
+        .put(format!("{}/api/channels/{}", base_url, staff_id))
+        .bearer_auth(&owner_token)
+        .json(&json!({ "name": "secret-plans" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .delete(format!("{}/api/channels/{}", base_url, staff_id))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(event_names(read_envelopes_until(&mut member_read, |_| false).await).is_empty());
+    assert_eq!(
+        sorted(event_names(read_envelopes_until(&mut owner_read, |_| false).await)),
+        vec!["audit", "audit", "channel_deleted", "channel_updated"]
+    );
+}
+
+type WsRead = futures_util::stream::SplitStream<
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+>;
+
+/// Read envelopes until `done` matches one (inclusive) or nothing arrives for 500ms.
+async fn read_envelopes_until(
+    read: &mut WsRead,
+    done: impl Fn(&united_server::proto::ws::Envelope) -> bool,
+) -> Vec<united_server::proto::ws::Envelope> {
+    let mut envelopes = Vec::new();
+    while let Ok(Some(Ok(Message::Binary(data)))) =
+        tokio::time::timeout(Duration::from_millis(500), read.next()).await
+    {
+        let envelope = united_server::proto::ws::Envelope::decode(data.as_ref()).unwrap();
+        let stop = done(&envelope);
+        envelopes.push(envelope);
+        if stop {
+            break;
+        }
+    }
+    envelopes
+}
+
+fn message_contents(envelopes: &[united_server::proto::ws::Envelope]) -> Vec<String> {
+    use united_server::proto::ws::envelope::Payload;
+
+    envelopes
+        .iter()
+        .filter_map(|e| match &e.payload {
+            Some(Payload::NewMessageEvent(event)) => Some(event.message.as_ref()?.content.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_ready_snapshot_and_session_resume() {
+    use united_server::proto::ws::envelope::Payload;
+
+    let (base_url, setup_token, addr) = start_test_server().await;
+    let (owner_token, _, _) = register_user(&base_url, &setup_token, "ResumeOwner").await;
+    let client = reqwest::Client::new();
+
+    let body: serde_json::Value = client
+        .get(format!("{}/api/channels", base_url))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let general_id = body["categories"][0]["channels"][0]["id"].as_str().unwrap().to_string();
+    let post = |content: &str| {
+        client
+            .post(format!("{}/api/channels/{}/messages", base_url, general_id))
+            .bearer_auth(&owner_token)
+            .json(&json!({ "content": content }))
+            .send()
+    };
+    assert_eq!(post("before connect").await.unwrap().status(), 201);
+
+    // A new session starts with a ReadyEvent snapshot
+    let (ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws?token={}", addr, owner_token))
+        .await
+        .expect("Failed to connect");
+    let (mut write, mut read) = ws.split();
+    let first = read_envelopes_until(&mut read, |_| true).await;
+    let ready = match &first[0].payload {
+        Some(Payload::ReadyEvent(ready)) => ready.clone(),
+        other => panic!("Expected ReadyEvent, got: {:?}", other),
+    };
+    assert!(!ready.session_id.is_empty());
+    assert!(!ready.resume_failed);
+    assert!(ready
+        .categories
+        .iter()
+        .flat_map(|c| c.channels.iter())
+        .any(|c| c.id == general_id));
+    assert!(!ready.roles.is_empty());
+    assert!(ready.voice_channels.is_empty());
+    let general_unread = ready.unread.iter().find(|u| u.channel_id == general_id).unwrap();
+    assert_eq!(general_unread.unread_count, 1);
+    assert_eq!(general_unread.latest_sequence, 1);
+
+    // Events carry increasing per-session sequence numbers
+    assert_eq!(post("one").await.unwrap().status(), 201);
+    assert_eq!(post("two").await.unwrap().status(), 201);
+    // (the legacy presence snapshot after the ReadyEvent is unnumbered, like the ReadyEvent)
+    let events: Vec<_> = read_envelopes_until(&mut read, |_| false)
+        .await
+        .into_iter()
+        .filter(|e| e.seq > 0)
+        .collect();
+    assert_eq!(message_contents(&events), vec!["one", "two"]);
+    assert!(events.windows(2).all(|w| w[1].seq == w[0].seq + 1));
+    let last_seq = events.last().unwrap().seq;
+
+    // Events published while disconnected are replayed on resume
+    write.send(Message::Close(None)).await.unwrap();
+    drop(write);
+    drop(read);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(post("missed 1").await.unwrap().status(), 201);
+    assert_eq!(post("missed 2").await.unwrap().status(), 201);
+
+    let (ws, _) = tokio_tungstenite::connect_async(format!(
+        "ws://{}/ws?token={}&session={}&last_seq={}",
+        addr, owner_token, ready.session_id, last_seq
+    ))
+    .await
+    .expect("Failed to resume");
+    let (_write, mut read) = ws.split();
+    let replayed = read_envelopes_until(&mut read, |e| {
+        matches!(e.payload, Some(Payload::ResumedEvent(_)))
+    })
+    .await;
+    let resumed = match &replayed.last().unwrap().payload {
+        Some(Payload::ResumedEvent(resumed)) => resumed.clone(),
+        other => panic!("Expected ResumedEvent, got: {:?}", other),
+    };
+    assert_eq!(resumed.session_id, ready.session_id);
+    assert_eq!(resumed.replayed as usize, replayed.len() - 1);
+    assert_eq!(message_contents(&replayed), vec!["missed 1", "missed 2"]);
+    assert!(replayed[..replayed.len() - 1]
+        .windows(2)
+        .all(|w| w[1].seq == w[0].seq + 1));
+    assert_eq!(replayed[0].seq, last_seq + 1);
+
+    // An unknown session falls back to a fresh one
+    let (ws, _) = tokio_tungstenite::connect_async(format!(
+        "ws://{}/ws?token={}&session=nonexistent&last_seq=3",
+        addr, owner_token
+    ))
+    .await
+    .expect("Failed to connect");
+    let (_write, mut read) = ws.split();
+    let first = read_envelopes_until(&mut read, |_| true).await;
+    match &first[0].payload {
+        Some(Payload::ReadyEvent(fresh)) => {
+            assert!(fresh.resume_failed);
+            assert_ne!(fresh.session_id, ready.session_id);
+        }
+        other => panic!("Expected ReadyEvent, got: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_outbound_queue_sheds_droppable_events_then_overflows() {
+    use axum::extract::ws::Message as WsMessage;
+    use united_server::ws::outbound::{self, OutboundMetrics, Outbound, CLOSE_SLOW_CONSUMER};
+
+    let metrics = Arc::new(OutboundMetrics::new());
+    let (tx, mut rx) = outbound::channel(3, metrics.clone());
+    tx.set_resume_session("session-1");
+    let text = |s: &str| WsMessage::Text(s.into());
+
+    // A full queue drops new droppable events, then evicts queued ones for required messages
+    tx.send(text("a")).unwrap();
+    tx.send_droppable(text("typing")).unwrap();
+    tx.send(text("b")).unwrap();
+    tx.send_droppable(text("presence")).unwrap();
+    assert_eq!(tx.len(), 3);
+    tx.send(text("c")).unwrap();
+    assert_eq!(metrics.dropped_events(), 2);
+    assert_eq!(metrics.peak_depth(), 3);
+
+    let mut received = Vec::new();
+    for _ in 0..3 {
+        match rx.recv().await {
+            Some(Outbound::Message(WsMessage::Text(t))) => received.push(t.to_string()),
+            other => panic!("Expected queued message, got: {:?}", other),
+        }
+    }
+    assert_eq!(received, vec!["a", "b", "c"]);
+
+    // With nothing left to shed, the connection is closed with a resume hint
+    for s in ["d", "e", "f"] {
+        tx.send(text(s)).unwrap();
+    }
+    assert!(tx.send(text("g")).is_err());
+    assert!(tx.is_closed());
+    assert_eq!(tx.len(), 0);
+    assert_eq!(metrics.slow_consumer_disconnects(), 1);
+    match rx.recv().await {
+        Some(Outbound::Close(frame)) => {
+            assert_eq!(frame.code, CLOSE_SLOW_CONSUMER);
+            assert!(frame.reason.contains("session-1"));
+        }
+        other => panic!("Expected slow-consumer close, got: {:?}", other),
+    }
+    assert!(rx.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn test_ws_metrics_report_queue_depth() {
+    let (base_url, setup_token, addr) = start_test_server().await;
+    let (owner_token, _, _) = register_user(&base_url, &setup_token, "MetricsOwner").await;
+    let (member_token, _, _) = register_user(&base_url, "", "MetricsMember").await;
+    let client = reqwest::Client::new();
+
+    let (ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws?token={}", addr, member_token))
+        .await
+        .expect("Failed to connect");
+    let (_write, mut read) = ws.split();
+    drain_presence_messages(&mut read).await;
+
+    let resp = client
+        .get(format!("{}/api/ws/metrics", base_url))
+        .bearer_auth(&member_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    let metrics: serde_json::Value = client
+        .get(format!("{}/api/ws/metrics", base_url))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(metrics["connections"], 1);
+    assert_eq!(metrics["detached_sessions"], 0);
+    assert_eq!(
+        metrics["queue_capacity"],
+        united_server::ws::outbound::OUTBOUND_QUEUE_LEN as u64
+    );
+    assert!(metrics["queue_depth_peak"].as_u64().unwrap() >= 1);
+    assert_eq!(metrics["slow_consumer_disconnects"], 0);
+}